@@ -1,4 +1,7 @@
+use ai_cli_memory_system::{MemoryEntry, MemorySystem};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
@@ -8,10 +11,161 @@ pub struct AgentConfig {
     pub max_iterations: u32,
 }
 
+/// A unit of work for an agent: free-form input plus structured parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentTask {
+    pub input: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+impl AgentTask {
+    pub fn new(input: impl Into<String>) -> Self {
+        AgentTask {
+            input: input.into(),
+            params: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_params(mut self, params: serde_json::Value) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Whether any parameters were supplied
+    pub fn has_params(&self) -> bool {
+        match &self.params {
+            serde_json::Value::Null => false,
+            serde_json::Value::Object(map) => !map.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+/// What an agent is doing right now
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentState {
+    #[default]
+    Idle,
+    Running,
+    Failed,
+}
+
+/// Runtime status of a registered agent
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentStatus {
+    pub state: AgentState,
+    pub current_task: Option<String>,
+    pub last_execution: Option<DateTime<Utc>>,
+    pub last_result: Option<String>,
+    pub last_error: Option<String>,
+    pub total_executions: u64,
+}
+
+impl AgentStatus {
+    /// Mark a task as started
+    pub fn start(&mut self, task: &str) {
+        self.state = AgentState::Running;
+        self.current_task = Some(task.to_string());
+    }
+
+    /// Record the outcome of the running task
+    pub fn finish(&mut self, result: &Result<String, ai_cli_utils::error::AIError>) {
+        self.current_task = None;
+        self.last_execution = Some(Utc::now());
+        self.total_executions += 1;
+        match result {
+            Ok(output) => {
+                self.state = AgentState::Idle;
+                self.last_result = Some(output.clone());
+                self.last_error = None;
+            }
+            Err(e) => {
+                self.state = AgentState::Failed;
+                self.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
 pub trait Agent: Send + Sync {
     fn get_config(&self) -> &AgentConfig;
     fn execute(&self, input: &str) -> Result<String, ai_cli_utils::error::AIError>;
     fn can_handle(&self, task: &str) -> bool;
+
+    /// Execute a task with structured parameters
+    ///
+    /// Agents that don't use parameters can rely on the default, which runs
+    /// `execute` on the task input.
+    fn execute_task(&self, task: &AgentTask) -> Result<String, ai_cli_utils::error::AIError> {
+        self.execute(&task.input)
+    }
+
+    /// Whether [`AgentFramework::save`](crate::AgentFramework::save) can
+    /// rebuild this agent from its config alone
+    fn is_persistable(&self) -> bool {
+        false
+    }
+
+    /// Execute with access to shared resources such as project memory
+    fn execute_with_context(
+        &self,
+        input: &str,
+        _ctx: &AgentContext,
+    ) -> Result<String, ai_cli_utils::error::AIError> {
+        self.execute(input)
+    }
+}
+
+/// Shared resources available to agents during execution
+#[derive(Clone)]
+pub struct AgentContext {
+    memory: Arc<Mutex<MemorySystem>>,
+}
+
+impl AgentContext {
+    pub fn new(memory: Arc<Mutex<MemorySystem>>) -> Self {
+        AgentContext { memory }
+    }
+
+    /// Get the shared memory system
+    pub fn memory(&self) -> &Arc<Mutex<MemorySystem>> {
+        &self.memory
+    }
+
+    pub fn store(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+        tags: Vec<String>,
+    ) -> Result<(), ai_cli_utils::error::AIError> {
+        self.lock()?.store(key.into(), value.into(), tags)
+    }
+
+    pub fn retrieve(&self, key: &str) -> Result<Option<MemoryEntry>, ai_cli_utils::error::AIError> {
+        Ok(self.lock()?.retrieve(key).cloned())
+    }
+
+    pub fn search(
+        &self,
+        tags: &[String],
+    ) -> Result<Vec<MemoryEntry>, ai_cli_utils::error::AIError> {
+        Ok(self
+            .lock()?
+            .search_by_tags(tags)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    fn lock(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, MemorySystem>, ai_cli_utils::error::AIError> {
+        self.memory.lock().map_err(|_| {
+            ai_cli_utils::error::AIError::GenericError("memory lock poisoned".to_string())
+        })
+    }
 }
 
 pub struct SimpleAgent {
@@ -41,4 +195,127 @@ impl Agent for SimpleAgent {
         // Placeholder implementation
         true
     }
+
+    fn is_persistable(&self) -> bool {
+        true
+    }
+
+    fn execute_task(&self, task: &AgentTask) -> Result<String, ai_cli_utils::error::AIError> {
+        let output = self.execute(&task.input)?;
+        if task.has_params() {
+            Ok(format!("{} with params: {}", output, task.params))
+        } else {
+            Ok(output)
+        }
+    }
+}
+
+/// Agent that records its results in project memory and recalls earlier ones
+///
+/// Results are tagged with the agent name so later runs can find them.
+pub struct MemoryAwareAgent {
+    config: AgentConfig,
+}
+
+impl MemoryAwareAgent {
+    pub fn new(config: AgentConfig) -> Self {
+        MemoryAwareAgent { config }
+    }
+}
+
+impl Agent for MemoryAwareAgent {
+    fn get_config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    fn execute(&self, input: &str) -> Result<String, ai_cli_utils::error::AIError> {
+        Ok(format!(
+            "Agent {} executed task: {}",
+            self.config.name, input
+        ))
+    }
+
+    fn can_handle(&self, _task: &str) -> bool {
+        true
+    }
+
+    fn execute_with_context(
+        &self,
+        input: &str,
+        ctx: &AgentContext,
+    ) -> Result<String, ai_cli_utils::error::AIError> {
+        let tags = vec![self.config.name.clone()];
+        let mut prior = ctx.search(&tags)?;
+        prior.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let output = match prior.last() {
+            Some(last) => format!("{} (previously: {})", self.execute(input)?, last.value),
+            None => self.execute(input)?,
+        };
+
+        let key = format!("{}:{:06}", self.config.name, prior.len());
+        ctx.store(key, output.clone(), tags)?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_memory_system::MemoryConfig;
+
+    fn config(name: &str) -> AgentConfig {
+        AgentConfig {
+            name: name.to_string(),
+            description: String::new(),
+            capabilities: vec![],
+            max_iterations: 1,
+        }
+    }
+
+    fn context() -> AgentContext {
+        AgentContext::new(Arc::new(Mutex::new(MemorySystem::new(
+            MemoryConfig::default(),
+        ))))
+    }
+
+    #[test]
+    fn test_execute_with_context_defaults_to_execute() {
+        let agent = SimpleAgent::new(config("simple"));
+        let ctx = context();
+
+        assert_eq!(
+            agent.execute_with_context("task", &ctx).unwrap(),
+            agent.execute("task").unwrap()
+        );
+        assert_eq!(ctx.memory().lock().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_simple_agent_echoes_params() {
+        let agent = SimpleAgent::new(config("simple"));
+
+        let plain = agent.execute_task(&AgentTask::new("build")).unwrap();
+        assert_eq!(plain, agent.execute("build").unwrap());
+
+        let task = AgentTask::new("build").with_params(serde_json::json!({ "target": "x86" }));
+        assert_eq!(
+            agent.execute_task(&task).unwrap(),
+            "Agent simple executed task: build with params: {\"target\":\"x86\"}"
+        );
+    }
+
+    #[test]
+    fn test_memory_aware_agent_recalls_prior_results() {
+        let agent = MemoryAwareAgent::new(config("recall"));
+        let ctx = context();
+
+        let first = agent.execute_with_context("first", &ctx).unwrap();
+        let second = agent.execute_with_context("second", &ctx).unwrap();
+
+        assert!(!first.contains("previously"));
+        assert!(second.contains("previously: Agent recall executed task: first"));
+        assert_eq!(ctx.search(&["recall".to_string()]).unwrap().len(), 2);
+        assert_eq!(ctx.retrieve("recall:000000").unwrap().unwrap().value, first);
+    }
 }
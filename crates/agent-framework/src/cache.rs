@@ -0,0 +1,300 @@
+//! Result caching for deterministic agents
+
+use crate::agent::{Agent, AgentConfig, AgentContext, AgentTask};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Cache counters reported by [`CachingAgent::cache_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+/// A task's input, with its serialized parameters when it has any
+type CacheKey = (String, Option<String>);
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, (String, Instant)>,
+    /// Keys from least to most recently used
+    order: VecDeque<CacheKey>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(k) = self.order.remove(pos) {
+                self.order.push_back(k);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> bool {
+        self.order.retain(|k| k != key);
+        self.entries.remove(key).is_some()
+    }
+}
+
+/// Decorator memoizing successful `execute` results by input
+///
+/// Tasks with parameters are cached under the input and parameters together.
+/// Entries are evicted least-recently-used once `capacity` is reached, and
+/// expire after the optional TTL. Errors are never cached, and
+/// `execute_with_context` always reaches the inner agent since it may have
+/// side effects on shared memory.
+pub struct CachingAgent {
+    inner: Box<dyn Agent>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    state: Mutex<CacheState>,
+}
+
+impl CachingAgent {
+    pub fn new(inner: Box<dyn Agent>, capacity: usize) -> Self {
+        CachingAgent {
+            inner,
+            capacity,
+            ttl: None,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Expire cached results after `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            size: state.entries.len(),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Drop the cached result for a task, returning whether one existed
+    pub fn invalidate(&self, task: &str) -> bool {
+        self.lock().remove(&(task.to_string(), None))
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.order.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // The cache holds no invariants a panic could break, so recover from poisoning
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lookup(&self, task: &CacheKey) -> Option<String> {
+        let mut state = self.lock();
+        let cached = state
+            .entries
+            .get(task)
+            .map(|(value, stored)| (value.clone(), *stored));
+
+        match cached {
+            Some((_, stored)) if self.ttl.is_some_and(|ttl| stored.elapsed() >= ttl) => {
+                state.remove(task);
+                state.misses += 1;
+                None
+            }
+            Some((value, _)) => {
+                state.touch(task);
+                state.hits += 1;
+                Some(value)
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&self, task: CacheKey, value: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        if state.entries.contains_key(&task) {
+            state.touch(&task);
+        } else {
+            while state.entries.len() >= self.capacity {
+                match state.order.pop_front() {
+                    Some(oldest) => {
+                        state.entries.remove(&oldest);
+                    }
+                    None => break,
+                }
+            }
+            state.order.push_back(task.clone());
+        }
+        state.entries.insert(task, (value, Instant::now()));
+    }
+}
+
+impl Agent for CachingAgent {
+    fn get_config(&self) -> &AgentConfig {
+        self.inner.get_config()
+    }
+
+    fn execute(&self, input: &str) -> Result<String, ai_cli_utils::error::AIError> {
+        let key = (input.to_string(), None);
+        if let Some(cached) = self.lookup(&key) {
+            return Ok(cached);
+        }
+
+        let result = self.inner.execute(input)?;
+        self.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn can_handle(&self, task: &str) -> bool {
+        self.inner.can_handle(task)
+    }
+
+    fn execute_task(&self, task: &AgentTask) -> Result<String, ai_cli_utils::error::AIError> {
+        if !task.has_params() {
+            return self.execute(&task.input);
+        }
+
+        let key = (task.input.clone(), Some(task.params.to_string()));
+        if let Some(cached) = self.lookup(&key) {
+            return Ok(cached);
+        }
+
+        let result = self.inner.execute_task(task)?;
+        self.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn execute_with_context(
+        &self,
+        input: &str,
+        ctx: &AgentContext,
+    ) -> Result<String, ai_cli_utils::error::AIError> {
+        self.inner.execute_with_context(input, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingAgent {
+        config: AgentConfig,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Agent for CountingAgent {
+        fn get_config(&self) -> &AgentConfig {
+            &self.config
+        }
+
+        fn execute(&self, input: &str) -> Result<String, ai_cli_utils::error::AIError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(input.to_uppercase())
+        }
+
+        fn can_handle(&self, task: &str) -> bool {
+            task != "refuse"
+        }
+    }
+
+    fn counting(calls: &Arc<AtomicUsize>) -> Box<dyn Agent> {
+        Box::new(CountingAgent {
+            config: AgentConfig {
+                name: "counter".to_string(),
+                description: String::new(),
+                capabilities: vec![],
+                max_iterations: 1,
+            },
+            calls: calls.clone(),
+        })
+    }
+
+    #[test]
+    fn test_repeated_task_runs_inner_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let agent = CachingAgent::new(counting(&calls), 4);
+
+        assert_eq!(agent.execute("task").unwrap(), "TASK");
+        assert_eq!(agent.execute("task").unwrap(), "TASK");
+        assert_eq!(agent.execute("task").unwrap(), "TASK");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let stats = agent.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let agent = CachingAgent::new(counting(&calls), 2);
+
+        agent.execute("a").unwrap();
+        agent.execute("b").unwrap();
+        agent.execute("a").unwrap(); // refresh a; b is now least recent
+        agent.execute("c").unwrap(); // evicts b
+        agent.execute("a").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        agent.execute("b").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_ttl_and_invalidate() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let agent = CachingAgent::new(counting(&calls), 4).with_ttl(Duration::from_millis(10));
+
+        agent.execute("task").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        agent.execute("task").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        assert!(agent.invalidate("task"));
+        assert!(!agent.invalidate("task"));
+        agent.execute("task").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_params_never_share_a_plain_input_key() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let agent = CachingAgent::new(counting(&calls), 4);
+        let params = serde_json::json!({"k": 1});
+
+        let task = AgentTask::new("x").with_params(params.clone());
+        agent.execute_task(&task).unwrap();
+        agent.execute(&format!("x\0{}", params)).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        agent.execute_task(&task).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_can_handle_delegates() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let agent = CachingAgent::new(counting(&calls), 1);
+
+        assert!(agent.can_handle("task"));
+        assert!(!agent.can_handle("refuse"));
+    }
+}
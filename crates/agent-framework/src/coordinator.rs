@@ -2,7 +2,6 @@ use std::collections::HashMap;
 
 pub struct AgentCoordinator {
     agents: HashMap<String, Box<dyn crate::agent::Agent>>,
-    #[allow(dead_code)]
     max_concurrent: u8,
 }
 
@@ -14,13 +13,19 @@ impl AgentCoordinator {
         }
     }
 
+    /// Change how many tasks [`execute_parallel`](Self::execute_parallel)
+    /// runs at once
+    pub fn set_max_concurrent(&mut self, max_concurrent: u8) {
+        self.max_concurrent = max_concurrent;
+    }
+
     pub fn add_agent(&mut self, name: String, agent: Box<dyn crate::agent::Agent>) {
         self.agents.insert(name, agent);
     }
 
     pub fn execute_task(&self, task: &str) -> Result<String, ai_cli_utils::error::AIError> {
         // Find an appropriate agent for the task
-        for (_name, agent) in &self.agents {
+        for agent in self.agents.values() {
             if agent.can_handle(task) {
                 return agent.execute(task);
             }
@@ -31,19 +36,34 @@ impl AgentCoordinator {
         ))
     }
 
-    pub fn execute_parallel(
+    /// Route a task with parameters to the first agent that can handle its input
+    pub fn execute_agent_task(
         &self,
-        tasks: Vec<&str>,
-    ) -> Result<Vec<String>, ai_cli_utils::error::AIError> {
-        let mut results = Vec::new();
-
-        for task in tasks {
-            match self.execute_task(task) {
-                Ok(result) => results.push(result),
-                Err(e) => results.push(format!("Error: {}", e)),
+        task: &crate::agent::AgentTask,
+    ) -> Result<String, ai_cli_utils::error::AIError> {
+        for agent in self.agents.values() {
+            if agent.can_handle(&task.input) {
+                return agent.execute_task(task);
             }
         }
 
-        Ok(results)
+        Err(ai_cli_utils::error::AIError::GenericError(
+            "No suitable agent found for task".to_string(),
+        ))
+    }
+
+    /// Run `tasks` at most `max_concurrent` at a time, keeping their order
+    pub fn execute_parallel(
+        &self,
+        tasks: Vec<&str>,
+    ) -> Result<Vec<String>, ai_cli_utils::error::AIError> {
+        Ok(crate::run_bounded(
+            &tasks,
+            usize::from(self.max_concurrent),
+            |task| match self.execute_task(task) {
+                Ok(result) => result,
+                Err(e) => format!("Error: {}", e),
+            },
+        ))
     }
 }
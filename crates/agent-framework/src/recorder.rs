@@ -0,0 +1,263 @@
+//! Recording workflow events for later inspection
+//!
+//! An [`EventRecorder`] subscribes to a [`Workflow`]'s events and writes each
+//! one to an [`EventSink`]: the memory system, as entries tagged
+//! `workflow:<id>`, or a JSON Lines file. Every attach starts a new run with
+//! its own ID, so repeated runs of a workflow keep separate traces. Once the
+//! workflow has finished, its event trace can be queried like any other
+//! memory or read back line by line.
+
+use crate::workflow::{Workflow, WorkflowEvent};
+use ai_cli_memory_system::MemorySystem;
+use ai_cli_utils::error::AIError;
+use chrono::Utc;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+/// Memory tag on every event recorded for the workflow `workflow_id`
+pub fn workflow_tag(workflow_id: &str) -> String {
+    format!("workflow:{}", workflow_id)
+}
+
+/// ID of a new recording run: its start time, then a random suffix
+fn new_run_id() -> String {
+    format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    )
+}
+
+/// Where recorded events are written
+#[derive(Clone)]
+pub enum EventSink {
+    /// One memory entry per event, keyed `workflow:<id>:<run>:<sequence>`
+    Memory(Arc<Mutex<MemorySystem>>),
+    /// One JSON object per line, appended to the file
+    Jsonl(PathBuf),
+}
+
+/// An event as written to a JSON Lines file
+#[derive(Serialize)]
+struct RecordedEvent<'a> {
+    workflow_id: &'a str,
+    run_id: &'a str,
+    #[serde(flatten)]
+    event: &'a WorkflowEvent,
+}
+
+impl EventSink {
+    /// Write one event; blocks on file IO and the memory lock
+    fn record(
+        &self,
+        workflow_id: &str,
+        run_id: &str,
+        sequence: usize,
+        event: &WorkflowEvent,
+    ) -> Result<(), AIError> {
+        match self {
+            Self::Memory(memory) => memory.lock().unwrap_or_else(|e| e.into_inner()).store(
+                format!("{}:{}:{:06}", workflow_tag(workflow_id), run_id, sequence),
+                serde_json::to_string(event)?,
+                vec![workflow_tag(workflow_id)],
+            ),
+            Self::Jsonl(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let line = serde_json::to_string(&RecordedEvent {
+                    workflow_id,
+                    run_id,
+                    event,
+                })?;
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                writeln!(file, "{}", line)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Writes workflow events to a sink while it is enabled
+#[derive(Clone)]
+pub struct EventRecorder {
+    sink: EventSink,
+    enabled: bool,
+}
+
+impl EventRecorder {
+    /// An enabled recorder writing to `sink`
+    pub fn new(sink: EventSink) -> Self {
+        Self {
+            sink,
+            enabled: true,
+        }
+    }
+
+    /// Turn recording on or off
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record `workflow`'s events from now until it is dropped, as a new run
+    ///
+    /// Returns `None` when recording is disabled. Otherwise the returned task
+    /// finishes once the workflow is dropped, yielding the number of events
+    /// recorded. Events are written on the blocking thread pool. Events that
+    /// fail to be written, or that are missed because the recorder fell
+    /// behind, are logged and skipped.
+    pub fn attach(&self, workflow: &Workflow) -> Option<JoinHandle<usize>> {
+        if !self.enabled {
+            return None;
+        }
+        let mut events = workflow.subscribe();
+        let workflow_id: Arc<str> = workflow.id().into();
+        let run_id: Arc<str> = new_run_id().into();
+        let sink = self.sink.clone();
+        Some(tokio::spawn(async move {
+            let mut recorded = 0;
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let (sink, id, run) = (sink.clone(), workflow_id.clone(), run_id.clone());
+                        let written = tokio::task::spawn_blocking(move || {
+                            sink.record(&id, &run, recorded, &event)
+                                .map_err(|e| (event.event_type, e.to_string()))
+                        })
+                        .await;
+                        match written {
+                            Ok(Ok(())) => recorded += 1,
+                            Ok(Err((event_type, e))) => log::warn!(
+                                "Not recording {} event of workflow {}: {}",
+                                event_type,
+                                workflow_id,
+                                e
+                            ),
+                            Err(e) => {
+                                log::warn!("Not recording event of workflow {}: {}", workflow_id, e)
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Missed {} events of workflow {}", missed, workflow_id)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            recorded
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::{StateHandler, WorkflowContext, WorkflowResult, WorkflowState};
+    use ai_cli_memory_system::MemoryConfig;
+    use async_trait::async_trait;
+
+    struct Step {
+        state: WorkflowState,
+        next: WorkflowState,
+    }
+
+    #[async_trait]
+    impl StateHandler for Step {
+        async fn execute(&self, _context: &mut WorkflowContext) -> WorkflowResult<WorkflowState> {
+            Ok(self.next.clone())
+        }
+
+        fn state(&self) -> WorkflowState {
+            self.state.clone()
+        }
+    }
+
+    async fn run_small_workflow(recorder: &EventRecorder) -> Option<usize> {
+        let workflow = Workflow::new("build", WorkflowState::Pending);
+        for (state, next) in [
+            (WorkflowState::Pending, WorkflowState::Running),
+            (WorkflowState::Running, WorkflowState::Completed),
+        ] {
+            workflow
+                .register_handler(Arc::new(Step { state, next }))
+                .await;
+        }
+        let recording = recorder.attach(&workflow);
+        workflow.run(10, None).await.unwrap();
+        drop(workflow);
+        match recording {
+            Some(task) => Some(task.await.unwrap()),
+            None => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workflow_events_are_recorded() {
+        let memory = Arc::new(Mutex::new(MemorySystem::new(MemoryConfig::default())));
+        let recorder = EventRecorder::new(EventSink::Memory(memory.clone()));
+        assert_eq!(run_small_workflow(&recorder).await, Some(3));
+
+        {
+            let memory = memory.lock().unwrap();
+            let mut entries = memory.search_by_tags(&[workflow_tag("build")]);
+            entries.sort_by(|a, b| a.key.cmp(&b.key));
+            let events: Vec<WorkflowEvent> = entries
+                .iter()
+                .map(|entry| serde_json::from_str(&entry.value).unwrap())
+                .collect();
+            let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+            assert_eq!(types, vec!["transition", "transition", "finished"]);
+            assert_eq!(events[1].data["to"], "completed");
+            assert!(entries[0].key.starts_with("workflow:build:"));
+            assert!(entries[0].key.ends_with(":000000"));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events").join("build.jsonl");
+        let recorder = EventRecorder::new(EventSink::Jsonl(path.clone()));
+        assert_eq!(run_small_workflow(&recorder).await, Some(3));
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["workflow_id"], "build");
+        assert_eq!(lines[0]["run_id"], lines[2]["run_id"]);
+        assert_eq!(lines[2]["data"]["state"], "completed");
+
+        assert_eq!(
+            run_small_workflow(&recorder.with_enabled(false)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runs_keep_separate_traces() {
+        let memory = Arc::new(Mutex::new(MemorySystem::new(MemoryConfig::default())));
+        let recorder = EventRecorder::new(EventSink::Memory(memory.clone()));
+        assert_eq!(run_small_workflow(&recorder).await, Some(3));
+        assert_eq!(run_small_workflow(&recorder).await, Some(3));
+
+        let memory = memory.lock().unwrap();
+        let entries = memory.search_by_tags(&[workflow_tag("build")]);
+        assert_eq!(entries.len(), 6);
+        let runs: std::collections::BTreeSet<&str> = entries
+            .iter()
+            .map(|entry| entry.key.rsplit_once(':').unwrap().0)
+            .collect();
+        assert_eq!(runs.len(), 2);
+    }
+}
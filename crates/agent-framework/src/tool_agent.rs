@@ -0,0 +1,421 @@
+//! ReAct-style agent that reasons with a model and acts through tools
+//!
+//! The model is asked to answer in the classic ReAct format:
+//!
+//! ```text
+//! Thought: I need to add the numbers
+//! Action: calculator
+//! Action Input: 2 + 3
+//! ```
+//!
+//! The tool output is fed back as an `Observation:` message and the loop
+//! repeats until the model replies with `Final Answer: ...` or the iteration
+//! budget from [`AgentConfig::max_iterations`] runs out.
+
+use crate::agent::{Agent, AgentConfig};
+use ai_cli_ai_engine::provider::{
+    AIProvider, Message, MessageRole, PromptRequest, RequestMetadata,
+};
+use ai_cli_ai_engine::template::PromptTemplate;
+use ai_cli_utils::error::AIError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// System prompt explaining the ReAct reply format
+const SYSTEM_PROMPT: &str = "You can use these tools:
+{{tools}}
+
+To use a tool, reply with:
+Thought: <your reasoning>
+Action: <tool name>
+Action Input: <input for the tool>
+
+You will then receive an Observation with the tool output.
+When you know the answer, reply with:
+Final Answer: <answer>";
+
+/// Tool implementation: takes the action input, returns the observation
+pub type ToolFn = Box<dyn Fn(&str) -> Result<String, AIError> + Send + Sync>;
+
+struct Tool {
+    description: String,
+    run: ToolFn,
+}
+
+/// One reason-act step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStep {
+    pub thought: Option<String>,
+    pub tool: String,
+    pub input: String,
+    pub observation: String,
+}
+
+/// Outcome of a [`ToolAgent`] run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRun {
+    pub answer: String,
+    pub steps: Vec<ToolStep>,
+}
+
+/// Parsed model reply
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Action {
+        thought: Option<String>,
+        tool: String,
+        input: String,
+    },
+    Final(String),
+}
+
+pub struct ToolAgent {
+    config: AgentConfig,
+    provider: Arc<dyn AIProvider>,
+    model: String,
+    tools: BTreeMap<String, Tool>,
+}
+
+impl ToolAgent {
+    pub fn new(
+        config: AgentConfig,
+        provider: Arc<dyn AIProvider>,
+        model: impl Into<String>,
+    ) -> Self {
+        ToolAgent {
+            config,
+            provider,
+            model: model.into(),
+            tools: BTreeMap::new(),
+        }
+    }
+
+    /// Register a named tool
+    pub fn with_tool(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        run: impl Fn(&str) -> Result<String, AIError> + Send + Sync + 'static,
+    ) -> Self {
+        self.tools.insert(
+            name.into(),
+            Tool {
+                description: description.into(),
+                run: Box::new(run),
+            },
+        );
+        self
+    }
+
+    /// Run the reason-act loop for a task
+    pub async fn run(&self, task: &str) -> Result<ToolRun, AIError> {
+        let mut messages = vec![Message {
+            role: MessageRole::User,
+            content: task.to_string(),
+            name: None,
+        }];
+        let mut steps = Vec::new();
+
+        for _ in 0..self.config.max_iterations {
+            let response = self
+                .provider
+                .send_prompt(PromptRequest {
+                    model: self.model.clone(),
+                    system_prompt: Some(self.system_prompt()),
+                    messages: messages.clone(),
+                    temperature: Some(0.0),
+                    max_tokens: None,
+                    stop_sequences: Some(vec!["Observation:".to_string()]),
+                    parameters: HashMap::new(),
+                    metadata: RequestMetadata::default(),
+                })
+                .await
+                .map_err(|e| AIError::GenericError(e.to_string()))?;
+
+            let (thought, tool, input) = match parse_reply(&response.content) {
+                Reply::Final(answer) => return Ok(ToolRun { answer, steps }),
+                Reply::Action {
+                    thought,
+                    tool,
+                    input,
+                } => (thought, tool, input),
+            };
+
+            let observation = match self.tools.get(&tool) {
+                Some(t) => (t.run)(&input).unwrap_or_else(|e| format!("Error: {}", e)),
+                None => format!(
+                    "Unknown tool: {}. Available tools: {}",
+                    tool,
+                    self.tools.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            };
+
+            messages.push(Message {
+                role: MessageRole::Assistant,
+                content: response.content,
+                name: None,
+            });
+            messages.push(Message {
+                role: MessageRole::User,
+                content: format!("Observation: {}", observation),
+                name: None,
+            });
+            steps.push(ToolStep {
+                thought,
+                tool,
+                input,
+                observation,
+            });
+        }
+
+        Err(AIError::GenericError(format!(
+            "Agent {} gave no final answer after {} iterations",
+            self.config.name, self.config.max_iterations
+        )))
+    }
+
+    fn system_prompt(&self) -> String {
+        let tools: Vec<String> = self
+            .tools
+            .iter()
+            .map(|(name, tool)| format!("- {}: {}", name, tool.description))
+            .collect();
+
+        let values = HashMap::from([("tools".to_string(), tools.join("\n"))]);
+        PromptTemplate::new(SYSTEM_PROMPT)
+            .and_then(|template| template.render(&values))
+            .expect("built-in ReAct prompt template is valid")
+    }
+}
+
+impl Agent for ToolAgent {
+    fn get_config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    /// Blocking wrapper around [`ToolAgent::run`]
+    ///
+    /// Inside a multi-threaded Tokio runtime this blocks the current worker;
+    /// a current-thread runtime cannot be blocked, so callers there must use
+    /// `run` directly.
+    fn execute(&self, input: &str) -> Result<String, AIError> {
+        use tokio::runtime::{Handle, RuntimeFlavor};
+
+        let run = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(self.run(input)))
+            }
+            Ok(_) => {
+                return Err(AIError::GenericError(
+                    "ToolAgent cannot block a current-thread runtime; call run() instead"
+                        .to_string(),
+                ))
+            }
+            Err(_) => tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(self.run(input)),
+        };
+
+        run.map(|run| run.answer)
+    }
+
+    fn can_handle(&self, _task: &str) -> bool {
+        !self.tools.is_empty()
+    }
+}
+
+/// Parse a ReAct reply; anything without an action is treated as the answer
+fn parse_reply(content: &str) -> Reply {
+    if let Some((_, answer)) = content.split_once("Final Answer:") {
+        return Reply::Final(answer.trim().to_string());
+    }
+
+    let mut thought = None;
+    let mut tool = None;
+    let mut input = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("Thought:") {
+            thought = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Action Input:") {
+            input = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Action:") {
+            tool = Some(value.trim().to_string());
+        }
+    }
+
+    match tool {
+        Some(tool) => Reply::Action {
+            thought,
+            tool,
+            input: input.unwrap_or_default(),
+        },
+        None => Reply::Final(content.trim().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::provider::{
+        FinishReason, HealthStatus, ModelInfo, PromptResponse, ProviderError, ProviderResult,
+        ResponseMetadata, ResponseStream, TokenUsage,
+    };
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Provider replaying scripted replies and recording the requests it saw
+    struct ScriptedProvider {
+        replies: Mutex<VecDeque<String>>,
+        requests: Mutex<Vec<PromptRequest>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(replies: &[&str]) -> Self {
+            Self {
+                replies: Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AIProvider for ScriptedProvider {
+        async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            let content = self
+                .replies
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| ProviderError::GenericError("script exhausted".to_string()))?;
+            Ok(PromptResponse {
+                content,
+                model: request.model,
+                usage: TokenUsage::empty(),
+                finish_reason: FinishReason::Stop,
+                metadata: ResponseMetadata {
+                    request_id: request.metadata.request_id,
+                    timestamp: chrono::Utc::now(),
+                    latency_ms: 0,
+                    cost: None,
+                },
+            })
+        }
+
+        async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+            Err(ProviderError::GenericError("Not implemented".to_string()))
+        }
+
+        async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+
+        async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+            Ok(HealthStatus::healthy(0))
+        }
+
+        fn name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    fn calculator(input: &str) -> Result<String, AIError> {
+        let (a, b) = input
+            .split_once('+')
+            .ok_or_else(|| AIError::GenericError(format!("cannot evaluate {}", input)))?;
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<f64>()
+                .map_err(|e| AIError::GenericError(e.to_string()))
+        };
+        Ok((parse(a)? + parse(b)?).to_string())
+    }
+
+    fn agent(provider: Arc<ScriptedProvider>, max_iterations: u32) -> ToolAgent {
+        let config = AgentConfig {
+            name: "math".to_string(),
+            description: "Does arithmetic".to_string(),
+            capabilities: vec!["math".to_string()],
+            max_iterations,
+        };
+        ToolAgent::new(config, provider, "test-model").with_tool(
+            "calculator",
+            "Adds two numbers, e.g. '2 + 3'",
+            calculator,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tool_agent_uses_calculator() {
+        let provider = Arc::new(ScriptedProvider::new(&[
+            "Thought: I should add them\nAction: calculator\nAction Input: 2 + 3",
+            "Thought: Now double it\nAction: calculator\nAction Input: 5 + 5",
+            "Final Answer: 10",
+        ]));
+
+        let run = agent(provider.clone(), 5)
+            .run("What is (2 + 3) * 2?")
+            .await
+            .unwrap();
+
+        assert_eq!(run.answer, "10");
+        assert_eq!(run.steps.len(), 2);
+        assert_eq!(run.steps[0].thought.as_deref(), Some("I should add them"));
+        assert_eq!(run.steps[0].observation, "5");
+        assert_eq!(run.steps[1].observation, "10");
+
+        let requests = provider.requests.lock().unwrap();
+        assert!(requests[0]
+            .system_prompt
+            .as_ref()
+            .unwrap()
+            .contains("- calculator: Adds two numbers"));
+        let last = requests[2].messages.last().unwrap();
+        assert_eq!(last.content, "Observation: 10");
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_are_observed() {
+        let provider = Arc::new(ScriptedProvider::new(&[
+            "Action: calculator\nAction Input: two plus three",
+            "Action: search\nAction Input: anything",
+            "Final Answer: unknown",
+        ]));
+
+        let run = agent(provider, 5).run("task").await.unwrap();
+
+        assert!(run.steps[0].observation.starts_with("Error:"));
+        assert!(run.steps[1].observation.starts_with("Unknown tool: search"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_agent_iteration_limit() {
+        let provider = Arc::new(ScriptedProvider::new(&[
+            "Action: calculator\nAction Input: 1 + 1",
+            "Action: calculator\nAction Input: 1 + 1",
+        ]));
+
+        let err = agent(provider, 2).run("loop").await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no final answer after 2 iterations"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_execute_blocks_on_multi_thread_runtime() {
+        let provider = Arc::new(ScriptedProvider::new(&["Final Answer: 42"]));
+
+        assert_eq!(agent(provider, 1).execute("answer").unwrap(), "42");
+    }
+
+    #[test]
+    fn test_parse_reply_without_action_is_final() {
+        assert_eq!(
+            parse_reply("The answer is 4."),
+            Reply::Final("The answer is 4.".to_string())
+        );
+    }
+}
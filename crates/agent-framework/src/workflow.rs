@@ -9,10 +9,16 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+
+/// Events buffered for each subscriber before the slowest starts missing them
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
 
 /// Workflow error types
 #[derive(Error, Debug)]
@@ -31,6 +37,12 @@ pub enum WorkflowError {
 
     #[error("Serialization error: {0}")]
     SerializationError(String),
+
+    #[error("Workflow exceeded {0} iterations without reaching a terminal state")]
+    IterationLimitExceeded(u32),
+
+    #[error("Workflow timed out after {0:?}")]
+    Timeout(Duration),
 }
 
 pub type WorkflowResult<T> = Result<T, WorkflowError>;
@@ -47,16 +59,31 @@ pub enum WorkflowState {
     Custom(String),
 }
 
-impl ToString for WorkflowState {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for WorkflowState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Pending => "pending".to_string(),
-            Self::Running => "running".to_string(),
-            Self::Completed => "completed".to_string(),
-            Self::Failed => "failed".to_string(),
-            Self::Cancelled => "cancelled".to_string(),
-            Self::Paused => "paused".to_string(),
-            Self::Custom(s) => s.clone(),
+            Self::Pending => write!(f, "pending"),
+            Self::Running => write!(f, "running"),
+            Self::Completed => write!(f, "completed"),
+            Self::Failed => write!(f, "failed"),
+            Self::Cancelled => write!(f, "cancelled"),
+            Self::Paused => write!(f, "paused"),
+            Self::Custom(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<&str> for WorkflowState {
+    /// Parse a state as displayed; any other name is a custom state
+    fn from(name: &str) -> Self {
+        match name {
+            "pending" => Self::Pending,
+            "running" => Self::Running,
+            "completed" => Self::Completed,
+            "failed" => Self::Failed,
+            "cancelled" => Self::Cancelled,
+            "paused" => Self::Paused,
+            other => Self::Custom(other.to_string()),
         }
     }
 }
@@ -155,7 +182,84 @@ pub struct Workflow {
     context: Arc<RwLock<WorkflowContext>>,
     handlers: Arc<RwLock<HashMap<String, Arc<dyn StateHandler>>>>,
     transitions: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    transition_labels: Arc<RwLock<HashMap<(String, String), String>>>,
     history: Arc<RwLock<Vec<StateTransition>>>,
+    events: broadcast::Sender<WorkflowEvent>,
+}
+
+/// Output format for workflow graphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid `stateDiagram-v2`
+    Mermaid,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            other => Err(format!(
+                "unknown graph format '{}'; expected dot or mermaid",
+                other
+            )),
+        }
+    }
+}
+
+/// A workflow's states and transitions, as kept in a JSON file
+///
+/// States are named as they are displayed (`pending`, `running`, ...);
+/// any other name is a custom state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowDefinition {
+    pub id: String,
+    pub initial: String,
+    #[serde(default)]
+    pub transitions: Vec<TransitionDefinition>,
+}
+
+/// An allowed transition in a [`WorkflowDefinition`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionDefinition {
+    pub from: String,
+    pub to: String,
+    /// When the transition is taken, shown as the edge label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+impl WorkflowDefinition {
+    /// A workflow in the initial state with the defined transitions and no
+    /// handlers
+    pub async fn build(&self) -> Workflow {
+        let workflow = Workflow::new(&self.id, WorkflowState::from(self.initial.as_str()));
+        for transition in &self.transitions {
+            let from = WorkflowState::from(transition.from.as_str());
+            let to = WorkflowState::from(transition.to.as_str());
+            match &transition.condition {
+                Some(condition) => {
+                    workflow
+                        .add_conditional_transition(from, to, condition)
+                        .await
+                }
+                None => workflow.add_transition(from, to).await,
+            }
+        }
+        workflow
+    }
+}
+
+/// Snapshot of the workflow structure used for rendering graphs
+struct GraphSnapshot {
+    states: BTreeSet<String>,
+    edges: Vec<(String, String, Option<String>)>,
+    current: String,
+    traversed: HashSet<(String, String)>,
 }
 
 /// State transition record
@@ -176,10 +280,26 @@ impl Workflow {
             context: Arc::new(RwLock::new(WorkflowContext::new(workflow_id))),
             handlers: Arc::new(RwLock::new(HashMap::new())),
             transitions: Arc::new(RwLock::new(HashMap::new())),
+            transition_labels: Arc::new(RwLock::new(HashMap::new())),
             history: Arc::new(RwLock::new(Vec::new())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Receive the events the workflow emits from now on
+    ///
+    /// The workflow emits `transition` on every state change, `aborted` when
+    /// a run is cut short and `finished` when a run ends. The channel closes
+    /// when the workflow is dropped.
+    pub fn subscribe(&self) -> broadcast::Receiver<WorkflowEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send `event` to every subscriber; a no-op when there are none
+    pub fn emit(&self, event: WorkflowEvent) {
+        let _ = self.events.send(event);
+    }
+
     /// Register a state handler
     pub async fn register_handler(&self, handler: Arc<dyn StateHandler>) {
         let state = handler.state().to_string();
@@ -195,10 +315,27 @@ impl Workflow {
             .write()
             .await
             .entry(from_str)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(to_str);
     }
 
+    /// Define an allowed transition with a condition label
+    ///
+    /// The label describes when the transition is taken and appears on the
+    /// edge in exported graphs.
+    pub async fn add_conditional_transition(
+        &self,
+        from: WorkflowState,
+        to: WorkflowState,
+        condition: impl Into<String>,
+    ) {
+        self.transition_labels
+            .write()
+            .await
+            .insert((from.to_string(), to.to_string()), condition.into());
+        self.add_transition(from, to).await;
+    }
+
     /// Get current state
     pub async fn current_state(&self) -> WorkflowState {
         self.current_state.read().await.clone()
@@ -219,17 +356,21 @@ impl Workflow {
         // Exit current state
         if let Some(handler) = self.handlers.read().await.get(&current.to_string()) {
             let mut ctx = self.context.write().await;
-            handler.on_exit(&mut *ctx).await?;
+            handler.on_exit(&mut ctx).await?;
         }
 
         // Record transition
+        let event = WorkflowEvent::new("transition")
+            .with_data("from", serde_json::Value::String(current.to_string()))
+            .with_data("to", serde_json::Value::String(new_state.to_string()));
         let transition = StateTransition {
             from: current.to_string(),
             to: new_state.to_string(),
-            timestamp: Utc::now(),
-            event: None,
+            timestamp: event.timestamp,
+            event: Some(event.clone()),
         };
         self.history.write().await.push(transition);
+        self.emit(event);
 
         // Update state
         *self.current_state.write().await = new_state.clone();
@@ -237,7 +378,7 @@ impl Workflow {
         // Enter new state
         if let Some(handler) = self.handlers.read().await.get(&new_state.to_string()) {
             let mut ctx = self.context.write().await;
-            handler.on_enter(&mut *ctx).await?;
+            handler.on_enter(&mut ctx).await?;
         }
 
         Ok(())
@@ -254,21 +395,52 @@ impl Workflow {
             .await
             .get(&state_str)
             .cloned()
-            .ok_or_else(|| WorkflowError::StateNotFound(state_str))?;
+            .ok_or(WorkflowError::StateNotFound(state_str))?;
 
         // Validate before execution
         {
             let ctx = self.context.read().await;
-            handler.validate(&*ctx).await?;
+            handler.validate(&ctx).await?;
         }
 
         // Execute handler
         let mut ctx = self.context.write().await;
-        handler.execute(&mut *ctx).await
+        handler.execute(&mut ctx).await
     }
 
     /// Run workflow until completion
-    pub async fn run(&self) -> WorkflowResult<()> {
+    ///
+    /// Each handler execution counts as one iteration, whether or not it changes
+    /// state. Exceeding `max_iterations` or the overall `timeout` moves the
+    /// workflow to [`WorkflowState::Failed`] and returns the corresponding error.
+    pub async fn run(&self, max_iterations: u32, timeout: Option<Duration>) -> WorkflowResult<()> {
+        let result = match timeout {
+            Some(limit) => tokio::time::timeout(limit, self.run_until_terminal(max_iterations))
+                .await
+                .unwrap_or(Err(WorkflowError::Timeout(limit))),
+            None => self.run_until_terminal(max_iterations).await,
+        };
+
+        if let Err(ref e @ (WorkflowError::IterationLimitExceeded(_) | WorkflowError::Timeout(_))) =
+            result
+        {
+            self.force_state(WorkflowState::Failed, e).await;
+        }
+
+        let mut finished = WorkflowEvent::new("finished").with_data(
+            "state",
+            serde_json::Value::String(self.current_state().await.to_string()),
+        );
+        if let Err(e) = &result {
+            finished = finished.with_data("error", serde_json::Value::String(e.to_string()));
+        }
+        self.emit(finished);
+
+        result
+    }
+
+    async fn run_until_terminal(&self, max_iterations: u32) -> WorkflowResult<()> {
+        let mut iterations = 0;
         loop {
             let current = self.current_state().await;
 
@@ -279,6 +451,11 @@ impl Workflow {
                 _ => {}
             }
 
+            if iterations >= max_iterations {
+                return Err(WorkflowError::IterationLimitExceeded(max_iterations));
+            }
+            iterations += 1;
+
             let next_state = self.execute().await?;
 
             if next_state != current {
@@ -289,6 +466,21 @@ impl Workflow {
         Ok(())
     }
 
+    /// Move to a state without validating the transition, recording why
+    async fn force_state(&self, state: WorkflowState, reason: &WorkflowError) {
+        let mut current = self.current_state.write().await;
+        let event = WorkflowEvent::new("aborted")
+            .with_data("reason", serde_json::Value::String(reason.to_string()));
+        self.history.write().await.push(StateTransition {
+            from: current.to_string(),
+            to: state.to_string(),
+            timestamp: event.timestamp,
+            event: Some(event.clone()),
+        });
+        *current = state;
+        self.emit(event);
+    }
+
     /// Check if transition is allowed
     async fn is_transition_allowed(&self, from: &WorkflowState, to: &WorkflowState) -> bool {
         let transitions = self.transitions.read().await;
@@ -315,6 +507,164 @@ impl Workflow {
     pub fn id(&self) -> &str {
         &self.id
     }
+
+    async fn graph_snapshot(&self) -> GraphSnapshot {
+        let transitions = self.transitions.read().await;
+        let labels = self.transition_labels.read().await;
+        let current = self.current_state.read().await.to_string();
+
+        let mut states: BTreeSet<String> = self.handlers.read().await.keys().cloned().collect();
+        states.insert(current.clone());
+
+        let mut edges = Vec::new();
+        for (from, targets) in transitions.iter() {
+            states.insert(from.clone());
+            for to in targets {
+                states.insert(to.clone());
+                let label = labels.get(&(from.clone(), to.clone())).cloned();
+                edges.push((from.clone(), to.clone(), label));
+            }
+        }
+        edges.sort();
+        edges.dedup();
+
+        let traversed = self
+            .history
+            .read()
+            .await
+            .iter()
+            .map(|t| (t.from.clone(), t.to.clone()))
+            .collect();
+
+        GraphSnapshot {
+            states,
+            edges,
+            current,
+            traversed,
+        }
+    }
+
+    /// Render the workflow as a Graphviz DOT digraph
+    ///
+    /// The current state is filled and transitions already taken are drawn bold.
+    pub async fn to_dot(&self) -> String {
+        let graph = self.graph_snapshot().await;
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut out = format!("digraph {} {{\n    rankdir=LR;\n", quote(&self.id));
+        for state in &graph.states {
+            if *state == graph.current {
+                let _ = writeln!(
+                    out,
+                    "    {} [style=filled, fillcolor=lightblue];",
+                    quote(state)
+                );
+            } else {
+                let _ = writeln!(out, "    {};", quote(state));
+            }
+        }
+        for (from, to, label) in &graph.edges {
+            let mut attrs = Vec::new();
+            if let Some(label) = label {
+                attrs.push(format!("label={}", quote(label)));
+            }
+            if graph.traversed.contains(&(from.clone(), to.clone())) {
+                attrs.push("color=blue, penwidth=2".to_string());
+            }
+            let attrs = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attrs.join(", "))
+            };
+            let _ = writeln!(out, "    {} -> {}{};", quote(from), quote(to), attrs);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the workflow as a Mermaid `stateDiagram-v2`
+    ///
+    /// The current state gets the `current` class and transitions already
+    /// taken are suffixed with `(taken)`.
+    pub async fn to_mermaid(&self) -> String {
+        let graph = self.graph_snapshot().await;
+        let ids = mermaid_ids(&graph.states);
+        let mermaid_id = |state: &str| ids.get(state).cloned().unwrap_or_default();
+
+        let mut out = String::from("stateDiagram-v2\n");
+        for state in &graph.states {
+            let id = &ids[state.as_str()];
+            if id != state {
+                let _ = writeln!(out, "    state \"{}\" as {}", state.replace('"', "'"), id);
+            }
+        }
+        for (from, to, label) in &graph.edges {
+            let taken = graph.traversed.contains(&(from.clone(), to.clone()));
+            let label = match (label, taken) {
+                (Some(label), true) => format!(" : {} (taken)", label),
+                (Some(label), false) => format!(" : {}", label),
+                (None, true) => " : (taken)".to_string(),
+                (None, false) => String::new(),
+            };
+            let _ = writeln!(
+                out,
+                "    {} --> {}{}",
+                mermaid_id(from),
+                mermaid_id(to),
+                label
+            );
+        }
+        out.push_str("    classDef current fill:#bde0fe,stroke:#1d3557\n");
+        let _ = writeln!(out, "    class {} current", mermaid_id(&graph.current));
+        out
+    }
+
+    /// Write the workflow graph to a file
+    pub async fn write_graph(
+        &self,
+        path: impl AsRef<Path>,
+        format: GraphFormat,
+    ) -> WorkflowResult<()> {
+        let graph = match format {
+            GraphFormat::Dot => self.to_dot().await,
+            GraphFormat::Mermaid => self.to_mermaid().await,
+        };
+        tokio::fs::write(path, graph)
+            .await
+            .map_err(|e| WorkflowError::ExecutionError(e.to_string()))
+    }
+}
+
+/// A distinct Mermaid state ID for each state
+///
+/// IDs must be plain identifiers. States that already are one keep their
+/// name; others are sanitized, with their index appended when that clashes.
+fn mermaid_ids(states: &BTreeSet<String>) -> HashMap<&str, String> {
+    let is_plain = |state: &str| state.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let mut ids: HashMap<&str, String> = states
+        .iter()
+        .filter(|state| is_plain(state))
+        .map(|state| (state.as_str(), state.clone()))
+        .collect();
+    let mut used: HashSet<String> = ids.values().cloned().collect();
+    for (index, state) in states.iter().enumerate() {
+        if ids.contains_key(state.as_str()) {
+            continue;
+        }
+        let base: String = state
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let mut id = base.clone();
+        let mut suffix = index;
+        while used.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        used.insert(id.clone());
+        ids.insert(state, id);
+    }
+    ids
 }
 
 #[cfg(test)]
@@ -388,4 +738,149 @@ mod tests {
         let next_state = workflow.execute().await.unwrap();
         assert_eq!(next_state, WorkflowState::Running);
     }
+
+    #[tokio::test]
+    async fn test_run_reaches_terminal_state() {
+        let workflow = Workflow::new("test", WorkflowState::Pending);
+        workflow
+            .register_handler(Arc::new(TestHandler {
+                state: WorkflowState::Pending,
+                next_state: WorkflowState::Completed,
+            }))
+            .await;
+
+        workflow.run(10, None).await.unwrap();
+        assert_eq!(workflow.current_state().await, WorkflowState::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_run_iteration_limit() {
+        let workflow = Workflow::new("test", WorkflowState::Running);
+        workflow
+            .register_handler(Arc::new(TestHandler {
+                state: WorkflowState::Running,
+                next_state: WorkflowState::Running,
+            }))
+            .await;
+
+        let err = workflow.run(5, None).await.unwrap_err();
+        assert!(matches!(err, WorkflowError::IterationLimitExceeded(5)));
+        assert_eq!(workflow.current_state().await, WorkflowState::Failed);
+
+        let history = workflow.history().await;
+        assert_eq!(history.last().unwrap().to, "failed");
+    }
+
+    struct SlowHandler;
+
+    #[async_trait]
+    impl StateHandler for SlowHandler {
+        async fn execute(&self, _context: &mut WorkflowContext) -> WorkflowResult<WorkflowState> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(WorkflowState::Completed)
+        }
+
+        fn state(&self) -> WorkflowState {
+            WorkflowState::Running
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_timeout() {
+        let workflow = Workflow::new("test", WorkflowState::Running);
+        workflow.register_handler(Arc::new(SlowHandler)).await;
+
+        let err = workflow
+            .run(10, Some(Duration::from_millis(20)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WorkflowError::Timeout(_)));
+        assert_eq!(workflow.current_state().await, WorkflowState::Failed);
+    }
+
+    async fn graph_workflow() -> Workflow {
+        let workflow = Workflow::new("review", WorkflowState::Pending);
+        workflow
+            .add_transition(WorkflowState::Pending, WorkflowState::Running)
+            .await;
+        workflow
+            .add_conditional_transition(
+                WorkflowState::Running,
+                WorkflowState::Custom("needs review".to_string()),
+                "changes > 10",
+            )
+            .await;
+        workflow
+            .add_transition(WorkflowState::Running, WorkflowState::Completed)
+            .await;
+        workflow.transition(WorkflowState::Running).await.unwrap();
+        workflow
+    }
+
+    #[tokio::test]
+    async fn test_workflow_to_dot() {
+        let dot = graph_workflow().await.to_dot().await;
+
+        assert!(dot.starts_with("digraph \"review\" {"));
+        assert!(dot.contains("\"running\" [style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("\"pending\" -> \"running\" [color=blue, penwidth=2];"));
+        assert!(dot.contains("\"running\" -> \"needs review\" [label=\"changes > 10\"];"));
+        assert!(dot.contains("\"running\" -> \"completed\";"));
+    }
+
+    #[tokio::test]
+    async fn test_definition_builds_graph() {
+        let definition: WorkflowDefinition = serde_json::from_str(
+            r#"{
+                "id": "review",
+                "initial": "pending",
+                "transitions": [
+                    {"from": "pending", "to": "running"},
+                    {"from": "running", "to": "needs review", "condition": "changes > 10"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let workflow = definition.build().await;
+
+        assert_eq!(workflow.current_state().await, WorkflowState::Pending);
+        let dot = workflow.to_dot().await;
+        assert!(dot.contains("\"pending\" [style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("\"running\" -> \"needs review\" [label=\"changes > 10\"];"));
+        assert_eq!("Mermaid".parse(), Ok(GraphFormat::Mermaid));
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_to_mermaid() {
+        let mermaid = graph_workflow().await.to_mermaid().await;
+
+        assert!(mermaid.starts_with("stateDiagram-v2\n"));
+        assert!(mermaid.contains("state \"needs review\" as needs_review"));
+        assert!(mermaid.contains("pending --> running : (taken)"));
+        assert!(mermaid.contains("running --> needs_review : changes > 10"));
+        assert!(mermaid.contains("class running current"));
+    }
+
+    #[tokio::test]
+    async fn test_mermaid_ids_are_distinct() {
+        let workflow = Workflow::new("clash", WorkflowState::Custom("a-b".to_string()));
+        for (from, to) in [("a-b", "a_b"), ("a_b", "a b"), ("a b", "a_b_2")] {
+            workflow
+                .add_transition(
+                    WorkflowState::Custom(from.to_string()),
+                    WorkflowState::Custom(to.to_string()),
+                )
+                .await;
+        }
+        let mermaid = workflow.to_mermaid().await;
+
+        // "a b" sorts first; "a_b" and "a_b_2" keep their names
+        assert!(mermaid.contains("state \"a b\" as a_b_0\n"), "{}", mermaid);
+        assert!(mermaid.contains("state \"a-b\" as a_b_1\n"), "{}", mermaid);
+        assert!(mermaid.contains("a_b_1 --> a_b\n"), "{}", mermaid);
+        assert!(mermaid.contains("a_b --> a_b_0\n"), "{}", mermaid);
+        assert!(mermaid.contains("a_b_0 --> a_b_2\n"), "{}", mermaid);
+        assert!(mermaid.contains("class a_b_1 current"), "{}", mermaid);
+    }
 }
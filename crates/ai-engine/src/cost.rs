@@ -0,0 +1,390 @@
+//! Session token and cost accounting
+
+use crate::provider::{
+    ModelPricing, PromptRequest, ProviderResult, ResponseStream, StreamChunk, TokenUsage,
+};
+use ai_cli_utils::error::{AIError, Result};
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Running totals of the tokens used in a session and what they cost
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    pricing: Option<ModelPricing>,
+    usage: TokenUsage,
+    requests: u64,
+    cost: f64,
+    budget_limit: Option<f64>,
+}
+
+impl CostTracker {
+    /// Tracker for a model whose price is unknown; cost is not estimated
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracker that estimates cost from `pricing`
+    pub fn with_pricing(pricing: ModelPricing) -> Self {
+        Self {
+            pricing: Some(pricing),
+            ..Self::default()
+        }
+    }
+
+    /// Change the price applied to future responses, e.g. after a model switch
+    pub fn set_pricing(&mut self, pricing: Option<ModelPricing>) {
+        self.pricing = pricing;
+    }
+
+    /// Refuse requests that would take the cost past `limit`
+    pub fn with_budget_limit(mut self, limit: Option<f64>) -> Self {
+        self.budget_limit = limit;
+        self
+    }
+
+    /// Get the budget, in the pricing's currency
+    pub fn budget_limit(&self) -> Option<f64> {
+        self.budget_limit
+    }
+
+    /// Fail if a request with `prompt_tokens` would take the cost past the
+    /// budget
+    ///
+    /// Only the prompt is known before a request is sent, so the reply is
+    /// counted once it is recorded. Without pricing the cost is unknown and
+    /// nothing is refused.
+    pub fn check_budget(&self, prompt_tokens: u32) -> Result<()> {
+        let (Some(limit), Some(pricing)) = (self.budget_limit, &self.pricing) else {
+            return Ok(());
+        };
+        let next = f64::from(prompt_tokens) / 1000.0 * pricing.prompt_price_per_1k;
+        if self.cost + next > limit {
+            return Err(AIError::GenericError("budget exceeded".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Add one response's usage to the totals
+    pub fn record(&mut self, usage: &TokenUsage) {
+        self.usage.prompt_tokens += usage.prompt_tokens;
+        self.usage.completion_tokens += usage.completion_tokens;
+        self.usage.total_tokens += usage.total_tokens;
+        self.requests += 1;
+        if let Some(pricing) = &self.pricing {
+            self.cost += f64::from(usage.prompt_tokens) / 1000.0 * pricing.prompt_price_per_1k
+                + f64::from(usage.completion_tokens) / 1000.0 * pricing.completion_price_per_1k;
+        }
+    }
+
+    /// Tokens used so far
+    pub fn usage(&self) -> &TokenUsage {
+        &self.usage
+    }
+
+    /// Number of responses recorded
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Estimated cost and its currency, if the price is known
+    pub fn estimated_cost(&self) -> Option<(f64, &str)> {
+        self.pricing
+            .as_ref()
+            .map(|pricing| (self.cost, pricing.currency.as_str()))
+    }
+}
+
+/// Rough token count for `text`, for providers that do not report usage
+///
+/// Uses the common estimate of four characters per token.
+pub fn estimate_tokens(text: &str) -> u32 {
+    u32::try_from(text.chars().count().div_ceil(4)).unwrap_or(u32::MAX)
+}
+
+/// Rough token count of everything `request` sends: the system prompt and
+/// every message
+pub fn estimate_prompt_tokens(request: &PromptRequest) -> u32 {
+    request
+        .system_prompt
+        .iter()
+        .map(String::as_str)
+        .chain(request.messages.iter().map(|m| m.content.as_str()))
+        .map(estimate_tokens)
+        .fold(0u32, u32::saturating_add)
+}
+
+/// Counts the tokens a model would see in some text
+pub trait Tokenizer: Send + Sync {
+    fn count(&self, text: &str) -> u32;
+
+    /// Short name shown next to counts, so estimates are labelled as such
+    fn name(&self) -> &str;
+}
+
+/// [`Tokenizer`] built on [`estimate_tokens`]; approximate for any model
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimatingTokenizer;
+
+impl Tokenizer for EstimatingTokenizer {
+    fn count(&self, text: &str) -> u32 {
+        estimate_tokens(text)
+    }
+
+    fn name(&self) -> &str {
+        "estimate"
+    }
+}
+
+/// Token usage of a streamed response, summed from its chunks
+///
+/// Falls back to [`estimate_tokens`] when no chunk reports usage.
+#[derive(Debug, Clone, Default)]
+pub struct StreamUsage {
+    prompt_estimate: u32,
+    completion_estimate: u32,
+    reported: Option<TokenUsage>,
+}
+
+impl StreamUsage {
+    /// Usage for the response to `request`, starting from nothing
+    pub fn new(request: &PromptRequest) -> Self {
+        Self {
+            prompt_estimate: estimate_prompt_tokens(request),
+            ..Self::default()
+        }
+    }
+
+    /// Account for one chunk
+    pub fn observe(&mut self, chunk: &StreamChunk) {
+        self.completion_estimate = self
+            .completion_estimate
+            .saturating_add(estimate_tokens(&chunk.content));
+        if let Some(usage) = &chunk.usage {
+            let total = self.reported.get_or_insert_with(TokenUsage::empty);
+            total.prompt_tokens += usage.prompt_tokens;
+            total.completion_tokens += usage.completion_tokens;
+            total.total_tokens += usage.total_tokens;
+        }
+    }
+
+    /// Whether the usage is estimated rather than reported by the provider
+    pub fn is_estimated(&self) -> bool {
+        self.reported.is_none()
+    }
+
+    /// Usage so far
+    pub fn usage(&self) -> TokenUsage {
+        self.reported
+            .clone()
+            .unwrap_or_else(|| TokenUsage::new(self.prompt_estimate, self.completion_estimate))
+    }
+}
+
+/// Response stream that records its token usage in a [`CostTracker`]
+///
+/// Usage is recorded once, when the stream ends, fails, or is dropped
+/// before finishing, so a response cut short still counts what it used.
+pub struct UsageStream {
+    inner: ResponseStream,
+    usage: StreamUsage,
+    tracker: Arc<Mutex<CostTracker>>,
+    recorded: bool,
+}
+
+impl UsageStream {
+    pub fn new(
+        inner: ResponseStream,
+        request: &PromptRequest,
+        tracker: Arc<Mutex<CostTracker>>,
+    ) -> Self {
+        Self {
+            inner,
+            usage: StreamUsage::new(request),
+            tracker,
+            recorded: false,
+        }
+    }
+
+    /// Usage of the chunks received so far; final once the stream has ended
+    pub fn usage(&self) -> TokenUsage {
+        self.usage.usage()
+    }
+
+    fn record(&mut self) {
+        if !self.recorded {
+            self.recorded = true;
+            let mut tracker = self.tracker.lock().unwrap_or_else(|e| e.into_inner());
+            tracker.record(&self.usage.usage());
+        }
+    }
+}
+
+impl Stream for UsageStream {
+    type Item = ProviderResult<StreamChunk>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = self.inner.as_mut().poll_next(cx);
+        match &item {
+            Poll::Ready(Some(Ok(chunk))) => self.usage.observe(chunk),
+            Poll::Ready(Some(Err(_)) | None) => self.record(),
+            Poll::Pending => {}
+        }
+        item
+    }
+}
+
+impl Drop for UsageStream {
+    fn drop(&mut self) {
+        self.record();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{FinishReason, Message, MessageRole, ProviderError, RequestMetadata};
+    use futures::StreamExt;
+    use std::collections::HashMap;
+
+    fn request(prompt: &str) -> PromptRequest {
+        PromptRequest {
+            model: "mock".to_string(),
+            system_prompt: None,
+            messages: vec![Message {
+                role: MessageRole::User,
+                content: prompt.to_string(),
+                name: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            parameters: HashMap::new(),
+            metadata: RequestMetadata::default(),
+        }
+    }
+
+    fn chunk(content: &str, usage: Option<TokenUsage>) -> ProviderResult<StreamChunk> {
+        Ok(StreamChunk {
+            content: content.to_string(),
+            finish_reason: usage.as_ref().map(|_| FinishReason::Stop),
+            usage,
+        })
+    }
+
+    /// What a mock streaming provider returns
+    fn mock_stream(chunks: Vec<ProviderResult<StreamChunk>>) -> ResponseStream {
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    #[test]
+    fn test_accumulates_usage_and_cost() {
+        let mut tracker = CostTracker::with_pricing(ModelPricing {
+            prompt_price_per_1k: 0.01,
+            completion_price_per_1k: 0.03,
+            currency: "USD".to_string(),
+        });
+        tracker.record(&TokenUsage::new(1000, 500));
+        tracker.record(&TokenUsage::new(2000, 0));
+
+        assert_eq!(tracker.usage().total_tokens, 3500);
+        assert_eq!(tracker.requests(), 2);
+        let (cost, currency) = tracker.estimated_cost().unwrap();
+        assert!((cost - 0.045).abs() < 1e-9);
+        assert_eq!(currency, "USD");
+    }
+
+    #[test]
+    fn test_unknown_pricing_has_no_cost() {
+        let mut tracker = CostTracker::new();
+        tracker.record(&TokenUsage::new(10, 10));
+        assert_eq!(tracker.usage().total_tokens, 20);
+        assert!(tracker.estimated_cost().is_none());
+    }
+
+    #[test]
+    fn test_refuses_requests_past_budget() {
+        let mut tracker = CostTracker::with_pricing(ModelPricing {
+            prompt_price_per_1k: 0.01,
+            completion_price_per_1k: 0.03,
+            currency: "USD".to_string(),
+        })
+        .with_budget_limit(Some(0.055));
+
+        // Each exchange costs 0.01 for the prompt and 0.015 for the reply
+        let prompt = estimate_prompt_tokens(&request(&"x".repeat(4000)));
+        assert_eq!(prompt, 1000);
+        for _ in 0..2 {
+            tracker.check_budget(prompt).unwrap();
+            tracker.record(&TokenUsage::new(prompt, 500));
+        }
+
+        // 0.05 spent, so another 0.01 prompt is refused but a short one fits
+        let err = tracker.check_budget(prompt).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: budget exceeded");
+        assert!(tracker.check_budget(100).is_ok());
+
+        // Without a price there is nothing to hold the budget against
+        let unpriced = CostTracker::new().with_budget_limit(Some(0.0));
+        assert!(unpriced.check_budget(prompt).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_records_reported_usage() {
+        let tracker = Arc::new(Mutex::new(CostTracker::new()));
+        let request = request("Say hello");
+        let mut stream = UsageStream::new(
+            mock_stream(vec![
+                chunk("Hel", None),
+                chunk("lo", None),
+                chunk("", Some(TokenUsage::new(12, 3))),
+            ]),
+            &request,
+            tracker.clone(),
+        );
+
+        let mut content = String::new();
+        while let Some(chunk) = stream.next().await {
+            content.push_str(&chunk.unwrap().content);
+        }
+        assert_eq!(content, "Hello");
+        assert_eq!(stream.usage(), TokenUsage::new(12, 3));
+        drop(stream);
+
+        let tracker = tracker.lock().unwrap();
+        assert_eq!(tracker.usage(), &TokenUsage::new(12, 3));
+        assert_eq!(tracker.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_stream_records_estimated_partial_usage() {
+        let tracker = Arc::new(Mutex::new(CostTracker::new()));
+        let request = request("12345678");
+        let mut stream = UsageStream::new(
+            mock_stream(vec![
+                chunk("abcd", None),
+                chunk("efgh", None),
+                Err(ProviderError::NetworkError("reset".to_string())),
+                chunk("never", None),
+            ]),
+            &request,
+            tracker.clone(),
+        );
+
+        let mut results = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let failed = chunk.is_err();
+            results.push(chunk);
+            if failed {
+                break;
+            }
+        }
+        assert_eq!(results.len(), 3);
+        assert_eq!(tracker.lock().unwrap().usage(), &TokenUsage::new(2, 2));
+
+        // Dropping after the failure does not count the response twice
+        drop(stream);
+        assert_eq!(tracker.lock().unwrap().requests(), 1);
+    }
+}
@@ -1,17 +1,46 @@
 //! AI provider integration and orchestration for AIrchitect CLI
 
+pub mod cost;
 pub mod orchestration;
+pub mod outbound;
+pub mod postprocess;
+pub mod prompts;
+pub mod provider;
 pub mod providers;
+pub mod quota;
+pub mod retry;
+pub mod schema;
+pub mod template;
 
+use ai_cli_security::secret::Secret;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIEngineConfig {
     pub default_provider: String,
     pub providers: HashMap<String, ProviderConfig>,
     pub max_retries: u8,
+    /// Provider request timeout in seconds
     pub timeout: u64,
+    /// Delay between retries
+    #[serde(default)]
+    pub backoff: retry::BackoffPolicy,
+}
+
+impl AIEngineConfig {
+    /// Retry policy for requests: `max_retries` attempts spaced by `backoff`
+    pub fn retry_policy(&self) -> retry::RetryPolicy {
+        retry::RetryPolicy::default()
+            .with_max_retries(u32::from(self.max_retries))
+            .with_backoff(self.backoff)
+    }
+
+    /// Effective request timeout, preferring a per-run override
+    pub fn request_timeout(&self, override_timeout: Option<Duration>) -> Duration {
+        override_timeout.unwrap_or(Duration::from_secs(self.timeout))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,7 +48,18 @@ pub struct ProviderConfig {
     pub name: String,
     pub enabled: bool,
     pub model: String,
+    /// Endpoint override; empty means the provider's default
+    #[serde(default)]
     pub base_url: String,
+    /// API key; when absent it is resolved from the environment or credential store
+    #[serde(default)]
+    pub api_key: Option<Secret>,
+    /// Azure deployment name; the model when absent
+    #[serde(default)]
+    pub deployment: Option<String>,
+    /// Azure API version; the adapter's default when absent
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 
 pub struct AIEngine {
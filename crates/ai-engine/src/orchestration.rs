@@ -1,4 +1,5 @@
 use crate::providers::AIProvider;
+use ai_cli_utils::error::AIError;
 
 pub struct ProviderOrchestrator {
     providers: Vec<AIProvider>,
@@ -9,47 +10,102 @@ impl ProviderOrchestrator {
         ProviderOrchestrator { providers }
     }
 
+    /// Create an orchestrator, rejecting an empty provider list up front
+    pub fn try_new(providers: Vec<AIProvider>) -> Result<Self, AIError> {
+        if providers.is_empty() {
+            return Err(AIError::NoProvidersConfigured);
+        }
+        Ok(Self::new(providers))
+    }
+
+    pub fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn has_provider(&self, name: &str) -> bool {
+        self.providers
+            .iter()
+            .any(|provider| provider.name() == name)
+    }
+
     pub async fn route_request(
         &self,
         prompt: &str,
         provider_name: &str,
-    ) -> Result<String, ai_cli_utils::error::AIError> {
-        for provider in &self.providers {
-            // Placeholder logic to route based on provider name
-            match provider {
-                AIProvider::OpenAI(_config) if provider_name == "openai" => {
-                    return provider.send_request(prompt).await;
-                }
-                AIProvider::Anthropic(_config) if provider_name == "anthropic" => {
-                    return provider.send_request(prompt).await;
-                }
-                AIProvider::Google(_config) if provider_name == "google" => {
-                    return provider.send_request(prompt).await;
-                }
-                AIProvider::Qwen(_config) if provider_name == "qwen" => {
-                    return provider.send_request(prompt).await;
-                }
-                _ => continue,
-            }
+    ) -> Result<String, AIError> {
+        if self.providers.is_empty() {
+            return Err(AIError::NoProvidersConfigured);
         }
 
-        Err(ai_cli_utils::error::AIError::GenericError(format!(
-            "Provider {} not found",
-            provider_name
-        )))
+        match self
+            .providers
+            .iter()
+            .find(|provider| provider.name() == provider_name)
+        {
+            Some(provider) => provider.send_request(prompt).await,
+            None => Err(AIError::GenericError(format!(
+                "Provider {} not found",
+                provider_name
+            ))),
+        }
     }
 
-    pub async fn fallback_request(
-        &self,
-        prompt: &str,
-    ) -> Result<String, ai_cli_utils::error::AIError> {
+    pub async fn fallback_request(&self, prompt: &str) -> Result<String, AIError> {
         // Try the first available provider as fallback
-        if let Some(provider) = self.providers.first() {
-            provider.send_request(prompt).await
-        } else {
-            Err(ai_cli_utils::error::AIError::GenericError(
-                "No providers available".to_string(),
-            ))
+        match self.providers.first() {
+            Some(provider) => provider.send_request(prompt).await,
+            None => Err(AIError::NoProvidersConfigured),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::OpenAIConfig;
+
+    fn openai() -> AIProvider {
+        AIProvider::OpenAI(OpenAIConfig {
+            api_key: "key".to_string(),
+            model: "gpt-4".to_string(),
+            base_url: String::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_empty_orchestrator_reports_no_providers() {
+        let orchestrator = ProviderOrchestrator::new(vec![]);
+
+        assert!(matches!(
+            orchestrator.route_request("hi", "openai").await,
+            Err(AIError::NoProvidersConfigured)
+        ));
+        assert!(matches!(
+            orchestrator.fallback_request("hi").await,
+            Err(AIError::NoProvidersConfigured)
+        ));
+    }
+
+    #[test]
+    fn test_try_new_rejects_empty() {
+        assert!(matches!(
+            ProviderOrchestrator::try_new(vec![]),
+            Err(AIError::NoProvidersConfigured)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_provider_is_not_found() {
+        let orchestrator = ProviderOrchestrator::try_new(vec![openai()]).unwrap();
+
+        assert_eq!(orchestrator.provider_count(), 1);
+        assert!(orchestrator.has_provider("openai"));
+        assert!(!orchestrator.has_provider("google"));
+
+        let err = orchestrator
+            .route_request("hi", "google")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Provider google not found"));
+    }
+}
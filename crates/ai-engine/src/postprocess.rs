@@ -0,0 +1,240 @@
+//! Transforming responses before they are shown
+//!
+//! A [`ProcessorChain`] runs [`ResponseProcessor`]s over a complete
+//! response in order. The built-in processors are available by name for
+//! configuration: `strip-thinking`, `extract-code` and `trim`.
+
+use crate::provider::PromptResponse;
+use std::sync::Arc;
+
+/// A transformation applied to a response after generation
+pub trait ResponseProcessor: Send + Sync {
+    fn process(&self, response: &mut PromptResponse);
+
+    /// Name the processor is configured by
+    fn name(&self) -> &str;
+}
+
+/// Removes leading and trailing whitespace
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimWhitespace;
+
+impl ResponseProcessor for TrimWhitespace {
+    fn process(&self, response: &mut PromptResponse) {
+        let trimmed = response.content.trim();
+        if trimmed.len() != response.content.len() {
+            response.content = trimmed.to_string();
+        }
+    }
+
+    fn name(&self) -> &str {
+        "trim"
+    }
+}
+
+/// Removes `<think>` and `<thinking>` sections that reasoning models emit
+///
+/// An unclosed tag removes everything after it, as happens when the reply
+/// is cut off mid-thought.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripThinkingTags;
+
+impl ResponseProcessor for StripThinkingTags {
+    fn process(&self, response: &mut PromptResponse) {
+        for tag in ["think", "thinking"] {
+            let open = format!("<{}>", tag);
+            let close = format!("</{}>", tag);
+            while let Some(start) = response.content.find(&open) {
+                let end = response.content[start..]
+                    .find(&close)
+                    .map_or(response.content.len(), |end| start + end + close.len());
+                response.content.replace_range(start..end, "");
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "strip-thinking"
+    }
+}
+
+/// Replaces the response with the contents of its fenced code blocks
+///
+/// Blocks are separated by a blank line. A response without code blocks is
+/// left as it is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractCodeBlocks;
+
+impl ResponseProcessor for ExtractCodeBlocks {
+    fn process(&self, response: &mut PromptResponse) {
+        let mut blocks: Vec<String> = Vec::new();
+        let mut current: Option<Vec<&str>> = None;
+        for line in response.content.lines() {
+            let is_fence = line.trim_start().starts_with("```");
+            match (&mut current, is_fence) {
+                (None, true) => current = Some(Vec::new()),
+                (None, false) => {}
+                (Some(lines), true) => {
+                    blocks.push(lines.join("\n"));
+                    current = None;
+                }
+                (Some(lines), false) => lines.push(line),
+            }
+        }
+        if let Some(lines) = current {
+            blocks.push(lines.join("\n"));
+        }
+        if !blocks.is_empty() {
+            response.content = blocks.join("\n\n");
+        }
+    }
+
+    fn name(&self) -> &str {
+        "extract-code"
+    }
+}
+
+/// Names accepted by [`builtin`]
+pub const BUILTIN_PROCESSORS: &[&str] = &["strip-thinking", "extract-code", "trim"];
+
+/// The built-in processor called `name`
+pub fn builtin(name: &str) -> Option<Arc<dyn ResponseProcessor>> {
+    match name {
+        "trim" => Some(Arc::new(TrimWhitespace)),
+        "strip-thinking" => Some(Arc::new(StripThinkingTags)),
+        "extract-code" => Some(Arc::new(ExtractCodeBlocks)),
+        _ => None,
+    }
+}
+
+/// Processors applied to each response, in order
+#[derive(Clone, Default)]
+pub struct ProcessorChain {
+    processors: Vec<Arc<dyn ResponseProcessor>>,
+}
+
+impl ProcessorChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain of the built-in processors called `names`, in that order
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names.iter().try_fold(Self::new(), |chain, name| {
+            let name = name.as_ref();
+            builtin(name)
+                .map(|processor| chain.with(processor))
+                .ok_or_else(|| {
+                    format!(
+                        "unknown response processor '{}'; expected any of {}",
+                        name,
+                        BUILTIN_PROCESSORS.join(", ")
+                    )
+                })
+        })
+    }
+
+    /// Append `processor` to the chain
+    pub fn with(mut self, processor: Arc<dyn ResponseProcessor>) -> Self {
+        self.processors.push(processor);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Names of the processors in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.iter().map(|p| p.name()).collect()
+    }
+
+    /// Run every processor over `response`
+    pub fn apply(&self, response: &mut PromptResponse) {
+        for processor in &self.processors {
+            processor.process(response);
+        }
+    }
+}
+
+impl std::fmt::Debug for ProcessorChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ProcessorChain")
+            .field(&self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{FinishReason, ResponseMetadata, TokenUsage};
+
+    fn response(content: &str) -> PromptResponse {
+        PromptResponse {
+            content: content.to_string(),
+            model: "mock".to_string(),
+            usage: TokenUsage::empty(),
+            finish_reason: FinishReason::Stop,
+            metadata: ResponseMetadata {
+                request_id: "req".to_string(),
+                timestamp: chrono::Utc::now(),
+                latency_ms: 0,
+                cost: None,
+            },
+        }
+    }
+
+    fn processed(processor: &dyn ResponseProcessor, content: &str) -> String {
+        let mut response = response(content);
+        processor.process(&mut response);
+        response.content
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        assert_eq!(processed(&TrimWhitespace, "\n  answer \n\n"), "answer");
+        assert_eq!(processed(&TrimWhitespace, "answer"), "answer");
+    }
+
+    #[test]
+    fn test_strip_thinking_tags() {
+        assert_eq!(
+            processed(
+                &StripThinkingTags,
+                "<think>plan it</think>Answer<thinking>more</thinking>!"
+            ),
+            "Answer!"
+        );
+        assert_eq!(
+            processed(&StripThinkingTags, "Answer <think>cut off"),
+            "Answer "
+        );
+        assert_eq!(processed(&StripThinkingTags, "a < b"), "a < b");
+    }
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let reply = "Here:\n```rust\nfn a() {}\n```\nand\n  ```\nb();\n  ```\nDone";
+        assert_eq!(processed(&ExtractCodeBlocks, reply), "fn a() {}\n\nb();");
+        assert_eq!(
+            processed(&ExtractCodeBlocks, "```sh\nls\n"),
+            "ls",
+            "an unclosed block runs to the end"
+        );
+        assert_eq!(processed(&ExtractCodeBlocks, "no code"), "no code");
+    }
+
+    #[test]
+    fn test_chain_runs_in_order() {
+        let chain = ProcessorChain::from_names(&["strip-thinking", "trim"]).unwrap();
+        assert_eq!(chain.names(), vec!["strip-thinking", "trim"]);
+        let mut reply = response("<think>hmm</think>\n\n  42\n");
+        chain.apply(&mut reply);
+        assert_eq!(reply.content, "42");
+
+        let err = ProcessorChain::from_names(&["trim", "shout"]).unwrap_err();
+        assert!(err.contains("'shout'"), "{}", err);
+        assert!(ProcessorChain::new().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Named system prompts
+//!
+//! A few personas ship with the binary; users add their own as files in
+//! `~/.ai/prompts/`, named after the prompt (`reviewer.md` defines
+//! `reviewer`). User prompts take precedence over built-ins of the same name.
+//! Prompts are [`PromptTemplate`]s, so they may use `{{variable}}`
+//! placeholders filled in by the command that sends them.
+
+use crate::template::{PromptTemplate, TemplateError};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Prompts embedded in the binary
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "code_reviewer",
+        "You are a meticulous code reviewer. Point out bugs, unclear naming, missing \
+         error handling and untested paths. Quote the code you comment on, explain why \
+         it matters and suggest a concrete fix. Do not restate what the code does.",
+    ),
+    (
+        "planner",
+        "You are a software project planner. Break the goal into small, ordered steps \
+         that can each be finished and verified on their own. Call out dependencies, \
+         risks and open questions before proposing a schedule.",
+    ),
+    (
+        "refactorer",
+        "You are an expert at refactoring. Improve structure and readability without \
+         changing behavior. Prefer small, mechanical steps, keep public interfaces \
+         stable unless asked otherwise, and explain each change briefly.",
+    ),
+];
+
+/// File extensions recognised as prompt files
+const PROMPT_EXTENSIONS: &[&str] = &["md", "txt"];
+
+/// Prompt library error types
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PromptLibraryError {
+    #[error("Unknown system prompt '@{name}'; available: {}", .available.join(", "))]
+    Unknown {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Invalid system prompt: {0}")]
+    Template(#[from] TemplateError),
+}
+
+/// Library of named system prompts
+#[derive(Debug, Clone, Default)]
+pub struct SystemPromptLibrary {
+    prompts: BTreeMap<String, String>,
+}
+
+impl SystemPromptLibrary {
+    /// Library containing only the built-in prompts
+    pub fn builtin() -> Self {
+        Self {
+            prompts: BUILTIN
+                .iter()
+                .map(|(name, prompt)| (name.to_string(), prompt.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Built-in prompts plus those in the user's prompt directory
+    ///
+    /// An unreadable directory is logged and skipped.
+    pub fn with_user_prompts() -> Self {
+        let mut library = Self::builtin();
+        if let Some(dir) = user_prompt_dir() {
+            if let Err(e) = library.load_dir(&dir) {
+                log::warn!("Cannot read prompts from {}: {}", dir.display(), e);
+            }
+        }
+        library
+    }
+
+    /// Add every prompt file in `dir`, returning how many were loaded
+    ///
+    /// A missing directory loads nothing.
+    pub fn load_dir(&mut self, dir: &Path) -> std::io::Result<usize> {
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+
+        let mut loaded = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_prompt = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PROMPT_EXTENSIONS.contains(&ext));
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if !is_prompt || !path.is_file() {
+                continue;
+            }
+
+            let prompt = std::fs::read_to_string(&path)?;
+            self.insert(name, prompt.trim());
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Add or replace a prompt
+    pub fn insert(&mut self, name: impl Into<String>, prompt: impl Into<String>) {
+        self.prompts.insert(name.into(), prompt.into());
+    }
+
+    /// Look up a prompt by name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.prompts.get(name).map(String::as_str)
+    }
+
+    /// Names of all prompts, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.prompts.keys().map(String::as_str).collect()
+    }
+
+    /// Turn a `--system-prompt` value into prompt text
+    ///
+    /// `@name` refers to a library prompt; anything else is used verbatim.
+    pub fn resolve(&self, spec: &str) -> Result<String, PromptLibraryError> {
+        let Some(name) = spec.strip_prefix('@') else {
+            return Ok(spec.to_string());
+        };
+        self.get(name)
+            .map(str::to_string)
+            .ok_or_else(|| PromptLibraryError::Unknown {
+                name: name.to_string(),
+                available: self.prompts.keys().cloned().collect(),
+            })
+    }
+
+    /// Like [`resolve`](Self::resolve), as a template to be rendered once
+    /// its variables are known
+    ///
+    /// Only library prompts are parsed; inline text renders verbatim, so
+    /// literal `{{` needs no escaping.
+    pub fn template(&self, spec: &str) -> Result<PromptTemplate, PromptLibraryError> {
+        let prompt = self.resolve(spec)?;
+        if spec.starts_with('@') {
+            Ok(PromptTemplate::new(prompt)?)
+        } else {
+            Ok(PromptTemplate::literal(prompt))
+        }
+    }
+}
+
+/// `~/.ai/prompts`, if a home directory is known
+pub fn user_prompt_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".ai").join("prompts"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_resolve_builtin_and_inline() {
+        let library = SystemPromptLibrary::builtin();
+        assert!(library
+            .resolve("@code_reviewer")
+            .unwrap()
+            .contains("code reviewer"));
+        assert_eq!(library.resolve("Be brief.").unwrap(), "Be brief.");
+        assert_eq!(
+            library.names(),
+            vec!["code_reviewer", "planner", "refactorer"]
+        );
+    }
+
+    #[test]
+    fn test_user_prompts_override_builtins() {
+        let dir = std::env::temp_dir().join(format!("ai-prompts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("pirate.md"), "Talk like a pirate.\n").unwrap();
+        std::fs::write(dir.join("planner.txt"), "Plan in haiku.").unwrap();
+        std::fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        let mut library = SystemPromptLibrary::builtin();
+        assert_eq!(library.load_dir(&dir).unwrap(), 2);
+        assert_eq!(library.resolve("@pirate").unwrap(), "Talk like a pirate.");
+        assert_eq!(library.resolve("@planner").unwrap(), "Plan in haiku.");
+        assert!(library.get("notes").is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_reference_lists_available() {
+        let library = SystemPromptLibrary::builtin();
+        let err = library.resolve("@nope").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown system prompt '@nope'; available: code_reviewer, planner, refactorer"
+        );
+    }
+
+    #[test]
+    fn test_library_prompts_are_templates() {
+        let mut library = SystemPromptLibrary::builtin();
+        library.insert("release", "Plan {{task}} for {{team|everyone}}.");
+        library.insert("broken", "Broken {{task");
+        let template = library.template("@release").unwrap();
+        assert_eq!(template.variables(), vec!["task", "team"]);
+        let values = HashMap::from([("task".to_string(), "the release".to_string())]);
+        assert_eq!(
+            template.render(&values).unwrap(),
+            "Plan the release for everyone."
+        );
+        assert!(template.render(&HashMap::new()).is_err());
+
+        assert_eq!(
+            library.template("@broken").unwrap_err(),
+            PromptLibraryError::Template(TemplateError::Unclosed(7))
+        );
+        assert!(library.template("@planner").unwrap().variables().is_empty());
+    }
+
+    #[test]
+    fn test_inline_prompts_keep_braces() {
+        let library = SystemPromptLibrary::builtin();
+        for inline in ["Answer as {{\"json\": true}}", "Broken {{task", "{{}}"] {
+            let template = library.template(inline).unwrap();
+            assert!(template.variables().is_empty());
+            assert_eq!(template.render(&HashMap::new()).unwrap(), inline);
+        }
+    }
+
+    #[test]
+    fn test_missing_dir_loads_nothing() {
+        let mut library = SystemPromptLibrary::builtin();
+        let missing = std::env::temp_dir().join("ai-prompts-does-not-exist");
+        assert_eq!(library.load_dir(&missing).unwrap(), 0);
+    }
+}
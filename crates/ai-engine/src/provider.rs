@@ -9,13 +9,15 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::RwLock;
+pub use tokio_util::sync::CancellationToken;
 
 /// Provider error types
 #[derive(Error, Debug)]
@@ -48,8 +50,95 @@ pub enum ProviderError {
     GenericError(String),
 }
 
+impl ProviderError {
+    /// Stable codes for every error kind, as returned by [`ProviderError::code`]
+    pub const CODES: &'static [&'static str] = &[
+        "auth",
+        "rate_limit",
+        "invalid_request",
+        "model",
+        "network",
+        "timeout",
+        "serialization",
+        "unavailable",
+        "generic",
+    ];
+
+    /// Short, stable name for the kind of error, e.g. `rate_limit`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProviderError::AuthError(_) => "auth",
+            ProviderError::RateLimitError(_) => "rate_limit",
+            ProviderError::InvalidRequest(_) => "invalid_request",
+            ProviderError::ModelError(_) => "model",
+            ProviderError::NetworkError(_) => "network",
+            ProviderError::TimeoutError(_) => "timeout",
+            ProviderError::SerializationError(_) => "serialization",
+            ProviderError::Unavailable(_) => "unavailable",
+            ProviderError::GenericError(_) => "generic",
+        }
+    }
+
+    /// Whether the error is likely to clear up on its own
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimitError(_)
+                | ProviderError::NetworkError(_)
+                | ProviderError::TimeoutError(_)
+                | ProviderError::Unavailable(_)
+        )
+    }
+}
+
 pub type ProviderResult<T> = Result<T, ProviderError>;
 
+/// Map an unsuccessful HTTP response to the matching [`ProviderError`]
+///
+/// The message is taken from the provider's JSON error body when it has one
+/// (`{"error": {"message": ...}}`, `{"error": ...}` or `{"message": ...}`),
+/// otherwise from the raw body.
+pub fn classify_status(status: u16, body: &str) -> ProviderError {
+    let detail = error_message(body);
+    let message = match reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|code| code.canonical_reason())
+    {
+        Some(reason) if detail.is_empty() => format!("{} {}", status, reason),
+        Some(reason) => format!("{} {}: {}", status, reason, detail),
+        None if detail.is_empty() => status.to_string(),
+        None => format!("{}: {}", status, detail),
+    };
+    match status {
+        401 | 403 => ProviderError::AuthError(message),
+        408 => ProviderError::TimeoutError(message),
+        429 => ProviderError::RateLimitError(message),
+        404 => ProviderError::ModelError(message),
+        400..=499 => ProviderError::InvalidRequest(message),
+        500..=599 => ProviderError::NetworkError(message),
+        _ => ProviderError::Unavailable(message),
+    }
+}
+
+/// The human-readable part of an error response body
+fn error_message(body: &str) -> String {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+    let found = match json.get("error") {
+        Some(serde_json::Value::String(message)) => Some(message.as_str()),
+        Some(error) => error.get("message").and_then(serde_json::Value::as_str),
+        None => json.get("message").and_then(serde_json::Value::as_str),
+    };
+    match found {
+        Some(message) => message.to_string(),
+        None => body.trim().to_string(),
+    }
+}
+
+/// Embedding vector
+pub type Vector = Vec<f32>;
+
 /// Response stream type
 pub type ResponseStream = Pin<Box<dyn Stream<Item = ProviderResult<StreamChunk>> + Send>>;
 
@@ -62,12 +151,54 @@ pub trait AIProvider: Send + Sync {
     /// Stream a prompt response
     async fn stream_prompt(&self, request: PromptRequest) -> ProviderResult<ResponseStream>;
 
+    /// Send a prompt, giving up as soon as `cancel` is cancelled
+    ///
+    /// Cancelling drops the pending request, closing its connection, and
+    /// returns [`ProviderError::GenericError`] with `cancelled`.
+    async fn send_prompt_cancellable(
+        &self,
+        request: PromptRequest,
+        cancel: Option<CancellationToken>,
+    ) -> ProviderResult<PromptResponse> {
+        with_cancellation(cancel.as_ref(), self.send_prompt(request)).await
+    }
+
+    /// Stream a prompt response, ending it as soon as `cancel` is cancelled
+    ///
+    /// A stream cancelled after it started yields one `cancelled` error and
+    /// then ends.
+    async fn stream_prompt_cancellable(
+        &self,
+        request: PromptRequest,
+        cancel: Option<CancellationToken>,
+    ) -> ProviderResult<ResponseStream> {
+        let stream = with_cancellation(cancel.as_ref(), self.stream_prompt(request)).await?;
+        Ok(match cancel {
+            Some(cancel) => cancellable_stream(stream, cancel),
+            None => stream,
+        })
+    }
+
     /// Get available models
     async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>>;
 
     /// Get provider health status
     async fn get_health_status(&self) -> ProviderResult<HealthStatus>;
 
+    /// Generate one embedding per input text
+    async fn embed(&self, _texts: Vec<String>, _model: &str) -> ProviderResult<Vec<Vector>> {
+        Err(ProviderError::InvalidRequest(
+            "embeddings not supported".to_string(),
+        ))
+    }
+
+    /// Prepare for the first request, e.g. by opening a pooled connection
+    ///
+    /// Nothing depends on it having run, so callers only log a failure.
+    async fn warmup(&self) -> ProviderResult<()> {
+        Ok(())
+    }
+
     /// Get provider name
     fn name(&self) -> &str;
 
@@ -114,15 +245,34 @@ pub struct Message {
 }
 
 /// Message role
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Serializes to OpenAI's wire values; adapters for providers with other
+/// roles map them when building requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     System,
     User,
     Assistant,
+    /// Result of a tool call
+    Tool,
+    /// Result of a function call, the predecessor of `Tool`
     Function,
 }
 
+impl MessageRole {
+    /// The role as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+            MessageRole::Tool => "tool",
+            MessageRole::Function => "function",
+        }
+    }
+}
+
 /// Request metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestMetadata {
@@ -130,6 +280,10 @@ pub struct RequestMetadata {
     pub timestamp: DateTime<Utc>,
     pub user_id: Option<String>,
     pub session_id: Option<String>,
+    /// Shared by every request made for the same command, to trace them
+    /// across logs
+    #[serde(default)]
+    pub correlation_id: Option<String>,
 }
 
 impl Default for RequestMetadata {
@@ -139,6 +293,20 @@ impl Default for RequestMetadata {
             timestamp: Utc::now(),
             user_id: None,
             session_id: None,
+            correlation_id: None,
+        }
+    }
+}
+
+impl RequestMetadata {
+    /// Metadata for another request in the same context: a new request ID
+    /// and timestamp, keeping the user, session and correlation IDs
+    pub fn renewed(&self) -> Self {
+        Self {
+            user_id: self.user_id.clone(),
+            session_id: self.session_id.clone(),
+            correlation_id: self.correlation_id.clone(),
+            ..Self::default()
         }
     }
 }
@@ -163,7 +331,7 @@ pub struct PromptResponse {
 }
 
 /// Token usage statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
@@ -216,6 +384,46 @@ pub struct StreamChunk {
     pub usage: Option<TokenUsage>,
 }
 
+/// What a streamed response delivered, and the error that cut it short
+#[derive(Debug, Default)]
+pub struct StreamResult {
+    /// Content of every chunk received
+    pub partial: String,
+    /// Error that ended the stream early, if any
+    pub error: Option<ProviderError>,
+}
+
+impl StreamResult {
+    /// Whether the stream ran to its end
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The whole content, or the error if the stream was cut short
+    pub fn into_result(self) -> ProviderResult<String> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.partial),
+        }
+    }
+}
+
+/// Read `stream` until it ends or fails, keeping what arrived before a
+/// failure
+pub async fn collect_stream(mut stream: ResponseStream) -> StreamResult {
+    let mut result = StreamResult::default();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => result.partial.push_str(&chunk.content),
+            Err(e) => {
+                result.error = Some(e);
+                break;
+            }
+        }
+    }
+    result
+}
+
 /// Model information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfo {
@@ -287,6 +495,61 @@ impl HealthStatus {
     }
 }
 
+/// Send a prompt, failing with [`ProviderError::TimeoutError`] if no response
+/// arrives within `timeout`
+pub async fn send_with_timeout(
+    provider: &dyn AIProvider,
+    request: PromptRequest,
+    timeout: Duration,
+) -> ProviderResult<PromptResponse> {
+    tokio::time::timeout(timeout, provider.send_prompt(request))
+        .await
+        .map_err(|_| {
+            ProviderError::TimeoutError(format!(
+                "{} did not respond within {}s",
+                provider.name(),
+                timeout.as_secs_f64()
+            ))
+        })?
+}
+
+/// Run `request` until it completes or `cancel` is cancelled
+///
+/// A cancelled request is dropped, so whatever it had in flight is
+/// abandoned.
+pub async fn with_cancellation<T>(
+    cancel: Option<&CancellationToken>,
+    request: impl std::future::Future<Output = ProviderResult<T>>,
+) -> ProviderResult<T> {
+    let Some(cancel) = cancel else {
+        return request.await;
+    };
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(cancelled()),
+        result = request => result,
+    }
+}
+
+/// `stream`, cut short with a `cancelled` error once `cancel` is cancelled
+pub fn cancellable_stream(stream: ResponseStream, cancel: CancellationToken) -> ResponseStream {
+    Box::pin(futures::stream::unfold(
+        Some((stream, cancel)),
+        |state| async move {
+            let (mut stream, cancel) = state?;
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => Some((Err(cancelled()), None)),
+                chunk = stream.next() => chunk.map(|chunk| (chunk, Some((stream, cancel)))),
+            }
+        },
+    ))
+}
+
+fn cancelled() -> ProviderError {
+    ProviderError::GenericError("cancelled".to_string())
+}
+
 /// Provider registry
 pub struct ProviderRegistry {
     providers: Arc<RwLock<HashMap<String, Arc<dyn AIProvider>>>>,
@@ -299,6 +562,38 @@ impl ProviderRegistry {
         }
     }
 
+    /// Registry holding a provider for every enabled entry in `configs`
+    ///
+    /// `build` turns a config into a provider (see the providers crate's
+    /// factory). Entries it fails on are returned by name with the error
+    /// instead of stopping the rest; disabled entries are skipped. The
+    /// registry is complete before it is returned, so nothing else can
+    /// observe it half-populated.
+    pub fn from_config<F>(
+        configs: &[crate::ProviderConfig],
+        mut build: F,
+    ) -> (Self, Vec<(String, ai_cli_utils::error::AIError)>)
+    where
+        F: FnMut(
+            &crate::ProviderConfig,
+        ) -> Result<Arc<dyn AIProvider>, ai_cli_utils::error::AIError>,
+    {
+        let mut providers = HashMap::new();
+        let mut failed = Vec::new();
+        for config in configs.iter().filter(|c| c.enabled) {
+            match build(config) {
+                Ok(provider) => {
+                    providers.insert(provider.name().to_string(), provider);
+                }
+                Err(e) => failed.push((config.name.clone(), e)),
+            }
+        }
+        let registry = Self {
+            providers: Arc::new(RwLock::new(providers)),
+        };
+        (registry, failed)
+    }
+
     pub async fn register(&self, provider: Arc<dyn AIProvider>) {
         let name = provider.name().to_string();
         self.providers.write().await.insert(name, provider);
@@ -373,6 +668,49 @@ mod tests {
         }
     }
 
+    /// Delegates to [`MockProvider`] after a delay
+    struct SlowProvider {
+        inner: MockProvider,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl AIProvider for SlowProvider {
+        async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.send_prompt(request).await
+        }
+
+        async fn stream_prompt(&self, request: PromptRequest) -> ProviderResult<ResponseStream> {
+            self.inner.stream_prompt(request).await
+        }
+
+        async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+            self.inner.get_models().await
+        }
+
+        async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+            self.inner.get_health_status().await
+        }
+
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+    }
+
+    fn empty_request() -> PromptRequest {
+        PromptRequest {
+            model: "test-model".to_string(),
+            system_prompt: None,
+            messages: vec![],
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            parameters: HashMap::new(),
+            metadata: RequestMetadata::default(),
+        }
+    }
+
     #[test]
     fn test_message_creation() {
         let msg = Message {
@@ -383,6 +721,30 @@ mod tests {
         assert_eq!(msg.content, "Hello");
     }
 
+    #[test]
+    fn test_message_role_round_trip() {
+        for role in [
+            MessageRole::System,
+            MessageRole::User,
+            MessageRole::Assistant,
+            MessageRole::Tool,
+            MessageRole::Function,
+        ] {
+            let json = serde_json::to_value(role).unwrap();
+            assert_eq!(json, serde_json::json!(role.as_str()));
+            assert_eq!(serde_json::from_value::<MessageRole>(json).unwrap(), role);
+        }
+
+        let message: Message =
+            serde_json::from_str(r#"{"role": "tool", "content": "42", "name": "calc"}"#).unwrap();
+        assert_eq!(message.role, MessageRole::Tool);
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["role"], "tool");
+        assert_eq!(json["name"], "calc");
+
+        assert!(serde_json::from_str::<MessageRole>(r#""Tool""#).is_err());
+    }
+
     #[test]
     fn test_token_usage() {
         let usage = TokenUsage::new(100, 50);
@@ -434,6 +796,108 @@ mod tests {
         assert_eq!(response.content, "Test response");
     }
 
+    #[tokio::test]
+    async fn test_send_with_timeout() {
+        let provider = SlowProvider {
+            inner: MockProvider {
+                name: "slow".to_string(),
+            },
+            delay: Duration::from_millis(200),
+        };
+
+        let err = send_with_timeout(&provider, empty_request(), Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::TimeoutError(ref msg) if msg.contains("slow")));
+
+        let response = send_with_timeout(&provider, empty_request(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Test response");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_long_running_request() {
+        let provider = SlowProvider {
+            inner: MockProvider {
+                name: "slow".to_string(),
+            },
+            delay: Duration::from_secs(60),
+        };
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let err = provider
+            .send_prompt_cancellable(empty_request(), Some(cancel))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::GenericError(ref msg) if msg == "cancelled"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Without a token the request runs as usual
+        let provider = SlowProvider {
+            delay: Duration::ZERO,
+            ..provider
+        };
+        let response = provider
+            .send_prompt_cancellable(empty_request(), None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Test response");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stream_mid_response() {
+        let chunks = futures::stream::iter(vec![Ok(StreamChunk {
+            content: "partial".to_string(),
+            finish_reason: None,
+            usage: None,
+        })])
+        .chain(futures::stream::pending());
+        let cancel = CancellationToken::new();
+        let mut stream = cancellable_stream(Box::pin(chunks), cancel.clone());
+
+        assert_eq!(stream.next().await.unwrap().unwrap().content, "partial");
+        cancel.cancel();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, ProviderError::GenericError(ref msg) if msg == "cancelled"));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_collect_stream_keeps_partial_content() {
+        let chunk = |content: &str| {
+            Ok(StreamChunk {
+                content: content.to_string(),
+                finish_reason: None,
+                usage: None,
+            })
+        };
+        let chunks = vec![
+            chunk("Hello "),
+            chunk("wor"),
+            Err(ProviderError::NetworkError("connection reset".to_string())),
+            chunk("ld"),
+        ];
+
+        let result = collect_stream(Box::pin(futures::stream::iter(chunks))).await;
+        assert_eq!(result.partial, "Hello wor");
+        assert!(!result.is_complete());
+        assert!(matches!(
+            result.into_result(),
+            Err(ProviderError::NetworkError(ref msg)) if msg == "connection reset"
+        ));
+
+        let result = collect_stream(Box::pin(futures::stream::iter(vec![chunk("done")]))).await;
+        assert!(result.is_complete());
+        assert_eq!(result.into_result().unwrap(), "done");
+    }
+
     #[tokio::test]
     async fn test_mock_provider_get_models() {
         let provider = MockProvider {
@@ -501,6 +965,19 @@ mod tests {
         assert!(registry.get("test").await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_embed_unsupported_by_default() {
+        let provider = MockProvider {
+            name: "test".to_string(),
+        };
+
+        let err = provider
+            .embed(vec!["hello".to_string()], "test-model")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::InvalidRequest(_)));
+    }
+
     #[test]
     fn test_provider_capabilities_default() {
         let caps = ProviderCapabilities::default();
@@ -512,5 +989,64 @@ mod tests {
     fn test_request_metadata_default() {
         let metadata = RequestMetadata::default();
         assert!(!metadata.request_id.is_empty());
+
+        let metadata = RequestMetadata {
+            correlation_id: Some("corr-1".to_string()),
+            ..metadata
+        };
+        let renewed = metadata.renewed();
+        assert_ne!(renewed.request_id, metadata.request_id);
+        assert_eq!(renewed.correlation_id.as_deref(), Some("corr-1"));
+    }
+
+    #[test]
+    fn test_classify_status() {
+        let cases: &[(u16, &str)] = &[
+            (400, "invalid_request"),
+            (401, "auth"),
+            (403, "auth"),
+            (404, "model"),
+            (408, "timeout"),
+            (422, "invalid_request"),
+            (429, "rate_limit"),
+            (500, "network"),
+            (502, "network"),
+            (503, "network"),
+            (302, "unavailable"),
+        ];
+        for (status, code) in cases {
+            assert_eq!(classify_status(*status, "").code(), *code, "{}", status);
+        }
+        assert!(classify_status(503, "").is_transient());
+        assert!(!classify_status(400, "").is_transient());
+    }
+
+    #[test]
+    fn test_classify_status_extracts_message() {
+        let openai = r#"{"error": {"message": "Incorrect API key provided", "type": "invalid_request_error"}}"#;
+        assert_eq!(
+            classify_status(401, openai).to_string(),
+            "Authentication error: 401 Unauthorized: Incorrect API key provided"
+        );
+
+        let anthropic =
+            r#"{"type": "error", "error": {"type": "rate_limit_error", "message": "Slow down"}}"#;
+        assert!(classify_status(429, anthropic)
+            .to_string()
+            .ends_with("429 Too Many Requests: Slow down"));
+
+        assert!(classify_status(400, r#"{"error": "bad field"}"#)
+            .to_string()
+            .ends_with(": bad field"));
+        assert!(classify_status(404, r#"{"message": "no such model"}"#)
+            .to_string()
+            .ends_with(": no such model"));
+        assert!(classify_status(500, "  upstream exploded\n")
+            .to_string()
+            .ends_with("500 Internal Server Error: upstream exploded"));
+        assert_eq!(
+            classify_status(503, "").to_string(),
+            "Network error: 503 Service Unavailable"
+        );
     }
 }
@@ -36,6 +36,15 @@ pub struct QwenConfig {
 }
 
 impl AIProvider {
+    pub fn name(&self) -> &str {
+        match self {
+            AIProvider::OpenAI(_) => "openai",
+            AIProvider::Anthropic(_) => "anthropic",
+            AIProvider::Google(_) => "google",
+            AIProvider::Qwen(_) => "qwen",
+        }
+    }
+
     pub async fn send_request(&self, prompt: &str) -> Result<String, ai_cli_utils::error::AIError> {
         // Placeholder implementation
         match self {
@@ -0,0 +1,281 @@
+//! Per-provider usage quotas
+//!
+//! Counters for requests and tokens are kept per provider and persisted to a
+//! JSON file so limits hold across runs. Each counter covers a rolling
+//! period that starts with its first recorded request; once the period has
+//! elapsed the counter starts over.
+
+use crate::provider::{ProviderError, ProviderResult, TokenUsage};
+use ai_cli_utils::error::{AIError, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Default quota period
+pub const DEFAULT_QUOTA_PERIOD_DAYS: i64 = 30;
+
+/// Limits for one provider; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    pub max_requests: Option<u64>,
+    pub max_tokens: Option<u64>,
+}
+
+/// Usage recorded for one provider in the current period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct QuotaCounter {
+    requests: u64,
+    tokens: u64,
+    period_start: DateTime<Utc>,
+}
+
+impl QuotaCounter {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            requests: 0,
+            tokens: 0,
+            period_start: now,
+        }
+    }
+}
+
+/// Usage and limits for one provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaStatus {
+    pub provider: String,
+    pub requests: u64,
+    pub tokens: u64,
+    pub limits: QuotaLimits,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+}
+
+impl QuotaStatus {
+    /// Whether either limit has been reached
+    pub fn exceeded(&self) -> bool {
+        self.limits
+            .max_requests
+            .is_some_and(|max| self.requests >= max)
+            || self.limits.max_tokens.is_some_and(|max| self.tokens >= max)
+    }
+}
+
+/// Enforces per-provider request and token quotas
+///
+/// Call [`QuotaManager::check`] before sending a request and
+/// [`QuotaManager::record`] with the response's usage after it succeeds.
+pub struct QuotaManager {
+    path: PathBuf,
+    period: Duration,
+    limits: HashMap<String, QuotaLimits>,
+    counters: Mutex<BTreeMap<String, QuotaCounter>>,
+}
+
+impl QuotaManager {
+    /// Load counters from `path`, starting empty if the file does not exist
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let counters = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            path,
+            period: Duration::days(DEFAULT_QUOTA_PERIOD_DAYS),
+            limits: HashMap::new(),
+            counters: Mutex::new(counters),
+        })
+    }
+
+    /// Set the length of the quota period
+    pub fn with_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set the limits for a provider
+    pub fn with_limits(mut self, provider: impl Into<String>, limits: QuotaLimits) -> Self {
+        self.limits.insert(provider.into(), limits);
+        self
+    }
+
+    /// Get the path of the counter file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fail if the provider has used up its quota for the current period
+    pub fn check(&self, provider: &str) -> Result<()> {
+        if self.status(provider).exceeded() {
+            return Err(AIError::GenericError(format!(
+                "quota exceeded for {}",
+                provider
+            )));
+        }
+        Ok(())
+    }
+
+    /// [`QuotaManager::check`] as a rate limit, so a request path fails over
+    /// to the next provider or exits as rate limited
+    pub fn check_request(&self, provider: &str) -> ProviderResult<()> {
+        self.check(provider).map_err(|e| {
+            ProviderError::RateLimitError(match e {
+                AIError::GenericError(message) => message,
+                other => other.to_string(),
+            })
+        })
+    }
+
+    /// Count a successful request and its tokens, then persist the counters
+    pub fn record(&self, provider: &str, usage: &TokenUsage) -> Result<()> {
+        let now = Utc::now();
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters
+            .entry(provider.to_string())
+            .or_insert_with(|| QuotaCounter::new(now));
+        if self.expired(counter, now) {
+            *counter = QuotaCounter::new(now);
+        }
+        counter.requests += 1;
+        counter.tokens += u64::from(usage.total_tokens);
+        self.save(&counters)
+    }
+
+    /// Usage and limits for every provider with a limit or recorded usage
+    pub fn quota_status(&self) -> Vec<QuotaStatus> {
+        let mut providers: Vec<String> = self.limits.keys().cloned().collect();
+        providers.extend(self.counters.lock().unwrap().keys().cloned());
+        providers.sort();
+        providers.dedup();
+        providers.iter().map(|p| self.status(p)).collect()
+    }
+
+    /// Clear the counters for one provider, or for all of them
+    pub fn reset(&self, provider: Option<&str>) -> Result<()> {
+        let mut counters = self.counters.lock().unwrap();
+        match provider {
+            Some(provider) => {
+                counters.remove(provider);
+            }
+            None => counters.clear(),
+        }
+        self.save(&counters)
+    }
+
+    fn status(&self, provider: &str) -> QuotaStatus {
+        let now = Utc::now();
+        let counter = self
+            .counters
+            .lock()
+            .unwrap()
+            .get(provider)
+            .filter(|counter| !self.expired(counter, now))
+            .cloned()
+            .unwrap_or_else(|| QuotaCounter::new(now));
+
+        QuotaStatus {
+            provider: provider.to_string(),
+            requests: counter.requests,
+            tokens: counter.tokens,
+            limits: self.limits.get(provider).copied().unwrap_or_default(),
+            period_start: counter.period_start,
+            period_end: counter.period_start + self.period,
+        }
+    }
+
+    fn expired(&self, counter: &QuotaCounter, now: DateTime<Utc>) -> bool {
+        now - counter.period_start >= self.period
+    }
+
+    fn save(&self, counters: &BTreeMap<String, QuotaCounter>) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(counters)?;
+        ai_cli_utils::fs::write_atomic(&self.path, &contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn limits(max_requests: Option<u64>, max_tokens: Option<u64>) -> QuotaLimits {
+        QuotaLimits {
+            max_requests,
+            max_tokens,
+        }
+    }
+
+    #[test]
+    fn test_request_quota_exceeded() {
+        let temp_dir = TempDir::new().unwrap();
+        let quotas = QuotaManager::new(temp_dir.path().join("quota.json"))
+            .unwrap()
+            .with_limits("openai", limits(Some(2), None));
+
+        for _ in 0..2 {
+            quotas.check("openai").unwrap();
+            quotas.record("openai", &TokenUsage::new(10, 5)).unwrap();
+        }
+
+        let err = quotas.check("openai").unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: quota exceeded for openai");
+        // Other providers are unaffected
+        quotas.check("anthropic").unwrap();
+    }
+
+    #[test]
+    fn test_token_quota_persists_across_instances() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("quota.json");
+        let quotas = QuotaManager::new(&path)
+            .unwrap()
+            .with_limits("openai", limits(None, Some(100)));
+        quotas.record("openai", &TokenUsage::new(60, 50)).unwrap();
+        drop(quotas);
+
+        let quotas = QuotaManager::new(&path)
+            .unwrap()
+            .with_limits("openai", limits(None, Some(100)));
+        assert!(quotas.check("openai").is_err());
+
+        let status = quotas.quota_status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].requests, 1);
+        assert_eq!(status[0].tokens, 110);
+        assert!(status[0].exceeded());
+
+        quotas.reset(Some("openai")).unwrap();
+        quotas.check("openai").unwrap();
+        assert_eq!(QuotaManager::new(&path).unwrap().quota_status(), vec![]);
+    }
+
+    #[test]
+    fn test_counters_roll_over_after_period() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("quota.json");
+        let stale = BTreeMap::from([(
+            "openai".to_string(),
+            QuotaCounter {
+                requests: 5,
+                tokens: 500,
+                period_start: Utc::now() - Duration::days(DEFAULT_QUOTA_PERIOD_DAYS + 1),
+            },
+        )]);
+        std::fs::write(&path, serde_json::to_string(&stale).unwrap()).unwrap();
+
+        let quotas = QuotaManager::new(&path)
+            .unwrap()
+            .with_limits("openai", limits(Some(5), None));
+        quotas.check("openai").unwrap();
+
+        quotas.record("openai", &TokenUsage::new(1, 1)).unwrap();
+        let status = &quotas.quota_status()[0];
+        assert_eq!(status.requests, 1);
+        assert_eq!(status.tokens, 2);
+    }
+}
@@ -0,0 +1,365 @@
+//! Retrying failed provider requests
+//!
+//! Which failures are retried is decided by [`ProviderError::code`]: by
+//! default the transient ones, or an explicit list such as
+//! `rate_limit,network`. How long to wait between attempts is decided by a
+//! [`BackoffPolicy`].
+
+use crate::provider::{ProviderError, ProviderResult};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Which errors are worth retrying
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RetryOn {
+    /// Errors for which [`ProviderError::is_transient`] holds
+    #[default]
+    Transient,
+    /// Errors with one of these codes; empty disables retries
+    Codes(BTreeSet<&'static str>),
+}
+
+impl RetryOn {
+    /// Whether `error` should be retried
+    pub fn matches(&self, error: &ProviderError) -> bool {
+        match self {
+            RetryOn::Transient => error.is_transient(),
+            RetryOn::Codes(codes) => codes.contains(error.code()),
+        }
+    }
+}
+
+impl FromStr for RetryOn {
+    type Err = String;
+
+    /// Parse `none`, `transient` or a comma-separated list of error codes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => return Ok(RetryOn::Codes(BTreeSet::new())),
+            "transient" | "default" => return Ok(RetryOn::Transient),
+            _ => {}
+        }
+        let mut codes = BTreeSet::new();
+        for code in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let known = ProviderError::CODES
+                .iter()
+                .find(|known| **known == code)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown error code '{}'; expected none, transient or any of {}",
+                        code,
+                        ProviderError::CODES.join(", ")
+                    )
+                })?;
+            codes.insert(*known);
+        }
+        if codes.is_empty() {
+            return Err("expected at least one error code".to_string());
+        }
+        Ok(RetryOn::Codes(codes))
+    }
+}
+
+impl fmt::Display for RetryOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryOn::Transient => f.write_str("transient"),
+            RetryOn::Codes(codes) if codes.is_empty() => f.write_str("none"),
+            RetryOn::Codes(codes) => {
+                f.write_str(&codes.iter().copied().collect::<Vec<_>>().join(","))
+            }
+        }
+    }
+}
+
+/// Exponential backoff between retries
+///
+/// Durations are configured in milliseconds: `base_ms` and `max_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackoffPolicy {
+    /// Delay before the first retry
+    #[serde(rename = "base_ms", with = "millis")]
+    pub base: Duration,
+    /// Growth of the delay from one retry to the next
+    pub factor: f64,
+    /// Upper bound on any delay
+    #[serde(rename = "max_ms", with = "millis")]
+    pub max: Duration,
+    /// Wait a random time up to the computed delay ("full jitter"), so
+    /// clients failing together do not retry together
+    pub jitter: bool,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(500),
+            factor: 2.0,
+            max: Duration::from_secs(60),
+            jitter: false,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Delay before retry number `attempt`, counting from zero
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+        let scaled = self.base.as_secs_f64() * self.factor.max(1.0).powi(exponent);
+        let delay = if scaled.is_finite() && scaled < self.max.as_secs_f64() {
+            Duration::from_secs_f64(scaled)
+        } else {
+            self.max
+        };
+        if self.jitter && !delay.is_zero() {
+            rand::thread_rng().gen_range(Duration::ZERO..=delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// (De)serializes a [`Duration`] as whole milliseconds
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// How often and on which errors to retry a request
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    pub backoff: BackoffPolicy,
+    pub retry_on: RetryOn,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: BackoffPolicy::default(),
+            retry_on: RetryOn::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self::default().with_max_retries(0)
+    }
+
+    pub fn with_retry_on(mut self, retry_on: RetryOn) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.backoff.base = base_delay;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Delay before retry number `retry`, counting from zero
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff.delay_for(retry)
+    }
+
+    /// Run `attempt` until it succeeds, fails with an error the policy does
+    /// not retry, or runs out of retries
+    pub async fn run<T, F, Fut>(&self, mut attempt: F) -> ProviderResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Err(e) if retry < self.max_retries && self.retry_on.matches(&e) => {
+                    let delay = self.delay(retry);
+                    log::debug!("Retrying in {:?} after {} error: {}", delay, e.code(), e);
+                    tokio::time::sleep(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fail with `error()` until the third attempt, counting attempts
+    async fn attempts_until_success(
+        policy: &RetryPolicy,
+        error: fn() -> ProviderError,
+    ) -> (ProviderResult<&'static str>, usize) {
+        let attempts = AtomicUsize::new(0);
+        let result = policy
+            .run(|| {
+                let n = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if n < 3 {
+                        Err(error())
+                    } else {
+                        Ok("done")
+                    }
+                }
+            })
+            .await;
+        (result, attempts.load(Ordering::SeqCst))
+    }
+
+    fn policy(retry_on: &str) -> RetryPolicy {
+        RetryPolicy::default()
+            .with_base_delay(Duration::ZERO)
+            .with_retry_on(retry_on.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_listed_error_is_retried() {
+        let policy = policy("model,network");
+        let (result, attempts) =
+            attempts_until_success(&policy, || ProviderError::ModelError("filtered".into())).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_error_is_not_retried() {
+        // Rate limits are transient, but not in the list
+        let policy = policy("network");
+        let (result, attempts) =
+            attempts_until_success(&policy, || ProviderError::RateLimitError("slow".into())).await;
+        assert_eq!(result.unwrap_err().code(), "rate_limit");
+        assert_eq!(attempts, 1);
+
+        let (result, attempts) = attempts_until_success(&self::policy("none"), || {
+            ProviderError::NetworkError("reset".into())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_retries_transient_errors_up_to_limit() {
+        let policy = RetryPolicy::default()
+            .with_base_delay(Duration::ZERO)
+            .with_max_retries(1);
+        let (result, attempts) =
+            attempts_until_success(&policy, || ProviderError::TimeoutError("slow".into())).await;
+        assert!(matches!(result, Err(ProviderError::TimeoutError(_))));
+        assert_eq!(attempts, 2);
+
+        let (_, attempts) =
+            attempts_until_success(&policy, || ProviderError::AuthError("denied".into())).await;
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_parse_retry_on() {
+        assert_eq!("transient".parse::<RetryOn>().unwrap(), RetryOn::Transient);
+        assert_eq!(
+            "none".parse::<RetryOn>().unwrap(),
+            RetryOn::Codes(BTreeSet::new())
+        );
+        assert_eq!(
+            " network, rate_limit "
+                .parse::<RetryOn>()
+                .unwrap()
+                .to_string(),
+            "network,rate_limit"
+        );
+        let err = "network,teapot".parse::<RetryOn>().unwrap_err();
+        assert!(err.contains("teapot"));
+        assert!(",".parse::<RetryOn>().is_err());
+    }
+
+    #[test]
+    fn test_delay_doubles() {
+        let policy = RetryPolicy::default().with_base_delay(Duration::from_millis(100));
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_backoff_grows_up_to_max() {
+        let backoff = BackoffPolicy {
+            base: Duration::from_millis(100),
+            factor: 3.0,
+            max: Duration::from_secs(2),
+            jitter: false,
+        };
+        let delays: Vec<Duration> = (0..6).map(|n| backoff.delay_for(n)).collect();
+        assert!(delays.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(delays[2], Duration::from_millis(900));
+        assert_eq!(delays[3], Duration::from_secs(2));
+        assert_eq!(backoff.delay_for(u32::MAX), Duration::from_secs(2));
+
+        // The default reproduces doubling from half a second
+        let default = BackoffPolicy::default();
+        assert_eq!(default.delay_for(0), Duration::from_millis(500));
+        assert_eq!(default.delay_for(2), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_delay() {
+        let backoff = BackoffPolicy {
+            base: Duration::from_millis(100),
+            factor: 2.0,
+            max: Duration::from_millis(500),
+            jitter: true,
+        };
+        for attempt in 0..8 {
+            let bound = BackoffPolicy {
+                jitter: false,
+                ..backoff
+            }
+            .delay_for(attempt);
+            for _ in 0..50 {
+                assert!(backoff.delay_for(attempt) <= bound);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backoff_config_in_milliseconds() {
+        let backoff: BackoffPolicy =
+            serde_json::from_str(r#"{"base_ms": 250, "jitter": true}"#).unwrap();
+        assert_eq!(backoff.base, Duration::from_millis(250));
+        assert_eq!(backoff.factor, 2.0);
+        assert!(backoff.jitter);
+        assert_eq!(
+            serde_json::to_value(BackoffPolicy::default()).unwrap(),
+            serde_json::json!({"base_ms": 500, "factor": 2.0, "max_ms": 60000, "jitter": false})
+        );
+    }
+}
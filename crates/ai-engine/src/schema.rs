@@ -0,0 +1,156 @@
+//! Holding structured responses to a JSON schema
+//!
+//! [`validate_response_json`] checks a reply against a schema. A
+//! [`ResponseSchema`] adds the number of times a reply that does not match is
+//! sent back to the model, with [`ResponseSchema::fix_prompt`], to be fixed.
+
+use ai_cli_utils::error::{AIError, Result};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::path::Path;
+
+/// Most times a non-matching response may be sent back to be fixed
+pub const MAX_SCHEMA_FIXES: u8 = 5;
+
+/// Parse `content` as JSON and check it against `schema`, returning the
+/// parsed value
+///
+/// A reply wrapped in a single code fence is accepted, as models often add
+/// one even when asked for bare JSON.
+pub fn validate_response_json(content: &str, schema: &Value) -> Result<Value> {
+    let compiled = compile(schema)?;
+    let value: Value = serde_json::from_str(strip_fence(content))
+        .map_err(|e| AIError::GenericError(format!("response is not JSON: {}", e)))?;
+    if let Err(errors) = compiled.validate(&value) {
+        let errors: Vec<String> = errors
+            .map(|e| match e.instance_path.to_string().as_str() {
+                "" => e.to_string(),
+                path => format!("{} at {}", e, path),
+            })
+            .collect();
+        return Err(AIError::GenericError(format!(
+            "response does not match the schema: {}",
+            errors.join("; ")
+        )));
+    }
+    Ok(value)
+}
+
+fn compile(schema: &Value) -> Result<JSONSchema> {
+    JSONSchema::compile(schema)
+        .map_err(|e| AIError::ConfigError(format!("invalid JSON schema: {}", e)))
+}
+
+/// The text inside a code fence around the whole of `content`, or all of it
+fn strip_fence(content: &str) -> &str {
+    let content = content.trim();
+    let Some(rest) = content.strip_prefix("```") else {
+        return content;
+    };
+    match (rest.find('\n'), rest.strip_suffix("```")) {
+        (Some(start), Some(inner)) if start <= inner.len() => inner[start..].trim(),
+        _ => content,
+    }
+}
+
+/// A schema responses must match
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    schema: Value,
+    max_fixes: u8,
+}
+
+impl ResponseSchema {
+    /// Hold responses to `schema`, which must itself be a valid schema
+    pub fn new(schema: Value) -> Result<Self> {
+        compile(&schema)?;
+        Ok(Self {
+            schema,
+            max_fixes: 0,
+        })
+    }
+
+    /// Load the schema in the JSON file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::new(serde_json::from_str(&contents)?)
+    }
+
+    /// Send a non-matching response back to be fixed up to `max_fixes` times
+    pub fn with_max_fixes(mut self, max_fixes: u8) -> Self {
+        self.max_fixes = max_fixes.min(MAX_SCHEMA_FIXES);
+        self
+    }
+
+    pub fn max_fixes(&self) -> u8 {
+        self.max_fixes
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Check `content` against the schema; see [`validate_response_json`]
+    pub fn validate(&self, content: &str) -> Result<Value> {
+        validate_response_json(content, &self.schema)
+    }
+
+    /// Follow-up message asking the model to fix a response that failed
+    /// validation with `error`
+    pub fn fix_prompt(&self, error: &AIError) -> String {
+        format!(
+            "Your reply was rejected: {}. Reply again with only JSON that matches this schema:\n{}",
+            error, self.schema
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "steps": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["title", "steps"]
+        })
+    }
+
+    #[test]
+    fn test_conforming_response() {
+        let value = validate_response_json(
+            "```json\n{\"title\": \"Paginate\", \"steps\": [\"add page\"]}\n```",
+            &schema(),
+        )
+        .unwrap();
+        assert_eq!(value["steps"][0], "add page");
+    }
+
+    #[test]
+    fn test_non_conforming_response() {
+        let err = validate_response_json(r#"{"title": 3}"#, &schema()).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("\"steps\" is a required property"),
+            "{}",
+            message
+        );
+        assert!(message.contains("at /title"), "{}", message);
+
+        let err = validate_response_json("Sure! Here is the plan.", &schema()).unwrap_err();
+        assert!(err.to_string().contains("response is not JSON"));
+    }
+
+    #[test]
+    fn test_invalid_schema_is_rejected() {
+        let err = ResponseSchema::new(json!({"type": "nonsense"})).unwrap_err();
+        assert!(matches!(err, AIError::ConfigError(_)));
+        let schema = ResponseSchema::new(schema()).unwrap().with_max_fixes(100);
+        assert_eq!(schema.max_fixes(), MAX_SCHEMA_FIXES);
+    }
+}
@@ -0,0 +1,216 @@
+//! Prompt templates with `{{variable}}` interpolation
+//!
+//! Placeholders are written `{{name}}`, or `{{name|default}}` to fall back to
+//! a default when no value is supplied. Whitespace around the name is ignored.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use thiserror::Error;
+
+/// Template error types
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Unresolved template variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+
+    #[error("Unclosed placeholder at byte {0}")]
+    Unclosed(usize),
+
+    #[error("Empty placeholder at byte {0}")]
+    EmptyName(usize),
+}
+
+/// Result type for template operations
+pub type TemplateResult<T> = Result<T, TemplateError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Variable {
+        name: String,
+        default: Option<String>,
+    },
+}
+
+/// A parsed prompt template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PromptTemplate {
+    source: String,
+    #[serde(skip)]
+    segments: Vec<Segment>,
+}
+
+impl PromptTemplate {
+    /// Parse a template, failing on unclosed or empty placeholders
+    pub fn new(source: impl Into<String>) -> TemplateResult<Self> {
+        let source = source.into();
+        let segments = parse(&source)?;
+        Ok(PromptTemplate { source, segments })
+    }
+
+    /// A template rendering `text` as it is, braces included
+    pub fn literal(text: impl Into<String>) -> Self {
+        let source = text.into();
+        let segments = vec![Segment::Text(source.clone())];
+        PromptTemplate { source, segments }
+    }
+
+    /// Get the original template text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Names of all variables referenced by the template, sorted and deduplicated
+    pub fn variables(&self) -> Vec<String> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Variable { name, .. } => Some(name.clone()),
+                Segment::Text(_) => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Substitute variables, failing with every unresolved name if any lack a value
+    pub fn render(&self, values: &HashMap<String, String>) -> TemplateResult<String> {
+        let mut output = String::with_capacity(self.source.len());
+        let mut missing = BTreeSet::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Variable { name, default } => {
+                    match values.get(name).or(default.as_ref()) {
+                        Some(value) => output.push_str(value),
+                        None => {
+                            missing.insert(name.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(output)
+        } else {
+            Err(TemplateError::MissingVariables(
+                missing.into_iter().collect(),
+            ))
+        }
+    }
+}
+
+impl TryFrom<String> for PromptTemplate {
+    type Error = TemplateError;
+
+    fn try_from(source: String) -> TemplateResult<Self> {
+        PromptTemplate::new(source)
+    }
+}
+
+impl From<PromptTemplate> for String {
+    fn from(template: PromptTemplate) -> Self {
+        template.source
+    }
+}
+
+fn parse(source: &str) -> TemplateResult<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = source;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or(TemplateError::Unclosed(offset + start))?;
+
+        let (name, default) = match after[..end].split_once('|') {
+            Some((name, default)) => (name.trim(), Some(default.to_string())),
+            None => (after[..end].trim(), None),
+        };
+        if name.is_empty() {
+            return Err(TemplateError::EmptyName(offset + start));
+        }
+        segments.push(Segment::Variable {
+            name: name.to_string(),
+            default,
+        });
+
+        let consumed = start + 2 + end + 2;
+        offset += consumed;
+        rest = &rest[consumed..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_interpolates_variables() {
+        let template = PromptTemplate::new("You are {{ role }} working on {{project}}.").unwrap();
+
+        let rendered = template
+            .render(&values(&[("role", "a planner"), ("project", "ai-cli")]))
+            .unwrap();
+        assert_eq!(rendered, "You are a planner working on ai-cli.");
+        assert_eq!(template.variables(), vec!["project", "role"]);
+    }
+
+    #[test]
+    fn test_render_reports_all_missing_variables() {
+        let template = PromptTemplate::new("{{a}} {{b}} {{a}} {{c|x}}").unwrap();
+
+        let err = template.render(&HashMap::new()).unwrap_err();
+        assert_eq!(
+            err,
+            TemplateError::MissingVariables(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(err.to_string(), "Unresolved template variables: a, b");
+    }
+
+    #[test]
+    fn test_render_uses_defaults() {
+        let template = PromptTemplate::new("Language: {{lang|Rust}}, tone: {{tone|}}.").unwrap();
+
+        assert_eq!(
+            template.render(&HashMap::new()).unwrap(),
+            "Language: Rust, tone: ."
+        );
+        assert_eq!(
+            template.render(&values(&[("lang", "Go")])).unwrap(),
+            "Language: Go, tone: ."
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            PromptTemplate::new("hello {{name").unwrap_err(),
+            TemplateError::Unclosed(6)
+        );
+        assert_eq!(
+            PromptTemplate::new("{{ }}").unwrap_err(),
+            TemplateError::EmptyName(0)
+        );
+    }
+}
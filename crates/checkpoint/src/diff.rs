@@ -0,0 +1,252 @@
+//! Differences between checkpoint contents
+//!
+//! Text snapshots are compared line by line into hunks that render as a
+//! unified diff or serialize as a change list. Data that is not UTF-8 is
+//! only summarised by size and checksum.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+use std::fmt::Write;
+
+/// Unchanged lines kept around each change
+const CONTEXT_LINES: usize = 3;
+
+/// How one line of a hunk changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl LineOp {
+    fn prefix(self) -> char {
+        match self {
+            LineOp::Equal => ' ',
+            LineOp::Insert => '+',
+            LineOp::Delete => '-',
+        }
+    }
+}
+
+/// A line in a hunk, without its line ending
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LineChange {
+    pub op: LineOp,
+    pub line: String,
+}
+
+/// A run of changes with its surrounding context
+///
+/// Line numbers count from 1, as in unified diff headers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub changes: Vec<LineChange>,
+}
+
+/// Size and checksum of data that cannot be compared as text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlobSummary {
+    pub size: usize,
+    pub sha256: String,
+}
+
+impl BlobSummary {
+    fn of(data: &[u8]) -> Self {
+        Self {
+            size: data.len(),
+            sha256: format!("{:x}", Sha256::digest(data)),
+        }
+    }
+}
+
+/// What differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DiffContent {
+    /// Both snapshots are text; no hunks means they are the same
+    Text { hunks: Vec<DiffHunk> },
+    /// At least one snapshot is not UTF-8
+    Binary { from: BlobSummary, to: BlobSummary },
+}
+
+/// Difference between two checkpoints, or a checkpoint and current data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckpointDiff {
+    /// Label of the older side, e.g. a checkpoint name
+    pub from: String,
+    /// Label of the newer side
+    pub to: String,
+    #[serde(flatten)]
+    pub content: DiffContent,
+}
+
+impl CheckpointDiff {
+    /// Compare `from_data` with `to_data`
+    pub fn new(
+        from: impl Into<String>,
+        from_data: &[u8],
+        to: impl Into<String>,
+        to_data: &[u8],
+    ) -> Self {
+        let content = match (std::str::from_utf8(from_data), std::str::from_utf8(to_data)) {
+            (Ok(old), Ok(new)) => DiffContent::Text {
+                hunks: text_hunks(old, new),
+            },
+            _ => DiffContent::Binary {
+                from: BlobSummary::of(from_data),
+                to: BlobSummary::of(to_data),
+            },
+        };
+        Self {
+            from: from.into(),
+            to: to.into(),
+            content,
+        }
+    }
+
+    /// Whether the two sides hold the same data
+    pub fn is_empty(&self) -> bool {
+        match &self.content {
+            DiffContent::Text { hunks } => hunks.is_empty(),
+            DiffContent::Binary { from, to } => from == to,
+        }
+    }
+
+    /// Render as a unified diff, or a one-line summary for binary data
+    ///
+    /// Identical snapshots render as an empty string.
+    pub fn to_unified(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut out = String::new();
+        match &self.content {
+            DiffContent::Text { hunks } => {
+                let _ = writeln!(out, "--- {}\n+++ {}", self.from, self.to);
+                for hunk in hunks {
+                    let _ = writeln!(
+                        out,
+                        "@@ -{},{} +{},{} @@",
+                        hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+                    );
+                    for change in &hunk.changes {
+                        let _ = writeln!(out, "{}{}", change.op.prefix(), change.line);
+                    }
+                }
+            }
+            DiffContent::Binary { from, to } => {
+                let _ = writeln!(
+                    out,
+                    "Binary data differs: {} ({} bytes, sha256 {}) and {} ({} bytes, sha256 {})",
+                    self.from, from.size, from.sha256, self.to, to.size, to.sha256
+                );
+            }
+        }
+        out
+    }
+}
+
+fn text_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    diff.grouped_ops(CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let changes = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| LineChange {
+                    op: match change.tag() {
+                        ChangeTag::Equal => LineOp::Equal,
+                        ChangeTag::Insert => LineOp::Insert,
+                        ChangeTag::Delete => LineOp::Delete,
+                    },
+                    line: change
+                        .value()
+                        .trim_end_matches('\n')
+                        .trim_end_matches('\r')
+                        .to_string(),
+                })
+                .collect();
+            Some(DiffHunk {
+                // An empty side starts at the line before, as `diff -u` writes it
+                old_start: old_range.start + usize::from(!old_range.is_empty()),
+                old_lines: old_range.len(),
+                new_start: new_range.start + usize::from(!new_range.is_empty()),
+                new_lines: new_range.len(),
+                changes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_snapshots_diff_as_hunks() {
+        let old: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 9\n", "line 9\nline 9.5\n");
+
+        let diff = CheckpointDiff::new("before", old.as_bytes(), "after", new.as_bytes());
+        assert!(!diff.is_empty());
+        let DiffContent::Text { hunks } = &diff.content else {
+            panic!("expected a text diff");
+        };
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(
+            (hunks[0].old_start, hunks[0].old_lines, hunks[0].new_lines),
+            (1, 5, 5)
+        );
+
+        let unified = diff.to_unified();
+        assert!(unified.starts_with(
+            "--- before\n+++ after\n@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n"
+        ));
+        assert!(unified.contains("\n@@ -7,4 +7,5 @@\n line 7\n"));
+        assert!(unified.ends_with(" line 9\n+line 9.5\n line 10\n"));
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["kind"], "text");
+        assert_eq!(json["hunks"][0]["changes"][1]["op"], "delete");
+        assert_eq!(json["hunks"][0]["changes"][2]["line"], "line two");
+
+        let same = CheckpointDiff::new("a", old.as_bytes(), "b", old.as_bytes());
+        assert!(same.is_empty());
+        assert_eq!(same.to_unified(), "");
+    }
+
+    #[test]
+    fn test_binary_snapshots_diff_as_summary() {
+        let old = [0u8, 159, 146, 150];
+        let new = [0u8, 159, 146, 150, 7];
+
+        let diff = CheckpointDiff::new("before", &old, "after", &new);
+        let DiffContent::Binary { from, to } = &diff.content else {
+            panic!("expected a binary diff");
+        };
+        assert_eq!((from.size, to.size), (4, 5));
+        assert_ne!(from.sha256, to.sha256);
+        assert_eq!(from.sha256.len(), 64);
+
+        let summary = diff.to_unified();
+        assert!(summary.starts_with("Binary data differs: before (4 bytes, sha256 "));
+        assert!(summary.contains("after (5 bytes"));
+        assert_eq!(serde_json::to_value(&diff).unwrap()["kind"], "binary");
+
+        assert!(CheckpointDiff::new("a", &old, "b", &old).is_empty());
+    }
+}
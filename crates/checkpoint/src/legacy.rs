@@ -0,0 +1,183 @@
+//! The original in-memory checkpoint types
+//!
+//! These predate [`CheckpointManager`](crate::manager::CheckpointManager)
+//! and only track metadata. They are kept for existing callers; convert
+//! their values with `From` to move to the manager types.
+#![allow(deprecated)]
+
+use crate::manager;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Settings of the in-memory checkpoint system
+#[deprecated(since = "1.0.0", note = "use `manager::CheckpointConfig`")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointConfig {
+    pub enabled: bool,
+    pub auto_checkpoint: bool,
+    pub max_checkpoints: u32,
+    pub retention_days: u32,
+}
+
+/// A checkpoint of the in-memory checkpoint system, without data
+#[deprecated(since = "1.0.0", note = "use `manager::Checkpoint`")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub timestamp: u64,
+    pub metadata: HashMap<String, String>,
+}
+
+/// In-memory list of checkpoints
+#[deprecated(since = "1.0.0", note = "use `manager::CheckpointManager`")]
+pub struct CheckpointSystem {
+    pub config: CheckpointConfig,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl CheckpointSystem {
+    pub fn new(config: CheckpointConfig) -> Self {
+        CheckpointSystem {
+            config,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    pub fn create_checkpoint(
+        &mut self,
+        name: String,
+        description: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<String, ai_cli_utils::error::AIError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let checkpoint = Checkpoint {
+            id: id.clone(),
+            name,
+            description,
+            timestamp,
+            metadata,
+        };
+
+        self.checkpoints.push(checkpoint);
+
+        // Enforce max checkpoints
+        if self.checkpoints.len() > self.config.max_checkpoints as usize {
+            self.checkpoints.remove(0); // Remove oldest checkpoint
+        }
+
+        Ok(id)
+    }
+
+    pub fn list_checkpoints(&self) -> Vec<&Checkpoint> {
+        self.checkpoints.iter().collect()
+    }
+
+    pub fn get_checkpoint(&self, id: &str) -> Option<&Checkpoint> {
+        self.checkpoints.iter().find(|cp| cp.id == id)
+    }
+
+    pub fn delete_checkpoint(&mut self, id: &str) -> Result<(), ai_cli_utils::error::AIError> {
+        let len_before = self.checkpoints.len();
+        self.checkpoints.retain(|cp| cp.id != id);
+
+        if self.checkpoints.len() == len_before {
+            Err(ai_cli_utils::error::AIError::GenericError(format!(
+                "Checkpoint {} not found",
+                id
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl From<CheckpointConfig> for manager::CheckpointConfig {
+    /// Keeps the checkpoint limit; everything else takes the manager defaults
+    fn from(config: CheckpointConfig) -> Self {
+        Self {
+            max_checkpoints: config.max_checkpoints as usize,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<Checkpoint> for manager::Checkpoint {
+    /// Metadata only: the storage key is the checkpoint ID, which holds no
+    /// data until the checkpoint is recreated through a manager
+    fn from(checkpoint: Checkpoint) -> Self {
+        let mut converted =
+            manager::Checkpoint::new(checkpoint.id.clone(), checkpoint.name, checkpoint.id);
+        if !checkpoint.description.is_empty() {
+            converted.description = Some(checkpoint.description);
+        }
+        converted.created_at = i64::try_from(checkpoint.timestamp)
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .unwrap_or_default();
+        converted.metadata = checkpoint.metadata;
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_config(max_checkpoints: u32) -> CheckpointConfig {
+        CheckpointConfig {
+            enabled: true,
+            auto_checkpoint: false,
+            max_checkpoints,
+            retention_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_legacy_system_keeps_newest() {
+        let mut system = CheckpointSystem::new(legacy_config(2));
+        let first = system
+            .create_checkpoint("a".to_string(), String::new(), HashMap::new())
+            .unwrap();
+        let second = system
+            .create_checkpoint("b".to_string(), String::new(), HashMap::new())
+            .unwrap();
+        system
+            .create_checkpoint("c".to_string(), String::new(), HashMap::new())
+            .unwrap();
+
+        assert_eq!(system.list_checkpoints().len(), 2);
+        assert!(system.get_checkpoint(&first).is_none());
+        system.delete_checkpoint(&second).unwrap();
+        assert!(system.delete_checkpoint(&second).is_err());
+    }
+
+    #[test]
+    fn test_convert_to_manager_types() {
+        let config = manager::CheckpointConfig::from(legacy_config(4));
+        assert_eq!(config.max_checkpoints, 4);
+        assert!(config.compression_enabled);
+
+        let legacy = Checkpoint {
+            id: "cp-1".to_string(),
+            name: "before refactor".to_string(),
+            description: String::new(),
+            timestamp: 1_700_000_000,
+            metadata: HashMap::from([("branch".to_string(), "main".to_string())]),
+        };
+        let checkpoint = manager::Checkpoint::from(legacy);
+        assert_eq!(checkpoint.id, "cp-1");
+        assert_eq!(checkpoint.key, "cp-1");
+        assert_eq!(checkpoint.name, "before refactor");
+        assert_eq!(checkpoint.description, None);
+        assert_eq!(checkpoint.created_at.timestamp(), 1_700_000_000);
+        assert_eq!(checkpoint.metadata["branch"], "main");
+    }
+}
@@ -5,14 +5,28 @@
 //! - Incremental snapshots
 //! - Compression and encryption
 //! - Metadata tracking
+//!
+//! Checkpoint data lives in a [`CheckpointStorage`] under the checkpoint's
+//! storage key; [`LocalStorage`] keeps it on disk. The index of checkpoints
+//! is stored alongside under [`INDEX_KEY`]. Data is written before the index
+//! refers to it and deleted only after the index stops referring to it, so
+//! a crash leaves at worst unreferenced data, which
+//! [`CheckpointManager::new`] removes.
+//!
+//! Every change holds the storage lock from writing data to rewriting the
+//! index, and starts from the index as stored, so managers in different
+//! processes sharing a store neither drop each other's checkpoints nor
+//! mistake each other's in-flight data for leftovers.
 
+use crate::storage::{CheckpointStorage, LocalStorage, StorageGuard};
 use ai_cli_security::encryption::Aes256GcmEncryption;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::RwLock;
 
 /// Checkpoint error types
@@ -39,6 +53,25 @@ pub enum CheckpointError {
 
 pub type CheckpointResult<T> = Result<T, CheckpointError>;
 
+/// Storage key of the persisted checkpoint index
+pub const INDEX_KEY: &str = "index";
+
+/// Read size used when streaming checkpoint files
+const RESTORE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// First line of an exported checkpoint archive
+const ARCHIVE_HEADER: &str = "AI-CHECKPOINT-ARCHIVE 1";
+
+/// Manifest of an exported checkpoint archive
+///
+/// An archive is the header line, this manifest as one line of JSON, then
+/// the stored bytes of each checkpoint back to back in manifest order.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifest {
+    /// The exported checkpoint and its ancestors, oldest ancestor first
+    checkpoints: Vec<Checkpoint>,
+}
+
 /// Checkpoint metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
@@ -46,7 +79,9 @@ pub struct Checkpoint {
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
-    pub file_path: PathBuf,
+    /// Key of the checkpoint data in the manager's storage
+    #[serde(default)]
+    pub key: String,
     pub size_bytes: u64,
     pub compressed: bool,
     pub encrypted: bool,
@@ -56,13 +91,13 @@ pub struct Checkpoint {
 }
 
 impl Checkpoint {
-    pub fn new(id: impl Into<String>, name: impl Into<String>, file_path: PathBuf) -> Self {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, key: impl Into<String>) -> Self {
         Self {
             id: id.into(),
             name: name.into(),
             description: None,
             created_at: Utc::now(),
-            file_path,
+            key: key.into(),
             size_bytes: 0,
             compressed: false,
             encrypted: false,
@@ -113,18 +148,50 @@ impl Default for CheckpointConfig {
 /// Checkpoint manager
 pub struct CheckpointManager {
     config: CheckpointConfig,
+    storage: Box<dyn CheckpointStorage>,
     checkpoints: Arc<RwLock<HashMap<String, Checkpoint>>>,
 }
 
 impl CheckpointManager {
-    /// Create a new checkpoint manager
+    /// Create a checkpoint manager storing data under `config.storage_path`
+    ///
+    /// Loads the checkpoints recorded there and removes data left behind by
+    /// a create or delete that was interrupted.
     pub fn new(config: CheckpointConfig) -> CheckpointResult<Self> {
-        // Create storage directory if it doesn't exist
-        std::fs::create_dir_all(&config.storage_path)?;
+        let storage = LocalStorage::open(&config.storage_path)?;
+        let checkpoints = {
+            let _guard = storage.lock_blocking()?;
+            recover(&storage)?
+        };
+        Ok(Self {
+            config,
+            storage: Box::new(storage),
+            checkpoints: Arc::new(RwLock::new(checkpoints)),
+        })
+    }
 
+    /// Create a checkpoint manager storing data in `storage`
+    ///
+    /// Like [`CheckpointManager::new`], loads the checkpoints recorded there
+    /// and removes data they do not refer to; `config.storage_path` is not
+    /// used.
+    pub async fn with_storage(
+        config: CheckpointConfig,
+        storage: Box<dyn CheckpointStorage>,
+    ) -> CheckpointResult<Self> {
+        let checkpoints = {
+            let _guard = storage.lock().await?;
+            let index = load_index(storage.as_ref()).await?;
+            for key in unreferenced(&index, storage.list().await?) {
+                log::warn!("Removing unreferenced checkpoint data {}", key);
+                storage.delete(&key).await?;
+            }
+            index
+        };
         Ok(Self {
             config,
-            checkpoints: Arc::new(RwLock::new(HashMap::new())),
+            storage,
+            checkpoints: Arc::new(RwLock::new(checkpoints)),
         })
     }
 
@@ -133,55 +200,52 @@ impl CheckpointManager {
         &self,
         name: impl Into<String>,
         data: &[u8],
+    ) -> CheckpointResult<Checkpoint> {
+        self.create(name.into(), None, data).await
+    }
+
+    /// Create a checkpoint with description
+    pub async fn create_checkpoint_with_description(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        data: &[u8],
+    ) -> CheckpointResult<Checkpoint> {
+        self.create(name.into(), Some(description.into()), data)
+            .await
+    }
+
+    async fn create(
+        &self,
+        name: String,
+        description: Option<String>,
+        data: &[u8],
     ) -> CheckpointResult<Checkpoint> {
         let id = uuid::Uuid::new_v4().to_string();
-        let name = name.into();
-        let file_name = format!("{}.ckpt", id);
-        let file_path = self.config.storage_path.join(&file_name);
 
         // Process data (compression, encryption)
         let processed_data = self.process_data(data)?;
 
-        // Write to file
-        tokio::fs::write(&file_path, &processed_data).await?;
+        // Store the checkpoint and enforce the limit under one lock, so
+        // concurrent creates can never leave more than max_checkpoints
+        let mut checkpoints = self.checkpoints.write().await;
+        let _guard = self.lock_index(&mut checkpoints).await?;
+        self.storage.put(&id, &processed_data).await?;
 
         // Calculate checksum
         let checksum = self.calculate_checksum(&processed_data);
 
         // Create checkpoint metadata
-        let mut checkpoint = Checkpoint::new(id.clone(), name, file_path);
+        let mut checkpoint = Checkpoint::new(id.clone(), name, id.clone());
+        checkpoint.description = description;
         checkpoint.size_bytes = processed_data.len() as u64;
         checkpoint.compressed = self.config.compression_enabled;
         checkpoint.encrypted = self.config.encryption_enabled;
         checkpoint.checksum = checksum;
 
-        // Store checkpoint
-        self.checkpoints
-            .write()
-            .await
-            .insert(id.clone(), checkpoint.clone());
-
-        // Enforce max checkpoints limit
-        self.enforce_checkpoint_limit().await?;
-
-        Ok(checkpoint)
-    }
-
-    /// Create a checkpoint with description
-    pub async fn create_checkpoint_with_description(
-        &self,
-        name: impl Into<String>,
-        description: impl Into<String>,
-        data: &[u8],
-    ) -> CheckpointResult<Checkpoint> {
-        let mut checkpoint = self.create_checkpoint(name, data).await?;
-        checkpoint.description = Some(description.into());
-
-        // Update stored checkpoint
-        self.checkpoints
-            .write()
-            .await
-            .insert(checkpoint.id.clone(), checkpoint.clone());
+        checkpoints.insert(id, checkpoint.clone());
+        let evicted = self.enforce_checkpoint_limit(&mut checkpoints);
+        self.save_index(&checkpoints, &evicted).await?;
 
         Ok(checkpoint)
     }
@@ -193,8 +257,7 @@ impl CheckpointManager {
             .get(id)
             .ok_or_else(|| CheckpointError::NotFound(id.to_string()))?;
 
-        // Read file
-        let data = tokio::fs::read(&checkpoint.file_path).await?;
+        let data = self.storage.get(&checkpoint.key).await?;
 
         // Verify checksum
         let checksum = self.calculate_checksum(&data);
@@ -206,11 +269,172 @@ impl CheckpointManager {
         self.unprocess_data(&data, checkpoint)
     }
 
+    /// Restore a checkpoint by streaming its contents into `writer`
+    ///
+    /// Unencrypted checkpoints are copied in chunks without buffering the
+    /// whole file, hashing as they go. Because the checksum can only be
+    /// compared at the end, a mismatch is reported after the data has been
+    /// written; write to a temporary location and discard it on error.
+    /// Encrypted checkpoints must be decrypted as a whole and are buffered.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn restore_checkpoint_to_writer<W: AsyncWrite + Unpin>(
+        &self,
+        id: &str,
+        mut writer: W,
+    ) -> CheckpointResult<u64> {
+        let checkpoint = self
+            .get_checkpoint(id)
+            .await
+            .ok_or_else(|| CheckpointError::NotFound(id.to_string()))?;
+
+        if checkpoint.encrypted {
+            let data = self.restore_checkpoint(id).await?;
+            writer.write_all(&data).await?;
+            writer.flush().await?;
+            return Ok(data.len() as u64);
+        }
+
+        use sha2::{Digest, Sha256};
+        let mut file = self.storage.reader(&checkpoint.key).await?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; RESTORE_CHUNK_SIZE];
+        let mut written = 0u64;
+        loop {
+            let read = file.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            writer.write_all(&buf[..read]).await?;
+            written += read as u64;
+        }
+        writer.flush().await?;
+
+        if format!("{:x}", hasher.finalize()) != checkpoint.checksum {
+            return Err(CheckpointError::Invalid("Checksum mismatch".to_string()));
+        }
+        Ok(written)
+    }
+
+    /// Export a checkpoint and its parent chain to a single archive file
+    ///
+    /// The stored bytes are copied as-is, so an encrypted checkpoint can only
+    /// be restored after import by a manager using the same password.
+    pub async fn export_checkpoint(&self, id: &str, out_path: &Path) -> CheckpointResult<()> {
+        let mut chain = Vec::new();
+        {
+            let checkpoints = self.checkpoints.read().await;
+            let mut next = Some(id.to_string());
+            while let Some(id) = next {
+                let checkpoint = checkpoints
+                    .get(&id)
+                    .ok_or_else(|| CheckpointError::NotFound(id.clone()))?;
+                if chain.iter().any(|c: &Checkpoint| c.id == checkpoint.id) {
+                    return Err(CheckpointError::Invalid(format!(
+                        "Parent chain of {} has a cycle",
+                        id
+                    )));
+                }
+                next = checkpoint.parent_id.clone();
+                chain.push(checkpoint.clone());
+            }
+        }
+        chain.reverse();
+
+        let manifest = serde_json::to_string(&ArchiveManifest {
+            checkpoints: chain.clone(),
+        })
+        .map_err(|e| CheckpointError::SerializationError(e.to_string()))?;
+        let mut archive = format!("{}\n{}\n", ARCHIVE_HEADER, manifest).into_bytes();
+        for checkpoint in &chain {
+            let data = self.storage.get(&checkpoint.key).await?;
+            if self.calculate_checksum(&data) != checkpoint.checksum {
+                return Err(CheckpointError::Invalid(format!(
+                    "Checksum mismatch for {}",
+                    checkpoint.id
+                )));
+            }
+            archive.extend_from_slice(&data);
+        }
+
+        tokio::fs::write(out_path, archive).await?;
+        Ok(())
+    }
+
+    /// Import an archive written by [`CheckpointManager::export_checkpoint`]
+    ///
+    /// Every checkpoint is verified against its checksum before anything is
+    /// registered. Checkpoints whose ID is already in use get a fresh one,
+    /// and parent links are rewritten to match. Returns the exported
+    /// checkpoint (the last in the chain).
+    pub async fn import_checkpoint(&self, in_path: &Path) -> CheckpointResult<Checkpoint> {
+        let archive = tokio::fs::read(in_path).await?;
+        let invalid =
+            |msg: &str| CheckpointError::Invalid(format!("{}: {}", in_path.display(), msg));
+
+        let (header, rest) = split_line(&archive).ok_or_else(|| invalid("missing header"))?;
+        if header != ARCHIVE_HEADER.as_bytes() {
+            return Err(invalid("not a checkpoint archive"));
+        }
+        let (manifest, mut data) = split_line(rest).ok_or_else(|| invalid("missing manifest"))?;
+        let manifest: ArchiveManifest = serde_json::from_slice(manifest)
+            .map_err(|e| CheckpointError::SerializationError(e.to_string()))?;
+        if manifest.checkpoints.is_empty() {
+            return Err(invalid("archive is empty"));
+        }
+
+        let mut blobs = Vec::with_capacity(manifest.checkpoints.len());
+        for checkpoint in &manifest.checkpoints {
+            let len = usize::try_from(checkpoint.size_bytes)
+                .ok()
+                .filter(|len| *len <= data.len())
+                .ok_or_else(|| invalid("archive is truncated"))?;
+            let (blob, rest) = data.split_at(len);
+            if self.calculate_checksum(blob) != checkpoint.checksum {
+                return Err(invalid(&format!("checksum mismatch for {}", checkpoint.id)));
+            }
+            blobs.push(blob);
+            data = rest;
+        }
+        if !data.is_empty() {
+            return Err(invalid("unexpected data after the last checkpoint"));
+        }
+
+        let mut checkpoints = self.checkpoints.write().await;
+        let _guard = self.lock_index(&mut checkpoints).await?;
+        let mut new_ids: HashMap<String, String> = HashMap::new();
+        let mut imported = Vec::with_capacity(blobs.len());
+        for (mut checkpoint, blob) in manifest.checkpoints.into_iter().zip(blobs) {
+            let old_id = checkpoint.id.clone();
+            if checkpoints.contains_key(&old_id) {
+                checkpoint.id = uuid::Uuid::new_v4().to_string();
+            }
+            checkpoint.parent_id = checkpoint
+                .parent_id
+                .map(|parent| new_ids.get(&parent).cloned().unwrap_or(parent));
+            checkpoint.key = checkpoint.id.clone();
+            self.storage.put(&checkpoint.key, blob).await?;
+
+            new_ids.insert(old_id, checkpoint.id.clone());
+            imported.push(checkpoint);
+        }
+
+        let last = imported.last().cloned().expect("archive is not empty");
+        for checkpoint in imported {
+            checkpoints.insert(checkpoint.id.clone(), checkpoint);
+        }
+        let evicted = self.enforce_checkpoint_limit(&mut checkpoints);
+        self.save_index(&checkpoints, &evicted).await?;
+
+        Ok(last)
+    }
+
     /// List all checkpoints
     pub async fn list_checkpoints(&self) -> Vec<Checkpoint> {
         let checkpoints = self.checkpoints.read().await;
         let mut list: Vec<_> = checkpoints.values().cloned().collect();
-        list.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        list.sort_by_key(|c| std::cmp::Reverse(c.created_at));
         list
     }
 
@@ -222,12 +446,10 @@ impl CheckpointManager {
     /// Delete a checkpoint
     pub async fn delete_checkpoint(&self, id: &str) -> CheckpointResult<bool> {
         let mut checkpoints = self.checkpoints.write().await;
+        let _guard = self.lock_index(&mut checkpoints).await?;
 
         if let Some(checkpoint) = checkpoints.remove(id) {
-            // Delete file
-            if checkpoint.file_path.exists() {
-                tokio::fs::remove_file(&checkpoint.file_path).await?;
-            }
+            self.save_index(&checkpoints, &[checkpoint]).await?;
             Ok(true)
         } else {
             Ok(false)
@@ -237,15 +459,9 @@ impl CheckpointManager {
     /// Clear all checkpoints
     pub async fn clear_all(&self) -> CheckpointResult<()> {
         let mut checkpoints = self.checkpoints.write().await;
-
-        for checkpoint in checkpoints.values() {
-            if checkpoint.file_path.exists() {
-                tokio::fs::remove_file(&checkpoint.file_path).await?;
-            }
-        }
-
-        checkpoints.clear();
-        Ok(())
+        let _guard = self.lock_index(&mut checkpoints).await?;
+        let removed: Vec<Checkpoint> = checkpoints.drain().map(|(_, c)| c).collect();
+        self.save_index(&checkpoints, &removed).await
     }
 
     /// Process data (compress and/or encrypt)
@@ -300,25 +516,59 @@ impl CheckpointManager {
         format!("{:x}", hasher.finalize())
     }
 
-    /// Enforce checkpoint limit
-    async fn enforce_checkpoint_limit(&self) -> CheckpointResult<()> {
-        let mut checkpoints = self.checkpoints.write().await;
+    /// Enforce checkpoint limit, removing the oldest checkpoints first
+    ///
+    /// Takes the already-locked index so callers can combine it with their
+    /// own update in one critical section. Returns the removed checkpoints,
+    /// whose data is deleted by [`CheckpointManager::save_index`].
+    fn enforce_checkpoint_limit(
+        &self,
+        checkpoints: &mut HashMap<String, Checkpoint>,
+    ) -> Vec<Checkpoint> {
+        let max_checkpoints = self.config.max_checkpoints;
+        if checkpoints.len() <= max_checkpoints {
+            return Vec::new();
+        }
 
-        if checkpoints.len() > self.config.max_checkpoints {
-            // Sort by creation time
-            let mut sorted: Vec<_> = checkpoints.values().cloned().collect();
-            sorted.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        // Sort by creation time
+        let mut sorted: Vec<_> = checkpoints.values().cloned().collect();
+        sorted.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
 
-            // Remove oldest checkpoints
-            let to_remove = checkpoints.len() - self.config.max_checkpoints;
-            for checkpoint in sorted.iter().take(to_remove) {
-                if checkpoint.file_path.exists() {
-                    tokio::fs::remove_file(&checkpoint.file_path).await?;
-                }
-                checkpoints.remove(&checkpoint.id);
-            }
+        // Remove oldest checkpoints
+        let to_remove = checkpoints.len() - max_checkpoints;
+        sorted.truncate(to_remove);
+        for checkpoint in &sorted {
+            checkpoints.remove(&checkpoint.id);
         }
+        sorted
+    }
+
+    /// Take the storage lock and replace the locked index with the stored
+    /// one, picking up changes made by other processes
+    async fn lock_index(
+        &self,
+        checkpoints: &mut HashMap<String, Checkpoint>,
+    ) -> CheckpointResult<StorageGuard> {
+        let guard = self.storage.lock().await?;
+        *checkpoints = load_index(self.storage.as_ref()).await?;
+        Ok(guard)
+    }
 
+    /// Persist the locked index, then delete the data of `removed`
+    async fn save_index(
+        &self,
+        checkpoints: &HashMap<String, Checkpoint>,
+        removed: &[Checkpoint],
+    ) -> CheckpointResult<()> {
+        let mut index: Vec<&Checkpoint> = checkpoints.values().collect();
+        index.sort_by(|a, b| a.id.cmp(&b.id));
+        let json = serde_json::to_vec_pretty(&index)
+            .map_err(|e| CheckpointError::SerializationError(e.to_string()))?;
+        self.storage.put(INDEX_KEY, &json).await?;
+
+        for checkpoint in removed {
+            self.storage.delete(&checkpoint.key).await?;
+        }
         Ok(())
     }
 
@@ -336,6 +586,58 @@ impl CheckpointManager {
     }
 }
 
+/// Read the index persisted in `storage` and remove data it does not refer to
+///
+/// Such data belongs to a create that died before recording it, or a delete
+/// that died after forgetting it.
+///
+/// The caller must hold the storage lock.
+fn recover(storage: &LocalStorage) -> CheckpointResult<HashMap<String, Checkpoint>> {
+    let index = match std::fs::read(storage.path(INDEX_KEY)?) {
+        Ok(json) => parse_index(&json)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    for key in unreferenced(&index, storage.keys()?) {
+        log::warn!("Removing unreferenced checkpoint data {}", key);
+        std::fs::remove_file(storage.path(&key)?)?;
+    }
+    Ok(index)
+}
+
+/// Read the index persisted in `storage`, empty when there is none yet
+async fn load_index(
+    storage: &dyn CheckpointStorage,
+) -> CheckpointResult<HashMap<String, Checkpoint>> {
+    match storage.get(INDEX_KEY).await {
+        Ok(json) => parse_index(&json),
+        Err(CheckpointError::NotFound(_)) => Ok(HashMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The stored `keys` other than the index that no checkpoint in `index` uses
+fn unreferenced(index: &HashMap<String, Checkpoint>, keys: Vec<String>) -> Vec<String> {
+    let referenced: HashSet<&str> = index.values().map(|c| c.key.as_str()).collect();
+    keys.into_iter()
+        .filter(|key| key != INDEX_KEY && !referenced.contains(key.as_str()))
+        .collect()
+}
+
+/// Checkpoints by ID from a stored index
+fn parse_index(json: &[u8]) -> CheckpointResult<HashMap<String, Checkpoint>> {
+    let index: Vec<Checkpoint> = serde_json::from_slice(json)
+        .map_err(|e| CheckpointError::SerializationError(format!("{}: {}", INDEX_KEY, e)))?;
+    Ok(index.into_iter().map(|c| (c.id.clone(), c)).collect())
+}
+
+/// Split off the first line, without its newline
+fn split_line(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = data.iter().position(|b| *b == b'\n')?;
+    Some((&data[..end], &data[end + 1..]))
+}
+
 /// Checkpoint statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckpointStats {
@@ -347,8 +649,48 @@ pub struct CheckpointStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
     use tempfile::TempDir;
 
+    /// Storage kept in memory, to show the manager only needs the trait;
+    /// clones share the blobs
+    #[derive(Default, Clone)]
+    struct MemoryStorage {
+        blobs: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CheckpointStorage for MemoryStorage {
+        async fn put(&self, key: &str, data: &[u8]) -> CheckpointResult<()> {
+            self.blobs
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        async fn get(&self, key: &str) -> CheckpointResult<Vec<u8>> {
+            self.blobs
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| CheckpointError::NotFound(key.to_string()))
+        }
+
+        async fn delete(&self, key: &str) -> CheckpointResult<bool> {
+            Ok(self.blobs.lock().unwrap().remove(key).is_some())
+        }
+
+        async fn list(&self) -> CheckpointResult<Vec<String>> {
+            Ok(self.blobs.lock().unwrap().keys().cloned().collect())
+        }
+
+        fn location(&self) -> String {
+            "memory".to_string()
+        }
+    }
+
     fn create_test_config(temp_dir: &TempDir) -> CheckpointConfig {
         CheckpointConfig {
             storage_path: temp_dir.path().to_path_buf(),
@@ -359,17 +701,24 @@ mod tests {
         }
     }
 
+    /// File a local-disk manager under `temp_dir` keeps `checkpoint` in
+    fn data_path(temp_dir: &TempDir, checkpoint: &Checkpoint) -> PathBuf {
+        LocalStorage::open(temp_dir.path())
+            .unwrap()
+            .path(&checkpoint.key)
+            .unwrap()
+    }
+
     #[test]
     fn test_checkpoint_creation() {
-        let checkpoint = Checkpoint::new("id1", "test", PathBuf::from("/test"));
+        let checkpoint = Checkpoint::new("id1", "test", "id1");
         assert_eq!(checkpoint.id, "id1");
         assert_eq!(checkpoint.name, "test");
     }
 
     #[test]
     fn test_checkpoint_with_description() {
-        let checkpoint = Checkpoint::new("id1", "test", PathBuf::from("/test"))
-            .with_description("Test description");
+        let checkpoint = Checkpoint::new("id1", "test", "id1").with_description("Test description");
         assert_eq!(checkpoint.description, Some("Test description".to_string()));
     }
 
@@ -389,6 +738,68 @@ mod tests {
         assert_eq!(manager.list_checkpoints().await.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_manager_with_pluggable_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::with_storage(
+            create_test_config(&temp_dir),
+            Box::new(MemoryStorage::default()),
+        )
+        .await
+        .unwrap();
+
+        let checkpoint = manager.create_checkpoint("cp", b"in memory").await.unwrap();
+        assert_eq!(
+            manager.restore_checkpoint(&checkpoint.id).await.unwrap(),
+            b"in memory"
+        );
+        let mut out = Vec::new();
+        manager
+            .restore_checkpoint_to_writer(&checkpoint.id, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(out, b"in memory");
+
+        assert!(manager.delete_checkpoint(&checkpoint.id).await.unwrap());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reopening_pluggable_storage_keeps_checkpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MemoryStorage::default();
+        let manager = CheckpointManager::with_storage(
+            create_test_config(&temp_dir),
+            Box::new(storage.clone()),
+        )
+        .await
+        .unwrap();
+        let first = manager.create_checkpoint("first", b"one").await.unwrap();
+        manager.create_checkpoint("second", b"two").await.unwrap();
+        drop(manager);
+        storage.put("orphan", b"never indexed").await.unwrap();
+
+        let manager = CheckpointManager::with_storage(
+            create_test_config(&temp_dir),
+            Box::new(storage.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(manager.list_checkpoints().await.len(), 2);
+        assert_eq!(manager.restore_checkpoint(&first.id).await.unwrap(), b"one");
+        assert!(matches!(
+            storage.get("orphan").await,
+            Err(CheckpointError::NotFound(_))
+        ));
+
+        manager.create_checkpoint("third", b"three").await.unwrap();
+        let reopened =
+            CheckpointManager::with_storage(create_test_config(&temp_dir), Box::new(storage))
+                .await
+                .unwrap();
+        assert_eq!(reopened.list_checkpoints().await.len(), 3);
+    }
+
     #[tokio::test]
     async fn test_create_checkpoint() {
         let temp_dir = TempDir::new().unwrap();
@@ -399,7 +810,7 @@ mod tests {
         let checkpoint = manager.create_checkpoint("test", data).await.unwrap();
 
         assert_eq!(checkpoint.name, "test");
-        assert!(checkpoint.file_path.exists());
+        assert!(data_path(&temp_dir, &checkpoint).exists());
     }
 
     #[tokio::test]
@@ -418,6 +829,110 @@ mod tests {
         assert_eq!(restored, original_data);
     }
 
+    #[tokio::test]
+    async fn test_restore_checkpoint_to_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+
+        // Larger than one chunk so the copy loops
+        let data: Vec<u8> = (0..RESTORE_CHUNK_SIZE * 2 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let checkpoint = manager.create_checkpoint("big", &data).await.unwrap();
+
+        let out_dir = TempDir::new().unwrap();
+        let out_path = out_dir.path().join("restored.bin");
+        let file = tokio::fs::File::create(&out_path).await.unwrap();
+        let written = manager
+            .restore_checkpoint_to_writer(&checkpoint.id, file)
+            .await
+            .unwrap();
+
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(std::fs::read(&out_path).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_restore_checkpoint_to_writer_detects_corruption() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        let checkpoint = manager.create_checkpoint("cp", b"original").await.unwrap();
+        std::fs::write(data_path(&temp_dir, &checkpoint), b"tampered").unwrap();
+
+        let mut out = Vec::new();
+        let result = manager
+            .restore_checkpoint_to_writer(&checkpoint.id, &mut out)
+            .await;
+        assert!(matches!(result, Err(CheckpointError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let source = CheckpointManager::new(create_test_config(&source_dir)).unwrap();
+        let base = source.create_checkpoint("base", b"full").await.unwrap();
+        let child = source.create_checkpoint("child", b"delta").await.unwrap();
+        source
+            .checkpoints
+            .write()
+            .await
+            .get_mut(&child.id)
+            .unwrap()
+            .parent_id = Some(base.id.clone());
+
+        let archive = source_dir.path().join("child.ckptar");
+        source.export_checkpoint(&child.id, &archive).await.unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = CheckpointManager::new(CheckpointConfig {
+            max_checkpoints: 10,
+            ..create_test_config(&target_dir)
+        })
+        .unwrap();
+        let imported = target.import_checkpoint(&archive).await.unwrap();
+        assert_eq!(imported.id, child.id);
+        assert_eq!(imported.parent_id.as_deref(), Some(base.id.as_str()));
+        assert!(data_path(&target_dir, &imported).exists());
+        assert_eq!(
+            target.restore_checkpoint(&child.id).await.unwrap(),
+            b"delta"
+        );
+        assert_eq!(target.restore_checkpoint(&base.id).await.unwrap(), b"full");
+
+        // Importing again collides with the existing IDs
+        let again = target.import_checkpoint(&archive).await.unwrap();
+        assert_ne!(again.id, child.id);
+        let parent = again.parent_id.unwrap();
+        assert_ne!(parent, base.id);
+        assert_eq!(target.restore_checkpoint(&parent).await.unwrap(), b"full");
+        assert_eq!(target.list_checkpoints().await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_corrupted_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        let checkpoint = manager.create_checkpoint("cp", b"payload").await.unwrap();
+        let archive = temp_dir.path().join("cp.ckptar");
+        manager
+            .export_checkpoint(&checkpoint.id, &archive)
+            .await
+            .unwrap();
+
+        let mut bytes = std::fs::read(&archive).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&archive, bytes).unwrap();
+
+        let other_dir = TempDir::new().unwrap();
+        let other = CheckpointManager::new(create_test_config(&other_dir)).unwrap();
+        let result = other.import_checkpoint(&archive).await;
+        assert!(
+            matches!(result, Err(CheckpointError::Invalid(ref msg)) if msg.contains("checksum"))
+        );
+        assert!(other.list_checkpoints().await.is_empty());
+    }
+
     #[tokio::test]
     async fn test_list_checkpoints() {
         let temp_dir = TempDir::new().unwrap();
@@ -441,7 +956,7 @@ mod tests {
         let deleted = manager.delete_checkpoint(&checkpoint.id).await.unwrap();
 
         assert!(deleted);
-        assert!(!checkpoint.file_path.exists());
+        assert!(!data_path(&temp_dir, &checkpoint).exists());
     }
 
     #[tokio::test]
@@ -463,6 +978,97 @@ mod tests {
         assert_eq!(list.len(), 3);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_creates_respect_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config(&temp_dir);
+        let max = config.max_checkpoints;
+        let manager = Arc::new(CheckpointManager::new(config).unwrap());
+
+        let tasks: Vec<_> = (0..40)
+            .map(|i| {
+                let manager = manager.clone();
+                tokio::spawn(async move {
+                    let data = format!("data {}", i);
+                    if i % 2 == 0 {
+                        manager
+                            .create_checkpoint(format!("cp{}", i), data.as_bytes())
+                            .await
+                    } else {
+                        manager
+                            .create_checkpoint_with_description(
+                                format!("cp{}", i),
+                                "desc",
+                                data.as_bytes(),
+                            )
+                            .await
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let checkpoints = manager.list_checkpoints().await;
+        assert_eq!(checkpoints.len(), max);
+        assert!(checkpoints.iter().all(|c| data_path(&temp_dir, c).exists()));
+
+        // The data of each checkpoint plus the index and the lock file
+        let files = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(files, max + 2);
+    }
+
+    #[tokio::test]
+    async fn test_restart_recovers_from_interrupted_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        let kept = manager.create_checkpoint("kept", b"data").await.unwrap();
+        drop(manager);
+
+        // A crash mid-write leaves a temporary file, and a crash between
+        // writing data and recording it leaves data the index never names
+        let partial = temp_dir.path().join(".crashed.ckpt.tmp-42");
+        std::fs::write(&partial, b"part").unwrap();
+        let orphan = temp_dir.path().join("orphan.ckpt");
+        std::fs::write(&orphan, b"orphaned").unwrap();
+
+        let manager = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        assert!(!partial.exists());
+        assert!(!orphan.exists());
+        let checkpoints = manager.list_checkpoints().await;
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].id, kept.id);
+        assert_eq!(manager.restore_checkpoint(&kept.id).await.unwrap(), b"data");
+    }
+
+    #[tokio::test]
+    async fn test_managers_sharing_a_store_keep_each_others_checkpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        let second = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+
+        let a = first.create_checkpoint("a", b"from first").await.unwrap();
+        let b = second.create_checkpoint("b", b"from second").await.unwrap();
+        // A third process starting now must not take either for leftovers
+        let third = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        assert_eq!(third.list_checkpoints().await.len(), 2);
+        assert!(first.delete_checkpoint(&b.id).await.unwrap());
+
+        let reopened = CheckpointManager::new(create_test_config(&temp_dir)).unwrap();
+        let names: Vec<String> = reopened
+            .list_checkpoints()
+            .await
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["a"]);
+        assert_eq!(
+            reopened.restore_checkpoint(&a.id).await.unwrap(),
+            b"from first"
+        );
+    }
+
     #[tokio::test]
     async fn test_get_stats() {
         let temp_dir = TempDir::new().unwrap();
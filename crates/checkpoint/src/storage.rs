@@ -1,4 +1,198 @@
+//! Where checkpoint data is kept
+//!
+//! [`CheckpointStorage`] stores opaque blobs under string keys; the
+//! checkpoint manager never sees paths. [`LocalStorage`] keeps each blob in
+//! a `<key>.ckpt` file under one directory, written to a temporary file,
+//! synced and renamed into place so a crash never leaves a partial blob.
+//!
+//! Several processes may share a store. [`CheckpointStorage::lock`] keeps
+//! them from interleaving changes to it; [`LocalStorage`] takes an advisory
+//! lock on a `.lock` file in its directory.
+
+use crate::manager::{CheckpointError, CheckpointResult};
+use ai_cli_utils::fs::write_atomic;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncRead;
+
+/// Name of the lock file in a [`LocalStorage`] directory
+const LOCK_FILE: &str = ".lock";
+
+/// Holds a store's lock until dropped
+pub type StorageGuard = Box<dyn Send + Sync>;
+
+/// Blob store for checkpoint data
+#[async_trait]
+pub trait CheckpointStorage: Send + Sync {
+    /// Store `data` under `key`, replacing anything already there
+    async fn put(&self, key: &str, data: &[u8]) -> CheckpointResult<()>;
+
+    /// Read the data stored under `key`
+    async fn get(&self, key: &str) -> CheckpointResult<Vec<u8>>;
+
+    /// Remove `key`, returning whether it existed
+    async fn delete(&self, key: &str) -> CheckpointResult<bool>;
+
+    /// Every stored key, in no particular order
+    async fn list(&self) -> CheckpointResult<Vec<String>>;
+
+    /// Read the data under `key` incrementally
+    ///
+    /// The default reads it whole; stores that can stream should override it.
+    async fn reader(&self, key: &str) -> CheckpointResult<Box<dyn AsyncRead + Send + Unpin>> {
+        Ok(Box::new(std::io::Cursor::new(self.get(key).await?)))
+    }
+
+    /// Lock the store against other processes until the guard is dropped
+    ///
+    /// The default does nothing, for stores that only one process uses.
+    async fn lock(&self) -> CheckpointResult<StorageGuard> {
+        Ok(Box::new(()))
+    }
+
+    /// Human-readable location of the store, for display
+    fn location(&self) -> String;
+}
+
+/// Checkpoint storage in a local directory
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Storage under `root`, creating the directory if needed
+    ///
+    /// Temporary files left by a write that never finished are removed.
+    /// Writes hold the store's lock, so a file another process is still
+    /// writing is never mistaken for one.
+    pub fn open(root: impl Into<PathBuf>) -> CheckpointResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        let storage = Self { root };
+        let _guard = storage.lock_blocking()?;
+        for entry in std::fs::read_dir(&storage.root)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let partial = name
+                .to_str()
+                .is_some_and(|n| n.starts_with('.') && n.contains(".ckpt.tmp"));
+            if partial {
+                log::warn!("Removing partial checkpoint write {:?}", name);
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(storage)
+    }
+
+    /// Take the store's lock, waiting for any other process holding it
+    ///
+    /// The lock is released when the returned file is closed.
+    pub fn lock_blocking(&self) -> CheckpointResult<File> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.root.join(LOCK_FILE))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// File holding the data for `key`
+    pub fn path(&self, key: &str) -> CheckpointResult<PathBuf> {
+        let valid = !key.is_empty()
+            && !key.starts_with('.')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(CheckpointError::StorageError(format!(
+                "Invalid storage key '{}'",
+                key
+            )));
+        }
+        Ok(self.root.join(format!("{}.ckpt", key)))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Every stored key, read without going through the async runtime
+    pub fn keys(&self) -> CheckpointResult<Vec<String>> {
+        let mut keys = Vec::new();
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(keys),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            if let Some(key) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|n| n.strip_suffix(".ckpt"))
+            {
+                keys.push(key.to_string());
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Map a missing file to [`CheckpointError::NotFound`]
+fn not_found(key: &str) -> impl FnOnce(std::io::Error) -> CheckpointError + '_ {
+    move |e| match e.kind() {
+        std::io::ErrorKind::NotFound => CheckpointError::NotFound(key.to_string()),
+        _ => CheckpointError::IoError(e),
+    }
+}
+
+#[async_trait]
+impl CheckpointStorage for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> CheckpointResult<()> {
+        write_atomic(&self.path(key)?, data)?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> CheckpointResult<Vec<u8>> {
+        tokio::fs::read(self.path(key)?)
+            .await
+            .map_err(not_found(key))
+    }
+
+    async fn delete(&self, key: &str) -> CheckpointResult<bool> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self) -> CheckpointResult<Vec<String>> {
+        self.keys()
+    }
+
+    async fn reader(&self, key: &str) -> CheckpointResult<Box<dyn AsyncRead + Send + Unpin>> {
+        let file = tokio::fs::File::open(self.path(key)?)
+            .await
+            .map_err(not_found(key))?;
+        Ok(Box::new(file))
+    }
+
+    async fn lock(&self) -> CheckpointResult<StorageGuard> {
+        let storage = self.clone();
+        let file = tokio::task::spawn_blocking(move || storage.lock_blocking())
+            .await
+            .map_err(|e| CheckpointError::StorageError(e.to_string()))??;
+        Ok(Box::new(file))
+    }
+
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
@@ -58,3 +252,59 @@ impl StorageBackend {
         Ok(checkpoints)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_local_storage_through_trait() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage: Box<dyn CheckpointStorage> =
+            Box::new(LocalStorage::open(temp_dir.path().join("store")).unwrap());
+
+        storage.put("a", b"first").await.unwrap();
+        storage.put("b", b"second").await.unwrap();
+        storage.put("a", b"replaced").await.unwrap();
+        assert_eq!(storage.get("a").await.unwrap(), b"replaced");
+
+        let mut streamed = Vec::new();
+        storage
+            .reader("b")
+            .await
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(streamed, b"second");
+
+        let mut keys = storage.list().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b"]);
+
+        assert!(storage.delete("a").await.unwrap());
+        assert!(!storage.delete("a").await.unwrap());
+        assert!(matches!(
+            storage.get("a").await,
+            Err(CheckpointError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_rejects_path_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalStorage::open(temp_dir.path()).unwrap();
+        for key in ["../escape", "a/b", "", ".hidden"] {
+            assert!(
+                matches!(
+                    storage.put(key, b"x").await,
+                    Err(CheckpointError::StorageError(_))
+                ),
+                "{:?}",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Capture build metadata for `ai version`
+//!
+//! Everything is exported as `AI_CLI_*` environment variables read with
+//! `env!` in `src/build_info.rs`. Missing tools (no git checkout, odd rustc)
+//! degrade to "unknown" rather than failing the build.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!(
+        "cargo:rustc-env=AI_CLI_GIT_COMMIT={}",
+        command_output("git", &["rev-parse", "--short=12", "HEAD"])
+    );
+
+    // Honor reproducible-build timestamps when provided
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=AI_CLI_BUILD_EPOCH={}", epoch);
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    println!(
+        "cargo:rustc-env=AI_CLI_RUSTC_VERSION={}",
+        command_output(&rustc, &["--version"])
+    );
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_lowercase().replace('_', "-"))
+        })
+        .filter(|name| name != "default")
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=AI_CLI_FEATURES={}", features.join(","));
+
+    // Refresh the commit hash when HEAD moves
+    for path in ["../../.git/HEAD", "../../.git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn command_output(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|stdout| stdout.trim().to_string())
+        .filter(|stdout| !stdout.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
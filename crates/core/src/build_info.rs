@@ -0,0 +1,83 @@
+//! Build metadata captured by `build.rs`
+
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+use std::fmt;
+
+/// Version and build details reported by `ai version`
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub build_timestamp: String,
+    pub rustc_version: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    /// Details of the running binary
+    pub fn current() -> Self {
+        let build_timestamp = env!("AI_CLI_BUILD_EPOCH")
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map_or_else(
+                || "unknown".to_string(),
+                |time| time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+
+        Self {
+            version: crate::VERSION,
+            git_commit: env!("AI_CLI_GIT_COMMIT"),
+            build_timestamp,
+            rustc_version: env!("AI_CLI_RUSTC_VERSION"),
+            features: env!("AI_CLI_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ai {}", self.version)?;
+        writeln!(f, "commit:   {}", self.git_commit)?;
+        writeln!(f, "built:    {}", self.build_timestamp)?;
+        writeln!(f, "rustc:    {}", self.rustc_version)?;
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        write!(f, "features: {}", features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_json_keys() {
+        let json = serde_json::to_value(BuildInfo::current()).unwrap();
+        for key in [
+            "version",
+            "git_commit",
+            "build_timestamp",
+            "rustc_version",
+            "features",
+        ] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(json["version"], crate::VERSION);
+        assert!(json["features"].is_array());
+    }
+
+    #[test]
+    fn test_build_info_display() {
+        let text = BuildInfo::current().to_string();
+        assert!(text.starts_with(&format!("ai {}\n", crate::VERSION)));
+        assert!(text.contains("rustc:"));
+    }
+}
@@ -0,0 +1,449 @@
+//! `agents` command handler
+
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::Prompter;
+use crate::cli::{AgentCommands, CliError, CliResult, CommandContext, Commands, InputValidator};
+use ai_cli_agent_framework::agent::{AgentConfig, AgentTask, SimpleAgent};
+use ai_cli_agent_framework::AgentFramework;
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Default iteration budget for agents created from the CLI
+const DEFAULT_MAX_ITERATIONS: u32 = 10;
+
+/// Handler for agent management commands
+///
+/// Agents created or removed here are written back to the registry file so
+/// they survive across sessions.
+pub struct AgentsHandler {
+    framework: Arc<RwLock<AgentFramework>>,
+    registry_path: PathBuf,
+    prompter: Arc<Prompter>,
+}
+
+impl AgentsHandler {
+    pub fn new(
+        framework: Arc<RwLock<AgentFramework>>,
+        registry_path: impl Into<PathBuf>,
+        prompter: Arc<Prompter>,
+    ) -> Self {
+        Self {
+            framework,
+            registry_path: registry_path.into(),
+            prompter,
+        }
+    }
+
+    fn save(&self, framework: &AgentFramework) -> CliResult<()> {
+        framework
+            .save(&self.registry_path)
+            .map(|_| ())
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", self.registry_path.display(), e)))
+    }
+
+    async fn list(&self, detailed: bool) -> CliResult<CommandResult> {
+        let framework = self.framework.read().await;
+        let mut names = framework.list_agents();
+        names.sort();
+
+        let data = if detailed {
+            let configs: Vec<&AgentConfig> = names
+                .iter()
+                .filter_map(|name| framework.get_agent(name))
+                .map(|agent| agent.get_config())
+                .collect();
+            serde_json::to_value(configs).map_err(|e| CliError::ValidationError(e.to_string()))?
+        } else {
+            serde_json::to_value(names).map_err(|e| CliError::ValidationError(e.to_string()))?
+        };
+
+        Ok(CommandResult::success_with_data(data))
+    }
+
+    async fn create(
+        &self,
+        name: &str,
+        capabilities: &[String],
+        description: Option<&str>,
+    ) -> CliResult<CommandResult> {
+        let mut framework = self.framework.write().await;
+        if framework.has_agent(name) {
+            return Ok(CommandResult::error(format!(
+                "Agent already exists: {}",
+                name
+            )));
+        }
+        if framework.agent_count() >= framework.config.max_agents as usize {
+            return Ok(CommandResult::error(format!(
+                "Agent limit reached ({})",
+                framework.config.max_agents
+            )));
+        }
+
+        let config = AgentConfig {
+            name: name.to_string(),
+            description: description.unwrap_or_default().to_string(),
+            capabilities: capabilities.to_vec(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        };
+        framework.register_agent(name.to_string(), Box::new(SimpleAgent::new(config)));
+        self.save(&framework).inspect_err(|_| {
+            framework.remove_agent(name);
+        })?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Created agent {}",
+            name
+        )))
+    }
+
+    async fn remove(&self, name: &str, force: bool) -> CliResult<CommandResult> {
+        if !self.framework.read().await.has_agent(name) {
+            return Ok(CommandResult::error(format!("Agent not found: {}", name)));
+        }
+
+        let prompt = format!("Remove agent {}?", name);
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Remove cancelled"));
+        }
+
+        let mut framework = self.framework.write().await;
+        let Some(agent) = framework.take_agent(name) else {
+            return Ok(CommandResult::error(format!("Agent not found: {}", name)));
+        };
+        self.save(&framework)
+            .inspect_err(|_| framework.register_agent(name.to_string(), agent))?;
+        Ok(CommandResult::success_with_message(format!(
+            "Removed agent {}",
+            name
+        )))
+    }
+
+    async fn status(&self, agent: Option<&str>) -> CliResult<CommandResult> {
+        let framework = self.framework.read().await;
+        let data = match agent {
+            Some(name) => match framework.agent_status(name) {
+                Some(status) => serde_json::to_value(status),
+                None => return Ok(CommandResult::error(format!("Agent not found: {}", name))),
+            },
+            None => {
+                let statuses: BTreeMap<_, _> = framework.all_statuses().into_iter().collect();
+                serde_json::to_value(statuses)
+            }
+        }
+        .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        Ok(CommandResult::success_with_data(data))
+    }
+
+    async fn execute_task(
+        &self,
+        agent: &str,
+        input: &str,
+        params: Option<&str>,
+    ) -> CliResult<CommandResult> {
+        let mut task = AgentTask::new(InputValidator::sanitize_input(input));
+        if let Some(params) = params {
+            task = task.with_params(InputValidator::validate_json(params)?);
+        }
+
+        let framework = self.framework.read().await;
+        if !framework.has_agent(agent) {
+            return Ok(CommandResult::error(format!("Agent not found: {}", agent)));
+        }
+
+        match framework.execute_task(agent, &task) {
+            Ok(output) => Ok(CommandResult::success_with_message(output)),
+            Err(e) => Ok(CommandResult::error(e.to_string())),
+        }
+    }
+
+    async fn execute_batch(
+        &self,
+        agent: &str,
+        inputs: &[String],
+        max_concurrent: Option<u8>,
+    ) -> CliResult<CommandResult> {
+        let tasks: Vec<AgentTask> = inputs
+            .iter()
+            .map(|input| AgentTask::new(InputValidator::sanitize_input(input)))
+            .collect();
+
+        let framework = self.framework.read().await;
+        if !framework.has_agent(agent) {
+            return Ok(CommandResult::error(format!("Agent not found: {}", agent)));
+        }
+
+        let results: Vec<serde_json::Value> = framework
+            .execute_batch(agent, &tasks, max_concurrent)
+            .into_iter()
+            .zip(inputs)
+            .map(|(result, input)| match result {
+                Ok(output) => json!({ "task": input, "output": output }),
+                Err(e) => json!({ "task": input, "error": e.to_string() }),
+            })
+            .collect();
+        Ok(CommandResult::success_with_data(json!(results)))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for AgentsHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::Agents { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "agents handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            AgentCommands::List { detailed } => self.list(*detailed).await,
+            AgentCommands::Create {
+                name,
+                capabilities,
+                description,
+            } => {
+                self.create(name, capabilities, description.as_deref())
+                    .await
+            }
+            AgentCommands::Execute {
+                agent,
+                task,
+                params,
+            } => self.execute_task(agent, task, params.as_deref()).await,
+            AgentCommands::Batch { agent, tasks } => {
+                self.execute_batch(agent, tasks, ctx.max_concurrent()).await
+            }
+            AgentCommands::Remove { name, force } => self.remove(name, *force).await,
+            AgentCommands::Status { agent } => self.status(agent.as_deref()).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "agents"
+    }
+
+    fn description(&self) -> &str {
+        "Manage agents"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use ai_cli_agent_framework::AgentConfig as FrameworkConfig;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn handler(temp_dir: &TempDir) -> AgentsHandler {
+        handler_with(temp_dir, Prompter::from_reader(Cursor::new(""), false))
+    }
+
+    fn handler_with(temp_dir: &TempDir, prompter: Prompter) -> AgentsHandler {
+        AgentsHandler::new(
+            Arc::new(RwLock::new(AgentFramework::new(FrameworkConfig::default()))),
+            temp_dir.path().join("agents.json"),
+            Arc::new(prompter),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_params() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "agents",
+                "execute",
+                "coder",
+                "refactor",
+                "--params",
+                r#"{"file":"main.rs"}"#,
+            ]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(
+            result.message.as_deref(),
+            Some(r#"Agent coder executed task: refactor with params: {"file":"main.rs"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_every_task_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "--max-concurrent",
+                "1",
+                "agents",
+                "batch",
+                "coder",
+                "lint",
+                "test",
+            ]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data[0]["task"], "lint");
+        assert_eq!(data[1]["output"], "Agent coder executed task: test");
+
+        let result = handler
+            .execute(&context(&["ai", "agents", "batch", "nobody", "lint"]))
+            .await
+            .unwrap();
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_invalid_params() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+
+        let err = handler
+            .execute(&context(&[
+                "ai", "agents", "execute", "coder", "task", "--params", "{oops",
+            ]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_status_reflects_executions() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+        handler
+            .execute(&context(&["ai", "agents", "execute", "coder", "refactor"]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "agents", "status", "coder"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["state"], "idle");
+        assert_eq!(data["total_executions"], 1);
+        assert_eq!(data["last_result"], "Agent coder executed task: refactor");
+
+        let all = handler
+            .execute(&context(&["ai", "agents", "status"]))
+            .await
+            .unwrap();
+        assert!(all.data.unwrap()["coder"].is_object());
+
+        let missing = handler
+            .execute(&context(&["ai", "agents", "status", "nobody"]))
+            .await
+            .unwrap();
+        assert!(!missing.success);
+    }
+
+    #[tokio::test]
+    async fn test_remove_requires_confirmation() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler_with(&temp_dir, Prompter::from_reader(Cursor::new("n\n"), true));
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "agents", "remove", "coder"]))
+            .await
+            .unwrap();
+        assert_eq!(result.message.as_deref(), Some("Remove cancelled"));
+        assert!(handler.framework.read().await.has_agent("coder"));
+
+        let result = handler
+            .execute(&context(&["ai", "agents", "remove", "coder", "--force"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(handler.framework.read().await.agent_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_save_keeps_agents_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+        let registry = temp_dir.path().join("agents.json");
+
+        std::fs::create_dir(&registry).unwrap();
+        assert!(handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .is_err());
+        assert!(!handler.framework.read().await.has_agent("coder"));
+
+        std::fs::remove_dir(&registry).unwrap();
+        handler
+            .execute(&context(&["ai", "agents", "create", "coder"]))
+            .await
+            .unwrap();
+        std::fs::remove_file(&registry).unwrap();
+        std::fs::create_dir(&registry).unwrap();
+        assert!(handler
+            .execute(&context(&["ai", "agents", "remove", "coder", "--force"]))
+            .await
+            .is_err());
+        assert!(handler.framework.read().await.has_agent("coder"));
+    }
+
+    #[tokio::test]
+    async fn test_created_agents_are_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        handler(&temp_dir)
+            .execute(&context(&[
+                "ai",
+                "agents",
+                "create",
+                "coder",
+                "--capabilities",
+                "rust",
+                "--description",
+                "Writes code",
+            ]))
+            .await
+            .unwrap();
+
+        let mut restored = AgentFramework::new(FrameworkConfig::default());
+        assert_eq!(
+            restored.load(temp_dir.path().join("agents.json")).unwrap(),
+            1
+        );
+        let config = restored.get_agent("coder").unwrap().get_config();
+        assert_eq!(config.description, "Writes code");
+        assert_eq!(config.capabilities, vec!["rust".to_string()]);
+    }
+}
@@ -0,0 +1,472 @@
+//! `chat` command handler
+
+use super::ProviderResolver;
+use crate::cli::history::{HistoryStore, Session};
+use crate::cli::repl::{BufReadLines, ChatSession, EditorReader, LineReader, ProviderLoader};
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{
+    CliConfig, CliError, CliResult, CommandContext, Commands, HistoryCommands, OutputFormat,
+    Prompter, RequestArgs,
+};
+use crate::logging;
+use crate::watcher::ConfigWatcher;
+use ai_cli_ai_engine::cost::CostTracker;
+use ai_cli_ai_engine::prompts::SystemPromptLibrary;
+use ai_cli_ai_engine::provider::{AIProvider, Message};
+use ai_cli_ai_engine::schema::ResponseSchema;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Handler for interactive chat sessions
+pub struct ChatHandler {
+    resolver: ProviderResolver,
+    prompts: SystemPromptLibrary,
+    settings: CliConfig,
+    prompter: Arc<Prompter>,
+    history: Option<HistoryStore>,
+    reader: Mutex<Option<Box<dyn LineReader>>>,
+}
+
+impl ChatHandler {
+    pub fn new(
+        resolver: ProviderResolver,
+        prompts: SystemPromptLibrary,
+        settings: CliConfig,
+        prompter: Arc<Prompter>,
+    ) -> Self {
+        Self {
+            resolver,
+            prompts,
+            settings,
+            prompter,
+            history: None,
+            reader: Mutex::new(None),
+        }
+    }
+
+    /// Record each session in `history`, except under `--read-only`
+    pub fn with_history(mut self, history: HistoryStore) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Read the next session's input from `reader` instead of the terminal
+    pub fn with_reader(self, reader: impl LineReader + 'static) -> Self {
+        *self.reader.lock() = Some(Box::new(reader));
+        self
+    }
+
+    fn reader(&self, no_input: bool) -> CliResult<Box<dyn LineReader>> {
+        if let Some(reader) = self.reader.lock().take() {
+            return Ok(reader);
+        }
+        if no_input {
+            return Err(CliError::NonInteractive(
+                "--no-input is set; chat needs messages to read".to_string(),
+            ));
+        }
+        if !io::stdin().is_terminal() {
+            return Ok(Box::new(BufReadLines::new(io::BufReader::new(io::stdin()))));
+        }
+
+        let history = self.settings.history_file.as_ref().map(PathBuf::from);
+        Ok(Box::new(EditorReader::new(
+            history,
+            self.settings.max_history,
+        )?))
+    }
+
+    /// Chat again from a recorded session, its messages the starting context
+    ///
+    /// The session's provider and model are used unless overridden.
+    pub async fn replay(
+        &self,
+        ctx: &CommandContext,
+        session: &Session,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> CliResult<CommandResult> {
+        let (provider, model) = self
+            .resolver
+            .resolve(
+                Some(provider.unwrap_or(&session.provider)),
+                Some(model.unwrap_or(&session.model)),
+            )
+            .await?;
+        self.run_session(ctx, provider, model, None, session.conversation(), None)
+            .await
+    }
+
+    async fn run_session(
+        &self,
+        ctx: &CommandContext,
+        provider: Arc<dyn AIProvider>,
+        model: String,
+        system_prompt: Option<String>,
+        messages: Vec<Message>,
+        config: Option<ConfigWatcher>,
+    ) -> CliResult<CommandResult> {
+        let request = request_args(ctx);
+        // `--provider` pins the provider even while the model follows the config
+        let loader = match &ctx.cli.command {
+            Some(Commands::Chat { provider: None, .. }) => Some(self.provider_loader(ctx)),
+            _ => None,
+        };
+        let generation = self
+            .resolver
+            .generation(provider.name(), ctx.cli.generation())?;
+        let mut reader = self.reader(ctx.cli.no_input)?;
+        let cost = cost_tracker(provider.as_ref(), &model, budget(ctx)).await;
+        let history = self
+            .history
+            .as_ref()
+            .filter(|_| !ctx.cli.read_only)
+            .map(|history| history.start(provider.name(), &model));
+        let mut session = ChatSession::new(provider, model)
+            .with_generation(generation)
+            .with_retry_policy(ctx.retry_policy())
+            .with_stop_sequences(request.stop_sequences())
+            .with_parameters(request.parameters())
+            .with_processors(self.resolver.processors()?)
+            .with_outbound_scanner(ctx.outbound_scanner())
+            .with_plain_output(!io::stdout().is_terminal())
+            .with_json_output(matches!(ctx.cli.format, OutputFormat::Json))
+            .with_interrupt(io::stdin().is_terminal())
+            .with_request_metadata(ctx.request_metadata().await)
+            .with_system_prompt(system_prompt)
+            .with_timeout(Some(self.resolver.request_timeout(ctx.timeout())?))
+            .with_messages(messages)
+            .with_history(history)
+            .with_cost_tracker(cost)
+            .with_quotas(self.resolver.quotas()?)
+            .with_metrics(self.resolver.metrics().cloned())
+            .with_schema(schema(ctx)?)
+            .with_config_watcher(config)
+            .with_provider_loader(loader);
+        session.run(reader.as_mut(), &mut io::stdout()).await?;
+
+        Ok(CommandResult::success())
+    }
+
+    /// Loads the provider a config reload switches the session to, keeping
+    /// the session's generation flags
+    fn provider_loader(&self, ctx: &CommandContext) -> ProviderLoader {
+        let resolver = self.resolver.clone();
+        let flags = ctx.cli.generation();
+        Box::new(move |name| {
+            let resolver = resolver.clone();
+            Box::pin(async move {
+                let (provider, model) = resolver.resolve(Some(&name), None).await?;
+                let generation = resolver.generation(provider.name(), flags)?;
+                Ok((provider, model, generation))
+            })
+        })
+    }
+}
+
+/// Log at debug level while a reload leaves `debug` set, at `base` otherwise
+///
+/// `RUST_LOG`, when set, keeps its filter.
+fn follow_debug(watcher: &ConfigWatcher, base: tracing::Level) {
+    if std::env::var_os("RUST_LOG").is_some() {
+        return;
+    }
+    watcher.on_reload(move |config| {
+        let level = match config.debug {
+            true => base.max(tracing::Level::DEBUG),
+            false => base,
+        };
+        if let Err(e) = logging::set_level(&level.to_string().to_lowercase()) {
+            tracing::debug!("Cannot change the log level: {}", e);
+        }
+    });
+}
+
+/// Request options of the chat being run
+fn request_args(ctx: &CommandContext) -> RequestArgs {
+    match &ctx.cli.command {
+        Some(Commands::Chat { request, .. }) => request.clone(),
+        _ => RequestArgs::default(),
+    }
+}
+
+/// `--schema` of the chat being run
+fn schema(ctx: &CommandContext) -> CliResult<Option<ResponseSchema>> {
+    match &ctx.cli.command {
+        Some(Commands::Chat { schema, .. }) => schema.load(),
+        _ => Ok(None),
+    }
+}
+
+/// `--budget` of the chat or replay being run
+fn budget(ctx: &CommandContext) -> Option<f64> {
+    match &ctx.cli.command {
+        Some(Commands::Chat { budget, .. })
+        | Some(Commands::History {
+            subcommand: HistoryCommands::Replay { budget, .. },
+        }) => *budget,
+        _ => None,
+    }
+}
+
+/// Tracker holding the session to `budget`, priced from the provider's
+/// model list
+///
+/// Models are only listed when there is a budget; a model without a price
+/// leaves the budget unenforced, with a warning.
+async fn cost_tracker(provider: &dyn AIProvider, model: &str, budget: Option<f64>) -> CostTracker {
+    let mut tracker = CostTracker::new().with_budget_limit(budget);
+    if budget.is_none() {
+        return tracker;
+    }
+    let pricing = match provider.get_models().await {
+        Ok(models) => models
+            .into_iter()
+            .find(|m| m.id == model)
+            .and_then(|m| m.pricing),
+        Err(e) => {
+            log::warn!("Cannot list {} models: {}", provider.name(), e);
+            None
+        }
+    };
+    if pricing.is_none() {
+        log::warn!(
+            "No pricing known for {}; --budget cannot be enforced",
+            model
+        );
+    }
+    tracker.set_pricing(pricing);
+    tracker
+}
+
+#[async_trait]
+impl CommandHandler for ChatHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (provider, model, system_prompt) = match &ctx.cli.command {
+            Some(Commands::Chat {
+                provider,
+                model,
+                system_prompt,
+                ..
+            }) => (provider, model, system_prompt),
+            _ => {
+                return Err(CliError::RoutingError(
+                    "chat handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        let system_prompt = system_prompt
+            .as_deref()
+            .map(|spec| self.prompts.template(spec))
+            .transpose()
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+        let picked = match (provider, model) {
+            (None, None) => self.resolver.pick(&self.prompter).await?,
+            _ => None,
+        };
+        let watcher = self.resolver.config_watcher()?;
+        if let Some(watcher) = &watcher {
+            follow_debug(watcher, ctx.cli.log_level());
+        }
+        // A provider and model from the config follow edits to it during the
+        // session; otherwise the watcher only drives the log level
+        let (config, _watcher) = match (&picked, model) {
+            (None, None) => (watcher, None),
+            _ => (None, watcher),
+        };
+        let (provider, model) = match &picked {
+            Some((provider, model)) => self.resolver.resolve(Some(provider), Some(model)).await?,
+            None => {
+                self.resolver
+                    .resolve(provider.as_deref(), model.as_deref())
+                    .await?
+            }
+        };
+        let values = HashMap::from([
+            ("provider".to_string(), provider.name().to_string()),
+            ("model".to_string(), model.clone()),
+        ]);
+        let system_prompt = system_prompt
+            .map(|template| template.render(&values))
+            .transpose()
+            .map_err(|e| CliError::ValidationError(format!("--system-prompt: {}", e)))?;
+        self.run_session(ctx, provider, model, system_prompt, Vec::new(), config)
+            .await
+    }
+
+    fn name(&self) -> &str {
+        "chat"
+    }
+
+    fn description(&self) -> &str {
+        "Start an interactive AI chat session"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    async fn handler(temp_dir: &TempDir, requests: Arc<Mutex<Vec<String>>>) -> ChatHandler {
+        handler_with_prompter(
+            temp_dir,
+            requests,
+            Prompter::from_reader(Cursor::new(""), false),
+        )
+        .await
+    }
+
+    async fn handler_with_prompter(
+        temp_dir: &TempDir,
+        requests: Arc<Mutex<Vec<String>>>,
+        prompter: Prompter,
+    ) -> ChatHandler {
+        // Answers every prompt with the model name it was asked for
+        let provider = StubProvider::new("openai")
+            .with_model("gpt-4", 8192)
+            .with_model("gpt-4o", 8192)
+            .replying(move |request| {
+                requests.lock().push(request.model.clone());
+                Ok(request.model.clone())
+            });
+        let resolver = test_support::resolver(temp_dir.path(), [provider]).await;
+        ChatHandler::new(
+            resolver,
+            SystemPromptLibrary::builtin(),
+            CliConfig::default(),
+            Arc::new(prompter),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chat_uses_configured_model() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler = handler(&temp_dir, requests.clone())
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n/model gpt-4o\nagain\n")));
+
+        let result = handler.execute(&context(&["ai", "chat"])).await.unwrap();
+        assert!(result.success);
+        assert_eq!(*requests.lock(), vec!["gpt-4", "gpt-4o"]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_unknown_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Arc::new(Mutex::new(Vec::new())))
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("")));
+
+        let err = handler
+            .execute(&context(&[
+                "ai",
+                "chat",
+                "--provider",
+                "nope",
+                "--model",
+                "x",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ConfigError(ref msg) if msg.contains("not configured")));
+    }
+
+    #[tokio::test]
+    async fn test_chat_unknown_system_prompt_reference() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler = handler(&temp_dir, requests.clone())
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n")));
+
+        let err = handler
+            .execute(&context(&["ai", "chat", "--system-prompt", "@nope"]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ValidationError(ref msg) if msg.contains("planner")));
+        assert!(requests.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chat_system_prompt_with_unknown_variable() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mut handler = handler(&temp_dir, requests.clone())
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n")));
+        handler
+            .prompts
+            .insert("persona", "You are {{model}}, answering as {{persona}}");
+
+        let err = handler
+            .execute(&context(&["ai", "chat", "--system-prompt", "@persona"]))
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, CliError::ValidationError(ref msg) if msg.ends_with("Unresolved template variables: persona")),
+            "{}",
+            err
+        );
+        assert!(requests.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chat_inline_system_prompt_is_not_a_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler = handler(&temp_dir, requests.clone())
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n")));
+
+        handler
+            .execute(&context(&[
+                "ai",
+                "chat",
+                "--system-prompt",
+                "Reply as {{\"answer\": ...}}",
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(requests.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chat_picks_provider_and_model_interactively() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let prompter = Prompter::from_reader(Cursor::new("1\n2\n"), true);
+        let handler = handler_with_prompter(&temp_dir, requests.clone(), prompter)
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n")));
+
+        let result = handler.execute(&context(&["ai", "chat"])).await.unwrap();
+        assert!(result.success);
+        assert_eq!(*requests.lock(), vec!["gpt-4o"]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_non_interactive_skips_picker() {
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let prompter = Prompter::from_reader(Cursor::new("2\n2\n"), false);
+        let handler = handler_with_prompter(&temp_dir, requests.clone(), prompter)
+            .await
+            .with_reader(BufReadLines::new(Cursor::new("hi\n")));
+
+        let result = handler.execute(&context(&["ai", "chat"])).await.unwrap();
+        assert!(result.success);
+        assert_eq!(*requests.lock(), vec!["gpt-4"]);
+    }
+}
@@ -0,0 +1,630 @@
+//! `checkpoint` command handler
+//!
+//! Checkpoints snapshot the session state file and restore it in place.
+//! Backups taken by `work` before it edits files are restored as files
+//! instead.
+
+use super::work::{FileBackup, WORK_BACKUP_DESCRIPTION};
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{
+    CheckpointCommands, CliError, CliResult, CommandContext, Commands, InputValidator, OutputFormat,
+};
+use crate::cli::{ProjectRoot, Prompter};
+use ai_cli_checkpoint::diff::CheckpointDiff;
+use ai_cli_checkpoint::manager::{Checkpoint, CheckpointManager};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Handler for checkpoint management commands
+pub struct CheckpointHandler {
+    manager: Arc<CheckpointManager>,
+    state_path: PathBuf,
+    /// Directory `work` edits files under, which its backups restore into
+    work_root: PathBuf,
+    prompter: Arc<Prompter>,
+}
+
+impl CheckpointHandler {
+    pub fn new(
+        manager: Arc<CheckpointManager>,
+        state_path: impl Into<PathBuf>,
+        prompter: Arc<Prompter>,
+    ) -> Self {
+        Self {
+            manager,
+            state_path: state_path.into(),
+            work_root: PathBuf::from("."),
+            prompter,
+        }
+    }
+
+    /// Restore `work` backups under `root` instead of the current directory
+    pub fn with_work_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.work_root = root.into();
+        self
+    }
+
+    /// Get the path of the state file being checkpointed
+    pub fn state_path(&self) -> &Path {
+        &self.state_path
+    }
+
+    /// Find a checkpoint by ID, falling back to the most recent one with a matching name
+    async fn resolve(&self, name_or_id: &str) -> CliResult<Checkpoint> {
+        if let Some(checkpoint) = self.manager.get_checkpoint(name_or_id).await {
+            return Ok(checkpoint);
+        }
+
+        self.manager
+            .list_checkpoints()
+            .await
+            .into_iter()
+            .find(|c| c.name == name_or_id)
+            .ok_or_else(|| {
+                CliError::ValidationError(format!("Checkpoint not found: {}", name_or_id))
+            })
+    }
+
+    async fn read_state(&self) -> CliResult<Vec<u8>> {
+        match tokio::fs::read(&self.state_path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(CliError::ValidationError(format!(
+                "Failed to read {}: {}",
+                self.state_path.display(),
+                e
+            ))),
+        }
+    }
+
+    async fn list(&self, all: bool, limit: usize) -> CliResult<CommandResult> {
+        let mut checkpoints = self.manager.list_checkpoints().await;
+        if !all {
+            checkpoints.truncate(limit);
+        }
+
+        let data = serde_json::to_value(&checkpoints)
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+        Ok(CommandResult::success_with_data(data))
+    }
+
+    async fn create(&self, name: &str, description: Option<&str>) -> CliResult<CommandResult> {
+        let data = self.read_state().await?;
+        let checkpoint = match description {
+            Some(description) => {
+                self.manager
+                    .create_checkpoint_with_description(name, description, &data)
+                    .await
+            }
+            None => self.manager.create_checkpoint(name, &data).await,
+        }
+        .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Created checkpoint {} ({})",
+            checkpoint.name, checkpoint.id
+        )))
+    }
+
+    /// The files saved in `checkpoint` if `work` took it before editing them
+    ///
+    /// The description only says how to read the data; a backup whose paths
+    /// leave the work root is refused, since imported checkpoints keep the
+    /// description they were exported with.
+    async fn work_backup(&self, checkpoint: &Checkpoint) -> CliResult<Option<FileBackup>> {
+        if checkpoint.description.as_deref() != Some(WORK_BACKUP_DESCRIPTION) {
+            return Ok(None);
+        }
+        let data = self.restore_data(checkpoint).await?;
+        let backup: FileBackup = serde_json::from_slice(&data).map_err(|e| {
+            CliError::ValidationError(format!(
+                "Checkpoint {} is not a valid work backup: {}",
+                checkpoint.id, e
+            ))
+        })?;
+        backup.targets(&ProjectRoot::new(&self.work_root)?)?;
+        Ok(Some(backup))
+    }
+
+    /// Save the current state, or the current contents of the files in
+    /// `files`, as a checkpoint before `checkpoint` replaces it
+    async fn backup(
+        &self,
+        checkpoint: &Checkpoint,
+        files: Option<&FileBackup>,
+    ) -> CliResult<Checkpoint> {
+        let (description, data) = match files {
+            Some(files) => {
+                let current = FileBackup::capture(
+                    &ProjectRoot::new(&self.work_root)?,
+                    files.files.keys().map(String::as_str),
+                )?;
+                let data = serde_json::to_vec(&current)
+                    .map_err(|e| CliError::ValidationError(e.to_string()))?;
+                (WORK_BACKUP_DESCRIPTION.to_string(), data)
+            }
+            None => (
+                format!(
+                    "State before restoring {} ({})",
+                    checkpoint.name, checkpoint.id
+                ),
+                self.read_state().await?,
+            ),
+        };
+        self.manager
+            .create_checkpoint_with_description(
+                &format!("pre-restore-{}", checkpoint.name),
+                &description,
+                &data,
+            )
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))
+    }
+
+    async fn restore(&self, name: &str, backup: bool, force: bool) -> CliResult<CommandResult> {
+        let checkpoint = self.resolve(name).await?;
+        let files = self.work_backup(&checkpoint).await?;
+
+        let prompt = match &files {
+            Some(files) => format!(
+                "Restore {} file(s) under {} from checkpoint '{}'?",
+                files.files.len(),
+                self.work_root.display(),
+                checkpoint.name
+            ),
+            None => format!(
+                "Restore checkpoint '{}' over {}?",
+                checkpoint.name,
+                self.state_path.display()
+            ),
+        };
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Restore cancelled"));
+        }
+
+        // Read the state first: taking the backup can evict the oldest
+        // checkpoint, which may be this one
+        let state = match &files {
+            Some(_) => Vec::new(),
+            None => self.restore_data(&checkpoint).await?,
+        };
+        let backup = match backup {
+            true => Some(self.backup(&checkpoint, files.as_ref()).await?),
+            false => None,
+        };
+        let restored = match &files {
+            Some(files) => ProjectRoot::new(&self.work_root)
+                .and_then(|root| files.restore(&root))
+                .map_err(|e| e.map_message(|m| format!("Failed to restore files: {}", m))),
+            None => self.write_state(&state).await,
+        };
+        if let Err(e) = restored {
+            return Err(match backup {
+                Some(backup) => e.map_message(|m| {
+                    format!(
+                        "{}; the previous state is saved as checkpoint {} ({})",
+                        m, backup.name, backup.id
+                    )
+                }),
+                None => e,
+            });
+        }
+
+        let restored = match &files {
+            Some(files) => format!("{} file(s) from checkpoint", files.files.len()),
+            None => "checkpoint".to_string(),
+        };
+        Ok(CommandResult::success_with_message(match backup {
+            Some(backup) => format!(
+                "Restored {} {} ({}); the previous state is saved as checkpoint {} ({})",
+                restored, checkpoint.name, checkpoint.id, backup.name, backup.id
+            ),
+            None => format!(
+                "Restored {} {} ({})",
+                restored, checkpoint.name, checkpoint.id
+            ),
+        }))
+    }
+
+    /// Replace the state file with `data`
+    async fn write_state(&self, data: &[u8]) -> CliResult<()> {
+        if let Some(parent) = self.state_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            }
+        }
+        tokio::fs::write(&self.state_path, data)
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))
+    }
+
+    async fn remove(&self, name: &str, force: bool) -> CliResult<CommandResult> {
+        let checkpoint = self.resolve(name).await?;
+
+        let prompt = format!("Remove checkpoint '{}'?", checkpoint.name);
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Remove cancelled"));
+        }
+
+        self.manager
+            .delete_checkpoint(&checkpoint.id)
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Removed checkpoint {} ({})",
+            checkpoint.name, checkpoint.id
+        )))
+    }
+
+    async fn export(&self, name: &str, output: &str) -> CliResult<CommandResult> {
+        InputValidator::validate_path(output)?;
+        let checkpoint = self.resolve(name).await?;
+
+        self.manager
+            .export_checkpoint(&checkpoint.id, Path::new(output))
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Exported checkpoint {} ({}) to {}",
+            checkpoint.name, checkpoint.id, output
+        )))
+    }
+
+    /// Compare checkpoint `from` with checkpoint `to`, or with the current
+    /// state file
+    async fn diff(&self, from: &str, to: Option<&str>, json: bool) -> CliResult<CommandResult> {
+        let from = self.resolve(from).await?;
+        let from_data = self.restore_data(&from).await?;
+        let (to_label, to_data) = match to {
+            Some(to) => {
+                let to = self.resolve(to).await?;
+                let data = self.restore_data(&to).await?;
+                (to.name, data)
+            }
+            None => (
+                self.state_path.display().to_string(),
+                self.read_state().await?,
+            ),
+        };
+        let diff = CheckpointDiff::new(from.name, &from_data, to_label, &to_data);
+
+        if json {
+            let data = serde_json::to_value(&diff)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+        if diff.is_empty() {
+            return Ok(CommandResult::success_with_message(format!(
+                "No differences between {} and {}",
+                diff.from, diff.to
+            )));
+        }
+        Ok(CommandResult::success_with_message(
+            diff.to_unified().trim_end().to_string(),
+        ))
+    }
+
+    async fn restore_data(&self, checkpoint: &Checkpoint) -> CliResult<Vec<u8>> {
+        self.manager
+            .restore_checkpoint(&checkpoint.id)
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))
+    }
+
+    async fn import(&self, path: &str) -> CliResult<CommandResult> {
+        InputValidator::validate_path(path)?;
+        let checkpoint = self
+            .manager
+            .import_checkpoint(Path::new(path))
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Imported checkpoint {} ({})",
+            checkpoint.name, checkpoint.id
+        )))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for CheckpointHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::Checkpoint { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "checkpoint handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            CheckpointCommands::List { all, limit } => self.list(*all, *limit).await,
+            CheckpointCommands::Create {
+                name, description, ..
+            } => self.create(name, description.as_deref()).await,
+            CheckpointCommands::Restore {
+                name,
+                backup,
+                force,
+            } => self.restore(name, *backup, *force).await,
+            CheckpointCommands::Remove { name, force } => self.remove(name, *force).await,
+            CheckpointCommands::Export { name, output } => self.export(name, output).await,
+            CheckpointCommands::Import { path } => self.import(path).await,
+            CheckpointCommands::Diff { from, to } => {
+                let json = matches!(ctx.cli.format, OutputFormat::Json);
+                self.diff(from, to.as_deref(), json).await
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "checkpoint"
+    }
+
+    fn description(&self) -> &str {
+        "Manage checkpoints of the session state"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use ai_cli_checkpoint::manager::CheckpointConfig;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn handler(temp_dir: &TempDir, prompter: Prompter) -> CheckpointHandler {
+        let config = CheckpointConfig {
+            storage_path: temp_dir.path().join("checkpoints"),
+            max_checkpoints: 10,
+            compression_enabled: false,
+            encryption_enabled: false,
+            encryption_password: None,
+        };
+        CheckpointHandler::new(
+            Arc::new(CheckpointManager::new(config).unwrap()),
+            temp_dir.path().join("state.json"),
+            Arc::new(prompter),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_restore_requires_terminal_without_force() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new("y\n"), false));
+        std::fs::write(handler.state_path(), b"v1").unwrap();
+        handler
+            .execute(&context(&["ai", "checkpoint", "create", "snap"]))
+            .await
+            .unwrap();
+
+        let err = handler
+            .execute(&context(&["ai", "checkpoint", "restore", "snap"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::NonInteractive(_)));
+        assert!(err.to_string().contains("--force"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_with_force_skips_prompt() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        std::fs::write(handler.state_path(), b"v1").unwrap();
+        handler
+            .execute(&context(&["ai", "checkpoint", "create", "snap"]))
+            .await
+            .unwrap();
+        std::fs::write(handler.state_path(), b"v2").unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "checkpoint",
+                "restore",
+                "snap",
+                "--force",
+            ]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(std::fs::read(handler.state_path()).unwrap(), b"v1");
+    }
+
+    #[tokio::test]
+    async fn test_restore_with_backup_saves_current_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        std::fs::write(handler.state_path(), b"v1").unwrap();
+        handler
+            .execute(&context(&["ai", "checkpoint", "create", "snap"]))
+            .await
+            .unwrap();
+        std::fs::write(handler.state_path(), b"v2").unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "checkpoint",
+                "restore",
+                "snap",
+                "--backup",
+                "--force",
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .contains("the previous state is saved as checkpoint pre-restore-snap"));
+        assert_eq!(std::fs::read(handler.state_path()).unwrap(), b"v1");
+
+        let backup = handler.resolve("pre-restore-snap").await.unwrap();
+        assert_eq!(handler.restore_data(&backup).await.unwrap(), b"v2");
+    }
+
+    #[tokio::test]
+    async fn test_work_backup_cannot_leave_work_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        let handler =
+            handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false)).with_work_root(&root);
+        let outside = temp_dir.path().join("outside.txt");
+
+        for (name, path) in [
+            ("dotdot", "../outside.txt".to_string()),
+            ("absolute", outside.display().to_string()),
+        ] {
+            let data = serde_json::json!({
+                "root": temp_dir.path(),
+                "files": {"inside.txt": "ok", path: "pwned"},
+            });
+            handler
+                .manager
+                .create_checkpoint_with_description(
+                    name,
+                    WORK_BACKUP_DESCRIPTION,
+                    &serde_json::to_vec(&data).unwrap(),
+                )
+                .await
+                .unwrap();
+
+            let err = handler
+                .execute(&context(&["ai", "checkpoint", "restore", name, "--force"]))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CliError::ValidationError(_)), "{}", err);
+            assert!(!outside.exists());
+            assert!(!root.join("inside.txt").exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backup_does_not_evict_restored_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        for i in 0..10 {
+            std::fs::write(handler.state_path(), format!("v{}", i)).unwrap();
+            handler
+                .execute(&context(&[
+                    "ai",
+                    "checkpoint",
+                    "create",
+                    &format!("cp{}", i),
+                ]))
+                .await
+                .unwrap();
+        }
+        std::fs::write(handler.state_path(), b"current").unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "checkpoint",
+                "restore",
+                "cp0",
+                "--backup",
+                "--force",
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Restored checkpoint cp0"));
+        assert_eq!(std::fs::read(handler.state_path()).unwrap(), b"v0");
+
+        let backup = handler.resolve("pre-restore-cp0").await.unwrap();
+        assert_eq!(handler.restore_data(&backup).await.unwrap(), b"current");
+    }
+
+    #[tokio::test]
+    async fn test_export_then_import() {
+        let source_dir = TempDir::new().unwrap();
+        let source = handler(&source_dir, Prompter::from_reader(Cursor::new(""), false));
+        std::fs::write(source.state_path(), b"v1").unwrap();
+        source
+            .execute(&context(&["ai", "checkpoint", "create", "snap"]))
+            .await
+            .unwrap();
+        let archive = source_dir.path().join("snap.ckptar");
+        let archive = archive.to_str().unwrap();
+        source
+            .execute(&context(&["ai", "checkpoint", "export", "snap", archive]))
+            .await
+            .unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = handler(&target_dir, Prompter::from_reader(Cursor::new(""), false));
+        let result = target
+            .execute(&context(&["ai", "checkpoint", "import", archive]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Imported checkpoint snap"));
+
+        target
+            .execute(&context(&[
+                "ai",
+                "checkpoint",
+                "restore",
+                "snap",
+                "--force",
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(target.state_path()).unwrap(), b"v1");
+    }
+
+    #[tokio::test]
+    async fn test_diff_against_current_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        std::fs::write(handler.state_path(), "a\nb\nc\n").unwrap();
+        handler
+            .execute(&context(&["ai", "checkpoint", "create", "snap"]))
+            .await
+            .unwrap();
+        std::fs::write(handler.state_path(), "a\nB\nc\n").unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "checkpoint", "diff", "snap"]))
+            .await
+            .unwrap();
+        let diff = result.message.unwrap();
+        assert!(diff.starts_with("--- snap\n+++ "));
+        assert!(diff.ends_with("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c"));
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "--format",
+                "json",
+                "checkpoint",
+                "diff",
+                "snap",
+            ]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["kind"], "text");
+        assert_eq!(data["hunks"][0]["changes"][2]["line"], "B");
+
+        std::fs::write(handler.state_path(), "a\nb\nc\n").unwrap();
+        let result = handler
+            .execute(&context(&["ai", "checkpoint", "diff", "snap"]))
+            .await
+            .unwrap();
+        assert!(result.message.unwrap().starts_with("No differences"));
+    }
+}
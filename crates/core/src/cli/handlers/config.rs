@@ -0,0 +1,932 @@
+//! `config` command handler
+//!
+//! Keys are dotted paths into the JSON form of [`AppConfig`], e.g.
+//! `default_provider` or `providers.0.default_model`.
+
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{
+    Cli, CliError, CliResult, CommandContext, Commands, ConfigCommands, OutputFormat,
+};
+use crate::cli::{InputValidator, Prompter};
+use crate::error::exit_code;
+use crate::{AppConfig, GenerationSettings};
+use ai_cli_ai_engine::postprocess::ProcessorChain;
+use ai_cli_providers::factory::{check_default_model, env_var_name};
+use ai_cli_security::secret::Secret;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Looks up an environment variable
+pub type EnvLookup = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// One setting that differs from its default
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// Dotted key; providers are addressed by name, e.g. `providers[openai].enabled`
+    pub key: String,
+    /// Default value, or `None` if the key only exists in the current config
+    pub default: Option<Value>,
+    /// Current value, or `None` if the key was removed
+    pub current: Option<Value>,
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", content = "from", rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    /// The config files, which replace the defaults wholesale
+    File,
+    /// The named environment variable
+    Env(String),
+    /// The named command-line flag
+    Flag(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::File => f.write_str("file"),
+            ConfigSource::Env(name) => write!(f, "env {}", name),
+            ConfigSource::Flag(name) => write!(f, "flag {}", name),
+        }
+    }
+}
+
+/// Handler for configuration commands
+pub struct ConfigHandler {
+    path: PathBuf,
+    base_layers: Vec<PathBuf>,
+    prompter: Arc<Prompter>,
+    env: EnvLookup,
+}
+
+impl ConfigHandler {
+    pub fn new(path: impl Into<PathBuf>, prompter: Arc<Prompter>) -> Self {
+        Self {
+            path: path.into(),
+            base_layers: Vec::new(),
+            prompter,
+            env: Arc::new(|name| std::env::var(name).ok()),
+        }
+    }
+
+    /// Merge the configuration file over `layers`, in order
+    ///
+    /// Changes are still written to the configuration file alone.
+    pub fn with_base_layers(mut self, layers: Vec<PathBuf>) -> Self {
+        self.base_layers = layers;
+        self
+    }
+
+    /// Every file the configuration is merged from, in order
+    fn layers(&self) -> Vec<PathBuf> {
+        let mut layers = self.base_layers.clone();
+        layers.push(self.path.clone());
+        layers
+    }
+
+    /// Read environment variables through `env` instead of the process
+    pub fn with_env(mut self, env: EnvLookup) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Get the path of the configuration file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the merged configuration, falling back to defaults when no file
+    /// exists
+    fn load(&self) -> CliResult<AppConfig> {
+        super::load_config(&self.layers())
+    }
+
+    fn save(&self, config: &AppConfig) -> CliResult<()> {
+        config
+            .save_to_file(&self.path)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", self.path.display(), e)))
+    }
+
+    fn show(&self, key: Option<&str>) -> CliResult<CommandResult> {
+        let mut value =
+            serde_json::to_value(self.load()?).map_err(|e| CliError::ConfigError(e.to_string()))?;
+        mask_api_keys(&mut value);
+
+        match key {
+            Some(key) => value
+                .pointer(&json_pointer(key))
+                .cloned()
+                .map(CommandResult::success_with_data)
+                .ok_or_else(|| CliError::ValidationError(format!("Unknown config key: {}", key))),
+            None => Ok(CommandResult::success_with_data(value)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> CliResult<CommandResult> {
+        let mut config =
+            serde_json::to_value(self.load()?).map_err(|e| CliError::ConfigError(e.to_string()))?;
+
+        let slot = config
+            .pointer_mut(&json_pointer(key))
+            .ok_or_else(|| CliError::ValidationError(format!("Unknown config key: {}", key)))?;
+        // Accept JSON literals (numbers, booleans, null), otherwise treat as a string
+        *slot = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+        let config: AppConfig = serde_json::from_value(config)
+            .map_err(|e| CliError::ValidationError(format!("Invalid value for {}: {}", key, e)))?;
+        self.save(&config)?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Set {} = {}",
+            key, value
+        )))
+    }
+
+    fn reset(&self, force: bool) -> CliResult<CommandResult> {
+        let prompt = format!("Reset {} to defaults?", self.path.display());
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Reset cancelled"));
+        }
+
+        self.save(&AppConfig::default())?;
+        Ok(CommandResult::success_with_message(format!(
+            "Reset {} to defaults",
+            self.path.display()
+        )))
+    }
+
+    fn diff(&self, format: &OutputFormat) -> CliResult<CommandResult> {
+        let to_value = |config: AppConfig| {
+            serde_json::to_value(config).map_err(|e| CliError::ConfigError(e.to_string()))
+        };
+        let mut changes = Vec::new();
+        diff_values(
+            "",
+            &to_value(AppConfig::default())?,
+            &to_value(self.load()?)?,
+            &mut changes,
+        );
+
+        if matches!(format, OutputFormat::Json) {
+            let data =
+                serde_json::to_value(changes).map_err(|e| CliError::ConfigError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+
+        if changes.is_empty() {
+            return Ok(CommandResult::success_with_message(
+                "Configuration matches the defaults",
+            ));
+        }
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(absent)".to_string(),
+        };
+        let lines: Vec<String> = changes
+            .iter()
+            .map(|c| format!("{}: {} -> {}", c.key, show(&c.default), show(&c.current)))
+            .collect();
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    /// The configuration commands would run with, and where each value
+    /// came from
+    ///
+    /// Mirrors how values are resolved elsewhere: `AI_PROVIDER` picks the
+    /// provider, `<NAME>_API_KEY` supplies keys the file leaves out, and the
+    /// generation flags win over every configured default.
+    fn resolve(&self, cli: &Cli) -> CliResult<(AppConfig, HashMap<String, ConfigSource>)> {
+        let mut config = self.load()?;
+        let mut overrides = HashMap::new();
+
+        if let Some(provider) = (self.env)("AI_PROVIDER").filter(|p| !p.is_empty()) {
+            config.default_provider = provider;
+            overrides.insert(
+                "default_provider".to_string(),
+                ConfigSource::Env("AI_PROVIDER".to_string()),
+            );
+        }
+        for provider in &mut config.providers {
+            if provider.api_key.as_ref().is_some_and(|key| !key.is_empty()) {
+                continue;
+            }
+            let var = env_var_name(&provider.name.to_lowercase());
+            if let Some(key) = (self.env)(&var).filter(|key| !key.is_empty()) {
+                provider.api_key = Some(Secret::from(key));
+                overrides.insert(
+                    format!("providers[{}].api_key", provider.name),
+                    ConfigSource::Env(var),
+                );
+            }
+        }
+
+        if let Some(temperature) = cli.temperature {
+            let flag = ConfigSource::Flag("--temperature".to_string());
+            config.default_temperature = Some(temperature);
+            overrides.insert("default_temperature".to_string(), flag.clone());
+            for provider in &mut config.providers {
+                if provider.default_temperature.is_some() {
+                    provider.default_temperature = Some(temperature);
+                    overrides.insert(
+                        format!("providers[{}].default_temperature", provider.name),
+                        flag.clone(),
+                    );
+                }
+            }
+        }
+        if let Some(max_tokens) = cli.max_tokens {
+            let flag = ConfigSource::Flag("--max-tokens".to_string());
+            config.default_max_tokens = Some(max_tokens);
+            overrides.insert("default_max_tokens".to_string(), flag.clone());
+            for provider in &mut config.providers {
+                if provider.default_max_tokens.is_some() {
+                    provider.default_max_tokens = Some(max_tokens);
+                    overrides.insert(
+                        format!("providers[{}].default_max_tokens", provider.name),
+                        flag.clone(),
+                    );
+                }
+            }
+        }
+        if !cli.provider_order.is_empty() {
+            config.failover_order = cli.provider_order.clone();
+            overrides.insert(
+                "failover_order".to_string(),
+                ConfigSource::Flag("--provider-order".to_string()),
+            );
+        }
+        if let Some(timeout) = cli.provider_timeout {
+            config.request_timeout = Some(timeout);
+            overrides.insert(
+                "request_timeout".to_string(),
+                ConfigSource::Flag("--provider-timeout".to_string()),
+            );
+        }
+
+        Ok((config, overrides))
+    }
+
+    fn effective(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (config, overrides) = self.resolve(&ctx.cli)?;
+        // Through text, so f32 settings read back as written (0.2, not
+        // 0.20000000298023224)
+        let mut value: Value = serde_json::to_string(&config)
+            .and_then(|json| serde_json::from_str(&json))
+            .map_err(|e| CliError::ConfigError(e.to_string()))?;
+        mask_api_keys(&mut value);
+
+        let file = AppConfig::load_layered_value(&self.layers())
+            .map_err(|e| CliError::ConfigError(e.to_string()))?;
+        let mut settings = Vec::new();
+        collect_settings("", &value, file.as_ref(), &overrides, &mut settings);
+
+        let verbose = ctx.cli.verbose > 0;
+        if matches!(ctx.cli.format, OutputFormat::Json) {
+            if !verbose {
+                return Ok(CommandResult::success_with_data(value));
+            }
+            let sources: BTreeMap<&str, String> = settings
+                .iter()
+                .map(|(key, _, source)| (key.as_str(), source.to_string()))
+                .collect();
+            return Ok(CommandResult::success_with_data(serde_json::json!({
+                "config": value,
+                "sources": sources,
+            })));
+        }
+
+        let lines: Vec<String> = settings
+            .iter()
+            .map(|(key, value, source)| match verbose {
+                true => format!("{} = {}  ({})", key, value, source),
+                false => format!("{} = {}", key, value),
+            })
+            .collect();
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    fn validate(&self) -> CliResult<CommandResult> {
+        let config = self.load()?;
+        if !config
+            .providers
+            .iter()
+            .any(|p| p.name == config.default_provider)
+        {
+            return Ok(CommandResult::error_with_code(
+                format!(
+                    "Default provider '{}' is not configured",
+                    config.default_provider
+                ),
+                exit_code::CONFIG,
+            ));
+        }
+
+        let global = GenerationSettings {
+            temperature: config.default_temperature,
+            max_tokens: config.default_max_tokens,
+        };
+        let generation = std::iter::once(("default", global)).chain(
+            config
+                .providers
+                .iter()
+                .map(|p| (p.name.as_str(), config.generation_defaults(&p.name))),
+        );
+        for (name, settings) in generation {
+            if let Err(e) = InputValidator::validate_generation(&settings) {
+                return Ok(CommandResult::error_with_code(
+                    format!("Invalid generation settings for {}: {}", name, e),
+                    exit_code::CONFIG,
+                ));
+            }
+        }
+        if let Some(Err(e)) = config
+            .request_timeout
+            .map(|secs| InputValidator::validate_timeout("request_timeout", secs))
+        {
+            return Ok(CommandResult::error_with_code(
+                format!("Invalid request_timeout: {}", e),
+                exit_code::CONFIG,
+            ));
+        }
+        if let Err(e) = ProcessorChain::from_names(&config.response_processors) {
+            return Ok(CommandResult::error_with_code(
+                format!("Invalid response_processors: {}", e),
+                exit_code::CONFIG,
+            ));
+        }
+
+        // Model lists can be stale, so unknown models only warn
+        let mut report = vec!["Configuration is valid".to_string()];
+        report.extend(
+            config
+                .providers
+                .iter()
+                .filter_map(|p| check_default_model(&p.into()))
+                .map(|warning| format!("Warning: {}", warning)),
+        );
+        Ok(CommandResult::success_with_message(report.join("\n")))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ConfigHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::Config { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "config handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            ConfigCommands::Show { key } => self.show(key.as_deref()),
+            ConfigCommands::Set { key, value } => self.set(key, value),
+            ConfigCommands::Reset { force } => self.reset(*force),
+            ConfigCommands::Validate => self.validate(),
+            ConfigCommands::Diff => self.diff(&ctx.cli.format),
+            ConfigCommands::Effective => self.effective(ctx),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "config"
+    }
+
+    fn description(&self) -> &str {
+        "Show and edit configuration"
+    }
+}
+
+/// Collect the leaf differences between two JSON trees
+///
+/// Arrays of named objects (the provider list) are matched by `name` rather
+/// than position, so reordering providers is not reported as a change.
+/// API keys are masked.
+fn diff_values(key: &str, default: &Value, current: &Value, changes: &mut Vec<ConfigChange>) {
+    let child = |name: &str| {
+        if key.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", key, name)
+        }
+    };
+
+    match (default, current) {
+        (Value::Object(default), Value::Object(current)) => {
+            let mut names: Vec<&String> = default.keys().chain(current.keys()).collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                match (default.get(name), current.get(name)) {
+                    (Some(d), Some(c)) => diff_values(&child(name), d, c, changes),
+                    (d, c) => push_change(&child(name), d, c, changes),
+                }
+            }
+        }
+        (Value::Array(default), Value::Array(current))
+            if default
+                .iter()
+                .chain(current)
+                .all(|v| v.get("name").is_some()) =>
+        {
+            let find = |items: &[Value], name: &Value| {
+                items.iter().find(|v| v.get("name") == Some(name)).cloned()
+            };
+            let mut names: Vec<&Value> = Vec::new();
+            for name in default.iter().chain(current).filter_map(|v| v.get("name")) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            for name in names {
+                let label = format!("{}[{}]", key, name.as_str().unwrap_or_default());
+                match (find(default, name), find(current, name)) {
+                    (Some(d), Some(c)) => diff_values(&label, &d, &c, changes),
+                    (d, c) => push_change(&label, d.as_ref(), c.as_ref(), changes),
+                }
+            }
+        }
+        (d, c) if d != c => push_change(key, Some(d), Some(c), changes),
+        _ => {}
+    }
+}
+
+fn push_change(
+    key: &str,
+    default: Option<&Value>,
+    current: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    let mask = |value: Option<&Value>| {
+        value.map(|v| match v {
+            Value::String(_) if key.ends_with("api_key") => Value::String("***".to_string()),
+            Value::Object(map) if map.contains_key("api_key") => {
+                let mut map = map.clone();
+                if map["api_key"].is_string() {
+                    map["api_key"] = Value::String("***".to_string());
+                }
+                Value::Object(map)
+            }
+            _ => v.clone(),
+        })
+    };
+    changes.push(ConfigChange {
+        key: key.to_string(),
+        default: mask(default),
+        current: mask(current),
+    });
+}
+
+/// Replace every string `api_key` in a JSON tree with `***`
+fn mask_api_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "api_key" && value.is_string() {
+                    *value = Value::String("***".to_string());
+                } else {
+                    mask_api_keys(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_api_keys),
+        _ => {}
+    }
+}
+
+/// Flatten a config tree into `(key, value, source)` leaves
+///
+/// Keys follow [`diff_values`], addressing providers by name. A value not
+/// overridden comes from the file when the file sets it, and from the
+/// defaults otherwise.
+fn collect_settings(
+    key: &str,
+    value: &Value,
+    file: Option<&Value>,
+    overrides: &HashMap<String, ConfigSource>,
+    settings: &mut Vec<(String, Value, ConfigSource)>,
+) {
+    let child = |name: &str| {
+        if key.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", key, name)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                let file = file.and_then(|f| f.get(name));
+                collect_settings(&child(name), value, file, overrides, settings);
+            }
+        }
+        Value::Array(items)
+            if !items.is_empty() && items.iter().all(|v| v.get("name").is_some()) =>
+        {
+            for item in items {
+                let name = &item["name"];
+                let file = file
+                    .and_then(Value::as_array)
+                    .and_then(|f| f.iter().find(|v| v.get("name") == Some(name)));
+                let label = format!("{}[{}]", key, name.as_str().unwrap_or_default());
+                collect_settings(&label, item, file, overrides, settings);
+            }
+        }
+        _ => {
+            let source = overrides.get(key).cloned().unwrap_or(match file {
+                Some(_) => ConfigSource::File,
+                None => ConfigSource::Default,
+            });
+            settings.push((key.to_string(), value.clone(), source));
+        }
+    }
+}
+
+/// Convert a dotted config key into a JSON pointer
+fn json_pointer(key: &str) -> String {
+    key.split('.').fold(String::new(), |mut pointer, part| {
+        pointer.push('/');
+        pointer.push_str(&part.replace('~', "~0").replace('/', "~1"));
+        pointer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use crate::cli::Cli;
+    use clap::Parser;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn handler(temp_dir: &TempDir, prompter: Prompter) -> ConfigHandler {
+        ConfigHandler::new(temp_dir.path().join("config.json"), Arc::new(prompter))
+    }
+
+    #[tokio::test]
+    async fn test_set_and_show_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+
+        handler
+            .execute(&context(&["ai", "config", "set", "debug", "true"]))
+            .await
+            .unwrap();
+        let result = handler
+            .execute(&context(&["ai", "config", "show", "debug"]))
+            .await
+            .unwrap();
+        assert_eq!(result.data, Some(Value::Bool(true)));
+    }
+
+    #[tokio::test]
+    async fn test_show_masks_api_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        std::fs::write(
+            handler.path(),
+            r#"{"debug": false, "default_provider": "openai",
+                "providers": [{"name": "openai", "enabled": true, "api_key": "sk-from-file"}]}"#,
+        )
+        .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "show"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["providers"][0]["api_key"], "***");
+        assert!(!data.to_string().contains("sk-"));
+
+        let result = handler
+            .execute(&context(&["ai", "config", "show", "providers.0.api_key"]))
+            .await
+            .unwrap();
+        assert_eq!(result.data, Some(Value::String("***".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_reset_with_force_skips_prompt() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        handler
+            .execute(&context(&[
+                "ai",
+                "config",
+                "set",
+                "default_provider",
+                "anthropic",
+            ]))
+            .await
+            .unwrap();
+
+        assert!(handler
+            .execute(&context(&["ai", "config", "reset"]))
+            .await
+            .is_err());
+
+        let result = handler
+            .execute(&context(&["ai", "config", "reset", "--force"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(
+            AppConfig::load_from_file(handler.path())
+                .unwrap()
+                .default_provider,
+            "openai"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_unknown_default_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        AppConfig {
+            default_provider: "missing".to_string(),
+            ..AppConfig::default()
+        }
+        .save_to_file(handler.path())
+        .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "validate"]))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.exit_code, exit_code::CONFIG);
+    }
+
+    #[tokio::test]
+    async fn test_validate_request_timeout_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        AppConfig {
+            request_timeout: Some(0),
+            ..AppConfig::default()
+        }
+        .save_to_file(handler.path())
+        .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "validate"]))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.exit_code, exit_code::CONFIG);
+        assert!(result
+            .message
+            .unwrap()
+            .contains("request_timeout must be between 1 and 600 seconds"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_warns_on_unlisted_default_model() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+        AppConfig::default().save_to_file(handler.path()).unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "validate"]))
+            .await
+            .unwrap();
+        assert_eq!(result.message.as_deref(), Some("Configuration is valid"));
+
+        let mut config = AppConfig::default();
+        config.providers[0].default_model = Some("gpt-5-typo".to_string());
+        config.save_to_file(handler.path()).unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "validate"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        let message = result.message.unwrap();
+        assert!(message.starts_with("Configuration is valid\nWarning: "));
+        assert!(message.contains("'gpt-5-typo' is not one of the models openai lists"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_reports_only_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false));
+
+        let result = handler
+            .execute(&context(&["ai", "config", "diff"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Configuration matches the defaults")
+        );
+
+        let mut config = AppConfig {
+            debug: true,
+            ..AppConfig::default()
+        };
+        config.providers.reverse();
+        config.providers[0].enabled = false;
+        config.providers[0].api_key = Some(Secret::from("sk-secret"));
+        config.providers.push(crate::ProviderConfig {
+            name: "google".to_string(),
+            enabled: true,
+            api_key: None,
+            default_model: None,
+            base_url: None,
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
+        });
+        config.save_to_file(handler.path()).unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "config", "diff"]))
+            .await
+            .unwrap();
+        let message = result.message.unwrap();
+        assert_eq!(
+            message.lines().collect::<Vec<_>>(),
+            vec![
+                "debug: false -> true",
+                "providers[anthropic].api_key: null -> \"***\"",
+                "providers[anthropic].enabled: true -> false",
+                "providers[google]: (absent) -> {\"api_key\":null,\"default_model\":null,\"enabled\":true,\"name\":\"google\"}",
+            ]
+        );
+        assert!(!message.contains("sk-secret"));
+
+        let result = handler
+            .execute(&context(&["ai", "--format", "json", "config", "diff"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data.as_array().unwrap().len(), 4);
+        assert_eq!(data[0]["key"], "debug");
+        assert_eq!(data[0]["default"], false);
+        assert_eq!(data[0]["current"], true);
+    }
+
+    #[tokio::test]
+    async fn test_effective_config_from_mixed_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let env: HashMap<&str, &str> = [
+            ("AI_PROVIDER", "anthropic"),
+            ("ANTHROPIC_API_KEY", "sk-ant-from-env"),
+            ("OPENAI_API_KEY", "sk-unused"),
+        ]
+        .into_iter()
+        .collect();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false))
+            .with_env(Arc::new(move |name| env.get(name).map(|v| v.to_string())));
+        std::fs::write(
+            handler.path(),
+            r#"{
+                "debug": true,
+                "default_provider": "openai",
+                "providers": [
+                    {"name": "openai", "enabled": true, "api_key": "sk-from-file",
+                     "default_model": "gpt-4o", "default_temperature": 0.7},
+                    {"name": "anthropic", "enabled": true, "api_key": null,
+                     "default_model": "claude-3-opus"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "-v",
+                "--temperature",
+                "0.2",
+                "config",
+                "effective",
+            ]))
+            .await
+            .unwrap();
+        let message = result.message.unwrap();
+        let lines: Vec<&str> = message.lines().collect();
+        for expected in [
+            "debug = true  (file)",
+            "default_provider = \"anthropic\"  (env AI_PROVIDER)",
+            "default_temperature = 0.2  (flag --temperature)",
+            "providers[openai].api_key = \"***\"  (file)",
+            "providers[openai].default_temperature = 0.2  (flag --temperature)",
+            "providers[anthropic].api_key = \"***\"  (env ANTHROPIC_API_KEY)",
+            "providers[anthropic].default_model = \"claude-3-opus\"  (file)",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{} not in\n{}",
+                expected,
+                message
+            );
+        }
+        assert!(!message.contains("sk-"));
+
+        // Without -v, JSON output is the masked config itself
+        let result = handler
+            .execute(&context(&["ai", "--format", "json", "config", "effective"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["default_provider"], "anthropic");
+        assert_eq!(data["providers"][0]["api_key"], "***");
+        assert_eq!(data["providers"][0]["default_temperature"], 0.7);
+        assert!(!data.to_string().contains("sk-"));
+    }
+
+    #[tokio::test]
+    async fn test_effective_config_without_file_is_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir, Prompter::from_reader(Cursor::new(""), false))
+            .with_env(Arc::new(|_| None));
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "-v",
+                "--format",
+                "json",
+                "config",
+                "effective",
+            ]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["config"]["default_provider"], "openai");
+        assert!(data["sources"]
+            .as_object()
+            .unwrap()
+            .values()
+            .all(|source| source == "default"));
+    }
+
+    #[tokio::test]
+    async fn test_layered_config_from_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("config.d");
+        std::fs::create_dir(&dir).unwrap();
+        AppConfig::default()
+            .save_to_file(dir.join("10-team.json"))
+            .unwrap();
+        std::fs::write(
+            dir.join("20-me.json"),
+            r#"{"providers": [{"name": "anthropic", "default_model": "claude-3-haiku"}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "not config").unwrap();
+
+        let cli =
+            Cli::try_parse_from(["ai", "-c", dir.to_str().unwrap(), "config", "show"]).unwrap();
+        let mut layers = crate::cli::handlers::config_layers(&cli);
+        assert_eq!(
+            layers
+                .iter()
+                .map(|l| l.file_name().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["10-team.json", "20-me.json"]
+        );
+
+        let path = layers.pop().unwrap();
+        let handler = ConfigHandler::new(
+            path,
+            Arc::new(Prompter::from_reader(Cursor::new(""), false)),
+        )
+        .with_base_layers(layers);
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "config",
+                "show",
+                "providers.1.default_model",
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(result.data, Some(Value::from("claude-3-haiku")));
+        let result = handler
+            .execute(&context(&["ai", "config", "show", "default_provider"]))
+            .await
+            .unwrap();
+        assert_eq!(result.data, Some(Value::from("openai")));
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        assert_eq!(json_pointer("providers.0.name"), "/providers/0/name");
+    }
+}
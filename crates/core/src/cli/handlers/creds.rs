@@ -0,0 +1,329 @@
+//! `creds` command handler
+
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::Prompter;
+use crate::cli::{CliError, CliResult, CommandContext, Commands, CredsCommands, InputValidator};
+use crate::error::exit_code;
+use ai_cli_security::credentials::CredentialManager;
+use ai_cli_security::secret::Secret;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Handler for credential management commands
+pub struct CredsHandler {
+    credentials: Arc<RwLock<CredentialManager>>,
+    prompter: Arc<Prompter>,
+}
+
+impl CredsHandler {
+    pub fn new(credentials: Arc<RwLock<CredentialManager>>, prompter: Arc<Prompter>) -> Self {
+        Self {
+            credentials,
+            prompter,
+        }
+    }
+
+    async fn list(&self, show_secrets: bool) -> CliResult<CommandResult> {
+        let credentials = self.credentials.read().await;
+        let mut names = credentials.list_credentials();
+        names.sort();
+
+        let entries: Vec<serde_json::Value> = names
+            .iter()
+            .map(|name| {
+                let value = credentials
+                    .get_credential(name)
+                    .map(Secret::expose)
+                    .unwrap_or_default();
+                let shown = if show_secrets {
+                    value.to_string()
+                } else {
+                    mask_secret(value)
+                };
+                serde_json::json!({ "provider": name, "key": shown })
+            })
+            .collect();
+
+        Ok(CommandResult::success_with_data(serde_json::Value::Array(
+            entries,
+        )))
+    }
+
+    async fn add(&self, provider: &str, key: Option<&str>) -> CliResult<CommandResult> {
+        InputValidator::validate_provider_name(provider)?;
+
+        let key = match key {
+            Some(key) => key.to_string(),
+            None => self
+                .prompter
+                .read_line(&format!("API key for {}: ", provider), "--key")?,
+        };
+        InputValidator::validate_api_key(&key)?;
+
+        let mut credentials = self.credentials.write().await;
+        credentials
+            .store_credential(provider.to_string(), key)
+            .map_err(|e| CliError::ConfigError(format!("{:#}", e)))?;
+
+        Ok(CommandResult::success_with_message(
+            match credentials.path() {
+                Some(path) => format!("Stored credential for {} in {}", provider, path.display()),
+                None => format!("Stored credential for {} for this session only", provider),
+            },
+        ))
+    }
+
+    async fn remove(&self, provider: &str, force: bool) -> CliResult<CommandResult> {
+        if self
+            .credentials
+            .read()
+            .await
+            .get_credential(provider)
+            .is_none()
+        {
+            return Ok(CommandResult::error(format!(
+                "No credential stored for {}",
+                provider
+            )));
+        }
+
+        let prompt = format!("Remove credential for {}?", provider);
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Remove cancelled"));
+        }
+
+        self.credentials
+            .write()
+            .await
+            .remove_credential(provider)
+            .map_err(|e| CliError::ConfigError(format!("{:#}", e)))?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Removed credential for {}",
+            provider
+        )))
+    }
+
+    async fn validate(&self, provider: Option<&str>) -> CliResult<CommandResult> {
+        let credentials = self.credentials.read().await;
+        let names = match provider {
+            Some(name) => vec![name.to_string()],
+            None => credentials.list_credentials(),
+        };
+
+        let mut invalid = Vec::new();
+        for name in &names {
+            match credentials.get_credential(name) {
+                Some(key) if InputValidator::validate_api_key(key.expose()).is_ok() => {}
+                _ => invalid.push(name.clone()),
+            }
+        }
+
+        if invalid.is_empty() {
+            Ok(CommandResult::success_with_message(format!(
+                "{} credential(s) valid",
+                names.len()
+            )))
+        } else {
+            invalid.sort();
+            Ok(CommandResult::error_with_code(
+                format!("Invalid or missing credentials: {}", invalid.join(", ")),
+                exit_code::AUTH,
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for CredsHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::Creds { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "creds handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            CredsCommands::List { show_secrets } => self.list(*show_secrets).await,
+            CredsCommands::Add { provider, key, .. } => self.add(provider, key.as_deref()).await,
+            CredsCommands::Remove { provider, force } => self.remove(provider, *force).await,
+            CredsCommands::Validate { provider } => self.validate(provider.as_deref()).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "creds"
+    }
+
+    fn description(&self) -> &str {
+        "Manage provider credentials"
+    }
+}
+
+/// Mask all but the last four characters of a secret
+fn mask_secret(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+
+    let visible: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(chars.len() - 4), visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use std::io::Cursor;
+
+    fn handler(prompter: Prompter) -> CredsHandler {
+        CredsHandler::new(
+            Arc::new(RwLock::new(CredentialManager::new())),
+            Arc::new(prompter),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_add_with_key_flag() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+        let ctx = context(&["ai", "creds", "add", "openai", "--key", "sk-test123"]);
+
+        let result = handler.execute(&ctx).await.unwrap();
+        assert!(result.success);
+        assert_eq!(
+            handler
+                .credentials
+                .read()
+                .await
+                .get_credential("openai")
+                .map(Secret::expose),
+            Some("sk-test123")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_added_credential_is_saved() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("credentials.json");
+        let handler = CredsHandler::new(
+            Arc::new(RwLock::new(CredentialManager::open(&path).unwrap())),
+            Arc::new(Prompter::from_reader(Cursor::new(""), false)),
+        );
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "creds",
+                "add",
+                "openai",
+                "--key",
+                "sk-test123",
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.message.unwrap(),
+            format!("Stored credential for openai in {}", path.display())
+        );
+        let saved = CredentialManager::open(&path).unwrap();
+        assert_eq!(
+            saved.get_credential("openai").map(Secret::expose),
+            Some("sk-test123")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_without_key_fails_when_not_a_terminal() {
+        let handler = handler(Prompter::from_reader(Cursor::new("sk-piped\n"), false));
+        let ctx = context(&["ai", "creds", "add", "openai"]);
+
+        let err = handler.execute(&ctx).await.unwrap_err();
+        assert!(matches!(err, CliError::NonInteractive(_)));
+        assert!(err.to_string().contains("--key"));
+        assert!(handler
+            .credentials
+            .read()
+            .await
+            .get_credential("openai")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_add_prompts_for_key_on_terminal() {
+        let handler = handler(Prompter::from_reader(Cursor::new("sk-typed\n"), true));
+        let ctx = context(&["ai", "creds", "add", "anthropic"]);
+
+        assert!(handler.execute(&ctx).await.unwrap().success);
+        assert_eq!(
+            handler
+                .credentials
+                .read()
+                .await
+                .get_credential("anthropic")
+                .map(Secret::expose),
+            Some("sk-typed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_masks_secrets() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+        handler
+            .execute(&context(&[
+                "ai",
+                "creds",
+                "add",
+                "openai",
+                "--key",
+                "sk-abcdef",
+            ]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&["ai", "creds", "list"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data[0]["key"], "*****cdef");
+    }
+
+    #[tokio::test]
+    async fn test_remove_with_force_skips_prompt() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+        handler
+            .execute(&context(&[
+                "ai", "creds", "add", "openai", "--key", "sk-abc",
+            ]))
+            .await
+            .unwrap();
+
+        let err = handler
+            .execute(&context(&["ai", "creds", "remove", "openai"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::NonInteractive(_)));
+
+        let result = handler
+            .execute(&context(&["ai", "creds", "remove", "openai", "--force"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(handler
+            .credentials
+            .read()
+            .await
+            .get_credential("openai")
+            .is_none());
+    }
+
+    #[test]
+    fn test_mask_secret_short() {
+        assert_eq!(mask_secret("abc"), "***");
+    }
+}
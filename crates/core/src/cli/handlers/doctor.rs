@@ -0,0 +1,404 @@
+//! `doctor` command handler
+//!
+//! Runs a series of independent checks and reports each as pass, warn or
+//! fail with a hint on how to fix it. Any failure makes the command fail.
+
+use super::ProviderResolver;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, Commands, OutputFormat};
+use crate::error::exit_code;
+use crate::{AppConfig, ProviderConfig};
+use ai_cli_providers::factory::default_base_url;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a reachability check waits for a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticResult {
+    pub check: String,
+    pub status: DiagnosticStatus,
+    pub message: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl DiagnosticResult {
+    pub fn pass(check: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            status: DiagnosticStatus::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warn(
+        check: impl Into<String>,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.into(),
+            status: DiagnosticStatus::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn fail(
+        check: impl Into<String>,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.into(),
+            status: DiagnosticStatus::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn line(&self) -> String {
+        let label = match self.status {
+            DiagnosticStatus::Pass => "PASS",
+            DiagnosticStatus::Warn => "WARN",
+            DiagnosticStatus::Fail => "FAIL",
+        };
+        let mut line = format!("[{}] {}: {}", label, self.check, self.message);
+        if let Some(hint) = &self.hint {
+            line.push_str(&format!("\n       {}", hint));
+        }
+        line
+    }
+}
+
+/// Check that the config file exists, parses and names a configured default
+pub fn check_config(path: &Path) -> DiagnosticResult {
+    const CHECK: &str = "config";
+    if !path.exists() {
+        return DiagnosticResult::warn(
+            CHECK,
+            format!("{} not found; using built-in defaults", path.display()),
+            "Run `ai config set default_provider <name>` to create it",
+        );
+    }
+    let config = match AppConfig::load_from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            return DiagnosticResult::fail(
+                CHECK,
+                format!("{} is invalid: {}", path.display(), e),
+                "Fix the file by hand or run `ai config reset`",
+            )
+        }
+    };
+    if !config
+        .providers
+        .iter()
+        .any(|p| p.name == config.default_provider)
+    {
+        return DiagnosticResult::fail(
+            CHECK,
+            format!(
+                "default provider '{}' is not configured",
+                config.default_provider
+            ),
+            "Run `ai config set default_provider <name>` with a configured provider",
+        );
+    }
+    DiagnosticResult::pass(CHECK, format!("{} is valid", path.display()))
+}
+
+/// Check that `provider` has a key and reports itself healthy
+pub async fn check_provider(resolver: &ProviderResolver, provider: &str) -> DiagnosticResult {
+    let check = format!("provider {}", provider);
+    let instance = match resolver.provider_by_name(provider).await {
+        Ok(instance) => instance,
+        Err(e) => {
+            return DiagnosticResult::fail(
+                check,
+                e.to_string(),
+                format!("Run `ai creds add {}` to store an API key", provider),
+            )
+        }
+    };
+    match instance.get_health_status().await {
+        Ok(status) if status.healthy => DiagnosticResult::pass(check, "credentials accepted"),
+        Ok(status) => DiagnosticResult::fail(
+            check,
+            status.error.unwrap_or_else(|| "unhealthy".to_string()),
+            format!("Check the key with `ai creds validate {}`", provider),
+        ),
+        Err(e) => DiagnosticResult::fail(
+            check,
+            e.to_string(),
+            format!("Check the key with `ai creds validate {}`", provider),
+        ),
+    }
+}
+
+/// Check that files can be created in `dir`, or where it would be created
+pub fn check_writable(dir: &Path) -> DiagnosticResult {
+    let check = format!("storage {}", dir.display());
+    // Probe the nearest existing ancestor so the check creates nothing lasting
+    let existing = dir
+        .ancestors()
+        .find(|p| p.as_os_str().is_empty() || p.is_dir())
+        .map(|p| {
+            if p.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                p
+            }
+        })
+        .unwrap_or(Path::new("."));
+    let probe = existing.join(format!(".ai-doctor-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            DiagnosticResult::pass(check, "writable")
+        }
+        Err(e) => DiagnosticResult::fail(
+            check,
+            format!("cannot write to {}: {}", existing.display(), e),
+            "Fix the directory permissions or run from a writable directory",
+        ),
+    }
+}
+
+/// Check that a TCP connection can be opened to `url`
+pub async fn check_reachable(url: &str) -> DiagnosticResult {
+    let check = format!("network {}", url);
+    let address = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)));
+    let Some((host, port)) = address else {
+        return DiagnosticResult::fail(
+            check,
+            "not a valid URL",
+            "Fix the provider's base_url in the config",
+        );
+    };
+    match tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        tokio::net::TcpStream::connect((host.as_str(), port)),
+    )
+    .await
+    {
+        Ok(Ok(_)) => DiagnosticResult::pass(check, "reachable"),
+        Ok(Err(e)) => DiagnosticResult::fail(
+            check,
+            format!("cannot connect: {}", e),
+            "Check your network connection, proxy and firewall settings",
+        ),
+        Err(_) => DiagnosticResult::fail(
+            check,
+            format!("no connection within {}s", CONNECT_TIMEOUT.as_secs()),
+            "Check your network connection, proxy and firewall settings",
+        ),
+    }
+}
+
+/// Endpoint a provider connects to
+fn endpoint(provider: &ProviderConfig) -> Option<String> {
+    provider
+        .base_url
+        .clone()
+        .or_else(|| default_base_url(&provider.name).map(str::to_string))
+}
+
+/// Handler for diagnosing the installation
+pub struct DoctorHandler {
+    resolver: ProviderResolver,
+    storage_dirs: Vec<PathBuf>,
+}
+
+impl DoctorHandler {
+    pub fn new(resolver: ProviderResolver, storage_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            resolver,
+            storage_dirs,
+        }
+    }
+
+    /// Run every check, skipping network ones when `offline` is set
+    pub async fn run_checks(&self, offline: bool) -> Vec<DiagnosticResult> {
+        let mut results = vec![check_config(self.resolver.config_path())];
+        // An unreadable config was reported above; later checks use defaults
+        let config = self.resolver.config().unwrap_or_default();
+
+        let enabled: Vec<&ProviderConfig> = config.providers.iter().filter(|p| p.enabled).collect();
+        if enabled.is_empty() {
+            results.push(DiagnosticResult::fail(
+                "providers",
+                "no provider is enabled",
+                "Run `ai providers add <name>` or enable one in the config",
+            ));
+        }
+        for provider in &enabled {
+            results.push(check_provider(&self.resolver, &provider.name).await);
+        }
+
+        for dir in &self.storage_dirs {
+            results.push(check_writable(dir));
+        }
+
+        if !offline {
+            for url in enabled.iter().filter_map(|p| endpoint(p)) {
+                results.push(check_reachable(&url).await);
+            }
+        }
+        results
+    }
+}
+
+#[async_trait]
+impl CommandHandler for DoctorHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let offline = match &ctx.cli.command {
+            Some(Commands::Doctor { offline }) => *offline,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "doctor handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        let results = self.run_checks(offline).await;
+        let failures = results
+            .iter()
+            .filter(|r| r.status == DiagnosticStatus::Fail)
+            .count();
+
+        if matches!(ctx.cli.format, OutputFormat::Json) {
+            let data = serde_json::to_value(&results)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            let mut result = CommandResult::success_with_data(data);
+            if failures > 0 {
+                result.success = false;
+                result.exit_code = exit_code::GENERAL;
+            }
+            return Ok(result);
+        }
+
+        let mut report: Vec<String> = results.iter().map(DiagnosticResult::line).collect();
+        if failures > 0 {
+            report.push(format!("{} of {} checks failed", failures, results.len()));
+            return Ok(CommandResult::error_with_code(
+                report.join("\n"),
+                exit_code::GENERAL,
+            ));
+        }
+        report.push(format!("{} checks run, none failed", results.len()));
+        Ok(CommandResult::success_with_message(report.join("\n")))
+    }
+
+    fn name(&self) -> &str {
+        "doctor"
+    }
+
+    fn description(&self) -> &str {
+        "Diagnose the installation"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use tempfile::TempDir;
+
+    /// Providers whose health checks accept openai's key and reject anthropic's
+    async fn resolver(temp_dir: &TempDir) -> ProviderResolver {
+        let providers = [
+            StubProvider::new("openai"),
+            StubProvider::new("anthropic").unhealthy("invalid API key"),
+        ];
+        test_support::resolver(temp_dir.path(), providers).await
+    }
+
+    #[test]
+    fn test_check_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        assert_eq!(check_config(&path).status, DiagnosticStatus::Warn);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let result = check_config(&path);
+        assert_eq!(result.status, DiagnosticStatus::Fail);
+        assert!(result.hint.unwrap().contains("ai config reset"));
+
+        AppConfig {
+            default_provider: "missing".to_string(),
+            ..AppConfig::default()
+        }
+        .save_to_file(&path)
+        .unwrap();
+        let result = check_config(&path);
+        assert_eq!(result.status, DiagnosticStatus::Fail);
+        assert!(result.message.contains("'missing'"));
+
+        AppConfig::default().save_to_file(&path).unwrap();
+        assert_eq!(check_config(&path).status, DiagnosticStatus::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_check_provider_auth() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(&temp_dir).await;
+
+        assert_eq!(
+            check_provider(&resolver, "openai").await.status,
+            DiagnosticStatus::Pass
+        );
+        let rejected = check_provider(&resolver, "anthropic").await;
+        assert_eq!(rejected.status, DiagnosticStatus::Fail);
+        assert_eq!(rejected.message, "invalid API key");
+        assert!(rejected
+            .hint
+            .unwrap()
+            .contains("ai creds validate anthropic"));
+
+        let missing = check_provider(&resolver, "google").await;
+        assert_eq!(missing.status, DiagnosticStatus::Fail);
+        assert!(missing.hint.unwrap().contains("ai creds add google"));
+    }
+
+    #[tokio::test]
+    async fn test_doctor_fails_on_any_failed_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(&temp_dir).await;
+        AppConfig::default()
+            .save_to_file(resolver.config_path())
+            .unwrap();
+        let handler = DoctorHandler::new(resolver, vec![temp_dir.path().join("checkpoints")]);
+
+        let result = handler
+            .execute(&context(&["ai", "doctor", "--offline"]))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.exit_code, exit_code::GENERAL);
+        let report = result.message.unwrap();
+        assert!(report.contains("[PASS] provider openai: credentials accepted"));
+        assert!(report.contains("[FAIL] provider anthropic: invalid API key"));
+        assert!(report.contains("[PASS] storage"));
+        assert!(!report.contains("network"));
+        assert!(report.ends_with("1 of 4 checks failed"));
+    }
+}
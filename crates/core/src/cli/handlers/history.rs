@@ -0,0 +1,202 @@
+//! `history` command handler
+
+use super::ChatHandler;
+use crate::cli::history::HistoryStore;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, Commands, HistoryCommands, OutputFormat};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Handler for browsing and replaying recorded chat sessions
+pub struct HistoryHandler {
+    store: HistoryStore,
+    chat: Arc<ChatHandler>,
+}
+
+impl HistoryHandler {
+    /// Handler reading `store`, replaying sessions through `chat`
+    pub fn new(store: HistoryStore, chat: Arc<ChatHandler>) -> Self {
+        Self { store, chat }
+    }
+
+    fn list(&self, json: bool) -> CliResult<CommandResult> {
+        let sessions = self.store.list()?;
+        if json {
+            let data = serde_json::to_value(&sessions)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+        if sessions.is_empty() {
+            return Ok(CommandResult::success_with_message("No chat history"));
+        }
+        let lines: Vec<String> = sessions
+            .iter()
+            .map(|s| {
+                format!(
+                    "{}  {}  {}/{}  {} messages",
+                    s.id,
+                    s.started_at.format("%Y-%m-%d %H:%M:%S"),
+                    s.provider,
+                    s.model,
+                    s.messages
+                )
+            })
+            .collect();
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    fn show(&self, id: &str, json: bool) -> CliResult<CommandResult> {
+        let session = self.store.load(id)?;
+        if json {
+            let data = serde_json::to_value(&session)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+        let mut lines = vec![format!(
+            "Session {} with {}/{}, started {}",
+            session.id,
+            session.provider,
+            session.model,
+            session.started_at.format("%Y-%m-%d %H:%M:%S")
+        )];
+        for message in &session.messages {
+            lines.push(format!("[{}] {}", message.role.as_str(), message.content));
+        }
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for HistoryHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::History { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "history handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        let json = matches!(ctx.cli.format, OutputFormat::Json);
+        match subcommand {
+            HistoryCommands::List => self.list(json),
+            HistoryCommands::Show { id } => self.show(id, json),
+            HistoryCommands::Replay {
+                id,
+                provider,
+                model,
+                ..
+            } => {
+                let session = self.store.load(id)?;
+                self.chat
+                    .replay(ctx, &session, provider.as_deref(), model.as_deref())
+                    .await
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "history"
+    }
+
+    fn description(&self) -> &str {
+        "Browse and continue past chat sessions"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use crate::cli::repl::BufReadLines;
+    use crate::cli::{CliConfig, Prompter};
+    use ai_cli_ai_engine::prompts::SystemPromptLibrary;
+    use parking_lot::Mutex;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    async fn chat(temp_dir: &TempDir, sent: Arc<Mutex<Vec<usize>>>, input: &str) -> ChatHandler {
+        // Echoes the last message back, recording how many messages it was sent
+        let provider = StubProvider::new("openai").replying(move |request| {
+            sent.lock().push(request.messages.len());
+            let last = request.messages.last().map(|m| m.content.clone());
+            Ok(format!("echo: {}", last.unwrap_or_default()))
+        });
+        let resolver = test_support::resolver(temp_dir.path(), [provider]).await;
+        ChatHandler::new(
+            resolver,
+            SystemPromptLibrary::builtin(),
+            CliConfig::default(),
+            Arc::new(Prompter::from_reader(Cursor::new(""), false)),
+        )
+        .with_history(HistoryStore::new(temp_dir.path().join("history")))
+        .with_reader(BufReadLines::new(Cursor::new(input.to_string())))
+    }
+
+    #[tokio::test]
+    async fn test_list_show_and_replay_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let store = HistoryStore::new(temp_dir.path().join("history"));
+
+        // A chat that ends before any message leaves no session behind
+        for input in ["hello\nagain\n", ""] {
+            chat(&temp_dir, sent.clone(), input)
+                .await
+                .execute(&context(&["ai", "chat", "--model", "gpt-4"]))
+                .await
+                .unwrap();
+        }
+        chat(&temp_dir, sent.clone(), "one more\n")
+            .await
+            .execute(&context(&["ai", "chat", "--model", "gpt-4o"]))
+            .await
+            .unwrap();
+
+        let replayer = HistoryHandler::new(
+            store.clone(),
+            Arc::new(chat(&temp_dir, sent.clone(), "and now?\n").await),
+        );
+        let result = replayer
+            .execute(&context(&["ai", "--format", "json", "history", "list"]))
+            .await
+            .unwrap();
+        let sessions = result.data.unwrap();
+        let sessions = sessions.as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let first = sessions
+            .iter()
+            .find(|s| s["model"] == "gpt-4")
+            .unwrap()
+            .clone();
+        assert_eq!(first["messages"], 4);
+        let id = first["id"].as_str().unwrap().to_string();
+
+        let result = replayer
+            .execute(&context(&["ai", "history", "show", &id]))
+            .await
+            .unwrap();
+        let transcript = result.message.unwrap();
+        assert!(transcript.contains("with openai/gpt-4"));
+        assert!(transcript.ends_with("[user] again\n[assistant] echo: again"));
+
+        // Replaying sends the recorded conversation along with the new message
+        sent.lock().clear();
+        replayer
+            .execute(&context(&["ai", "history", "replay", &id]))
+            .await
+            .unwrap();
+        assert_eq!(*sent.lock(), vec![5]);
+        assert_eq!(store.list().unwrap().len(), 3);
+
+        let err = replayer
+            .execute(&context(&["ai", "history", "show", "../config"]))
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, CliError::ValidationError(ref msg) if msg.contains("Invalid session ID"))
+        );
+    }
+}
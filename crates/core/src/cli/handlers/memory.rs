@@ -0,0 +1,279 @@
+//! `memory` command handler
+//!
+//! Projects are represented as tags on memory entries.
+
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::Prompter;
+use crate::cli::{CliError, CliResult, CommandContext, Commands, MemoryCommands, OutputFormat};
+use ai_cli_memory_system::MemorySystem;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Handler for project memory commands
+pub struct MemoryHandler {
+    memory: Arc<RwLock<MemorySystem>>,
+    prompter: Arc<Prompter>,
+}
+
+impl MemoryHandler {
+    pub fn new(memory: Arc<RwLock<MemorySystem>>, prompter: Arc<Prompter>) -> Self {
+        Self { memory, prompter }
+    }
+
+    async fn list(&self, project: Option<&str>, limit: usize) -> CliResult<CommandResult> {
+        let memory = self.memory.read().await;
+        let mut entries = match project {
+            Some(project) => memory.search_by_tags(&[project.to_string()]),
+            None => memory.entries(),
+        };
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.key.cmp(&b.key)));
+        entries.truncate(limit);
+
+        let data =
+            serde_json::to_value(&entries).map_err(|e| CliError::ValidationError(e.to_string()))?;
+        Ok(CommandResult::success_with_data(data))
+    }
+
+    async fn stats(&self, format: &OutputFormat) -> CliResult<CommandResult> {
+        let stats = self.memory.read().await.stats();
+        if matches!(format, OutputFormat::Json) {
+            let data = serde_json::to_value(&stats)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+
+        let mut lines = vec![
+            format!("Entries: {}", stats.total_entries),
+            format!("Size:    ~{} bytes", stats.total_bytes),
+        ];
+        if let (Some(oldest), Some(newest)) = (stats.oldest_timestamp, stats.newest_timestamp) {
+            lines.push(format!("Oldest:  {}", format_timestamp(oldest)));
+            lines.push(format!("Newest:  {}", format_timestamp(newest)));
+        }
+        if !stats.tags.is_empty() {
+            lines.push("Tags:".to_string());
+            lines.extend(
+                stats
+                    .tags
+                    .iter()
+                    .map(|(tag, count)| format!("  {} ({})", tag, count)),
+            );
+        }
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    async fn gc(&self, format: &OutputFormat) -> CliResult<CommandResult> {
+        let mut memory = self.memory.write().await;
+        let report = memory.gc();
+        persist(&memory)?;
+        drop(memory);
+        if matches!(format, OutputFormat::Json) {
+            let data = serde_json::to_value(&report)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+        Ok(CommandResult::success_with_message(format!(
+            "Removed {} memory entries ({} expired, {} evicted), reclaiming ~{} bytes",
+            report.removed(),
+            report.expired,
+            report.evicted,
+            report.bytes_reclaimed
+        )))
+    }
+
+    async fn clear(&self, project: Option<&str>, force: bool) -> CliResult<CommandResult> {
+        let prompt = match project {
+            Some(project) => format!("Clear memory for project '{}'?", project),
+            None => "Clear all memory?".to_string(),
+        };
+        if !self.prompter.confirm(&prompt, force)? {
+            return Ok(CommandResult::success_with_message("Clear cancelled"));
+        }
+
+        let mut memory = self.memory.write().await;
+        let removed = match project {
+            Some(project) => {
+                let keys: Vec<String> = memory
+                    .search_by_tags(&[project.to_string()])
+                    .into_iter()
+                    .map(|entry| entry.key.clone())
+                    .collect();
+                for key in &keys {
+                    memory.remove(key);
+                }
+                keys.len()
+            }
+            None => {
+                let count = memory.count();
+                memory.clear();
+                count
+            }
+        };
+
+        persist(&memory)?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Cleared {} memory entries",
+            removed
+        )))
+    }
+}
+
+/// Save a file-backed memory after a change
+fn persist(memory: &MemorySystem) -> CliResult<()> {
+    memory.persist().map_err(|e| {
+        let path = memory.path().unwrap_or(std::path::Path::new("memory"));
+        CliError::ConfigError(format!("{}: {}", path.display(), e))
+    })
+}
+
+/// Render Unix seconds as RFC 3339
+fn format_timestamp(secs: u64) -> String {
+    i64::try_from(secs)
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| secs.to_string())
+}
+
+#[async_trait]
+impl CommandHandler for MemoryHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let subcommand = match &ctx.cli.command {
+            Some(Commands::Memory { subcommand }) => subcommand,
+            _ => {
+                return Err(CliError::RoutingError(
+                    "memory handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            MemoryCommands::List { project, limit } => self.list(project.as_deref(), *limit).await,
+            MemoryCommands::Stats => self.stats(&ctx.cli.format).await,
+            MemoryCommands::Gc => self.gc(&ctx.cli.format).await,
+            MemoryCommands::Clear { project, force } => {
+                self.clear(project.as_deref(), *force).await
+            }
+            MemoryCommands::Search { .. } => {
+                Ok(CommandResult::error("memory search is not supported yet"))
+            }
+            MemoryCommands::Export { .. } | MemoryCommands::Import { .. } => Ok(
+                CommandResult::error("memory export/import is not supported yet"),
+            ),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn description(&self) -> &str {
+        "Manage project memory"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use ai_cli_memory_system::{MemoryConfig, MemoryEntry};
+    use std::io::Cursor;
+
+    fn handler(prompter: Prompter) -> MemoryHandler {
+        let mut memory = MemorySystem::new(MemoryConfig::default());
+        memory
+            .store("a".to_string(), "1".to_string(), vec!["web".to_string()])
+            .unwrap();
+        memory
+            .store("b".to_string(), "2".to_string(), vec!["cli".to_string()])
+            .unwrap();
+        MemoryHandler::new(Arc::new(RwLock::new(memory)), Arc::new(prompter))
+    }
+
+    #[tokio::test]
+    async fn test_clear_project_with_force() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "clear", "web", "--force"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let memory = handler.memory.read().await;
+        assert!(memory.retrieve("a").is_none());
+        assert!(memory.retrieve("b").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clear_declined_keeps_entries() {
+        let handler = handler(Prompter::from_reader(Cursor::new("n\n"), true));
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "clear"]))
+            .await
+            .unwrap();
+        assert_eq!(result.message.as_deref(), Some("Clear cancelled"));
+        assert_eq!(handler.memory.read().await.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "stats", "--format", "json"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["total_entries"], 2);
+        assert_eq!(data["tags"]["web"], 1);
+        assert_eq!(data["tags"]["cli"], 1);
+        assert_eq!(data["total_bytes"], 2 + 3 + 2 + 3);
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "stats"]))
+            .await
+            .unwrap();
+        let message = result.message.unwrap();
+        assert!(message.contains("Entries: 2"));
+        assert!(message.contains("  web (1)"));
+    }
+
+    #[tokio::test]
+    async fn test_gc_removes_expired_entries() {
+        let handler = handler(Prompter::from_reader(Cursor::new(""), false));
+        handler.memory.write().await.insert(MemoryEntry {
+            key: "old".to_string(),
+            value: "stale".to_string(),
+            timestamp: 0,
+            tags: vec!["web".to_string()],
+            embedding: None,
+        });
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "gc", "--format", "json"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["expired"], 1);
+        assert_eq!(data["evicted"], 0);
+        assert_eq!(data["bytes_reclaimed"], 3 + 5 + 3);
+
+        let memory = handler.memory.read().await;
+        assert!(memory.retrieve("old").is_none());
+        assert_eq!(memory.count(), 2);
+        drop(memory);
+
+        let result = handler
+            .execute(&context(&["ai", "memory", "gc"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Removed 0 memory entries (0 expired, 0 evicted), reclaiming ~0 bytes")
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Built-in command handlers
+//!
+//! Each handler owns one top-level command and dispatches on its subcommand.
+
+pub mod agents;
+pub mod chat;
+pub mod checkpoint;
+pub mod config;
+pub mod creds;
+pub mod doctor;
+pub mod history;
+pub mod memory;
+pub mod plan;
+pub mod providers;
+pub mod resolver;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod tokens;
+pub mod version;
+pub mod work;
+pub mod workflow;
+
+pub use agents::AgentsHandler;
+pub use chat::ChatHandler;
+pub use checkpoint::CheckpointHandler;
+pub use config::ConfigHandler;
+pub use creds::CredsHandler;
+pub use doctor::DoctorHandler;
+pub use history::HistoryHandler;
+pub use memory::MemoryHandler;
+pub use plan::PlanHandler;
+pub use providers::ProvidersHandler;
+pub use resolver::ProviderResolver;
+pub use tokens::TokensHandler;
+pub use version::VersionHandler;
+pub use work::WorkHandler;
+pub use workflow::WorkflowHandler;
+
+use super::history::HistoryStore;
+use super::{Cli, CliConfig, CliError, CliResult, CommandRouter, Prompter};
+use crate::error::AICliError;
+use crate::metrics::Metrics;
+use crate::AppConfig;
+use ai_cli_agent_framework::{AgentConfig, AgentFramework};
+use ai_cli_ai_engine::prompts::SystemPromptLibrary;
+use ai_cli_ai_engine::provider::ProviderRegistry;
+use ai_cli_checkpoint::manager::{CheckpointConfig, CheckpointManager};
+use ai_cli_memory_system::{MemoryConfig, MemorySystem};
+use ai_cli_security::credentials::CredentialManager;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Default location of the session state file snapshotted by checkpoints
+pub const DEFAULT_STATE_PATH: &str = ".ai/state.json";
+
+/// Default directory of recorded chat sessions
+pub const DEFAULT_HISTORY_PATH: &str = ".ai/history";
+
+/// Default file backing project memory
+pub const DEFAULT_MEMORY_PATH: &str = ".ai/memory.json";
+
+/// Default registry of user-defined agents
+pub const DEFAULT_AGENTS_PATH: &str = ".ai/agents.json";
+
+/// Default file of the usage counted against provider quotas
+pub const DEFAULT_QUOTA_PATH: &str = ".ai/quota.json";
+
+/// Default file of credentials added with `creds add`
+pub const DEFAULT_CREDENTIALS_PATH: &str = ".ai/credentials.json";
+
+/// Default configuration file used when `--config` is not given
+pub const DEFAULT_CONFIG_PATH: &str = ".ai/config.json";
+
+/// Config files named by `--config`, in the order they are merged
+///
+/// A directory stands for the `*.json` files in it, sorted by name. Without
+/// any file this is just [`DEFAULT_CONFIG_PATH`]. The last file is the one
+/// configuration changes are written to.
+pub fn config_layers(cli: &Cli) -> Vec<PathBuf> {
+    let mut layers = Vec::new();
+    for path in cli.config.iter().map(PathBuf::from) {
+        if !path.is_dir() {
+            layers.push(path);
+            continue;
+        }
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|file| file.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        layers.extend(files);
+    }
+    if layers.is_empty() {
+        layers.push(PathBuf::from(DEFAULT_CONFIG_PATH));
+    }
+    layers
+}
+
+/// Load the configuration merged from `layers`
+pub(crate) fn load_config(layers: &[PathBuf]) -> CliResult<AppConfig> {
+    AppConfig::load_layered(layers).map_err(|e| match e {
+        AICliError::ConfigError(msg) => CliError::ConfigError(msg),
+        other => CliError::ConfigError(other.to_string()),
+    })
+}
+
+/// Build a router with all built-in handlers registered, recording provider
+/// requests in `metrics`
+pub fn default_router(cli: &Cli, metrics: Arc<Metrics>) -> CliResult<CommandRouter> {
+    let prompter = Arc::new(Prompter::stdin(cli.no_input));
+    let checkpoints = Arc::new(
+        CheckpointManager::new(CheckpointConfig::default())
+            .map_err(|e| CliError::ConfigError(e.to_string()))?,
+    );
+
+    let mut agents = AgentFramework::new(AgentConfig::default());
+    agents
+        .load(DEFAULT_AGENTS_PATH)
+        .map_err(|e| CliError::ConfigError(format!("{}: {}", DEFAULT_AGENTS_PATH, e)))?;
+
+    let credentials = Arc::new(RwLock::new(
+        CredentialManager::open(DEFAULT_CREDENTIALS_PATH)
+            .map_err(|e| CliError::ConfigError(format!("{}: {:#}", DEFAULT_CREDENTIALS_PATH, e)))?,
+    ));
+    let mut config_layers = config_layers(cli);
+    let config_path = config_layers.pop().unwrap_or_default();
+
+    let resolver = ProviderResolver::new(
+        Arc::new(ProviderRegistry::new()),
+        credentials.clone(),
+        &config_path,
+    )
+    .with_base_layers(config_layers.clone())
+    .with_quota_path(DEFAULT_QUOTA_PATH)
+    .with_metrics(metrics);
+    if cli.warmup || resolver.config().is_ok_and(|config| config.warmup) {
+        let resolver = resolver.clone();
+        tokio::spawn(async move { resolver.warm_up().await });
+    }
+    let doctor = DoctorHandler::new(
+        resolver.clone(),
+        vec![
+            PathBuf::from(DEFAULT_STATE_PATH)
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+            CheckpointConfig::default().storage_path,
+        ],
+    );
+
+    let memory = Arc::new(RwLock::new(
+        MemorySystem::open(MemoryConfig::default(), DEFAULT_MEMORY_PATH)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", DEFAULT_MEMORY_PATH, e)))?,
+    ));
+
+    let history = HistoryStore::new(DEFAULT_HISTORY_PATH);
+    let chat = Arc::new(
+        ChatHandler::new(
+            resolver.clone(),
+            SystemPromptLibrary::with_user_prompts(),
+            CliConfig::default(),
+            prompter.clone(),
+        )
+        .with_history(history.clone()),
+    );
+
+    let mut router = CommandRouter::new();
+    router
+        .register_shared(chat.clone())
+        .register(HistoryHandler::new(history, chat))
+        .register(ProvidersHandler::new(resolver.clone()))
+        .register(TokensHandler::new(resolver.clone()))
+        .register(PlanHandler::new(
+            resolver.clone(),
+            SystemPromptLibrary::with_user_prompts(),
+            memory.clone(),
+        ))
+        .register(
+            WorkHandler::new(resolver, checkpoints.clone(), ".", prompter.clone())
+                .with_memory(memory.clone()),
+        )
+        .register(CredsHandler::new(credentials, prompter.clone()))
+        .register(MemoryHandler::new(memory, prompter.clone()))
+        .register(AgentsHandler::new(
+            Arc::new(RwLock::new(agents)),
+            DEFAULT_AGENTS_PATH,
+            prompter.clone(),
+        ))
+        .register(CheckpointHandler::new(
+            checkpoints,
+            DEFAULT_STATE_PATH,
+            prompter.clone(),
+        ))
+        .register(ConfigHandler::new(config_path, prompter).with_base_layers(config_layers))
+        .register(WorkflowHandler)
+        .register(doctor)
+        .register(VersionHandler);
+
+    Ok(router)
+}
@@ -0,0 +1,446 @@
+//! `plan` command handler
+//!
+//! Asks the provider for a step-by-step plan. Unless told otherwise the plan
+//! is kept in memory under the project, where `work` on the same project
+//! finds it and sends it along as context.
+
+use super::ProviderResolver;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, Commands, InputValidator};
+use crate::GenerationSettings;
+use ai_cli_ai_engine::prompts::SystemPromptLibrary;
+use ai_cli_ai_engine::provider::{
+    send_with_timeout, Message, MessageRole, PromptRequest, PromptResponse, ProviderError,
+};
+use ai_cli_memory_system::MemorySystem;
+use ai_cli_utils::fs::write_atomic;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// System prompt used when no `--template` is given
+const DEFAULT_TEMPLATE: &str = "planner";
+
+/// Tag carried by every stored plan
+pub const PLAN_TAG: &str = "plan";
+
+/// Memory key of the plan for `project`; `None` is the current directory
+pub fn plan_key(project: Option<&str>) -> String {
+    format!("{}:{}", PLAN_TAG, project.unwrap_or("."))
+}
+
+/// Handler for planning sessions
+pub struct PlanHandler {
+    resolver: ProviderResolver,
+    prompts: SystemPromptLibrary,
+    memory: Arc<RwLock<MemorySystem>>,
+}
+
+impl PlanHandler {
+    pub fn new(
+        resolver: ProviderResolver,
+        prompts: SystemPromptLibrary,
+        memory: Arc<RwLock<MemorySystem>>,
+    ) -> Self {
+        Self {
+            resolver,
+            prompts,
+            memory,
+        }
+    }
+
+    /// Keep `plan` as the current plan for `project`
+    async fn remember(&self, project: Option<&str>, plan: &str) -> CliResult<()> {
+        let mut tags = vec![PLAN_TAG.to_string()];
+        tags.extend(project.map(str::to_string));
+
+        let mut memory = self.memory.write().await;
+        memory
+            .store(plan_key(project), plan.to_string(), tags)
+            .and_then(|()| memory.persist())
+            .map_err(|e| CliError::ConfigError(format!("Cannot store plan in memory: {}", e)))
+    }
+
+    /// Send the conversation so far to the first provider in `order` that
+    /// answers
+    async fn send(
+        &self,
+        ctx: &CommandContext,
+        order: &[String],
+        generation: &BTreeMap<String, GenerationSettings>,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> CliResult<PromptResponse> {
+        let timeout = self.resolver.request_timeout(ctx.timeout())?;
+        let scanner = ctx.outbound_scanner();
+        let metadata = ctx.request_metadata().await;
+        let response = self
+            .resolver
+            .route_with_failover(order, None, |provider, model| {
+                let generation = generation.get(provider.name()).copied().unwrap_or_default();
+                let mut request = PromptRequest {
+                    model,
+                    system_prompt: Some(system_prompt.to_string()),
+                    messages: messages.to_vec(),
+                    temperature: generation.temperature,
+                    max_tokens: generation.max_tokens,
+                    stop_sequences: None,
+                    parameters: Default::default(),
+                    metadata: metadata.renewed(),
+                };
+                let scanned = scanner
+                    .check(&mut request)
+                    .map_err(|e| ProviderError::InvalidRequest(e.to_string()));
+                async move {
+                    scanned?;
+                    ctx.retry_policy()
+                        .run(|| async {
+                            send_with_timeout(provider.as_ref(), request.clone(), timeout).await
+                        })
+                        .await
+                }
+            })
+            .await?;
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl CommandHandler for PlanHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (task, project, template, output, interactive, no_remember, schema) =
+            match &ctx.cli.command {
+                Some(Commands::Plan {
+                    task,
+                    project,
+                    template,
+                    output,
+                    interactive,
+                    no_remember,
+                    schema,
+                    ..
+                }) => (
+                    task,
+                    project,
+                    template,
+                    output,
+                    *interactive,
+                    *no_remember,
+                    schema,
+                ),
+                _ => {
+                    return Err(CliError::RoutingError(
+                        "plan handler received a different command".to_string(),
+                    ))
+                }
+            };
+        if interactive {
+            return Ok(CommandResult::error(
+                "interactive planning is not supported yet",
+            ));
+        }
+        let task = task
+            .as_deref()
+            .ok_or_else(|| CliError::ValidationError("--task is required".to_string()))?;
+        if let Some(output) = output {
+            InputValidator::validate_path(output)?;
+        }
+        let schema = schema.load()?;
+        let template = template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+        let mut values = HashMap::from([("task".to_string(), task.to_string())]);
+        if let Some(project) = project {
+            values.insert("project".to_string(), project.clone());
+        }
+        let system_prompt = self
+            .prompts
+            .template(&format!("@{}", template))
+            .map_err(|e| CliError::ValidationError(e.to_string()))?
+            .render(&values)
+            .map_err(|e| CliError::ValidationError(format!("--template {}: {}", template, e)))?;
+
+        let order = self.resolver.failover_order(&ctx.cli.provider_order)?;
+        let mut generation = BTreeMap::new();
+        for name in &order {
+            generation.insert(
+                name.clone(),
+                self.resolver.generation(name, ctx.cli.generation())?,
+            );
+        }
+        let content = match project {
+            Some(project) => format!("Project: {}\n\nGoal: {}", project, task),
+            None => format!("Goal: {}", task),
+        };
+        let processors = self.resolver.processors()?;
+        let mut messages = vec![Message {
+            role: MessageRole::User,
+            content,
+            name: None,
+        }];
+        let mut fixes = 0;
+        let plan = loop {
+            let mut response = self
+                .send(ctx, &order, &generation, &system_prompt, &messages)
+                .await?;
+            processors.apply(&mut response);
+            let Some(schema) = &schema else {
+                break response.content;
+            };
+            match schema.validate(&response.content) {
+                Ok(_) => break response.content,
+                Err(e) if fixes < schema.max_fixes() => {
+                    fixes += 1;
+                    log::debug!("Asking for a plan that matches the schema: {}", e);
+                    messages.push(Message {
+                        role: MessageRole::Assistant,
+                        content: response.content,
+                        name: None,
+                    });
+                    messages.push(Message {
+                        role: MessageRole::User,
+                        content: schema.fix_prompt(&e),
+                        name: None,
+                    });
+                }
+                Err(e) => return Ok(CommandResult::error(format!("Plan rejected: {}", e))),
+            }
+        };
+
+        // Like chat history, nothing is kept in read-only mode
+        if !no_remember && !ctx.cli.read_only {
+            self.remember(project.as_deref(), &plan).await?;
+        }
+        match output {
+            Some(output) => {
+                write_atomic(Path::new(output), plan.as_bytes())
+                    .map_err(|e| CliError::ValidationError(format!("{}: {}", output, e)))?;
+                Ok(CommandResult::success_with_message(format!(
+                    "Plan written to {}",
+                    output
+                )))
+            }
+            None => Ok(CommandResult::success_with_message(plan)),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "plan"
+    }
+
+    fn description(&self) -> &str {
+        "Draft a step-by-step plan"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use crate::cli::handlers::WorkHandler;
+    use crate::cli::Prompter;
+    use ai_cli_ai_engine::provider::ProviderError;
+    use ai_cli_checkpoint::manager::{CheckpointConfig, CheckpointManager};
+    use ai_cli_memory_system::MemoryConfig;
+    use parking_lot::Mutex;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    /// Plans when asked by the planner, proposes no edits otherwise, and
+    /// records every prompt it is sent
+    fn planning(prompts: Arc<Mutex<Vec<String>>>) -> StubProvider {
+        StubProvider::new("openai").replying(move |request| {
+            prompts.lock().push(request.messages[0].content.clone());
+            let planning = request
+                .system_prompt
+                .as_deref()
+                .is_some_and(|prompt| prompt.contains("planner"));
+            Ok(match planning {
+                true => "1. Add a page parameter\n2. Test it".to_string(),
+                false => r#"{"edits": []}"#.to_string(),
+            })
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_provider_failures_exit_with_their_code() {
+        use crate::error::{exit_code, ErrorReport};
+
+        for (status, code, retryable) in [
+            (401, exit_code::AUTH, false),
+            (429, exit_code::RATE_LIMIT, true),
+            (504, exit_code::TIMEOUT, true),
+        ] {
+            let temp_dir = TempDir::new().unwrap();
+            // Fails every request as a provider answering with `status`
+            // would; with 504 it never answers, leaving the request timeout
+            // to fail it
+            let provider = StubProvider::new("openai").replying(move |_| {
+                Err(match status {
+                    401 => ProviderError::AuthError("invalid API key".to_string()),
+                    _ => ProviderError::RateLimitError("slow down".to_string()),
+                })
+            });
+            let provider = match status {
+                504 => provider.with_delay(std::time::Duration::MAX),
+                _ => provider,
+            };
+            let resolver = test_support::resolver(temp_dir.path(), [provider]).await;
+            let memory = Arc::new(RwLock::new(MemorySystem::new(MemoryConfig::default())));
+            let planner = PlanHandler::new(resolver, SystemPromptLibrary::builtin(), memory);
+
+            let err = planner
+                .execute(&context(&["ai", "plan", "--task", "paginate"]))
+                .await
+                .unwrap_err();
+            let report = ErrorReport::from(&err);
+            assert_eq!(
+                (report.code, report.retryable),
+                (code, retryable),
+                "{}",
+                status
+            );
+            assert!(
+                report.message.contains("openai failed"),
+                "{}",
+                report.message
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_template_is_rendered() {
+        let temp_dir = TempDir::new().unwrap();
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let resolver = test_support::resolver(temp_dir.path(), [planning(prompts.clone())]).await;
+        let mut library = SystemPromptLibrary::builtin();
+        // The provider only plans when the rendered prompt mentions a planner
+        library.insert("release", "Act as {{task}} for {{project|the team}}.");
+        library.insert("dated", "Plan {{task}} by {{deadline}}.");
+        let planner = PlanHandler::new(
+            resolver,
+            library,
+            Arc::new(RwLock::new(MemorySystem::new(MemoryConfig::default()))),
+        );
+
+        let result = planner
+            .execute(&context(&[
+                "ai",
+                "plan",
+                "--template",
+                "release",
+                "--task",
+                "a planner",
+                "--no-remember",
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("1. Add a page parameter"));
+
+        let err = planner
+            .execute(&context(&[
+                "ai",
+                "plan",
+                "--template",
+                "dated",
+                "--task",
+                "the release",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            CliError::ValidationError(
+                "--template dated: Unresolved template variables: deadline".to_string()
+            )
+            .to_string()
+        );
+        assert_eq!(prompts.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plan_is_remembered_for_work() {
+        let temp_dir = TempDir::new().unwrap();
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let resolver = test_support::resolver(temp_dir.path(), [planning(prompts.clone())]).await;
+        let memory_path = temp_dir.path().join("memory.json");
+        let memory = Arc::new(RwLock::new(
+            MemorySystem::open(MemoryConfig::default(), &memory_path).unwrap(),
+        ));
+        let planner = PlanHandler::new(
+            resolver.clone(),
+            SystemPromptLibrary::builtin(),
+            memory.clone(),
+        );
+
+        let result = planner
+            .execute(&context(&[
+                "ai",
+                "plan",
+                "--project",
+                "api",
+                "--task",
+                "paginate",
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("1. Add a page parameter"));
+        let stored = MemorySystem::open(MemoryConfig::default(), &memory_path).unwrap();
+        let entry = stored.retrieve(&plan_key(Some("api"))).unwrap();
+        assert_eq!(entry.tags, vec!["plan".to_string(), "api".to_string()]);
+
+        // Work on the project sends the plan along with the task
+        std::fs::create_dir_all(temp_dir.path().join("api")).unwrap();
+        let worker = WorkHandler::new(
+            resolver,
+            Arc::new(
+                CheckpointManager::new(CheckpointConfig {
+                    storage_path: temp_dir.path().join("checkpoints"),
+                    ..CheckpointConfig::default()
+                })
+                .unwrap(),
+            ),
+            temp_dir.path(),
+            Arc::new(Prompter::from_reader(Cursor::new(""), false)),
+        )
+        .with_memory(memory.clone());
+        worker
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--project",
+                "api",
+                "--task",
+                "do step 1",
+            ]))
+            .await
+            .unwrap();
+        let sent = prompts.lock().last().cloned().unwrap();
+        assert!(
+            sent.contains("Plan:\n1. Add a page parameter\n2. Test it"),
+            "{}",
+            sent
+        );
+        assert!(sent.ends_with("Task: do step 1"));
+
+        // A plan made with --no-remember leaves the stored one alone
+        planner
+            .execute(&context(&[
+                "ai",
+                "plan",
+                "--task",
+                "scratch",
+                "--no-remember",
+            ]))
+            .await
+            .unwrap();
+        assert!(memory.read().await.retrieve(&plan_key(None)).is_none());
+    }
+}
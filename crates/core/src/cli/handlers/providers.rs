@@ -0,0 +1,586 @@
+//! `providers` command handler
+
+use super::ProviderResolver;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{
+    CliError, CliResult, CommandContext, Commands, InputValidator, OutputFormat, ProvidersCommands,
+};
+use crate::{AppConfig, ProviderConfig};
+use ai_cli_ai_engine::provider::{
+    AIProvider, Message, MessageRole, PromptRequest, ProviderError, ProviderResult, RequestMetadata,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Most runs `providers bench` sends to each provider
+const MAX_BENCH_RUNS: usize = 100;
+
+/// Latency of one provider over a `providers bench`
+///
+/// Times are in milliseconds, over the successful runs. A provider that
+/// failed has an `error` and no times.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub provider: String,
+    pub model: Option<String>,
+    pub runs: usize,
+    pub median_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    /// Median time to the first chunk, for providers that stream
+    pub first_token_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl BenchResult {
+    fn failed(provider: &str, model: Option<String>, error: impl ToString) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model,
+            runs: 0,
+            median_ms: None,
+            p95_ms: None,
+            first_token_ms: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// The `p`th percentile of `samples`, by nearest rank
+fn percentile(samples: &[Duration], p: f64) -> Option<u64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let rank = ((p * sorted.len() as f64).ceil() as usize).max(1);
+    sorted.get(rank - 1).map(|d| d.as_millis() as u64)
+}
+
+/// Send `request` once, returning the total time and, when streamed, the
+/// time to the first chunk
+async fn timed_run(
+    provider: &dyn AIProvider,
+    request: PromptRequest,
+) -> ProviderResult<(Duration, Option<Duration>)> {
+    let started = Instant::now();
+    if !provider.capabilities().streaming {
+        provider.send_prompt(request).await?;
+        return Ok((started.elapsed(), None));
+    }
+    let mut stream = provider.stream_prompt(request).await?;
+    let mut first = None;
+    while let Some(chunk) = stream.next().await {
+        chunk?;
+        first.get_or_insert_with(|| started.elapsed());
+    }
+    Ok((started.elapsed(), first))
+}
+
+/// Send `prompt` to `provider` `runs` times in a row
+///
+/// The first failure ends the benchmark for that provider.
+async fn bench_provider(
+    provider: Arc<dyn AIProvider>,
+    model: String,
+    prompt: &str,
+    runs: usize,
+    metadata: &RequestMetadata,
+    timeout: Option<Duration>,
+) -> BenchResult {
+    let mut totals = Vec::with_capacity(runs);
+    let mut firsts = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let request = PromptRequest {
+            model: model.clone(),
+            system_prompt: None,
+            messages: vec![Message {
+                role: MessageRole::User,
+                content: prompt.to_string(),
+                name: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            parameters: Default::default(),
+            metadata: metadata.renewed(),
+        };
+        let run = timed_run(provider.as_ref(), request);
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ProviderError::TimeoutError(format!(
+                        "no reply within {}s",
+                        timeout.as_secs_f64()
+                    )))
+                }),
+            None => run.await,
+        };
+        match result {
+            Ok((total, first)) => {
+                totals.push(total);
+                firsts.extend(first);
+            }
+            Err(e) => return BenchResult::failed(provider.name(), Some(model), e),
+        }
+    }
+    BenchResult {
+        provider: provider.name().to_string(),
+        model: Some(model),
+        runs: totals.len(),
+        median_ms: percentile(&totals, 0.5),
+        p95_ms: percentile(&totals, 0.95),
+        first_token_ms: percentile(&firsts, 0.5),
+        error: None,
+    }
+}
+
+/// Handler for listing and managing providers
+pub struct ProvidersHandler {
+    resolver: ProviderResolver,
+}
+
+impl ProvidersHandler {
+    pub fn new(resolver: ProviderResolver) -> Self {
+        Self { resolver }
+    }
+
+    fn save(&self, config: &AppConfig) -> CliResult<()> {
+        let path = self.resolver.config_path();
+        config
+            .save_to_file(path)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    async fn list(&self, all: bool, test: bool, format: &OutputFormat) -> CliResult<CommandResult> {
+        let config = self.resolver.config()?;
+        let mut rows = Vec::new();
+        for provider in config.providers.iter().filter(|p| all || p.enabled) {
+            let health = if test {
+                Some(match self.resolver.provider_by_name(&provider.name).await {
+                    Ok(instance) => match instance.get_health_status().await {
+                        Ok(status) if status.healthy => "ok".to_string(),
+                        Ok(status) => status.error.unwrap_or_else(|| "unhealthy".to_string()),
+                        Err(e) => e.to_string(),
+                    },
+                    Err(e) => e.to_string(),
+                })
+            } else {
+                None
+            };
+            rows.push((provider, health));
+        }
+
+        if matches!(format, OutputFormat::Json) {
+            let data = rows
+                .iter()
+                .map(|(provider, health)| {
+                    json!({
+                        "name": provider.name,
+                        "enabled": provider.enabled,
+                        "default": provider.name == config.default_provider,
+                        "default_model": provider.default_model,
+                        "base_url": provider.base_url,
+                        "health": health,
+                    })
+                })
+                .collect();
+            return Ok(CommandResult::success_with_data(serde_json::Value::Array(
+                data,
+            )));
+        }
+
+        if rows.is_empty() {
+            return Ok(CommandResult::success_with_message(
+                "No providers configured",
+            ));
+        }
+        let lines: Vec<String> = rows
+            .iter()
+            .map(|(provider, health)| {
+                let marker = if provider.name == config.default_provider {
+                    '*'
+                } else {
+                    ' '
+                };
+                let mut line = format!(
+                    "{} {} ({})",
+                    marker,
+                    provider.name,
+                    provider
+                        .default_model
+                        .as_deref()
+                        .unwrap_or("no default model")
+                );
+                if !provider.enabled {
+                    line.push_str(" [disabled]");
+                }
+                if let Some(health) = health {
+                    line.push_str(&format!(" - {}", health));
+                }
+                line
+            })
+            .collect();
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    /// Benchmark every enabled provider at once, fastest first
+    async fn bench(
+        &self,
+        ctx: &CommandContext,
+        prompt: &str,
+        runs: usize,
+    ) -> CliResult<CommandResult> {
+        InputValidator::validate_limit(runs, MAX_BENCH_RUNS)?;
+        let config = self.resolver.config()?;
+        let metadata = ctx.request_metadata().await;
+
+        let benches = config
+            .providers
+            .iter()
+            .filter(|p| p.enabled)
+            .map(|p| async {
+                let resolved = self.resolver.resolve(Some(&p.name), None).await;
+                match resolved {
+                    Ok((provider, model)) => {
+                        bench_provider(provider, model, prompt, runs, &metadata, ctx.timeout())
+                            .await
+                    }
+                    Err(e) => BenchResult::failed(&p.name, p.default_model.clone(), e),
+                }
+            });
+        let mut results = futures::future::join_all(benches).await;
+        results.sort_by_key(|r| (r.median_ms.is_none(), r.median_ms));
+
+        if matches!(ctx.cli.format, OutputFormat::Json) {
+            let data = serde_json::to_value(&results)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+        if results.is_empty() {
+            return Ok(CommandResult::success_with_message("No providers enabled"));
+        }
+
+        let ms = |value: Option<u64>| value.map_or("-".to_string(), |v| format!("{}ms", v));
+        let name_width = results
+            .iter()
+            .map(|r| r.provider.len())
+            .fold("provider".len(), usize::max);
+        let model_width = results
+            .iter()
+            .map(|r| r.model.as_deref().unwrap_or("-").len())
+            .fold("model".len(), usize::max);
+        let mut lines = vec![format!(
+            "{:<name_width$}  {:<model_width$}  {:>8}  {:>8}  {:>11}",
+            "provider", "model", "median", "p95", "first token"
+        )];
+        for result in &results {
+            let model = result.model.as_deref().unwrap_or("-");
+            let line = match &result.error {
+                Some(error) => format!(
+                    "{:<name_width$}  {:<model_width$}  failed: {}",
+                    result.provider, model, error
+                ),
+                None => format!(
+                    "{:<name_width$}  {:<model_width$}  {:>8}  {:>8}  {:>11}",
+                    result.provider,
+                    model,
+                    ms(result.median_ms),
+                    ms(result.p95_ms),
+                    ms(result.first_token_ms)
+                ),
+            };
+            lines.push(line.trim_end().to_string());
+        }
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    fn add(
+        &self,
+        name: &str,
+        base_url: Option<&str>,
+        model: Option<&str>,
+    ) -> CliResult<CommandResult> {
+        InputValidator::validate_provider_name(name)?;
+        if let Some(url) = base_url {
+            InputValidator::validate_base_url(url)?;
+        }
+
+        let mut config = self.resolver.config()?;
+        if config.providers.iter().any(|p| p.name == name) {
+            return Err(CliError::ValidationError(format!(
+                "Provider '{}' is already configured",
+                name
+            )));
+        }
+        config.providers.push(ProviderConfig {
+            name: name.to_string(),
+            enabled: true,
+            api_key: None,
+            default_model: model.map(str::to_string),
+            base_url: base_url.map(str::to_string),
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
+        });
+        self.save(&config)?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Added provider {}",
+            name
+        )))
+    }
+
+    fn remove(&self, name: &str, new_default: Option<&str>) -> CliResult<CommandResult> {
+        let mut config = self.resolver.config()?;
+        if !config.providers.iter().any(|p| p.name == name) {
+            return Err(CliError::ValidationError(format!(
+                "Provider '{}' is not configured",
+                name
+            )));
+        }
+        config.providers.retain(|p| p.name != name);
+
+        if config.default_provider == name {
+            let new_default = new_default.ok_or_else(|| {
+                CliError::ValidationError(format!(
+                    "'{}' is the default provider; pass --new-default <name>",
+                    name
+                ))
+            })?;
+            if !config.providers.iter().any(|p| p.name == new_default) {
+                return Err(CliError::ValidationError(format!(
+                    "New default provider '{}' is not configured",
+                    new_default
+                )));
+            }
+            config.default_provider = new_default.to_string();
+        }
+        self.save(&config)?;
+
+        Ok(CommandResult::success_with_message(format!(
+            "Removed provider {}",
+            name
+        )))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ProvidersHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (all, test, subcommand) = match &ctx.cli.command {
+            Some(Commands::Providers {
+                all,
+                test,
+                subcommand,
+            }) => (*all, *test, subcommand),
+            _ => {
+                return Err(CliError::RoutingError(
+                    "providers handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand {
+            None => self.list(all, test, &ctx.cli.format).await,
+            Some(ProvidersCommands::Add {
+                name,
+                base_url,
+                model,
+            }) => self.add(name, base_url.as_deref(), model.as_deref()),
+            Some(ProvidersCommands::Remove { name, new_default }) => {
+                self.remove(name, new_default.as_deref())
+            }
+            Some(ProvidersCommands::Bench { prompt, runs }) => self.bench(ctx, prompt, *runs).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "providers"
+    }
+
+    fn description(&self) -> &str {
+        "List and manage AI providers"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use ai_cli_ai_engine::provider::{ProviderError, ProviderRegistry};
+    use ai_cli_security::credentials::CredentialManager;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    fn handler(temp_dir: &TempDir) -> ProvidersHandler {
+        ProvidersHandler::new(ProviderResolver::new(
+            Arc::new(ProviderRegistry::new()),
+            Arc::new(RwLock::new(CredentialManager::new())),
+            temp_dir.path().join("config.json"),
+        ))
+    }
+
+    fn saved(temp_dir: &TempDir) -> AppConfig {
+        AppConfig::load_from_file(temp_dir.path().join("config.json")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_then_remove_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "providers",
+                "add",
+                "local",
+                "--base-url",
+                "http://localhost:8080/v1",
+                "--model",
+                "llama3",
+            ]))
+            .await
+            .unwrap();
+        assert!(result.success);
+        let local = saved(&temp_dir)
+            .providers
+            .into_iter()
+            .find(|p| p.name == "local")
+            .unwrap();
+        assert_eq!(local.base_url.as_deref(), Some("http://localhost:8080/v1"));
+        assert_eq!(local.default_model.as_deref(), Some("llama3"));
+
+        let result = handler
+            .execute(&context(&["ai", "providers", "--format", "json"]))
+            .await
+            .unwrap();
+        assert_eq!(result.data.unwrap().as_array().unwrap().len(), 3);
+
+        handler
+            .execute(&context(&["ai", "providers", "remove", "local"]))
+            .await
+            .unwrap();
+        assert!(saved(&temp_dir).providers.iter().all(|p| p.name != "local"));
+    }
+
+    #[tokio::test]
+    async fn test_add_rejects_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+
+        for args in [
+            &["ai", "providers", "add", "bad name"][..],
+            &["ai", "providers", "add", "local", "--base-url", "localhost"][..],
+            &["ai", "providers", "add", "openai"][..],
+        ] {
+            let err = handler.execute(&context(args)).await.err().unwrap();
+            assert!(matches!(err, CliError::ValidationError(_)), "{:?}", args);
+        }
+        assert!(!temp_dir.path().join("config.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_remove_default_requires_new_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir);
+
+        let err = handler
+            .execute(&context(&["ai", "providers", "remove", "openai"]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ValidationError(ref msg) if msg.contains("--new-default")));
+
+        handler
+            .execute(&context(&[
+                "ai",
+                "providers",
+                "remove",
+                "openai",
+                "--new-default",
+                "anthropic",
+            ]))
+            .await
+            .unwrap();
+        let config = saved(&temp_dir);
+        assert_eq!(config.default_provider, "anthropic");
+        assert_eq!(config.providers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bench_orders_providers_by_latency() {
+        let temp_dir = TempDir::new().unwrap();
+        let ready = |name: &str, delay| {
+            StubProvider::new(name)
+                .streaming()
+                .with_delay(Duration::from_millis(delay))
+                .replying(|_| Ok("ready".to_string()))
+        };
+        let providers = [
+            ready("openai", 40),
+            ready("anthropic", 5),
+            StubProvider::new("local")
+                .streaming()
+                .replying(|_| Err(ProviderError::Unavailable("local is down".to_string()))),
+        ];
+        let resolver = test_support::resolver(temp_dir.path(), providers).await;
+        let handler = ProvidersHandler::new(resolver);
+        handler
+            .execute(&context(&[
+                "ai",
+                "providers",
+                "add",
+                "local",
+                "--model",
+                "tiny",
+            ]))
+            .await
+            .unwrap();
+
+        let result = handler
+            .execute(&context(&[
+                "ai",
+                "--format",
+                "json",
+                "providers",
+                "bench",
+                "--runs",
+                "3",
+            ]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        let names: Vec<&str> = data
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["provider"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["anthropic", "openai", "local"]);
+        assert_eq!(data[0]["runs"], 3);
+        assert!(data[1]["median_ms"].as_u64().unwrap() >= 40);
+        assert!(data[1]["first_token_ms"].as_u64().unwrap() >= 40);
+        assert!(data[2]["error"].as_str().unwrap().contains("local is down"));
+        assert!(data[2]["median_ms"].is_null());
+
+        let result = handler
+            .execute(&context(&["ai", "providers", "bench", "--runs", "1"]))
+            .await
+            .unwrap();
+        let table = result.message.unwrap();
+        assert!(table.starts_with("provider   model"), "{}", table);
+        assert!(table.contains("local      tiny           failed: "));
+
+        let err = handler
+            .execute(&context(&["ai", "providers", "bench", "--runs", "0"]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ValidationError(_)));
+    }
+}
@@ -0,0 +1,724 @@
+//! Provider lookup shared by the handlers that talk to a model
+//!
+//! Providers are built on first use, so a missing API key only matters to
+//! the provider actually being used.
+
+use crate::cli::{CliError, CliResult, InputValidator, Prompter};
+use crate::metrics::Metrics;
+use crate::watcher::ConfigWatcher;
+use crate::{AppConfig, GenerationSettings};
+use ai_cli_ai_engine::postprocess::ProcessorChain;
+use ai_cli_ai_engine::provider::{
+    AIProvider, PromptResponse, ProviderRegistry, ProviderResult, TokenUsage,
+};
+use ai_cli_ai_engine::quota::QuotaManager;
+use ai_cli_providers::factory::build_provider_with_credentials;
+use ai_cli_security::credentials::CredentialManager;
+use ai_cli_utils::error::AIError;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// A provider reply whose tokens count against the provider's quota
+pub trait Metered {
+    fn token_usage(&self) -> &TokenUsage;
+}
+
+impl Metered for PromptResponse {
+    fn token_usage(&self) -> &TokenUsage {
+        &self.usage
+    }
+}
+
+/// Resolves a provider and model from flags and the config file
+#[derive(Clone)]
+pub struct ProviderResolver {
+    providers: Arc<ProviderRegistry>,
+    credentials: Arc<RwLock<CredentialManager>>,
+    config_path: PathBuf,
+    base_layers: Vec<PathBuf>,
+    quota_path: Option<PathBuf>,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl ProviderResolver {
+    pub fn new(
+        providers: Arc<ProviderRegistry>,
+        credentials: Arc<RwLock<CredentialManager>>,
+        config_path: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            providers,
+            credentials,
+            config_path: config_path.into(),
+            base_layers: Vec::new(),
+            quota_path: None,
+            metrics: None,
+        }
+    }
+
+    /// Record every provider request and its latency in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Get the registry provider requests are recorded in
+    pub fn metrics(&self) -> Option<&Arc<Metrics>> {
+        self.metrics.as_ref()
+    }
+
+    /// Keep the usage counted against provider quotas in `path`
+    pub fn with_quota_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.quota_path = Some(path.into());
+        self
+    }
+
+    /// Quotas of the providers that have one configured
+    ///
+    /// `None` when no provider has a quota or no counter file is set.
+    pub fn quotas(&self) -> CliResult<Option<Arc<QuotaManager>>> {
+        let Some(path) = &self.quota_path else {
+            return Ok(None);
+        };
+        let config = self.config()?;
+        let mut limited = config
+            .providers
+            .iter()
+            .filter_map(|p| p.quota.map(|limits| (p.name.clone(), limits)))
+            .peekable();
+        if limited.peek().is_none() {
+            return Ok(None);
+        }
+        let quotas = QuotaManager::new(path)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        Ok(Some(Arc::new(
+            limited.fold(quotas, |quotas, (name, limits)| {
+                quotas.with_limits(name, limits)
+            }),
+        )))
+    }
+
+    /// Merge the configuration file over `layers`, in order
+    pub fn with_base_layers(mut self, layers: Vec<PathBuf>) -> Self {
+        self.base_layers = layers;
+        self
+    }
+
+    /// Get the path of the configuration file
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    /// Load the configuration merged from its files, falling back to
+    /// defaults when none exists
+    pub fn config(&self) -> CliResult<AppConfig> {
+        let mut layers = self.base_layers.clone();
+        layers.push(self.config_path.clone());
+        super::load_config(&layers)
+    }
+
+    /// Watch the configuration file for changes made during a session
+    ///
+    /// Returns `None` when there is no file to watch, or with a warning when
+    /// it cannot be watched.
+    pub fn config_watcher(&self) -> CliResult<Option<ConfigWatcher>> {
+        if !self.config_path.is_file() {
+            return Ok(None);
+        }
+        match ConfigWatcher::new(&self.config_path, self.config()?) {
+            Ok(watcher) => Ok(Some(watcher)),
+            Err(e) => {
+                tracing::warn!("Config changes will need a restart: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Time limit for each provider request: `override_timeout` from
+    /// `--provider-timeout` when given, the configured one otherwise
+    pub fn request_timeout(&self, override_timeout: Option<Duration>) -> CliResult<Duration> {
+        let config = self.config()?;
+        if let Some(secs) = config.request_timeout {
+            InputValidator::validate_timeout("request_timeout", secs).map_err(|e| {
+                CliError::ConfigError(format!("{}: {}", self.config_path.display(), e))
+            })?;
+        }
+        Ok(config.engine_config().request_timeout(override_timeout))
+    }
+
+    /// Pick the provider and model, preferring explicit flags over the config
+    pub async fn resolve(
+        &self,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> CliResult<(Arc<dyn AIProvider>, String)> {
+        let config = self.config()?;
+        let name = provider.unwrap_or(&config.default_provider);
+        let model = model
+            .map(str::to_string)
+            .or_else(|| {
+                config
+                    .providers
+                    .iter()
+                    .find(|p| p.name == name)
+                    .and_then(|p| p.default_model.clone())
+            })
+            .ok_or_else(|| {
+                CliError::ConfigError(format!("No model configured for {}; pass --model", name))
+            })?;
+
+        Ok((self.provider(name, &config).await?, model))
+    }
+
+    /// Generation settings for `provider`: `flags` first, then the
+    /// provider's configured defaults, then the global ones
+    pub fn generation(
+        &self,
+        provider: &str,
+        flags: GenerationSettings,
+    ) -> CliResult<GenerationSettings> {
+        let defaults = self.config()?.generation_defaults(provider);
+        InputValidator::validate_generation(&defaults)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", self.config_path.display(), e)))?;
+        Ok(flags.or(defaults))
+    }
+
+    /// The configured chain of response processors
+    pub fn processors(&self) -> CliResult<ProcessorChain> {
+        ProcessorChain::from_names(&self.config()?.response_processors)
+            .map_err(|e| CliError::ConfigError(format!("{}: {}", self.config_path.display(), e)))
+    }
+
+    /// Providers to try for this run, in order
+    ///
+    /// `preference` (from `--provider-order`) wins over the configured
+    /// failover order, which wins over just the default provider. Every
+    /// name must be a configured provider.
+    pub fn failover_order(&self, preference: &[String]) -> CliResult<Vec<String>> {
+        let config = self.config()?;
+        let is_unknown = |name: &&String| !config.providers.iter().any(|p| &p.name == *name);
+        if !preference.is_empty() {
+            if let Some(unknown) = preference.iter().find(is_unknown) {
+                return Err(CliError::ValidationError(format!(
+                    "Unknown provider '{}' in --provider-order",
+                    unknown
+                )));
+            }
+            return Ok(preference.to_vec());
+        }
+        if let Some(unknown) = config.failover_order.iter().find(is_unknown) {
+            return Err(CliError::ConfigError(format!(
+                "{}: unknown provider '{}' in failover_order",
+                self.config_path.display(),
+                unknown
+            )));
+        }
+        if config.failover_order.is_empty() {
+            Ok(vec![config.default_provider.clone()])
+        } else {
+            Ok(config.failover_order.clone())
+        }
+    }
+
+    /// Send a request to each provider in `order` until one succeeds
+    ///
+    /// `send` gets the provider and the model to use: `model` when given,
+    /// else the provider's default. A provider that cannot be built, has
+    /// used up its quota or fails with a transient error is skipped; any
+    /// other failure is returned straight away. The reply's tokens are
+    /// counted against the quota of the provider that sent it.
+    pub async fn route_with_failover<T, F, Fut>(
+        &self,
+        order: &[String],
+        model: Option<&str>,
+        mut send: F,
+    ) -> CliResult<T>
+    where
+        T: Metered,
+        F: FnMut(Arc<dyn AIProvider>, String) -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let quotas = self.quotas()?;
+        let mut last_error = None;
+        for name in order {
+            let (provider, model) = match self.resolve(Some(name), model).await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    log::warn!("Skipping provider {}: {}", name, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let admitted = match &quotas {
+                Some(quotas) => quotas.check_request(name),
+                None => Ok(()),
+            };
+            let sent = match admitted {
+                Ok(()) => {
+                    let started = Instant::now();
+                    let sent = send(provider, model).await;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_provider_request(name, started.elapsed());
+                    }
+                    sent
+                }
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(value) => {
+                    if let Some(quotas) = &quotas {
+                        if let Err(e) = quotas.record(name, value.token_usage()) {
+                            log::warn!("Cannot count usage of {} against its quota: {}", name, e);
+                        }
+                    }
+                    return Ok(value);
+                }
+                Err(e) => {
+                    let transient = e.is_transient();
+                    let err = CliError::provider_failure(name, e);
+                    if !transient {
+                        return Err(err);
+                    }
+                    log::warn!("{}; trying the next provider", err);
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| CliError::ConfigError("No providers to try".to_string())))
+    }
+
+    /// Let the user choose a provider and model when neither was given
+    ///
+    /// Returns `None` without prompting unless input is interactive and more
+    /// than one provider is enabled, so scripted runs keep using the
+    /// configured default.
+    pub async fn pick(&self, prompter: &Prompter) -> CliResult<Option<(String, String)>> {
+        if !prompter.is_interactive() {
+            return Ok(None);
+        }
+        let config = self.config()?;
+        let enabled: Vec<_> = config.providers.iter().filter(|p| p.enabled).collect();
+        if enabled.len() < 2 {
+            return Ok(None);
+        }
+
+        let labels: Vec<String> = enabled
+            .iter()
+            .map(|p| match &p.default_model {
+                Some(model) => format!("{} ({})", p.name, model),
+                None => p.name.clone(),
+            })
+            .collect();
+        let default = enabled
+            .iter()
+            .position(|p| p.name == config.default_provider)
+            .unwrap_or(0);
+        let chosen = enabled[prompter.select("Provider", &labels, default, "--provider")?];
+
+        // Listing models may need the network; fall back to the configured model
+        let provider = self.provider(&chosen.name, &config).await?;
+        let models: Vec<String> = match provider.get_models().await {
+            Ok(models) => models.into_iter().map(|m| m.id).collect(),
+            Err(e) => {
+                log::debug!("Cannot list models for {}: {}", chosen.name, e);
+                Vec::new()
+            }
+        };
+        let model = if models.len() > 1 {
+            let default = chosen
+                .default_model
+                .as_ref()
+                .and_then(|model| models.iter().position(|m| m == model))
+                .unwrap_or(0);
+            models[prompter.select("Model", &models, default, "--model")?].clone()
+        } else {
+            chosen
+                .default_model
+                .clone()
+                .or_else(|| models.into_iter().next())
+                .ok_or_else(|| {
+                    CliError::ConfigError(format!(
+                        "No model configured for {}; pass --model",
+                        chosen.name
+                    ))
+                })?
+        };
+
+        Ok(Some((chosen.name.clone(), model)))
+    }
+
+    /// Warm up the default provider ahead of its first request
+    ///
+    /// The provider is built and kept in the registry so later requests
+    /// reuse its connection. Failures are logged, never returned: the
+    /// request that follows reports any real problem.
+    pub async fn warm_up(&self) {
+        let started = Instant::now();
+        let name = match self.config() {
+            Ok(config) => config.default_provider,
+            Err(e) => {
+                log::debug!("Skipping warm-up: {}", e);
+                return;
+            }
+        };
+        let provider = match self.provider_by_name(&name).await {
+            Ok(provider) => provider,
+            Err(e) => {
+                log::debug!("Skipping warm-up of {}: {}", name, e);
+                return;
+            }
+        };
+        match provider.warmup().await {
+            Ok(()) => log::debug!("Warmed up {} in {:?}", name, started.elapsed()),
+            Err(e) => log::warn!("Warm-up of {} failed: {}", name, e),
+        }
+    }
+
+    /// Get a provider by name, building it from the config on first use
+    pub async fn provider_by_name(&self, name: &str) -> CliResult<Arc<dyn AIProvider>> {
+        let config = self.config()?;
+        self.provider(name, &config).await
+    }
+
+    /// Get a registered provider, building it from the config on first use
+    async fn provider(&self, name: &str, config: &AppConfig) -> CliResult<Arc<dyn AIProvider>> {
+        if let Some(provider) = self.providers.get(name).await {
+            return Ok(provider);
+        }
+
+        let provider_config = config
+            .providers
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| {
+                CliError::ConfigError(format!("Provider '{}' is not configured", name))
+            })?;
+        if !provider_config.enabled {
+            return Err(CliError::ConfigError(format!(
+                "Provider '{}' is disabled",
+                name
+            )));
+        }
+
+        let credentials = self.credentials.read().await;
+        let provider = build_provider_with_credentials(&provider_config.into(), Some(&credentials))
+            .map_err(|e| match e {
+                AIError::ConfigError(msg) => CliError::ConfigError(msg),
+                other => CliError::ConfigError(other.to_string()),
+            })?;
+        self.providers.register(provider.clone()).await;
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::StubProvider;
+    use ai_cli_ai_engine::provider::{FinishReason, ProviderError, ResponseMetadata};
+    use ai_cli_ai_engine::quota::QuotaLimits;
+    use tempfile::TempDir;
+
+    /// A reply of `content` that used 15 tokens
+    fn reply(content: String) -> PromptResponse {
+        PromptResponse {
+            content,
+            model: "m".to_string(),
+            usage: TokenUsage::new(10, 5),
+            finish_reason: FinishReason::Stop,
+            metadata: ResponseMetadata {
+                request_id: "r".to_string(),
+                timestamp: chrono::Utc::now(),
+                latency_ms: 0,
+                cost: None,
+            },
+        }
+    }
+
+    fn resolver(temp_dir: &TempDir, config: AppConfig) -> ProviderResolver {
+        let path = temp_dir.path().join("config.json");
+        config.save_to_file(&path).unwrap();
+        ProviderResolver::new(
+            Arc::new(ProviderRegistry::new()),
+            Arc::new(RwLock::new(CredentialManager::new())),
+            path,
+        )
+    }
+
+    #[test]
+    fn test_generation_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = AppConfig {
+            default_temperature: Some(0.2),
+            default_max_tokens: Some(1024),
+            ..AppConfig::default()
+        };
+        config.providers[1].default_temperature = Some(0.9);
+        let resolver = resolver(&temp_dir, config);
+
+        let global = resolver
+            .generation("openai", GenerationSettings::default())
+            .unwrap();
+        assert_eq!(global.temperature, Some(0.2));
+        assert_eq!(global.max_tokens, Some(1024));
+
+        let provider = resolver
+            .generation("anthropic", GenerationSettings::default())
+            .unwrap();
+        assert_eq!(provider.temperature, Some(0.9));
+        assert_eq!(provider.max_tokens, Some(1024));
+
+        let flags = GenerationSettings {
+            temperature: Some(0.0),
+            max_tokens: None,
+        };
+        let flagged = resolver.generation("anthropic", flags).unwrap();
+        assert_eq!(flagged.temperature, Some(0.0));
+        assert_eq!(flagged.max_tokens, Some(1024));
+    }
+
+    #[test]
+    fn test_request_timeout_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let default = resolver(&temp_dir, AppConfig::default());
+        assert_eq!(
+            default.request_timeout(None).unwrap(),
+            Duration::from_secs(crate::DEFAULT_REQUEST_TIMEOUT_SECS)
+        );
+
+        let resolver = resolver(
+            &temp_dir,
+            AppConfig {
+                request_timeout: Some(30),
+                ..AppConfig::default()
+            },
+        );
+        assert_eq!(
+            resolver.request_timeout(None).unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            resolver
+                .request_timeout(Some(Duration::from_secs(5)))
+                .unwrap(),
+            Duration::from_secs(5)
+        );
+
+        let resolver = self::resolver(
+            &temp_dir,
+            AppConfig {
+                request_timeout: Some(0),
+                ..AppConfig::default()
+            },
+        );
+        let err = resolver.request_timeout(None).unwrap_err();
+        assert!(matches!(err, CliError::ConfigError(_)));
+        assert!(err
+            .to_string()
+            .contains("request_timeout must be between 1 and 600 seconds"));
+    }
+
+    #[test]
+    fn test_invalid_configured_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(
+            &temp_dir,
+            AppConfig {
+                default_max_tokens: Some(0),
+                ..AppConfig::default()
+            },
+        );
+
+        let err = resolver
+            .generation("openai", GenerationSettings::default())
+            .unwrap_err();
+        assert!(matches!(err, CliError::ConfigError(ref msg) if msg.contains("Max tokens")));
+    }
+
+    #[test]
+    fn test_failover_order_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(&temp_dir, AppConfig::default());
+        assert_eq!(resolver.failover_order(&[]).unwrap(), vec!["openai"]);
+
+        let resolver = resolver_with_order(&temp_dir, &["anthropic", "openai"]);
+        assert_eq!(
+            resolver.failover_order(&[]).unwrap(),
+            vec!["anthropic", "openai"]
+        );
+        let preference = vec!["openai".to_string(), "anthropic".to_string()];
+        assert_eq!(resolver.failover_order(&preference).unwrap(), preference);
+    }
+
+    #[test]
+    fn test_failover_order_rejects_unknown_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(&temp_dir, AppConfig::default());
+        let err = resolver
+            .failover_order(&["openai".to_string(), "gemini".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, CliError::ValidationError(ref msg) if msg.contains("'gemini'")));
+
+        let resolver = resolver_with_order(&temp_dir, &["openai", "gemini"]);
+        let err = resolver.failover_order(&[]).unwrap_err();
+        assert!(matches!(err, CliError::ConfigError(ref msg) if msg.contains("'gemini'")));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_swallows_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let warmed = resolver(&temp_dir, AppConfig::default());
+        let provider = Arc::new(StubProvider::new("openai").cold());
+        warmed.providers.register(provider.clone()).await;
+
+        warmed.warm_up().await;
+        assert_eq!(provider.warmups(), 1);
+
+        // A default provider that cannot be built is skipped as well
+        let mut config = AppConfig::default();
+        config.providers[0].enabled = false;
+        let other = TempDir::new().unwrap();
+        resolver(&other, config).warm_up().await;
+    }
+
+    #[tokio::test]
+    async fn test_route_with_failover() {
+        let temp_dir = TempDir::new().unwrap();
+        let resolver = resolver(&temp_dir, AppConfig::default());
+        resolver
+            .providers
+            .register(Arc::new(StubProvider::new("openai")))
+            .await;
+        resolver
+            .providers
+            .register(Arc::new(StubProvider::new("anthropic")))
+            .await;
+        let order = vec!["openai".to_string(), "anthropic".to_string()];
+
+        // A transient failure moves on to the next provider and its model
+        let answer = resolver
+            .route_with_failover(&order, None, |provider, model| async move {
+                match provider.name() {
+                    "openai" => Err(ProviderError::RateLimitError("slow down".to_string())),
+                    name => Ok(reply(format!("{} {}", name, model))),
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(answer.content, "anthropic claude-3-opus");
+
+        // Any other failure stops at once
+        let mut tried = Vec::new();
+        let err = resolver
+            .route_with_failover(&order, Some("m"), |provider, _| {
+                tried.push(provider.name().to_string());
+                async { Err::<PromptResponse, _>(ProviderError::AuthError("bad key".to_string())) }
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::AuthError(ref msg) if msg.contains("openai failed")));
+        assert_eq!(err.exit_code(), crate::error::exit_code::AUTH);
+        assert_eq!(tried, vec!["openai"]);
+
+        // The last transient failure keeps its kind when every provider fails
+        let err = resolver
+            .route_with_failover(&order, None, |_, _| async {
+                Err::<PromptResponse, _>(ProviderError::RateLimitError("slow down".to_string()))
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::RateLimitError(ref msg) if msg.contains("anthropic failed"))
+        );
+        assert_eq!(err.exit_code(), crate::error::exit_code::RATE_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_route_with_failover_enforces_quotas() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.providers[0].quota = Some(QuotaLimits {
+            max_requests: None,
+            max_tokens: Some(20),
+        });
+        let resolver =
+            resolver(&temp_dir, config).with_quota_path(temp_dir.path().join("quota.json"));
+        for name in ["openai", "anthropic"] {
+            resolver
+                .providers
+                .register(Arc::new(StubProvider::new(name)))
+                .await;
+        }
+        let order = vec!["openai".to_string(), "anthropic".to_string()];
+        let send = |provider: Arc<dyn AIProvider>, _| async move {
+            Ok(reply(provider.name().to_string()))
+        };
+
+        // 15 tokens leave openai under its limit, 30 put it over
+        for expected in ["openai", "openai", "anthropic"] {
+            let answer = resolver
+                .route_with_failover(&order, None, send)
+                .await
+                .unwrap();
+            assert_eq!(answer.content, expected);
+        }
+        let status = resolver.quotas().unwrap().unwrap().quota_status();
+        assert_eq!(
+            (status[1].provider.as_str(), status[1].tokens),
+            ("openai", 30)
+        );
+
+        let err = resolver
+            .route_with_failover(&order[..1], None, send)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("quota exceeded for openai"),
+            "{}",
+            err
+        );
+        assert_eq!(err.exit_code(), crate::error::exit_code::RATE_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_route_with_failover_records_metrics() {
+        let temp_dir = TempDir::new().unwrap();
+        let metrics = Arc::new(Metrics::new());
+        let resolver = resolver(&temp_dir, AppConfig::default()).with_metrics(metrics.clone());
+        for name in ["openai", "anthropic"] {
+            resolver
+                .providers
+                .register(Arc::new(StubProvider::new(name)))
+                .await;
+        }
+        let order = vec!["openai".to_string(), "anthropic".to_string()];
+
+        resolver
+            .route_with_failover(&order, None, |provider, _| async move {
+                match provider.name() {
+                    "openai" => Err(ProviderError::Unavailable("down".to_string())),
+                    name => Ok(reply(name.to_string())),
+                }
+            })
+            .await
+            .unwrap();
+        let output = metrics.render_prometheus();
+        assert!(output.contains("ai_cli_provider_requests_total{provider=\"openai\"} 1"));
+        assert!(output.contains("ai_cli_provider_requests_total{provider=\"anthropic\"} 1"));
+        assert!(output
+            .contains("ai_cli_provider_request_duration_seconds_count{provider=\"anthropic\"} 1"));
+    }
+
+    fn resolver_with_order(temp_dir: &TempDir, order: &[&str]) -> ProviderResolver {
+        resolver(
+            temp_dir,
+            AppConfig {
+                failover_order: order.iter().map(|name| name.to_string()).collect(),
+                ..AppConfig::default()
+            },
+        )
+    }
+}
@@ -0,0 +1,193 @@
+//! Stubs shared by the handler tests
+
+use super::ProviderResolver;
+use crate::cli::{Cli, CommandContext};
+use ai_cli_ai_engine::provider::{
+    AIProvider, FinishReason, HealthStatus, ModelInfo, PromptRequest, PromptResponse,
+    ProviderCapabilities, ProviderError, ProviderRegistry, ProviderResult, ResponseMetadata,
+    ResponseStream, StreamChunk, TokenUsage,
+};
+use ai_cli_security::credentials::CredentialManager;
+use async_trait::async_trait;
+use clap::Parser;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Parse `args`, program name first, into a command context
+pub fn context(args: &[&str]) -> CommandContext {
+    CommandContext::new(Cli::try_parse_from(args).unwrap())
+}
+
+/// Resolver over `providers`, reading `config.json` in `dir`
+pub async fn resolver(
+    dir: &Path,
+    providers: impl IntoIterator<Item = StubProvider>,
+) -> ProviderResolver {
+    let registry = Arc::new(ProviderRegistry::new());
+    for provider in providers {
+        registry.register(Arc::new(provider)).await;
+    }
+    ProviderResolver::new(
+        registry,
+        Arc::new(RwLock::new(CredentialManager::new())),
+        dir.join("config.json"),
+    )
+}
+
+type Reply = Box<dyn Fn(&PromptRequest) -> ProviderResult<String> + Send + Sync>;
+
+/// Provider answering every request through a closure
+///
+/// Without a reply every request fails. Streaming is off unless enabled, and
+/// then sends the reply as one chunk.
+pub struct StubProvider {
+    name: String,
+    reply: Reply,
+    models: Vec<ModelInfo>,
+    unhealthy: Option<String>,
+    delay: Option<Duration>,
+    streaming: bool,
+    cold: bool,
+    warmups: AtomicUsize,
+}
+
+impl StubProvider {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            reply: Box::new(|_| Err(ProviderError::Unavailable("not used".to_string()))),
+            models: Vec::new(),
+            unhealthy: None,
+            delay: None,
+            streaming: false,
+            cold: false,
+            warmups: AtomicUsize::new(0),
+        }
+    }
+
+    /// Answer each request with what `reply` returns for it
+    pub fn replying(
+        mut self,
+        reply: impl Fn(&PromptRequest) -> ProviderResult<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.reply = Box::new(reply);
+        self
+    }
+
+    /// List a model with `context_window` tokens
+    pub fn with_model(mut self, id: &str, context_window: u32) -> Self {
+        self.models.push(ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            context_window,
+            max_output_tokens: None,
+            pricing: None,
+            capabilities: vec![],
+        });
+        self
+    }
+
+    /// Fail health checks with `reason`
+    pub fn unhealthy(mut self, reason: &str) -> Self {
+        self.unhealthy = Some(reason.to_string());
+        self
+    }
+
+    /// Wait `delay` before answering; `Duration::MAX` never answers
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Advertise and support streaming
+    pub fn streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    /// Fail every warm-up
+    pub fn cold(mut self) -> Self {
+        self.cold = true;
+        self
+    }
+
+    /// How many times the provider was warmed up
+    pub fn warmups(&self) -> usize {
+        self.warmups.load(Ordering::SeqCst)
+    }
+
+    async fn answer(&self, request: &PromptRequest) -> ProviderResult<String> {
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        (self.reply)(request)
+    }
+}
+
+#[async_trait]
+impl AIProvider for StubProvider {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let content = self.answer(&request).await?;
+        Ok(PromptResponse {
+            content,
+            model: request.model,
+            usage: TokenUsage::empty(),
+            finish_reason: FinishReason::Stop,
+            metadata: ResponseMetadata {
+                request_id: request.metadata.request_id,
+                timestamp: chrono::Utc::now(),
+                latency_ms: 0,
+                cost: None,
+            },
+        })
+    }
+
+    async fn stream_prompt(&self, request: PromptRequest) -> ProviderResult<ResponseStream> {
+        if !self.streaming {
+            return Err(ProviderError::InvalidRequest("no streaming".to_string()));
+        }
+        let content = self.answer(&request).await?;
+        let chunk = Ok(StreamChunk {
+            content,
+            finish_reason: None,
+            usage: None,
+        });
+        Ok(Box::pin(futures::stream::iter([chunk])))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        Ok(self.models.clone())
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        Ok(match &self.unhealthy {
+            Some(reason) => HealthStatus::unhealthy(reason.clone()),
+            None => HealthStatus::healthy(0),
+        })
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.warmups.fetch_add(1, Ordering::SeqCst);
+        if self.cold {
+            return Err(ProviderError::NetworkError(
+                "connection refused".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: self.streaming,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! `tokens` command handler
+//!
+//! Counts the tokens in a prompt and, when a model can be resolved, checks
+//! the count against that model's context window.
+
+use super::ProviderResolver;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, Commands, OutputFormat};
+use ai_cli_ai_engine::cost::{EstimatingTokenizer, Tokenizer};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Handler for the `tokens` command
+pub struct TokensHandler {
+    resolver: ProviderResolver,
+    tokenizer: Arc<dyn Tokenizer>,
+}
+
+impl TokensHandler {
+    /// Handler counting with the estimating tokenizer
+    pub fn new(resolver: ProviderResolver) -> Self {
+        Self::with_tokenizer(resolver, Arc::new(EstimatingTokenizer))
+    }
+
+    pub fn with_tokenizer(resolver: ProviderResolver, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        Self {
+            resolver,
+            tokenizer,
+        }
+    }
+
+    /// The resolved model and its context window, if the provider lists it
+    async fn context_window(
+        &self,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> CliResult<(String, Option<u32>)> {
+        let (provider, model) = self.resolver.resolve(provider, model).await?;
+        let window = match provider.get_models().await {
+            Ok(models) => models
+                .into_iter()
+                .find(|m| m.id == model)
+                .map(|m| m.context_window),
+            Err(e) => {
+                log::warn!("Cannot list {} models: {}", provider.name(), e);
+                None
+            }
+        };
+        Ok((model, window))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for TokensHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (text, file, provider, model) = match &ctx.cli.command {
+            Some(Commands::Tokens {
+                text,
+                file,
+                provider,
+                model,
+            }) => (text, file, provider.as_deref(), model.as_deref()),
+            _ => {
+                return Err(CliError::RoutingError(
+                    "tokens handler received a different command".to_string(),
+                ))
+            }
+        };
+
+        let text = match (text, file) {
+            (Some(text), _) => text.clone(),
+            (None, Some(file)) => std::fs::read_to_string(file)
+                .map_err(|e| CliError::ValidationError(format!("{}: {}", file, e)))?,
+            (None, None) => {
+                return Err(CliError::ValidationError(
+                    "Pass the text to count or --file".to_string(),
+                ))
+            }
+        };
+        let tokens = self.tokenizer.count(&text);
+
+        // Without an explicit target, a missing default model only means
+        // there is no window to compare against
+        let target = match self.context_window(provider, model).await {
+            Ok(target) => Some(target),
+            Err(e) if provider.is_none() && model.is_none() => {
+                log::debug!("No model to check the context window of: {}", e);
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        let window = target.as_ref().and_then(|(_, window)| *window);
+        let remaining = window.map(|window| i64::from(window) - i64::from(tokens));
+
+        if matches!(ctx.cli.format, OutputFormat::Json) {
+            return Ok(CommandResult::success_with_data(json!({
+                "tokens": tokens,
+                "tokenizer": self.tokenizer.name(),
+                "model": target.as_ref().map(|(model, _)| model),
+                "context_window": window,
+                "remaining": remaining,
+            })));
+        }
+
+        let mut lines = vec![format!("Tokens: {} ({})", tokens, self.tokenizer.name())];
+        match (&target, window, remaining) {
+            (Some((model, _)), Some(window), Some(remaining)) if remaining >= 0 => {
+                lines.push(format!(
+                    "{}: {} token context window, {} remaining",
+                    model, window, remaining
+                ))
+            }
+            (Some((model, _)), Some(window), Some(remaining)) => lines.push(format!(
+                "{}: {} token context window, exceeded by {}",
+                model, window, -remaining
+            )),
+            (Some((model, _)), _, _) => lines.push(format!("{}: context window unknown", model)),
+            (None, _, _) => {}
+        }
+        Ok(CommandResult::success_with_message(lines.join("\n")))
+    }
+
+    fn name(&self) -> &str {
+        "tokens"
+    }
+
+    fn description(&self) -> &str {
+        "Count tokens against a model's context window"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use crate::cli::Cli;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    /// Handler over a provider listing one model with a small context window
+    async fn handler(temp_dir: &TempDir) -> TokensHandler {
+        let provider = StubProvider::new("mock").with_model("small-1", 10);
+        TokensHandler::new(test_support::resolver(temp_dir.path(), [provider]).await)
+    }
+
+    async fn run(handler: &TokensHandler, args: &[&str]) -> CliResult<CommandResult> {
+        handler.execute(&context(&[&["ai"], args].concat())).await
+    }
+
+    #[tokio::test]
+    async fn test_counts_against_context_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir).await;
+
+        // 12 characters at four per token
+        let result = run(
+            &handler,
+            &[
+                "--format",
+                "json",
+                "tokens",
+                "hello world!",
+                "--provider",
+                "mock",
+                "--model",
+                "small-1",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result.data.unwrap(),
+            json!({
+                "tokens": 3,
+                "tokenizer": "estimate",
+                "model": "small-1",
+                "context_window": 10,
+                "remaining": 7,
+            })
+        );
+
+        let prompt = temp_dir.path().join("prompt.md");
+        std::fs::write(&prompt, "x".repeat(48)).unwrap();
+        let result = run(
+            &handler,
+            &[
+                "tokens",
+                "--file",
+                prompt.to_str().unwrap(),
+                "--provider",
+                "mock",
+                "--model",
+                "small-1",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result.message.unwrap(),
+            "Tokens: 12 (estimate)\nsmall-1: 10 token context window, exceeded by 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_model_has_unknown_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir).await;
+
+        let result = run(
+            &handler,
+            &["tokens", "abcd", "--provider", "mock", "--model", "large-2"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result.message.unwrap(),
+            "Tokens: 1 (estimate)\nlarge-2: context window unknown"
+        );
+
+        assert!(Cli::try_parse_from(["ai", "tokens"]).is_err());
+        assert!(Cli::try_parse_from(["ai", "tokens", "abc", "--file", "p.md"]).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! `version` command handler
+//!
+//! Also serves `--version`, which prints just the version unless `-v` asks
+//! for the build details.
+
+use crate::build_info::BuildInfo;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, OutputFormat};
+use async_trait::async_trait;
+
+/// Handler reporting version and build details
+pub struct VersionHandler;
+
+#[async_trait]
+impl CommandHandler for VersionHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let info = BuildInfo::current();
+
+        if matches!(ctx.cli.format, OutputFormat::Json) {
+            let data = serde_json::to_value(&info)
+                .map_err(|e| CliError::ValidationError(e.to_string()))?;
+            return Ok(CommandResult::success_with_data(data));
+        }
+
+        if ctx.cli.version && ctx.cli.verbose == 0 {
+            return Ok(CommandResult::success_with_message(format!(
+                "ai {}",
+                info.version
+            )));
+        }
+        Ok(CommandResult::success_with_message(info.to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "version"
+    }
+
+    fn description(&self) -> &str {
+        "Show version and build details"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+
+    fn context(args: &[&str]) -> CommandContext {
+        let mut cli = Cli::try_parse_from(args).unwrap();
+        if cli.version {
+            cli.command = Some(Commands::Version);
+        }
+        CommandContext::new(cli)
+    }
+
+    #[tokio::test]
+    async fn test_version_flag_is_plain() {
+        let result = VersionHandler
+            .execute(&context(&["ai", "--version"]))
+            .await
+            .unwrap();
+        assert_eq!(result.message, Some(format!("ai {}", crate::VERSION)));
+
+        let result = VersionHandler
+            .execute(&context(&["ai", "--version", "-v"]))
+            .await
+            .unwrap();
+        assert!(result.message.unwrap().contains("commit:"));
+    }
+
+    #[tokio::test]
+    async fn test_version_json() {
+        let result = VersionHandler
+            .execute(&context(&["ai", "version", "--format", "json"]))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        for key in [
+            "version",
+            "git_commit",
+            "build_timestamp",
+            "rustc_version",
+            "features",
+        ] {
+            assert!(data.get(key).is_some(), "missing {}", key);
+        }
+    }
+}
@@ -0,0 +1,663 @@
+//! `work` command handler
+//!
+//! Asks the provider for a set of whole-file edits, previews them and writes
+//! them after confirmation. The original contents are saved as a checkpoint
+//! first, so a bad edit can be undone with `ai checkpoint restore <id>`. If a
+//! write fails partway, the files already written are put back.
+
+use super::plan::plan_key;
+use super::ProviderResolver;
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{
+    CliError, CliResult, CommandContext, Commands, InputValidator, ProjectRoot, Prompter,
+};
+use ai_cli_ai_engine::provider::{
+    send_with_timeout, Message, MessageRole, PromptRequest, ProviderError,
+};
+use ai_cli_checkpoint::manager::CheckpointManager;
+use ai_cli_memory_system::MemorySystem;
+use ai_cli_utils::fs::write_atomic;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+const SYSTEM_PROMPT: &str = "You edit files in a software project. Reply with only a JSON \
+object of the form {\"summary\": \"<one line>\", \"edits\": [{\"path\": \"<path relative to \
+the project root>\", \"new_content\": \"<complete new file contents>\"}]}.";
+
+/// Description of the checkpoints holding a [`FileBackup`]
+pub const WORK_BACKUP_DESCRIPTION: &str = "work-backup";
+
+/// A whole-file replacement proposed by the provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEdit {
+    pub path: String,
+    pub new_content: String,
+}
+
+/// The provider's answer to a work task
+#[derive(Debug, Clone, Deserialize)]
+pub struct EditPlan {
+    #[serde(default)]
+    pub summary: Option<String>,
+    pub edits: Vec<FileEdit>,
+}
+
+impl EditPlan {
+    /// Parse a plan from a model reply, tolerating a surrounding code fence or prose
+    pub fn parse(reply: &str) -> CliResult<Self> {
+        let json = match (reply.find('{'), reply.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &reply[start..=end],
+            _ => {
+                return Err(CliError::ValidationError(
+                    "Provider reply contains no edit plan".to_string(),
+                ))
+            }
+        };
+        serde_json::from_str(json)
+            .map_err(|e| CliError::ValidationError(format!("Invalid edit plan: {}", e)))
+    }
+}
+
+/// Original contents of the files an edit plan touches
+///
+/// Files are keyed by their path relative to the directory `work` runs in.
+/// `None` marks a file that did not exist before the edit. Backups can be
+/// imported from elsewhere, so every path is checked against the root it is
+/// restored under rather than trusted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBackup {
+    pub files: BTreeMap<String, Option<String>>,
+}
+
+impl FileBackup {
+    /// Current contents of `paths` under `root`
+    pub fn capture<'a>(
+        root: &ProjectRoot,
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> CliResult<Self> {
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let target = InputValidator::validate_path_within(root, path)?;
+            let original = match std::fs::read_to_string(&target) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(CliError::ValidationError(format!("{}: {}", path, e)));
+                }
+            };
+            files.insert(path.to_string(), original);
+        }
+        Ok(Self { files })
+    }
+
+    /// Where each file goes under `root`, failing if any path leaves it
+    pub fn targets(&self, root: &ProjectRoot) -> CliResult<Vec<PathBuf>> {
+        self.files
+            .keys()
+            .map(|path| InputValidator::validate_path_within(root, path))
+            .collect()
+    }
+
+    /// Put every file under `root` back as it was, removing files the edit
+    /// created
+    ///
+    /// Nothing is written unless every path stays inside `root`.
+    pub fn restore(&self, root: &ProjectRoot) -> CliResult<()> {
+        let targets = self.targets(root)?;
+        for ((path, original), target) in self.files.iter().zip(&targets) {
+            let restored = match original {
+                Some(content) => write_atomic(target, content.as_bytes()),
+                None if target.exists() => std::fs::remove_file(target),
+                None => Ok(()),
+            };
+            restored.map_err(|e| CliError::ValidationError(format!("{}: {}", path, e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Handler for work sessions that edit project files
+pub struct WorkHandler {
+    resolver: ProviderResolver,
+    checkpoints: Arc<CheckpointManager>,
+    /// Directory `--project` is relative to, and the project without it
+    root: PathBuf,
+    prompter: Arc<Prompter>,
+    memory: Option<Arc<RwLock<MemorySystem>>>,
+}
+
+impl WorkHandler {
+    /// Handler editing files under `root`
+    pub fn new(
+        resolver: ProviderResolver,
+        checkpoints: Arc<CheckpointManager>,
+        root: impl Into<PathBuf>,
+        prompter: Arc<Prompter>,
+    ) -> Self {
+        Self {
+            resolver,
+            checkpoints,
+            root: root.into(),
+            prompter,
+            memory: None,
+        }
+    }
+
+    /// Send the plan stored in `memory` for the project along with each task
+    pub fn with_memory(mut self, memory: Arc<RwLock<MemorySystem>>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// The plan `ai plan` stored for `project`, if any
+    async fn stored_plan(&self, project: Option<&str>) -> Option<String> {
+        let memory = self.memory.as_ref()?.read().await;
+        let plan = memory.retrieve(&plan_key(project))?;
+        log::debug!("Using the stored plan for {}", project.unwrap_or("."));
+        Some(plan.value.clone())
+    }
+
+    /// The directory edits are confined to, inside the work root
+    fn project_root(&self, project: Option<&str>) -> CliResult<ProjectRoot> {
+        let root = ProjectRoot::new(&self.root)?;
+        match project {
+            Some(project) => {
+                ProjectRoot::new(InputValidator::validate_path_within(&root, project)?)
+            }
+            None => Ok(root),
+        }
+    }
+
+    /// Path of `target` relative to the work root, as a backup keys it
+    fn backup_key(work_root: &ProjectRoot, target: &Path) -> CliResult<String> {
+        target
+            .strip_prefix(work_root.path())
+            .ok()
+            .and_then(Path::to_str)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                CliError::ValidationError(format!(
+                    "Path escapes the project directory: {}",
+                    target.display()
+                ))
+            })
+    }
+
+    /// Check that every edit stays inside `root`, returning where each one goes
+    fn validate(&self, root: &ProjectRoot, edits: &[FileEdit]) -> CliResult<Vec<PathBuf>> {
+        edits
+            .iter()
+            .map(|edit| InputValidator::validate_path_within(root, &edit.path))
+            .collect()
+    }
+
+    fn preview(&self, root: &Path, plan: &EditPlan) -> String {
+        let mut lines = Vec::new();
+        if let Some(summary) = &plan.summary {
+            lines.push(summary.clone());
+        }
+        for edit in &plan.edits {
+            let new_lines = edit.new_content.lines().count();
+            let line = match std::fs::read_to_string(root.join(&edit.path)) {
+                Ok(old) => format!(
+                    "  modify {} ({} -> {} lines)",
+                    edit.path,
+                    old.lines().count(),
+                    new_lines
+                ),
+                Err(_) => format!("  create {} ({} lines)", edit.path, new_lines),
+            };
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    fn commit(&self, root: &Path, edits: &[FileEdit], message: &str) -> Result<(), String> {
+        let git = |args: &[&str]| -> Result<(), String> {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(root)
+                .output()
+                .map_err(|e| e.to_string())?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+            }
+        };
+
+        let mut add = vec!["add", "--"];
+        add.extend(edits.iter().map(|edit| edit.path.as_str()));
+        git(&add)?;
+        git(&["commit", "-m", message])
+    }
+
+    fn is_git_repo(&self, root: &Path) -> bool {
+        Command::new("git")
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .current_dir(root)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait]
+impl CommandHandler for WorkHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let (project, task, auto_commit, provider, model, options) = match &ctx.cli.command {
+            Some(Commands::Work {
+                project,
+                task,
+                auto_commit,
+                provider,
+                model,
+                request,
+            }) => (project, task, *auto_commit, provider, model, request),
+            _ => {
+                return Err(CliError::RoutingError(
+                    "work handler received a different command".to_string(),
+                ))
+            }
+        };
+        let task = task
+            .as_deref()
+            .ok_or_else(|| CliError::ValidationError("--task is required".to_string()))?;
+        let root = self.project_root(project.as_deref())?;
+
+        let order = match provider {
+            Some(provider) => vec![provider.clone()],
+            None => self.resolver.failover_order(&ctx.cli.provider_order)?,
+        };
+        let mut generation = BTreeMap::new();
+        for name in &order {
+            generation.insert(
+                name.clone(),
+                self.resolver.generation(name, ctx.cli.generation())?,
+            );
+        }
+        let mut content = match project {
+            Some(project) => format!("Project: {}\n\nTask: {}", project, task),
+            None => task.to_string(),
+        };
+        if let Some(plan) = self.stored_plan(project.as_deref()).await {
+            content = format!("Plan:\n{}\n\n{}", plan.trim_end(), content);
+        }
+        let processors = self.resolver.processors()?;
+        let timeout = self.resolver.request_timeout(ctx.timeout())?;
+        let scanner = ctx.outbound_scanner();
+        let metadata = ctx.request_metadata().await;
+        let mut response = self
+            .resolver
+            .route_with_failover(&order, model.as_deref(), |provider, model| {
+                let generation = generation.get(provider.name()).copied().unwrap_or_default();
+                let mut request = PromptRequest {
+                    model,
+                    system_prompt: Some(SYSTEM_PROMPT.to_string()),
+                    messages: vec![Message {
+                        role: MessageRole::User,
+                        content: content.clone(),
+                        name: None,
+                    }],
+                    temperature: generation.temperature,
+                    max_tokens: generation.max_tokens,
+                    stop_sequences: options.stop_sequences(),
+                    parameters: options.parameters(),
+                    metadata: metadata.renewed(),
+                };
+                log::debug!(
+                    "Sending work request {} to {} (correlation {})",
+                    request.metadata.request_id,
+                    provider.name(),
+                    request.metadata.correlation_id.as_deref().unwrap_or("none")
+                );
+                let scanned = scanner
+                    .check(&mut request)
+                    .map_err(|e| ProviderError::InvalidRequest(e.to_string()));
+                async move {
+                    scanned?;
+                    ctx.retry_policy()
+                        .run(|| async {
+                            send_with_timeout(provider.as_ref(), request.clone(), timeout).await
+                        })
+                        .await
+                }
+            })
+            .await?;
+
+        processors.apply(&mut response);
+        let plan = EditPlan::parse(&response.content)?;
+        if plan.edits.is_empty() {
+            return Ok(CommandResult::success_with_message("No edits proposed"));
+        }
+        let targets = self.validate(&root, &plan.edits)?;
+
+        eprintln!("{}", self.preview(root.path(), &plan));
+        let prompt = format!("Apply {} edit(s)?", plan.edits.len());
+        if !self.prompter.confirm(&prompt, auto_commit)? {
+            return Ok(CommandResult::success_with_message("Edits discarded"));
+        }
+
+        let work_root = ProjectRoot::new(&self.root)?;
+        let keys = targets
+            .iter()
+            .map(|target| Self::backup_key(&work_root, target))
+            .collect::<CliResult<Vec<_>>>()?;
+        let backup = FileBackup::capture(&work_root, keys.iter().map(String::as_str))?;
+        let data =
+            serde_json::to_vec(&backup).map_err(|e| CliError::ValidationError(e.to_string()))?;
+        let checkpoint = self
+            .checkpoints
+            .create_checkpoint_with_description(
+                format!("work: {}", task),
+                WORK_BACKUP_DESCRIPTION,
+                &data,
+            )
+            .await
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+
+        for (edit, target) in plan.edits.iter().zip(&targets) {
+            if let Err(e) = write_atomic(target, edit.new_content.as_bytes()) {
+                let outcome = match backup.restore(&work_root) {
+                    Ok(()) => "no files were changed".to_string(),
+                    Err(restore) => format!(
+                        "rolling back failed ({}); restore checkpoint {} to undo the edits",
+                        restore, checkpoint.id
+                    ),
+                };
+                return Err(CliError::ValidationError(format!(
+                    "{}: {}; {}",
+                    edit.path, e, outcome
+                )));
+            }
+        }
+
+        let mut message = format!(
+            "Applied {} edit(s); originals saved in checkpoint {}",
+            plan.edits.len(),
+            checkpoint.id
+        );
+        if auto_commit && self.is_git_repo(root.path()) {
+            if let Err(e) = self.commit(root.path(), &plan.edits, task) {
+                return Ok(CommandResult::error(format!(
+                    "{}, but git commit failed: {}",
+                    message, e
+                )));
+            }
+            message.push_str("; committed");
+        }
+
+        let data = serde_json::to_value(&plan.edits)
+            .map_err(|e| CliError::ValidationError(e.to_string()))?;
+        Ok(CommandResult {
+            message: Some(message),
+            ..CommandResult::success_with_data(data)
+        })
+    }
+
+    fn name(&self) -> &str {
+        "work"
+    }
+
+    fn description(&self) -> &str {
+        "Apply provider-proposed file edits"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::checkpoint::CheckpointHandler;
+    use crate::cli::handlers::test_support::{self, context, StubProvider};
+    use ai_cli_checkpoint::manager::CheckpointConfig;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    const REPLY: &str = r#"Here you go:
+```json
+{"summary": "Greet politely", "edits": [
+  {"path": "src/hello.txt", "new_content": "hello, world\n"},
+  {"path": "NOTES.md", "new_content": "new file\n"}
+]}
+```"#;
+
+    async fn handler(temp_dir: &TempDir, reply: &str, prompter: Prompter) -> WorkHandler {
+        // Always proposes the same reply
+        let reply = reply.to_string();
+        let provider = StubProvider::new("openai").replying(move |_| Ok(reply.clone()));
+        let resolver = test_support::resolver(temp_dir.path(), [provider]).await;
+        let checkpoints = CheckpointManager::new(CheckpointConfig {
+            storage_path: temp_dir.path().join("checkpoints"),
+            ..CheckpointConfig::default()
+        })
+        .unwrap();
+
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/hello.txt"), "hi\n").unwrap();
+
+        WorkHandler::new(resolver, Arc::new(checkpoints), root, Arc::new(prompter))
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = EditPlan::parse(REPLY).unwrap();
+        assert_eq!(plan.summary.as_deref(), Some("Greet politely"));
+        assert_eq!(plan.edits.len(), 2);
+        assert_eq!(plan.edits[0].path, "src/hello.txt");
+
+        assert!(EditPlan::parse("I can't do that").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_after_confirmation_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(
+            &temp_dir,
+            REPLY,
+            Prompter::from_reader(Cursor::new("y\n"), true),
+        )
+        .await;
+
+        let result = handler
+            .execute(&context(&["ai", "work", "--task", "greet"]))
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let root = temp_dir.path().join("project");
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/hello.txt")).unwrap(),
+            "hello, world\n"
+        );
+        assert!(root.join("NOTES.md").exists());
+
+        let checkpoint = handler.checkpoints.list_checkpoints().await.remove(0);
+        assert!(result.message.unwrap().ends_with(&checkpoint.id));
+        let checkpoints = CheckpointHandler::new(
+            handler.checkpoints.clone(),
+            temp_dir.path().join("state.json"),
+            Arc::new(Prompter::from_reader(Cursor::new(""), false)),
+        )
+        .with_work_root(&root);
+        let result = checkpoints
+            .execute(&context(&[
+                "ai",
+                "checkpoint",
+                "restore",
+                &checkpoint.id,
+                "--force",
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Restored 2 file(s) from checkpoint work: greet"));
+        assert!(!temp_dir.path().join("state.json").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/hello.txt")).unwrap(),
+            "hi\n"
+        );
+        assert!(!root.join("NOTES.md").exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let reply = r#"{"edits": [
+            {"path": "src/hello.txt", "new_content": "hello\n"},
+            {"path": "blocker", "new_content": "a file\n"},
+            {"path": "blocker/inner.txt", "new_content": "needs a directory\n"}
+        ]}"#;
+        let handler = handler(
+            &temp_dir,
+            reply,
+            Prompter::from_reader(Cursor::new(""), false),
+        )
+        .await;
+
+        let err = handler
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--task",
+                "block",
+                "--auto-commit",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("no files were changed"), "{}", err);
+        let root = temp_dir.path().join("project");
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/hello.txt")).unwrap(),
+            "hi\n"
+        );
+        assert!(!root.join("blocker").exists());
+    }
+
+    #[tokio::test]
+    async fn test_declined_edits_are_not_written() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(
+            &temp_dir,
+            REPLY,
+            Prompter::from_reader(Cursor::new("n\n"), true),
+        )
+        .await;
+
+        let result = handler
+            .execute(&context(&["ai", "work", "--task", "greet"]))
+            .await
+            .unwrap();
+        assert_eq!(result.message.as_deref(), Some("Edits discarded"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("project/src/hello.txt")).unwrap(),
+            "hi\n"
+        );
+        assert!(handler.checkpoints.list_checkpoints().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_paths_outside_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let reply = r#"{"edits": [{"path": "../escape.txt", "new_content": "x"}]}"#;
+        let handler = handler(
+            &temp_dir,
+            reply,
+            Prompter::from_reader(Cursor::new(""), false),
+        )
+        .await;
+
+        let err = handler
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--task",
+                "escape",
+                "--auto-commit",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ValidationError(_)));
+        assert!(!temp_dir.path().join("escape.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rejects_symlink_out_of_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let reply = r#"{"edits": [{"path": "out/escape.txt", "new_content": "x"}]}"#;
+        let handler = handler(
+            &temp_dir,
+            reply,
+            Prompter::from_reader(Cursor::new(""), false),
+        )
+        .await;
+        std::os::unix::fs::symlink(temp_dir.path(), temp_dir.path().join("project/out")).unwrap();
+
+        let err = handler
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--task",
+                "escape",
+                "--auto-commit",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("escapes the project directory"));
+        assert!(!temp_dir.path().join("escape.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_project_selects_subdirectory() {
+        let temp_dir = TempDir::new().unwrap();
+        let reply = r#"{"edits": [{"path": "hello.txt", "new_content": "hey\n"}]}"#;
+        let handler = handler(
+            &temp_dir,
+            reply,
+            Prompter::from_reader(Cursor::new(""), false),
+        )
+        .await;
+
+        handler
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--project",
+                "src",
+                "--task",
+                "greet",
+                "--auto-commit",
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("project/src/hello.txt")).unwrap(),
+            "hey\n"
+        );
+
+        let err = handler
+            .execute(&context(&[
+                "ai",
+                "work",
+                "--project",
+                "missing",
+                "--task",
+                "x",
+            ]))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::ValidationError(_)));
+    }
+}
@@ -0,0 +1,166 @@
+//! `workflow` command handler
+//!
+//! Workflows are read from JSON definition files (see
+//! [`WorkflowDefinition`]) and drawn without being run.
+
+use crate::cli::router::{CommandHandler, CommandResult};
+use crate::cli::{CliError, CliResult, CommandContext, Commands, InputValidator, WorkflowCommands};
+use ai_cli_agent_framework::workflow::{GraphFormat, WorkflowDefinition};
+use async_trait::async_trait;
+use std::path::Path;
+
+/// Handler for workflow commands
+pub struct WorkflowHandler;
+
+impl WorkflowHandler {
+    async fn graph(
+        &self,
+        definition: &str,
+        output: Option<&str>,
+        format: Option<GraphFormat>,
+    ) -> CliResult<CommandResult> {
+        let json = std::fs::read_to_string(definition)
+            .map_err(|e| CliError::ValidationError(format!("{}: {}", definition, e)))?;
+        let definition: WorkflowDefinition = serde_json::from_str(&json)
+            .map_err(|e| CliError::ValidationError(format!("{}: {}", definition, e)))?;
+        let workflow = definition.build().await;
+
+        let format = format.unwrap_or_else(|| match output.map(Path::new) {
+            Some(path) if path.extension().is_some_and(|ext| ext == "mmd") => GraphFormat::Mermaid,
+            _ => GraphFormat::Dot,
+        });
+        let Some(output) = output else {
+            let graph = match format {
+                GraphFormat::Dot => workflow.to_dot().await,
+                GraphFormat::Mermaid => workflow.to_mermaid().await,
+            };
+            return Ok(CommandResult::success_with_message(graph.trim_end()));
+        };
+
+        InputValidator::validate_path(output)?;
+        workflow
+            .write_graph(output, format)
+            .await
+            .map_err(|e| CliError::ValidationError(format!("{}: {}", output, e)))?;
+        Ok(CommandResult::success_with_message(format!(
+            "Graph of workflow {} written to {}",
+            definition.id, output
+        )))
+    }
+}
+
+#[async_trait]
+impl CommandHandler for WorkflowHandler {
+    async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+        match &ctx.cli.command {
+            Some(Commands::Workflow {
+                subcommand:
+                    WorkflowCommands::Graph {
+                        definition,
+                        output,
+                        graph_format,
+                    },
+            }) => {
+                self.graph(definition, output.as_deref(), *graph_format)
+                    .await
+            }
+            _ => Err(CliError::RoutingError(
+                "workflow handler received a different command".to_string(),
+            )),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "workflow"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect workflow definitions"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::test_support::context;
+    use crate::cli::Cli;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    const DEFINITION: &str = r#"{
+        "id": "review",
+        "initial": "pending",
+        "transitions": [
+            {"from": "pending", "to": "running"},
+            {"from": "running", "to": "needs review", "condition": "changes > 10"},
+            {"from": "running", "to": "completed"}
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_graph_printed_and_written() {
+        let temp_dir = TempDir::new().unwrap();
+        let definition = temp_dir.path().join("review.json");
+        std::fs::write(&definition, DEFINITION).unwrap();
+        let definition = definition.to_str().unwrap();
+
+        let result = WorkflowHandler
+            .execute(&context(&["ai", "workflow", "graph", definition]))
+            .await
+            .unwrap();
+        let dot = result.message.unwrap();
+        assert!(dot.starts_with("digraph \"review\" {"), "{}", dot);
+        assert!(dot.contains("\"running\" -> \"needs review\" [label=\"changes > 10\"];"));
+
+        let output = temp_dir.path().join("review.mmd");
+        let result = WorkflowHandler
+            .execute(&context(&[
+                "ai",
+                "workflow",
+                "graph",
+                definition,
+                "--output",
+                output.to_str().unwrap(),
+            ]))
+            .await
+            .unwrap();
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Graph of workflow review written to"));
+        let mermaid = std::fs::read_to_string(&output).unwrap();
+        assert!(mermaid.starts_with("stateDiagram-v2\n"));
+        assert!(mermaid.contains("class pending current"));
+    }
+
+    #[tokio::test]
+    async fn test_graph_rejects_bad_input() {
+        assert!(Cli::try_parse_from([
+            "ai",
+            "workflow",
+            "graph",
+            "x.json",
+            "--graph-format",
+            "svg"
+        ])
+        .is_err());
+
+        let temp_dir = TempDir::new().unwrap();
+        let definition = temp_dir.path().join("broken.json");
+        std::fs::write(&definition, r#"{"id": "x"}"#).unwrap();
+        let err = WorkflowHandler
+            .execute(&context(&[
+                "ai",
+                "workflow",
+                "graph",
+                definition.to_str().unwrap(),
+            ]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::ValidationError(ref msg) if msg.contains("initial")),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Persisted chat sessions
+//!
+//! Each chat session is a JSONL file `<id>.jsonl` in the history directory.
+//! The first line records the provider and model, every later line one
+//! message. Lines are appended as the conversation goes, so a session cut
+//! short keeps everything up to its last exchange.
+
+use super::{CliError, CliResult};
+use ai_cli_ai_engine::provider::{Message, MessageRole};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// One line of a session file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HistoryRecord {
+    Start {
+        provider: String,
+        model: String,
+        started_at: DateTime<Utc>,
+    },
+    Message(HistoryMessage),
+}
+
+/// A message as recorded in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryMessage {
+    pub role: MessageRole,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<&HistoryMessage> for Message {
+    fn from(message: &HistoryMessage) -> Self {
+        Message {
+            role: message.role,
+            content: message.content.clone(),
+            name: None,
+        }
+    }
+}
+
+/// Overview of a recorded session, for listing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub provider: String,
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    pub messages: usize,
+}
+
+/// A recorded session with its transcript
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    pub id: String,
+    pub provider: String,
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    pub messages: Vec<HistoryMessage>,
+}
+
+impl Session {
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            started_at: self.started_at,
+            messages: self.messages.len(),
+        }
+    }
+
+    /// The transcript as conversation messages, to continue it
+    pub fn conversation(&self) -> Vec<Message> {
+        self.messages.iter().map(Message::from).collect()
+    }
+}
+
+/// Directory of recorded chat sessions
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    dir: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Start recording a new session
+    ///
+    /// Nothing is written until the first message, so sessions that end
+    /// without one leave no file behind.
+    pub fn start(&self, provider: &str, model: &str) -> SessionLog {
+        let started_at = Utc::now();
+        let id = format!(
+            "{}-{}",
+            started_at.format("%Y%m%d-%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        SessionLog {
+            path: self.dir.join(format!("{}.jsonl", id)),
+            start: Some(HistoryRecord::Start {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                started_at,
+            }),
+        }
+    }
+
+    /// Every recorded session, most recent first
+    ///
+    /// Files that cannot be read are skipped with a warning.
+    pub fn list(&self) -> CliResult<Vec<SessionSummary>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(self.error(e)),
+        };
+
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let name = entry.map_err(|e| self.error(e))?.file_name();
+            let Some(id) = name.to_str().and_then(|n| n.strip_suffix(".jsonl")) else {
+                continue;
+            };
+            match self.load(id) {
+                Ok(session) => sessions.push(session.summary()),
+                Err(e) => log::warn!("Skipping history session {}: {}", id, e),
+            }
+        }
+        sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+        Ok(sessions)
+    }
+
+    /// Read the session `id`
+    pub fn load(&self, id: &str) -> CliResult<Session> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid {
+            return Err(CliError::ValidationError(format!(
+                "Invalid session ID '{}'",
+                id
+            )));
+        }
+
+        let path = self.dir.join(format!("{}.jsonl", id));
+        let contents = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                CliError::ValidationError(format!("No session '{}' in history", id))
+            }
+            _ => self.error(e),
+        })?;
+
+        let mut records = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<HistoryRecord>(line)
+                    .map_err(|e| CliError::ValidationError(format!("{}: {}", path.display(), e)))
+            });
+        let Some(HistoryRecord::Start {
+            provider,
+            model,
+            started_at,
+        }) = records.next().transpose()?
+        else {
+            return Err(CliError::ValidationError(format!(
+                "{}: missing session header",
+                path.display()
+            )));
+        };
+        let messages = records
+            .filter_map(|record| match record {
+                Ok(HistoryRecord::Message(message)) => Some(Ok(message)),
+                Ok(HistoryRecord::Start { .. }) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<CliResult<_>>()?;
+
+        Ok(Session {
+            id: id.to_string(),
+            provider,
+            model,
+            started_at,
+            messages,
+        })
+    }
+
+    fn error(&self, e: io::Error) -> CliError {
+        CliError::ConfigError(format!("{}: {}", self.dir.display(), e))
+    }
+}
+
+/// Appends the messages of one session to its history file
+#[derive(Debug)]
+pub struct SessionLog {
+    path: PathBuf,
+    /// Header still to be written before the first message
+    start: Option<HistoryRecord>,
+}
+
+impl SessionLog {
+    /// Record `message`, creating the file on the first one
+    pub fn append(&mut self, message: &Message) -> io::Result<()> {
+        let mut lines = Vec::new();
+        if let Some(start) = &self.start {
+            lines.push(start.clone());
+        }
+        lines.push(HistoryRecord::Message(HistoryMessage {
+            role: message.role,
+            content: message.content.clone(),
+            timestamp: Utc::now(),
+        }));
+
+        let mut text = String::new();
+        for line in &lines {
+            text.push_str(&serde_json::to_string(line).map_err(io::Error::other)?);
+            text.push('\n');
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(text.as_bytes())?;
+        self.start = None;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
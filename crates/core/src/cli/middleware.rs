@@ -1,12 +1,18 @@
 //! Middleware pipeline for command pre/post processing
 
-use super::router::CommandResult;
-use super::{CliError, CliResult, CommandContext};
+use super::router::{CommandResult, CommandRouter};
+use super::{CliError, CliResult, CommandContext, Commands, CORRELATION_ID};
+use crate::metrics::Metrics;
 use async_trait::async_trait;
-use clap::Parser;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use tracing::{debug, instrument};
 
+/// The rest of a command's execution: its handler, wrapped by the
+/// middlewares of lower priority
+pub type Next<'a> = Pin<Box<dyn Future<Output = CliResult<CommandResult>> + Send + 'a>>;
+
 /// Middleware trait for command processing
 #[async_trait]
 pub trait Middleware: Send + Sync {
@@ -22,8 +28,31 @@ pub trait Middleware: Send + Sync {
         Ok(())
     }
 
+    /// Run the command as `next`, e.g. to bound it; higher priorities wrap
+    /// lower ones
+    async fn around<'a>(
+        &'a self,
+        ctx: &'a CommandContext,
+        next: Next<'a>,
+    ) -> CliResult<CommandResult> {
+        let _ = ctx;
+        next.await
+    }
+
     /// Get middleware name
     fn name(&self) -> &str;
+
+    /// Position in the chain: higher priorities run `before` earlier and
+    /// `after` later; equal priorities keep insertion order
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Whether the chain should run this middleware for `command`
+    fn applies_to(&self, command: &Option<Commands>) -> bool {
+        let _ = command;
+        true
+    }
 }
 
 /// Middleware chain executor
@@ -39,25 +68,60 @@ impl MiddlewareChain {
         }
     }
 
-    /// Add middleware to the chain
+    /// Add middleware to the chain, placed by its [`Middleware::priority`]
+    #[allow(clippy::should_implement_trait)]
     pub fn add<M: Middleware + 'static>(mut self, middleware: M) -> Self {
         self.middlewares.push(Arc::new(middleware));
+        // Stable, so insertion order breaks ties
+        self.middlewares
+            .sort_by_key(|m| std::cmp::Reverse(m.priority()));
         self
     }
 
-    /// Execute before middlewares
+    /// Names of the middlewares in `before` order
+    pub fn names(&self) -> Vec<&str> {
+        self.middlewares.iter().map(|m| m.name()).collect()
+    }
+
+    /// Execute before middlewares, highest priority first
     #[instrument(skip(self, ctx))]
     pub async fn execute_before(&self, ctx: &mut CommandContext) -> CliResult<()> {
         for middleware in &self.middlewares {
+            if !middleware.applies_to(&ctx.cli.command) {
+                continue;
+            }
             debug!("Executing before middleware: {}", middleware.name());
-            middleware.before(ctx).await.map_err(|e| {
-                CliError::MiddlewareError(format!("{} failed: {}", middleware.name(), e))
-            })?;
+            middleware
+                .before(ctx)
+                .await
+                .map_err(|e| wrap_error(middleware.name(), e))?;
         }
         Ok(())
     }
 
-    /// Execute after middlewares (in reverse order)
+    /// Run the before pass, `router`'s handler inside each middleware's
+    /// [`Middleware::around`], then the after pass
+    pub async fn execute(
+        &self,
+        ctx: &mut CommandContext,
+        router: &CommandRouter,
+    ) -> CliResult<CommandResult> {
+        self.execute_before(ctx).await?;
+        let result = {
+            let ctx = &*ctx;
+            let mut next: Next<'_> = Box::pin(router.route(ctx));
+            for middleware in self.middlewares.iter().rev() {
+                if middleware.applies_to(&ctx.cli.command) {
+                    next = middleware.around(ctx, next);
+                }
+            }
+            next.await?
+        };
+        self.execute_after(ctx, &result).await?;
+        Ok(result)
+    }
+
+    /// Execute after middlewares, lowest priority first
     #[instrument(skip(self, ctx, result))]
     pub async fn execute_after(
         &self,
@@ -65,15 +129,32 @@ impl MiddlewareChain {
         result: &CommandResult,
     ) -> CliResult<()> {
         for middleware in self.middlewares.iter().rev() {
+            if !middleware.applies_to(&ctx.cli.command) {
+                continue;
+            }
             debug!("Executing after middleware: {}", middleware.name());
-            middleware.after(ctx, result).await.map_err(|e| {
-                CliError::MiddlewareError(format!("{} failed: {}", middleware.name(), e))
-            })?;
+            middleware
+                .after(ctx, result)
+                .await
+                .map_err(|e| wrap_error(middleware.name(), e))?;
         }
         Ok(())
     }
 }
 
+/// Attribute an uncategorised failure to the middleware that raised it
+///
+/// Validation, config and input errors pass through unchanged so their exit
+/// codes survive the chain.
+fn wrap_error(name: &str, error: CliError) -> CliError {
+    match error {
+        CliError::RoutingError(_) | CliError::MiddlewareError(_) => {
+            CliError::MiddlewareError(format!("{} failed: {}", name, error))
+        }
+        other => other,
+    }
+}
+
 impl Default for MiddlewareChain {
     fn default() -> Self {
         Self::new()
@@ -88,13 +169,24 @@ impl Middleware for LoggingMiddleware {
     async fn before(&self, ctx: &mut CommandContext) -> CliResult<()> {
         ctx.set_metadata("start_time".to_string(), format!("{:?}", ctx.start_time))
             .await;
-        debug!("Command execution started");
+        let correlation_id = match ctx.get_metadata(CORRELATION_ID).await {
+            Some(correlation_id) => correlation_id,
+            None => {
+                let correlation_id = uuid::Uuid::new_v4().to_string();
+                ctx.set_metadata(CORRELATION_ID.to_string(), correlation_id.clone())
+                    .await;
+                correlation_id
+            }
+        };
+        debug!(correlation_id, "Command execution started");
         Ok(())
     }
 
     async fn after(&self, ctx: &mut CommandContext, result: &CommandResult) -> CliResult<()> {
         let elapsed = ctx.start_time.elapsed();
+        let correlation_id = ctx.get_metadata(CORRELATION_ID).await.unwrap_or_default();
         debug!(
+            correlation_id,
             "Command execution completed in {:?} with status: {}",
             elapsed,
             if result.success { "success" } else { "failure" }
@@ -105,22 +197,37 @@ impl Middleware for LoggingMiddleware {
     fn name(&self) -> &str {
         "logging"
     }
+
+    fn priority(&self) -> i32 {
+        10
+    }
 }
 
 /// Metrics middleware
+///
+/// Counts commands on the way in and records latency and failures on the
+/// way out.
 pub struct MetricsMiddleware {
-    command_counter: Arc<parking_lot::RwLock<u64>>,
+    metrics: Arc<Metrics>,
 }
 
 impl MetricsMiddleware {
     pub fn new() -> Self {
-        Self {
-            command_counter: Arc::new(parking_lot::RwLock::new(0)),
-        }
+        Self::with_registry(Arc::new(Metrics::new()))
+    }
+
+    /// Record into a shared registry
+    pub fn with_registry(metrics: Arc<Metrics>) -> Self {
+        Self { metrics }
+    }
+
+    /// Get the registry this middleware records into
+    pub fn registry(&self) -> &Arc<Metrics> {
+        &self.metrics
     }
 
     pub fn get_count(&self) -> u64 {
-        *self.command_counter.read()
+        self.metrics.command_total()
     }
 }
 
@@ -132,14 +239,27 @@ impl Default for MetricsMiddleware {
 
 #[async_trait]
 impl Middleware for MetricsMiddleware {
-    async fn before(&self, _ctx: &mut CommandContext) -> CliResult<()> {
-        *self.command_counter.write() += 1;
+    async fn before(&self, ctx: &mut CommandContext) -> CliResult<()> {
+        self.metrics.record_command(ctx.cli.command_name());
+        Ok(())
+    }
+
+    async fn after(&self, ctx: &mut CommandContext, result: &CommandResult) -> CliResult<()> {
+        self.metrics
+            .observe_command_latency(ctx.cli.command_name(), ctx.start_time.elapsed());
+        if !result.success {
+            self.metrics.record_error(result.exit_code);
+        }
         Ok(())
     }
 
     fn name(&self) -> &str {
         "metrics"
     }
+
+    fn priority(&self) -> i32 {
+        20
+    }
 }
 
 /// Validation middleware
@@ -155,12 +275,82 @@ impl Middleware for ValidationMiddleware {
     fn name(&self) -> &str {
         "validation"
     }
+
+    fn priority(&self) -> i32 {
+        100
+    }
+}
+
+/// Timeout middleware
+///
+/// Holds the whole command, retries and failover included, to
+/// `--command-timeout`. Each provider request has its own
+/// `--provider-timeout` within that.
+pub struct TimeoutMiddleware;
+
+#[async_trait]
+impl Middleware for TimeoutMiddleware {
+    async fn around<'a>(
+        &'a self,
+        ctx: &'a CommandContext,
+        next: Next<'a>,
+    ) -> CliResult<CommandResult> {
+        let Some(limit) = ctx.command_timeout() else {
+            return next.await;
+        };
+        tokio::time::timeout(limit, next).await.map_err(|_| {
+            CliError::Timeout(format!(
+                "'{}' did not finish within {}s",
+                ctx.cli.command_name(),
+                limit.as_secs()
+            ))
+        })?
+    }
+
+    fn name(&self) -> &str {
+        "timeout"
+    }
+
+    fn priority(&self) -> i32 {
+        30
+    }
+}
+
+/// Read-only middleware
+///
+/// Rejects mutating commands when `--read-only` is set, before any handler
+/// runs.
+pub struct ReadOnlyMiddleware;
+
+#[async_trait]
+impl Middleware for ReadOnlyMiddleware {
+    async fn before(&self, ctx: &mut CommandContext) -> CliResult<()> {
+        let mutating = ctx.cli.command.as_ref().is_some_and(|c| c.is_mutating());
+        if ctx.cli.read_only && mutating {
+            return Err(CliError::ValidationError(format!(
+                "read-only mode: '{}' would modify state",
+                ctx.cli.command_name()
+            )));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "read-only"
+    }
+
+    fn priority(&self) -> i32 {
+        90
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cli::Cli;
+    use ai_cli_ai_engine::provider::ProviderError;
+    use clap::Parser;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_middleware_chain_creation() {
@@ -179,7 +369,7 @@ mod tests {
     #[tokio::test]
     async fn test_middleware_chain_execute_before() {
         let chain = MiddlewareChain::new().add(LoggingMiddleware);
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let mut ctx = CommandContext::new(cli);
 
         let result = chain.execute_before(&mut ctx).await;
@@ -189,7 +379,7 @@ mod tests {
     #[tokio::test]
     async fn test_middleware_chain_execute_after() {
         let chain = MiddlewareChain::new().add(LoggingMiddleware);
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let mut ctx = CommandContext::new(cli);
         let result = CommandResult::success();
 
@@ -200,30 +390,48 @@ mod tests {
     #[tokio::test]
     async fn test_logging_middleware() {
         let middleware = LoggingMiddleware;
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let mut ctx = CommandContext::new(cli);
 
         assert!(middleware.before(&mut ctx).await.is_ok());
+        let correlation_id = ctx.get_metadata(CORRELATION_ID).await.unwrap();
+        assert!(!correlation_id.is_empty());
 
         let result = CommandResult::success();
         assert!(middleware.after(&mut ctx, &result).await.is_ok());
+
+        // A correlation ID set by the caller is kept
+        assert!(middleware.before(&mut ctx).await.is_ok());
+        assert_eq!(
+            ctx.get_metadata(CORRELATION_ID).await.unwrap(),
+            correlation_id
+        );
     }
 
     #[tokio::test]
     async fn test_metrics_middleware() {
         let middleware = MetricsMiddleware::new();
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let mut ctx = CommandContext::new(cli);
 
         assert_eq!(middleware.get_count(), 0);
         middleware.before(&mut ctx).await.unwrap();
         assert_eq!(middleware.get_count(), 1);
+
+        middleware
+            .after(&mut ctx, &CommandResult::error("failed"))
+            .await
+            .unwrap();
+        let output = middleware.registry().render_prometheus();
+        assert!(output.contains("ai_cli_commands_total{command=\"chat\"} 1"));
+        assert!(output.contains("ai_cli_command_duration_seconds_count{command=\"chat\"} 1"));
+        assert!(output.contains("ai_cli_errors_total{code=\"1\"} 1"));
     }
 
     #[tokio::test]
     async fn test_validation_middleware() {
         let middleware = ValidationMiddleware;
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let mut ctx = CommandContext::new(cli);
 
         assert!(middleware.before(&mut ctx).await.is_ok());
@@ -232,15 +440,237 @@ mod tests {
     #[tokio::test]
     async fn test_validation_middleware_fails() {
         let middleware = ValidationMiddleware;
-        let cli = Cli {
-            verbose: 10, // Invalid value
-            config: None,
-            no_color: false,
-            format: crate::cli::OutputFormat::Text,
-            command: None,
-        };
+        let mut cli = Cli::try_parse_from(["ai"]).unwrap();
+        cli.verbose = 10; // Invalid value
         let mut ctx = CommandContext::new(cli);
 
         assert!(middleware.before(&mut ctx).await.is_err());
     }
+
+    #[tokio::test]
+    async fn test_chain_preserves_validation_errors() {
+        let chain = MiddlewareChain::new().add(ValidationMiddleware);
+        let cli = Cli::try_parse_from(["ai", "--timeout", "0", "chat"]).unwrap();
+        let mut ctx = CommandContext::new(cli);
+
+        let err = chain.execute_before(&mut ctx).await.unwrap_err();
+        assert!(matches!(err, CliError::ValidationError(_)));
+        assert_eq!(err.exit_code(), crate::error::exit_code::VALIDATION);
+    }
+
+    /// Fails each time after `attempt_secs`, within any provider timeout
+    struct SlowRetryingHandler {
+        attempt_secs: u64,
+        attempts: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl crate::cli::router::CommandHandler for SlowRetryingHandler {
+        async fn execute(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
+            let limit = ctx.timeout().unwrap();
+            ctx.retry_policy()
+                .run(|| async {
+                    self.attempts
+                        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let request = tokio::time::sleep(Duration::from_secs(self.attempt_secs));
+                    match tokio::time::timeout(limit, request).await {
+                        Ok(()) => Err::<(), _>(ProviderError::Unavailable("busy".to_string())),
+                        Err(_) => Err(ProviderError::TimeoutError("too slow".to_string())),
+                    }
+                })
+                .await
+                .map_err(|e| CliError::RoutingError(e.to_string()))?;
+            Ok(CommandResult::success())
+        }
+
+        fn name(&self) -> &str {
+            "chat"
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_command_timeout_bounds_retries() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut router = CommandRouter::new();
+        router.register(SlowRetryingHandler {
+            attempt_secs: 25,
+            attempts: attempts.clone(),
+        });
+        let chain = MiddlewareChain::new()
+            .add(ValidationMiddleware)
+            .add(TimeoutMiddleware);
+        let cli = Cli::try_parse_from([
+            "ai",
+            "--provider-timeout",
+            "30",
+            "--command-timeout",
+            "60",
+            "chat",
+        ])
+        .unwrap();
+
+        let err = chain
+            .execute(&mut CommandContext::new(cli), &router)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::Timeout(_)), "{}", err);
+        assert_eq!(err.exit_code(), crate::error::exit_code::TIMEOUT);
+        // Every attempt beat the provider timeout; the third ran past the
+        // command's
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_middleware() {
+        let chain = MiddlewareChain::new().add(ReadOnlyMiddleware);
+
+        let cli =
+            Cli::try_parse_from(["ai", "--read-only", "config", "set", "debug", "true"]).unwrap();
+        let err = chain
+            .execute_before(&mut CommandContext::new(cli))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::ValidationError(ref msg) if msg.starts_with("read-only mode"))
+        );
+
+        for args in [
+            &["ai", "--read-only", "config", "show"][..],
+            &["ai", "--read-only", "memory", "list"][..],
+            &["ai", "config", "set", "debug", "true"][..],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert!(chain
+                .execute_before(&mut CommandContext::new(cli))
+                .await
+                .is_ok());
+        }
+    }
+
+    /// Records its name into a shared log on both sides of the command
+    struct Recorder {
+        name: &'static str,
+        priority: i32,
+        log: Arc<parking_lot::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        async fn before(&self, _ctx: &mut CommandContext) -> CliResult<()> {
+            self.log.lock().push(format!("before:{}", self.name));
+            Ok(())
+        }
+
+        async fn after(&self, _ctx: &mut CommandContext, _result: &CommandResult) -> CliResult<()> {
+            self.log.lock().push(format!("after:{}", self.name));
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_orders_by_priority() {
+        let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let recorder = |name, priority| Recorder {
+            name,
+            priority,
+            log: log.clone(),
+        };
+        let chain = MiddlewareChain::new()
+            .add(recorder("low", -5))
+            .add(recorder("first-default", 0))
+            .add(recorder("high", 50))
+            .add(recorder("second-default", 0));
+
+        let mut ctx = CommandContext::new(Cli::try_parse_from(["ai", "chat"]).unwrap());
+        chain.execute_before(&mut ctx).await.unwrap();
+        chain
+            .execute_after(&mut ctx, &CommandResult::success())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *log.lock(),
+            vec![
+                "before:high",
+                "before:first-default",
+                "before:second-default",
+                "before:low",
+                "after:low",
+                "after:second-default",
+                "after:first-default",
+                "after:high",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builtin_priorities_put_validation_first() {
+        let chain = MiddlewareChain::new()
+            .add(LoggingMiddleware)
+            .add(MetricsMiddleware::new())
+            .add(ReadOnlyMiddleware)
+            .add(ValidationMiddleware);
+        assert_eq!(
+            chain.names(),
+            vec!["validation", "read-only", "metrics", "logging"]
+        );
+    }
+
+    /// Counts invocations, skipping `config` commands
+    struct NotForConfig {
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Middleware for NotForConfig {
+        async fn before(&self, _ctx: &mut CommandContext) -> CliResult<()> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn after(&self, _ctx: &mut CommandContext, _result: &CommandResult) -> CliResult<()> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "not-for-config"
+        }
+
+        fn applies_to(&self, command: &Option<Commands>) -> bool {
+            !matches!(command, Some(Commands::Config { .. }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_skips_middleware_not_applying() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let chain = MiddlewareChain::new().add(NotForConfig {
+            calls: calls.clone(),
+        });
+        let run = |args: &'static [&'static str]| {
+            let chain = &chain;
+            async move {
+                let mut ctx = CommandContext::new(Cli::try_parse_from(args).unwrap());
+                chain.execute_before(&mut ctx).await.unwrap();
+                chain
+                    .execute_after(&mut ctx, &CommandResult::success())
+                    .await
+                    .unwrap();
+            }
+        };
+
+        run(&["ai", "config", "show"]).await;
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+        run(&["ai", "chat"]).await;
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 }
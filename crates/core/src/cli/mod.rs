@@ -7,19 +7,34 @@
 //! - Input validation and sanitization
 //! - Colored help output with examples
 
-use clap::{CommandFactory, Parser, Subcommand};
+use crate::error::exit_code;
+use crate::logging::audit::AuditEntry;
+use ai_cli_agent_framework::workflow::GraphFormat;
+use ai_cli_ai_engine::outbound::{OutboundScanner, SecretPolicy};
+use ai_cli_ai_engine::provider::{ProviderError, RequestMetadata};
+use ai_cli_ai_engine::retry::{RetryOn, RetryPolicy};
+use ai_cli_ai_engine::schema::{ResponseSchema, MAX_SCHEMA_FIXES};
+use clap::builder::styling::{AnsiColor, Styles};
+use clap::{Args, ColorChoice, CommandFactory, FromArgMatches, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
 
+pub mod handlers;
+pub mod history;
 pub mod middleware;
+pub mod output;
+pub mod prompt;
+pub mod repl;
 pub mod router;
 pub mod validator;
 
 pub use middleware::{Middleware, MiddlewareChain};
+pub use prompt::Prompter;
 pub use router::CommandRouter;
-pub use validator::InputValidator;
+pub use validator::{InputValidator, ProjectRoot};
 
 /// CLI Error types
 #[derive(Error, Debug)]
@@ -38,10 +53,201 @@ pub enum CliError {
 
     #[error("Configuration error: {0}")]
     ConfigError(String),
+
+    #[error("Interactive input unavailable: {0}")]
+    NonInteractive(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Authentication failed: {0}")]
+    AuthError(String),
+
+    #[error("Rate limited: {0}")]
+    RateLimitError(String),
+
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+impl CliError {
+    /// Process exit code for this error; see [`crate::error::exit_code`]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::InvalidCommand(_)
+            | CliError::ValidationError(_)
+            | CliError::NonInteractive(_) => exit_code::VALIDATION,
+            CliError::ConfigError(_) => exit_code::CONFIG,
+            CliError::Timeout(_) => exit_code::TIMEOUT,
+            CliError::AuthError(_) => exit_code::AUTH,
+            CliError::RateLimitError(_) => exit_code::RATE_LIMIT,
+            CliError::RoutingError(_)
+            | CliError::MiddlewareError(_)
+            | CliError::ProviderError(_) => exit_code::GENERAL,
+        }
+    }
+
+    /// The same kind of error with its message rewritten by `f`
+    pub fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            CliError::InvalidCommand(m) => CliError::InvalidCommand(f(m)),
+            CliError::ValidationError(m) => CliError::ValidationError(f(m)),
+            CliError::RoutingError(m) => CliError::RoutingError(f(m)),
+            CliError::MiddlewareError(m) => CliError::MiddlewareError(f(m)),
+            CliError::ConfigError(m) => CliError::ConfigError(f(m)),
+            CliError::NonInteractive(m) => CliError::NonInteractive(f(m)),
+            CliError::Timeout(m) => CliError::Timeout(f(m)),
+            CliError::AuthError(m) => CliError::AuthError(f(m)),
+            CliError::RateLimitError(m) => CliError::RateLimitError(f(m)),
+            CliError::ProviderError(m) => CliError::ProviderError(f(m)),
+        }
+    }
+
+    /// A failure of the provider `name`, keeping the kind of `err` so the
+    /// command exits with the matching code
+    pub fn provider_failure(name: &str, err: ProviderError) -> Self {
+        let message = format!("{} failed: {}", name, err);
+        Self::from_provider(&err, message)
+    }
+
+    fn from_provider(err: &ProviderError, message: String) -> Self {
+        match err {
+            ProviderError::AuthError(_) => CliError::AuthError(message),
+            ProviderError::RateLimitError(_) => CliError::RateLimitError(message),
+            ProviderError::TimeoutError(_) => CliError::Timeout(message),
+            ProviderError::InvalidRequest(_) => CliError::ValidationError(message),
+            _ => CliError::ProviderError(message),
+        }
+    }
+}
+
+impl From<ProviderError> for CliError {
+    fn from(err: ProviderError) -> Self {
+        let message = err.to_string();
+        Self::from_provider(&err, message)
+    }
 }
 
 pub type CliResult<T> = Result<T, CliError>;
 
+/// Upper bound accepted for `--provider-timeout` and `--command-timeout`,
+/// in seconds
+pub const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Upper bound accepted for `--max-concurrent`
+pub const MAX_CONCURRENT: u8 = 64;
+
+/// Colors used when rendering help
+const HELP_STYLES: Styles = Styles::styled()
+    .header(AnsiColor::Yellow.on_default().bold())
+    .usage(AnsiColor::Yellow.on_default().bold())
+    .literal(AnsiColor::Green.on_default().bold())
+    .placeholder(AnsiColor::Cyan.on_default())
+    .valid(AnsiColor::Green.on_default())
+    .invalid(AnsiColor::Red.on_default().bold())
+    .error(AnsiColor::Red.on_default().bold());
+
+const ROOT_EXAMPLES: &str = "\
+Examples:
+  ai chat                                   Chat with the default provider
+  ai chat --mode work --provider anthropic  Chat in work mode with Anthropic
+  ai plan --template feature -o plan.md     Write a plan from a template
+  ai providers --test                       Check that providers respond
+  ai --env-file .env.local chat             Load API keys from a dotenv file
+  ai -c team.json -c me.json chat           Merge a personal config over a shared one";
+
+const CHAT_EXAMPLES: &str = "\
+Examples:
+  ai chat --mode work --provider anthropic
+  ai chat --model gpt-4o --system-prompt @code_reviewer
+  ai chat --system-prompt 'Answer in one sentence.'
+  ai chat --temperature 0.2 --stop END --param top_p=0.9
+  ai chat --model gpt-4o --budget 0.50
+  ai chat --schema answer.schema.json --schema-retries 2
+  echo 'Explain lifetimes' | ai chat
+  echo 'Explain lifetimes' | ai --format json chat";
+
+const PLAN_EXAMPLES: &str = "\
+Examples:
+  ai plan --interactive
+  ai plan --template feature --output plan.md
+  ai plan --project api --task 'add pagination to /users'
+  ai plan --task 'try a rewrite' --no-remember
+  ai plan --task 'split the parser' --schema plan.schema.json";
+
+const WORK_EXAMPLES: &str = "\
+Examples:
+  ai work --project api --task 'add pagination to /users'
+  ai work --task 'fix clippy warnings' --auto-commit
+  ai --on-secret redact work --task 'tidy deploy.yml'
+  ai work --provider openai --model gpt-4o
+  ai checkpoint restore 'work: tidy deploy.yml'   (undo a work session)";
+
+const PROVIDERS_EXAMPLES: &str = "\
+Examples:
+  ai providers --all
+  ai providers --test
+  ai providers add local --base-url http://localhost:8080/v1 --model llama3
+  ai providers remove openai --new-default anthropic
+  ai providers bench --runs 10";
+
+const CREDS_EXAMPLES: &str = "\
+Examples:
+  ai creds add openai --default
+  ai creds list
+  ai creds validate anthropic";
+
+const MEMORY_EXAMPLES: &str = "\
+Examples:
+  ai memory search 'database schema' --threshold 0.8
+  ai memory stats
+  ai memory gc
+  ai memory export memory.json";
+
+const AGENTS_EXAMPLES: &str = "\
+Examples:
+  ai agents list --detailed
+  ai agents create reviewer --capabilities code-review
+  ai --max-concurrent 2 agents batch reviewer \"check auth.rs\" \"check db.rs\"";
+
+const CHECKPOINT_EXAMPLES: &str = "\
+Examples:
+  ai checkpoint create before-refactor
+  ai checkpoint list
+  ai checkpoint diff before-refactor
+  ai checkpoint restore before-refactor --backup
+  ai checkpoint export before-refactor refactor.ckpt";
+
+const HISTORY_EXAMPLES: &str = "\
+Examples:
+  ai history list
+  ai history show 20240501-093000-1a2b3c4d
+  ai history replay 20240501-093000-1a2b3c4d --model gpt-4o";
+
+const WORKFLOW_EXAMPLES: &str = "\
+Examples:
+  ai workflow graph review.json
+  ai workflow graph review.json --output review.mmd
+  ai workflow graph review.json --output review.txt --graph-format mermaid";
+
+const CONFIG_EXAMPLES: &str = "\
+Examples:
+  ai config show
+  ai config set default_provider anthropic
+  ai config effective -v --temperature 0.2";
+
+const DOCTOR_EXAMPLES: &str = "\
+Examples:
+  ai doctor
+  ai doctor --offline
+  ai doctor --format json";
+
+const TOKENS_EXAMPLES: &str = "\
+Examples:
+  ai tokens \"Summarise this repository\"
+  ai tokens --file prompt.md --model gpt-4
+  ai tokens --file prompt.md --provider anthropic --format json";
+
 /// AIrchitect CLI - Advanced AI-powered development assistant
 #[derive(Parser, Debug, Clone)]
 #[command(
@@ -50,46 +256,196 @@ pub type CliResult<T> = Result<T, CliError>;
     author = "AIrchitect Team",
     about = "Advanced AI-powered development assistant",
     long_about = "AIrchitect CLI provides intelligent code generation, project planning, \
-                  and automated development assistance using state-of-the-art AI models."
+                  and automated development assistance using state-of-the-art AI models.",
+    after_help = ROOT_EXAMPLES,
+    styles = HELP_STYLES,
+    disable_version_flag = true
 )]
 pub struct Cli {
+    /// Print version (add -v for build details)
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
     /// Enable verbose output (use multiple times for more verbosity)
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     pub verbose: u8,
 
-    /// Configuration file path
-    #[arg(short, long, value_name = "FILE", global = true, env = "AI_CONFIG")]
-    pub config: Option<String>,
+    /// Print only command results and errors; wins over `--verbose`
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Configuration file path; repeat (or separate with commas) to merge
+    /// later files over earlier ones, or name a directory to merge the JSON
+    /// files in it
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        global = true,
+        env = "AI_CONFIG",
+        value_delimiter = ','
+    )]
+    pub config: Vec<String>,
+
+    /// Load environment variables from this dotenv file; `./.env` is
+    /// loaded when present. Variables already set take precedence.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub env_file: Option<String>,
 
     /// Disable colored output
     #[arg(long, global = true)]
     pub no_color: bool,
 
-    /// Output format (json, yaml, text)
+    /// Never prompt for input; fail instead of waiting on stdin
+    #[arg(long, global = true, env = "AI_NO_INPUT")]
+    pub no_input: bool,
+
+    /// Output format: text, json, yaml, markdown or csv
     #[arg(long, default_value = "text", global = true)]
     pub format: OutputFormat,
 
+    /// Serve Prometheus metrics on this local port while the command runs
+    ///
+    /// The server stops when the command exits, so only long-running
+    /// commands such as an interactive chat can be scraped.
+    #[arg(long, global = true, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+
+    /// Export tracing spans to this OpenTelemetry collector over OTLP/HTTP,
+    /// e.g. http://localhost:4318 (requires building with the `otlp` feature)
+    ///
+    /// Only spans at or above the log level are exported; pass -v for the
+    /// command's routing and provider spans.
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        env = "OTEL_EXPORTER_OTLP_ENDPOINT"
+    )]
+    pub otlp_endpoint: Option<String>,
+
+    /// Time limit for each provider request in seconds, retries getting a
+    /// fresh limit, overriding the configured value
+    #[arg(long, visible_alias = "timeout", global = true, value_name = "SECONDS")]
+    pub provider_timeout: Option<u64>,
+
+    /// Time limit for the whole command in seconds, retries and failover
+    /// included
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub command_timeout: Option<u64>,
+
+    /// Most agent tasks to run at once, overriding the configured limit
+    #[arg(long, global = true, value_name = "N")]
+    pub max_concurrent: Option<u8>,
+
+    /// Sampling temperature (0.0-2.0), overriding the configured default
+    #[arg(long, global = true, value_name = "VALUE")]
+    pub temperature: Option<f32>,
+
+    /// Maximum tokens to generate, overriding the configured default
+    #[arg(long, global = true, value_name = "TOKENS")]
+    pub max_tokens: Option<u32>,
+
+    /// Errors to retry provider requests on: comma-separated error codes,
+    /// `transient` (the default) or `none`
+    #[arg(long, global = true, value_name = "CODES")]
+    pub retry_on: Option<RetryOn>,
+
+    /// What to do when a prompt about to be sent contains a possible
+    /// secret: warn (the default), redact it, or block the request
+    #[arg(long, global = true, value_name = "POLICY", env = "AI_ON_SECRET")]
+    pub on_secret: Option<SecretPolicy>,
+
+    /// Providers to fail over between for this run, in order, overriding
+    /// the configured failover order
+    #[arg(long, global = true, value_name = "PROVIDERS", value_delimiter = ',')]
+    pub provider_order: Vec<String>,
+
+    /// Reject any command that would change files, credentials or config
+    #[arg(long, global = true, env = "AI_READ_ONLY")]
+    pub read_only: bool,
+
+    /// Print errors to stderr as JSON, whatever the output format
+    #[arg(long, global = true, env = "AI_JSON_ERRORS")]
+    pub json_errors: bool,
+
+    /// Connect to the default provider in the background while the command
+    /// starts, as the `warmup` config setting does
+    #[arg(long, global = true, env = "AI_WARMUP")]
+    pub warmup: bool,
+
+    /// Cut printed results after this many bytes, overriding the
+    /// `max_output_bytes` config setting; files written with `--output`
+    /// are never cut
+    #[arg(long, global = true, value_name = "BYTES")]
+    pub max_output_bytes: Option<usize>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
-/// Output format options
-#[derive(Debug, Clone, Serialize, Deserialize, clap::ValueEnum)]
-#[serde(rename_all = "lowercase")]
+/// Output format, named on the command line and rendered by the formatter
+/// registered under that name in an [`output::FormatterRegistry`]
+///
+/// Handlers with a structured form of their output check for
+/// [`OutputFormat::Json`]; any name is accepted here and checked against
+/// the registry before the command runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum OutputFormat {
     Json,
     Yaml,
     Text,
+    /// Any other format, such as `markdown` or `csv`
+    Other(String),
+}
+
+impl OutputFormat {
+    /// Name of the format in the registry
+    pub fn name(&self) -> &str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Text => "text",
+            OutputFormat::Other(name) => name,
+        }
+    }
+}
+
+impl From<String> for OutputFormat {
+    fn from(name: String) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => OutputFormat::Json,
+            "yaml" => OutputFormat::Yaml,
+            "text" => OutputFormat::Text,
+            other => OutputFormat::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<OutputFormat> for String {
+    fn from(format: OutputFormat) -> Self {
+        format.name().to_string()
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// Top-level commands
 #[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     /// Start an interactive AI chat session
-    #[command(alias = "c")]
+    #[command(visible_alias = "c", after_help = CHAT_EXAMPLES)]
     Chat {
         /// Set initial mode (planning or work)
-        #[arg(short, long, default_value = "planning")]
+        ///
+        /// Long form only: `-m` used to be claimed by both this and --model,
+        /// and now always means --model.
+        #[arg(long, default_value = "planning")]
         mode: String,
 
         /// Specify AI provider to use
@@ -100,15 +456,39 @@ pub enum Commands {
         #[arg(short, long)]
         model: Option<String>,
 
-        /// System prompt override
+        /// System prompt text, or @name for a prompt from the library
+        ///
+        /// A library prompt may refer to {{provider}} and {{model}}, as
+        /// chosen when the session starts; text is sent as written.
         #[arg(long)]
         system_prompt: Option<String>,
+
+        /// End the session before a request would take its estimated cost
+        /// past this amount, in the model's pricing currency
+        #[arg(long, value_name = "AMOUNT", value_parser = parse_budget)]
+        budget: Option<f64>,
+
+        #[command(flatten)]
+        request: RequestArgs,
+
+        #[command(flatten)]
+        schema: SchemaArgs,
     },
 
     /// Start a planning session
-    #[command(alias = "p")]
+    #[command(visible_alias = "p", after_help = PLAN_EXAMPLES)]
     Plan {
-        /// Planning template to use
+        /// Goal to plan for
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Project the plan is for; `work` on the same project picks it up
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Planning template to use: a system prompt name (default: planner)
+        ///
+        /// The prompt may refer to {{task}} and, with --project, {{project}}.
         #[arg(short, long)]
         template: Option<String>,
 
@@ -119,12 +499,24 @@ pub enum Commands {
         /// Interactive mode
         #[arg(short, long)]
         interactive: bool,
+
+        /// Store the plan in memory for later work sessions (the default)
+        #[arg(long, overrides_with = "no_remember")]
+        remember: bool,
+
+        /// Do not store the plan in memory
+        #[arg(long, overrides_with = "remember")]
+        no_remember: bool,
+
+        #[command(flatten)]
+        schema: SchemaArgs,
     },
 
     /// Start a work session
-    #[command(alias = "w")]
+    #[command(visible_alias = "w", after_help = WORK_EXAMPLES)]
     Work {
-        /// Project to work on
+        /// Project directory to edit files in; edits cannot leave it
+        /// (default: current directory)
         #[arg(short, long)]
         project: Option<String>,
 
@@ -132,13 +524,24 @@ pub enum Commands {
         #[arg(short, long)]
         task: Option<String>,
 
-        /// Auto-commit changes
+        /// Apply edits without confirmation and commit them with git
         #[arg(long)]
         auto_commit: bool,
+
+        /// Specify AI provider to use
+        #[arg(long, env = "AI_PROVIDER")]
+        provider: Option<String>,
+
+        /// Model to use
+        #[arg(long)]
+        model: Option<String>,
+
+        #[command(flatten)]
+        request: RequestArgs,
     },
 
-    /// List available AI providers
-    #[command(alias = "prov")]
+    /// List and manage AI providers
+    #[command(visible_alias = "prov", after_help = PROVIDERS_EXAMPLES)]
     Providers {
         /// Show all providers including unavailable ones
         #[arg(short, long)]
@@ -147,41 +550,151 @@ pub enum Commands {
         /// Test provider connectivity
         #[arg(short, long)]
         test: bool,
+
+        #[command(subcommand)]
+        subcommand: Option<ProvidersCommands>,
     },
 
     /// Manage credentials
-    #[command(alias = "cr")]
+    #[command(visible_alias = "cr", after_help = CREDS_EXAMPLES)]
     Creds {
         #[command(subcommand)]
         subcommand: CredsCommands,
     },
 
     /// Manage project memory
-    #[command(alias = "mem")]
+    #[command(visible_alias = "mem", after_help = MEMORY_EXAMPLES)]
     Memory {
         #[command(subcommand)]
         subcommand: MemoryCommands,
     },
 
     /// Manage agents
-    #[command(alias = "ag")]
+    #[command(visible_alias = "ag", after_help = AGENTS_EXAMPLES)]
     Agents {
         #[command(subcommand)]
         subcommand: AgentCommands,
     },
 
     /// Manage checkpoints
-    #[command(alias = "cp")]
+    #[command(visible_alias = "cp", after_help = CHECKPOINT_EXAMPLES)]
     Checkpoint {
         #[command(subcommand)]
         subcommand: CheckpointCommands,
     },
 
+    /// Browse and continue past chat sessions
+    #[command(after_help = HISTORY_EXAMPLES)]
+    History {
+        #[command(subcommand)]
+        subcommand: HistoryCommands,
+    },
+
+    /// Inspect workflow definitions
+    #[command(after_help = WORKFLOW_EXAMPLES)]
+    Workflow {
+        #[command(subcommand)]
+        subcommand: WorkflowCommands,
+    },
+
     /// Show configuration
+    #[command(after_help = CONFIG_EXAMPLES)]
     Config {
         #[command(subcommand)]
         subcommand: ConfigCommands,
     },
+
+    /// Check the configuration, credentials, storage and network
+    #[command(after_help = DOCTOR_EXAMPLES)]
+    Doctor {
+        /// Skip checks that need the network
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Count the tokens in a prompt and check it fits a model's context window
+    #[command(after_help = TOKENS_EXAMPLES)]
+    Tokens {
+        /// Text to count
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        text: Option<String>,
+
+        /// Read the text from a file
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// Provider whose model to check
+        #[arg(short, long, env = "AI_PROVIDER")]
+        provider: Option<String>,
+
+        /// Model whose context window to check
+        #[arg(long)]
+        model: Option<String>,
+    },
+
+    /// Show version and build details
+    Version,
+
+    /// Command provided at runtime (e.g. by a plugin); the first element is its name
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+impl Commands {
+    /// Whether the command can change state (files, credentials, config)
+    ///
+    /// Used to enforce `--read-only`. Runtime-registered commands are
+    /// treated as mutating since their effects are unknown.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Commands::Chat { .. }
+            | Commands::Plan { .. }
+            | Commands::Doctor { .. }
+            | Commands::Tokens { .. }
+            | Commands::History { .. }
+            | Commands::Version => false,
+            Commands::Work { .. } | Commands::External(_) => true,
+            Commands::Providers { subcommand, .. } => matches!(
+                subcommand,
+                Some(ProvidersCommands::Add { .. } | ProvidersCommands::Remove { .. })
+            ),
+            Commands::Creds { subcommand } => matches!(
+                subcommand,
+                CredsCommands::Add { .. } | CredsCommands::Remove { .. }
+            ),
+            Commands::Memory { subcommand } => matches!(
+                subcommand,
+                MemoryCommands::Clear { .. } | MemoryCommands::Gc | MemoryCommands::Import { .. }
+            ),
+            // Executing an agent can take arbitrary actions
+            Commands::Agents { subcommand } => matches!(
+                subcommand,
+                AgentCommands::Create { .. }
+                    | AgentCommands::Remove { .. }
+                    | AgentCommands::Execute { .. }
+                    | AgentCommands::Batch { .. }
+            ),
+            Commands::Checkpoint { subcommand } => matches!(
+                subcommand,
+                CheckpointCommands::Create { .. }
+                    | CheckpointCommands::Restore { .. }
+                    | CheckpointCommands::Remove { .. }
+                    | CheckpointCommands::Export { .. }
+                    | CheckpointCommands::Import { .. }
+            ),
+            Commands::Config { subcommand } => matches!(
+                subcommand,
+                ConfigCommands::Set { .. } | ConfigCommands::Reset { .. }
+            ),
+            Commands::Workflow { subcommand } => matches!(
+                subcommand,
+                WorkflowCommands::Graph {
+                    output: Some(_),
+                    ..
+                }
+            ),
+        }
+    }
 }
 
 /// Credential management commands
@@ -249,6 +762,12 @@ pub enum MemoryCommands {
         threshold: f32,
     },
 
+    /// Show entry counts, tags and size
+    Stats,
+
+    /// Remove expired entries, then the oldest ones while over the size limit
+    Gc,
+
     /// Clear memory
     Clear {
         /// Project to clear (all if not specified)
@@ -296,7 +815,7 @@ pub enum AgentCommands {
         name: String,
 
         /// Agent capabilities
-        #[arg(short, long)]
+        #[arg(long)]
         capabilities: Vec<String>,
 
         /// Agent description
@@ -327,6 +846,16 @@ pub enum AgentCommands {
         params: Option<String>,
     },
 
+    /// Run several tasks on an agent in parallel
+    Batch {
+        /// Agent name
+        agent: String,
+
+        /// Tasks to execute
+        #[arg(required = true)]
+        tasks: Vec<String>,
+    },
+
     /// Show agent status
     Status {
         /// Agent name (all if not specified)
@@ -386,6 +915,21 @@ pub enum CheckpointCommands {
         force: bool,
     },
 
+    /// Export a checkpoint and its parents to an archive file
+    Export {
+        /// Checkpoint name or ID
+        name: String,
+
+        /// Archive file to write
+        output: String,
+    },
+
+    /// Import a checkpoint archive
+    Import {
+        /// Archive file to read
+        path: String,
+    },
+
     /// Show checkpoint diff
     Diff {
         /// First checkpoint
@@ -396,6 +940,96 @@ pub enum CheckpointCommands {
     },
 }
 
+/// Provider management commands
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProvidersCommands {
+    /// Add a provider to the configuration
+    Add {
+        /// Provider name
+        name: String,
+
+        /// API endpoint (defaults to the provider's standard endpoint)
+        #[arg(long)]
+        base_url: Option<String>,
+
+        /// Default model
+        #[arg(long)]
+        model: Option<String>,
+    },
+
+    /// Remove a provider from the configuration
+    Remove {
+        /// Provider name
+        name: String,
+
+        /// Provider to make the default when removing the current default
+        #[arg(long)]
+        new_default: Option<String>,
+    },
+
+    /// Compare the latency of the enabled providers
+    Bench {
+        /// Prompt sent to every provider
+        #[arg(long, default_value = "Reply with the word ready.")]
+        prompt: String,
+
+        /// Times to send the prompt to each provider
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+    },
+}
+
+/// Chat history commands
+#[derive(Subcommand, Debug, Clone)]
+pub enum HistoryCommands {
+    /// List recorded sessions, most recent first
+    List,
+
+    /// Print a session's transcript
+    Show {
+        /// Session ID
+        id: String,
+    },
+
+    /// Start a chat that continues a recorded session
+    Replay {
+        /// Session ID
+        id: String,
+
+        /// Provider to continue with; the session's by default
+        #[arg(short, long)]
+        provider: Option<String>,
+
+        /// Model to continue with; the session's by default
+        #[arg(long)]
+        model: Option<String>,
+
+        /// End the session before a request would take its estimated cost
+        /// past this amount, in the model's pricing currency
+        #[arg(long, value_name = "AMOUNT", value_parser = parse_budget)]
+        budget: Option<f64>,
+    },
+}
+
+/// Workflow commands
+#[derive(Subcommand, Debug, Clone)]
+pub enum WorkflowCommands {
+    /// Draw a workflow's states and transitions as Graphviz DOT or Mermaid
+    Graph {
+        /// Workflow definition file (JSON with id, initial and transitions)
+        definition: String,
+
+        /// File to write the graph to; printed when omitted
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Graph syntax: dot or mermaid (default: mermaid for a .mmd
+        /// output file, dot otherwise)
+        #[arg(long, value_name = "FORMAT")]
+        graph_format: Option<GraphFormat>,
+    },
+}
+
 /// Configuration commands
 #[derive(Subcommand, Debug, Clone)]
 pub enum ConfigCommands {
@@ -423,6 +1057,93 @@ pub enum ConfigCommands {
 
     /// Validate configuration
     Validate,
+
+    /// Show settings that differ from the defaults
+    Diff,
+
+    /// Show the configuration in effect after applying environment
+    /// variables and flags, with secrets masked; add -v for where each
+    /// value came from
+    #[command(visible_alias = "dump")]
+    Effective,
+}
+
+/// Request options passed through to the provider
+#[derive(Args, Debug, Clone, Default)]
+pub struct RequestArgs {
+    /// Stop generating at this sequence (repeatable)
+    #[arg(long = "stop", value_name = "SEQ")]
+    pub stop: Vec<String>,
+
+    /// Extra provider parameter; values are read as JSON when they parse,
+    /// otherwise as strings (repeatable)
+    #[arg(long = "param", value_name = "KEY=VALUE", value_parser = parse_param)]
+    pub params: Vec<(String, serde_json::Value)>,
+}
+
+impl RequestArgs {
+    /// Stop sequences for the request, if any were given
+    pub fn stop_sequences(&self) -> Option<Vec<String>> {
+        (!self.stop.is_empty()).then(|| self.stop.clone())
+    }
+
+    /// Extra parameters for the request; later values win for repeated keys
+    pub fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        self.params.iter().cloned().collect()
+    }
+}
+
+/// Structured-output options: a JSON schema replies must match
+#[derive(Args, Debug, Clone, Default)]
+pub struct SchemaArgs {
+    /// JSON schema file the reply must match
+    #[arg(long, value_name = "FILE")]
+    pub schema: Option<String>,
+
+    /// Times to ask the model to fix a reply that does not match `--schema`
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "schema",
+        value_parser = clap::value_parser!(u8).range(0..=MAX_SCHEMA_FIXES as i64)
+    )]
+    pub schema_retries: u8,
+}
+
+impl SchemaArgs {
+    /// Load the `--schema` file, if one was given
+    pub fn load(&self) -> CliResult<Option<ResponseSchema>> {
+        let Some(path) = &self.schema else {
+            return Ok(None);
+        };
+        InputValidator::validate_path(path)?;
+        ResponseSchema::load(path)
+            .map(|schema| Some(schema.with_max_fixes(self.schema_retries)))
+            .map_err(|e| CliError::ValidationError(format!("{}: {}", path, e)))
+    }
+}
+
+/// Parse a `--param` value such as `top_p=0.9` or `user=alice`
+fn parse_param(arg: &str) -> Result<(String, serde_json::Value), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", arg))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("missing parameter name in '{}'", arg));
+    }
+    let value = serde_json::from_str(value)
+        .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    Ok((key.to_string(), value))
+}
+
+/// Parse a `--budget` amount, which must be a non-negative number
+fn parse_budget(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(budget) if budget.is_finite() && budget >= 0.0 => Ok(budget),
+        _ => Err(format!("expected a non-negative amount, got '{}'", arg)),
+    }
 }
 
 /// CLI configuration
@@ -463,17 +1184,67 @@ impl Default for CliConfig {
 impl Cli {
     /// Parse CLI arguments
     pub fn parse_args() -> Self {
-        Self::parse()
+        Self::parse_from_args(std::env::args_os())
+    }
+
+    /// Parse `args`, coloring help and errors unless color is turned off
+    pub fn parse_from_args<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+        let matches = Self::styled_command(Self::color_enabled(&args)).get_matches_from(args);
+        Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Whether help may be colored: not with `--no-color` or a non-empty `NO_COLOR`
+    pub fn color_enabled(args: &[std::ffi::OsString]) -> bool {
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        !no_color_env && !args.iter().any(|arg| arg == "--no-color")
+    }
+
+    /// The clap command, with color forced off when `color` is false
+    pub fn styled_command(color: bool) -> clap::Command {
+        let choice = if color {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        };
+        Self::command().color(choice)
+    }
+
+    /// Render the long help for the top level or a subcommand, without colors
+    pub fn render_help(subcommand: Option<&str>) -> CliResult<String> {
+        let mut cmd = Self::styled_command(false);
+        let help = match subcommand {
+            None => cmd.render_long_help(),
+            Some(name) => {
+                cmd.build();
+                cmd.find_subcommand_mut(name)
+                    .ok_or_else(|| {
+                        CliError::ValidationError(format!("Unknown command '{}'", name))
+                    })?
+                    .render_long_help()
+            }
+        };
+        Ok(help.to_string())
     }
 
     /// Generate help text with colors
     pub fn print_help() -> CliResult<()> {
-        let mut cmd = Self::command();
+        let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+        let mut cmd = Self::styled_command(Self::color_enabled(&args));
         cmd.print_help()
             .map_err(|e| CliError::ConfigError(e.to_string()))?;
         Ok(())
     }
 
+    /// Whether errors should be reported as JSON (`--json-errors` or `--format json`)
+    pub fn json_errors(&self) -> bool {
+        self.json_errors || matches!(self.format, OutputFormat::Json)
+    }
+
     /// Validate CLI arguments
     pub fn validate(&self) -> CliResult<()> {
         // Validate verbose level
@@ -483,8 +1254,28 @@ impl Cli {
             ));
         }
 
-        // Validate config file if specified
-        if let Some(config_path) = &self.config {
+        for (flag, timeout) in [
+            ("--provider-timeout", self.provider_timeout),
+            ("--command-timeout", self.command_timeout),
+        ] {
+            if let Some(timeout) = timeout {
+                InputValidator::validate_timeout(flag, timeout)?;
+            }
+        }
+
+        if let Some(max_concurrent) = self.max_concurrent {
+            if !(1..=MAX_CONCURRENT).contains(&max_concurrent) {
+                return Err(CliError::ValidationError(format!(
+                    "Max concurrent tasks must be between 1 and {}",
+                    MAX_CONCURRENT
+                )));
+            }
+        }
+
+        InputValidator::validate_generation(&self.generation())?;
+
+        // Validate config files if specified
+        for config_path in &self.config {
             if !std::path::Path::new(config_path).exists() {
                 return Err(CliError::ValidationError(format!(
                     "Config file not found: {}",
@@ -496,8 +1287,41 @@ impl Cli {
         Ok(())
     }
 
-    /// Get log level based on verbose flag
+    /// Generation settings given with `--temperature` and `--max-tokens`
+    pub fn generation(&self) -> crate::GenerationSettings {
+        crate::GenerationSettings {
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+        }
+    }
+
+    /// Name of the top-level command, as used for handler lookup
+    pub fn command_name(&self) -> &str {
+        match &self.command {
+            Some(Commands::Chat { .. }) => "chat",
+            Some(Commands::Plan { .. }) => "plan",
+            Some(Commands::Work { .. }) => "work",
+            Some(Commands::Providers { .. }) => "providers",
+            Some(Commands::Creds { .. }) => "creds",
+            Some(Commands::Memory { .. }) => "memory",
+            Some(Commands::Agents { .. }) => "agents",
+            Some(Commands::Checkpoint { .. }) => "checkpoint",
+            Some(Commands::History { .. }) => "history",
+            Some(Commands::Workflow { .. }) => "workflow",
+            Some(Commands::Config { .. }) => "config",
+            Some(Commands::Doctor { .. }) => "doctor",
+            Some(Commands::Tokens { .. }) => "tokens",
+            Some(Commands::Version) => "version",
+            Some(Commands::External(args)) => args.first().map_or("default", String::as_str),
+            None => "default",
+        }
+    }
+
+    /// Get log level based on the verbose and quiet flags
     pub fn log_level(&self) -> tracing::Level {
+        if self.quiet {
+            return tracing::Level::ERROR;
+        }
         match self.verbose {
             0 => tracing::Level::WARN,
             1 => tracing::Level::INFO,
@@ -507,6 +1331,16 @@ impl Cli {
     }
 }
 
+/// Context metadata key for the ID tying a command's provider requests,
+/// logs and audit entries together
+pub const CORRELATION_ID: &str = "correlation_id";
+
+/// Context metadata key for the session requests are made in
+pub const SESSION_ID: &str = "session_id";
+
+/// Context metadata key for the user requests are made for
+pub const USER_ID: &str = "user_id";
+
 /// Command context shared across middleware
 #[derive(Debug, Clone)]
 pub struct CommandContext {
@@ -533,65 +1367,360 @@ impl CommandContext {
         let metadata = self.metadata.read().await;
         metadata.get(key).cloned()
     }
+
+    /// Metadata for a provider request made by this command
+    ///
+    /// Copies the session, user and correlation IDs from the context
+    /// metadata; each call gets its own request ID.
+    pub async fn request_metadata(&self) -> RequestMetadata {
+        let metadata = self.metadata.read().await;
+        RequestMetadata {
+            user_id: metadata.get(USER_ID).cloned(),
+            session_id: metadata.get(SESSION_ID).cloned(),
+            correlation_id: metadata.get(CORRELATION_ID).cloned(),
+            ..RequestMetadata::default()
+        }
+    }
+
+    /// Audit entry for `action`, attributed to the context's user and
+    /// carrying its correlation ID
+    pub async fn audit_entry(&self, event_type: &str, action: &str) -> AuditEntry {
+        let metadata = self.metadata.read().await;
+        let user = metadata.get(USER_ID).map_or("local", String::as_str);
+        let mut entry = AuditEntry::new(event_type, user, action);
+        if let Some(correlation_id) = metadata.get(CORRELATION_ID) {
+            entry = entry.with_metadata(CORRELATION_ID, correlation_id);
+        }
+        entry
+    }
+
+    /// Agent task concurrency requested with `--max-concurrent`, if any
+    pub fn max_concurrent(&self) -> Option<u8> {
+        self.cli.max_concurrent
+    }
+
+    /// Provider request timeout requested with `--provider-timeout`, if any
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.cli
+            .provider_timeout
+            .map(std::time::Duration::from_secs)
+    }
+
+    /// Time limit for the whole command requested with `--command-timeout`,
+    /// if any
+    pub fn command_timeout(&self) -> Option<std::time::Duration> {
+        self.cli.command_timeout.map(std::time::Duration::from_secs)
+    }
+
+    /// Retry policy for provider requests, honouring `--retry-on`
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default().with_retry_on(self.cli.retry_on.clone().unwrap_or_default())
+    }
+
+    /// Scanner for outgoing prompts, honouring `--on-secret`
+    pub fn outbound_scanner(&self) -> OutboundScanner {
+        OutboundScanner::new(self.cli.on_secret.unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
 
     #[test]
     fn test_cli_parse_basic() {
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         assert!(matches!(cli.command, Some(Commands::Chat { .. })));
     }
 
     #[test]
     fn test_cli_parse_with_verbose() {
-        let cli = Cli::try_parse_from(&["ai", "-vvv", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "-vvv", "chat"]).unwrap();
         assert_eq!(cli.verbose, 3);
     }
 
     #[test]
     fn test_cli_parse_with_config() {
-        let cli = Cli::try_parse_from(&["ai", "--config", "test.toml", "chat"]).unwrap();
-        assert_eq!(cli.config, Some("test.toml".to_string()));
+        let cli = Cli::try_parse_from(["ai", "--config", "test.toml", "chat"]).unwrap();
+        assert_eq!(cli.config, vec!["test.toml"]);
+
+        let cli = Cli::try_parse_from(["ai", "-c", "team.json", "-c", "me.json", "chat"]).unwrap();
+        assert_eq!(cli.config, vec!["team.json", "me.json"]);
+        let cli = Cli::try_parse_from(["ai", "chat", "-c", "team.json,me.json"]).unwrap();
+        assert_eq!(cli.config, vec!["team.json", "me.json"]);
     }
 
     #[test]
     fn test_cli_log_level() {
-        let cli = Cli::try_parse_from(&["ai", "-vv", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "-vv", "chat"]).unwrap();
         assert_eq!(cli.log_level(), tracing::Level::DEBUG);
+
+        let cli = Cli::try_parse_from(["ai", "-vv", "-q", "chat"]).unwrap();
+        assert_eq!(cli.log_level(), tracing::Level::ERROR);
+    }
+
+    #[test]
+    fn test_cli_chat_model_short_flag() {
+        let cli = Cli::try_parse_from(["ai", "chat", "-m", "gpt-4o", "--mode", "work"]).unwrap();
+        match cli.command {
+            Some(Commands::Chat { model, mode, .. }) => {
+                assert_eq!(model.as_deref(), Some("gpt-4o"));
+                assert_eq!(mode, "work");
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cli_otlp_endpoint() {
+        let cli = Cli::try_parse_from(["ai", "chat", "--otlp-endpoint", "http://localhost:4318"])
+            .unwrap();
+        assert_eq!(cli.otlp_endpoint.as_deref(), Some("http://localhost:4318"));
     }
 
     #[test]
     fn test_cli_validate_excessive_verbose() {
-        let cli = Cli {
-            verbose: 10,
-            config: None,
-            no_color: false,
-            format: OutputFormat::Text,
-            command: None,
-        };
+        let mut cli = Cli::try_parse_from(["ai"]).unwrap();
+        cli.verbose = 10;
         assert!(cli.validate().is_err());
     }
 
+    #[test]
+    fn test_cli_parse_timeout() {
+        let cli = Cli::try_parse_from(["ai", "--timeout", "30", "chat"]).unwrap();
+        assert_eq!(cli.provider_timeout, Some(30));
+        assert_eq!(
+            CommandContext::new(cli).timeout(),
+            Some(std::time::Duration::from_secs(30))
+        );
+
+        let cli = Cli::try_parse_from([
+            "ai",
+            "chat",
+            "--provider-timeout",
+            "5",
+            "--command-timeout",
+            "20",
+        ])
+        .unwrap();
+        assert_eq!(cli.provider_timeout, Some(5));
+        assert_eq!(
+            CommandContext::new(cli).command_timeout(),
+            Some(std::time::Duration::from_secs(20))
+        );
+
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
+        assert_eq!(cli.provider_timeout, None);
+        assert_eq!(CommandContext::new(cli).timeout(), None);
+
+        assert!(Cli::try_parse_from(["ai", "--timeout", "soon", "chat"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_validate_timeout_range() {
+        for (timeout, valid) in [
+            (0, false),
+            (1, true),
+            (600, true),
+            (601, false),
+            (86_400, false),
+        ] {
+            for flag in ["--provider-timeout", "--command-timeout"] {
+                let cli = Cli::try_parse_from(["ai", flag, &timeout.to_string(), "chat"]).unwrap();
+                assert_eq!(cli.validate().is_ok(), valid, "{} {}", flag, timeout);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_validate_generation_flags() {
+        for (args, valid) in [
+            (&["--temperature", "0.7"][..], true),
+            (&["--temperature", "2.0"], true),
+            (&["--temperature", "2.5"], false),
+            (&["--temperature=-0.1"], false),
+            (&["--max-tokens", "512"], true),
+            (&["--max-tokens", "0"], false),
+        ] {
+            let cli = Cli::try_parse_from(["ai"].iter().chain(args).chain(&["chat"])).unwrap();
+            assert_eq!(cli.validate().is_ok(), valid, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_request_args() {
+        let cli = Cli::try_parse_from([
+            "ai",
+            "chat",
+            "--stop",
+            "END",
+            "--stop",
+            "\n\n",
+            "--param",
+            "top_p=0.9",
+            "--param",
+            "seed=42",
+            "--param",
+            "json_mode=true",
+            "--param",
+            "user=alice",
+            "--param",
+            "logit_bias={\"50256\": -100}",
+        ])
+        .unwrap();
+        let Some(Commands::Chat { request, .. }) = cli.command else {
+            panic!("expected chat");
+        };
+
+        assert_eq!(
+            request.stop_sequences(),
+            Some(vec!["END".to_string(), "\n\n".to_string()])
+        );
+        let params = request.parameters();
+        assert_eq!(params["top_p"], serde_json::json!(0.9));
+        assert_eq!(params["seed"], serde_json::json!(42));
+        assert_eq!(params["json_mode"], serde_json::json!(true));
+        assert_eq!(params["user"], serde_json::json!("alice"));
+        assert_eq!(params["logit_bias"], serde_json::json!({"50256": -100}));
+
+        let work = Cli::try_parse_from(["ai", "work", "--task", "x"]).unwrap();
+        let Some(Commands::Work { request, .. }) = work.command else {
+            panic!("expected work");
+        };
+        assert_eq!(request.stop_sequences(), None);
+        assert!(request.parameters().is_empty());
+    }
+
+    #[test]
+    fn test_cli_rejects_invalid_param() {
+        for arg in ["top_p", "=0.9"] {
+            let err = Cli::try_parse_from(["ai", "chat", "--param", arg]).unwrap_err();
+            assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+            assert!(err.to_string().contains(arg), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_retry_on() {
+        let cli = Cli::try_parse_from(["ai", "--retry-on", "network,model", "chat"]).unwrap();
+        let policy = CommandContext::new(cli).retry_policy();
+        assert_eq!(policy.retry_on.to_string(), "model,network");
+
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
+        assert_eq!(
+            CommandContext::new(cli).retry_policy().retry_on,
+            RetryOn::Transient
+        );
+
+        let err = Cli::try_parse_from(["ai", "--retry-on", "sometimes", "chat"]).unwrap_err();
+        assert!(err.to_string().contains("unknown error code 'sometimes'"));
+    }
+
+    #[test]
+    fn test_cli_parse_provider_order() {
+        let cli =
+            Cli::try_parse_from(["ai", "work", "--provider-order", "openai,anthropic,google"])
+                .unwrap();
+        assert_eq!(cli.provider_order, vec!["openai", "anthropic", "google"]);
+
+        let cli = Cli::try_parse_from(["ai", "work"]).unwrap();
+        assert!(cli.provider_order.is_empty());
+    }
+
+    #[test]
+    fn test_cli_parse_env_file() {
+        let cli = Cli::try_parse_from(["ai", "chat", "--env-file", ".env.local"]).unwrap();
+        assert_eq!(cli.env_file.as_deref(), Some(".env.local"));
+        assert!(Cli::try_parse_from(["ai", "chat"])
+            .unwrap()
+            .env_file
+            .is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_on_secret() {
+        let cli = Cli::try_parse_from(["ai", "--on-secret", "block", "chat"]).unwrap();
+        assert_eq!(
+            CommandContext::new(cli).outbound_scanner().policy(),
+            SecretPolicy::Block
+        );
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
+        assert_eq!(
+            CommandContext::new(cli).outbound_scanner().policy(),
+            SecretPolicy::Warn
+        );
+        assert!(Cli::try_parse_from(["ai", "--on-secret", "ignore", "chat"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_max_concurrent() {
+        let cli = Cli::try_parse_from(["ai", "--max-concurrent", "8", "agents", "list"]).unwrap();
+        assert!(cli.validate().is_ok());
+        assert_eq!(CommandContext::new(cli).max_concurrent(), Some(8));
+
+        for out_of_range in ["0", "65"] {
+            let cli =
+                Cli::try_parse_from(["ai", "--max-concurrent", out_of_range, "chat"]).unwrap();
+            assert!(matches!(cli.validate(), Err(CliError::ValidationError(_))));
+        }
+        assert!(Cli::try_parse_from(["ai", "--max-concurrent", "-1", "chat"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_budget() {
+        let cli = Cli::try_parse_from(["ai", "chat", "--budget", "0.5"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Chat { budget: Some(b), .. }) if b == 0.5));
+        assert!(Cli::try_parse_from(["ai", "chat", "--budget", "-1"]).is_err());
+        assert!(Cli::try_parse_from(["ai", "chat", "--budget", "lots"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_error_exit_codes() {
+        let cases = [
+            (CliError::InvalidCommand("x".into()), exit_code::VALIDATION),
+            (CliError::ValidationError("x".into()), exit_code::VALIDATION),
+            (CliError::NonInteractive("x".into()), exit_code::VALIDATION),
+            (CliError::ConfigError("x".into()), exit_code::CONFIG),
+            (CliError::Timeout("x".into()), exit_code::TIMEOUT),
+            (CliError::RoutingError("x".into()), exit_code::GENERAL),
+            (CliError::MiddlewareError("x".into()), exit_code::GENERAL),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code, "{}", error);
+        }
+    }
+
     #[test]
     fn test_output_format_serialization() {
         let format = OutputFormat::Json;
         let json = serde_json::to_string(&format).unwrap();
         assert_eq!(json, r#""json""#);
+
+        let cli = Cli::try_parse_from(["ai", "--format", "Markdown", "version"]).unwrap();
+        assert_eq!(cli.format, OutputFormat::Other("markdown".to_string()));
+        let json = serde_json::to_string(&cli.format).unwrap();
+        assert_eq!(
+            serde_json::from_str::<OutputFormat>(&json).unwrap(),
+            cli.format
+        );
     }
 
     #[test]
     fn test_command_context_creation() {
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let ctx = CommandContext::new(cli);
         assert!(ctx.start_time.elapsed().as_secs() < 1);
     }
 
     #[tokio::test]
     async fn test_command_context_metadata() {
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let ctx = CommandContext::new(cli);
 
         ctx.set_metadata("key".to_string(), "value".to_string())
@@ -600,6 +1729,30 @@ mod tests {
         assert_eq!(value, Some("value".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_request_metadata_from_context() {
+        let ctx = CommandContext::new(Cli::try_parse_from(["ai", "chat"]).unwrap());
+        let metadata = ctx.request_metadata().await;
+        assert!(metadata.correlation_id.is_none());
+
+        for (key, value) in [
+            (SESSION_ID, "session-1"),
+            (USER_ID, "ada"),
+            (CORRELATION_ID, "corr-1"),
+        ] {
+            ctx.set_metadata(key.to_string(), value.to_string()).await;
+        }
+        let metadata = ctx.request_metadata().await;
+        assert_eq!(metadata.session_id.as_deref(), Some("session-1"));
+        assert_eq!(metadata.user_id.as_deref(), Some("ada"));
+        assert_eq!(metadata.correlation_id.as_deref(), Some("corr-1"));
+        assert_ne!(ctx.request_metadata().await.request_id, metadata.request_id);
+
+        let entry = ctx.audit_entry("command", "chat").await;
+        assert_eq!(entry.user, "ada");
+        assert_eq!(entry.metadata.get(CORRELATION_ID).unwrap(), "corr-1");
+    }
+
     #[test]
     fn test_cli_config_default() {
         let config = CliConfig::default();
@@ -608,12 +1761,57 @@ mod tests {
         assert!(config.colors);
     }
 
+    #[test]
+    fn test_cli_parse_no_input() {
+        let cli = Cli::try_parse_from(["ai", "--no-input", "creds", "list"]).unwrap();
+        assert!(cli.no_input);
+
+        let cli = Cli::try_parse_from(["ai", "creds", "list"]).unwrap();
+        assert!(!cli.no_input);
+    }
+
+    #[test]
+    fn test_cli_parse_external_command() {
+        let cli = Cli::try_parse_from(["ai", "deploy", "--env", "prod"]).unwrap();
+        assert_eq!(cli.command_name(), "deploy");
+        match cli.command {
+            Some(Commands::External(args)) => assert_eq!(args, ["deploy", "--env", "prod"]),
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_cli_subcommand_aliases() {
-        let cli = Cli::try_parse_from(&["ai", "c"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "c"]).unwrap();
         assert!(matches!(cli.command, Some(Commands::Chat { .. })));
 
-        let cli = Cli::try_parse_from(&["ai", "p"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "p"]).unwrap();
         assert!(matches!(cli.command, Some(Commands::Plan { .. })));
     }
+
+    #[test]
+    fn test_chat_help_shows_examples_and_aliases() {
+        let help = Cli::render_help(Some("chat")).unwrap();
+        assert!(help.contains("ai chat --mode work --provider anthropic"));
+        assert!(help.contains("Examples:"));
+        assert!(!help.contains("\x1b["));
+
+        let help = Cli::render_help(None).unwrap();
+        assert!(help.contains("[alias: c]"));
+        assert!(matches!(
+            Cli::render_help(Some("nope")),
+            Err(CliError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_color_flag_disables_color() {
+        let args = |list: &[&str]| -> Vec<std::ffi::OsString> {
+            list.iter().map(std::ffi::OsString::from).collect()
+        };
+        assert!(!Cli::color_enabled(&args(&["ai", "--no-color", "chat"])));
+        if std::env::var_os("NO_COLOR").is_none() {
+            assert!(Cli::color_enabled(&args(&["ai", "chat"])));
+        }
+    }
 }
@@ -0,0 +1,337 @@
+//! Rendering command results for `--format`
+//!
+//! Each format is an [`OutputFormatter`] registered by name in a
+//! [`FormatterRegistry`]. The built-in formats are `text`, `json`, `yaml`,
+//! `markdown` and `csv`; the last two lay out tabular `data` (an array of
+//! objects) as a table.
+
+use super::router::CommandResult;
+use super::{CliError, CliResult};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Renders a command result for display
+pub trait OutputFormatter: Send + Sync {
+    fn format(&self, result: &CommandResult) -> String;
+
+    /// Name the format is selected by with `--format`
+    fn name(&self) -> &str;
+}
+
+/// The message, then any data as indented JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextFormatter;
+
+impl OutputFormatter for TextFormatter {
+    fn format(&self, result: &CommandResult) -> String {
+        let mut parts = Vec::new();
+        parts.extend(result.message.clone());
+        parts.extend(result.data.as_ref().map(pretty_json));
+        parts.join("\n")
+    }
+
+    fn name(&self) -> &str {
+        "text"
+    }
+}
+
+/// The data as indented JSON, or the message as a JSON object when there is
+/// no data
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormatter;
+
+impl OutputFormatter for JsonFormatter {
+    fn format(&self, result: &CommandResult) -> String {
+        match (&result.data, &result.message) {
+            (Some(data), _) => pretty_json(data),
+            (None, Some(message)) => pretty_json(&serde_json::json!({ "message": message })),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "json"
+    }
+}
+
+/// The data as YAML, or the message as a YAML mapping when there is no data
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlFormatter;
+
+impl OutputFormatter for YamlFormatter {
+    fn format(&self, result: &CommandResult) -> String {
+        let value = match (&result.data, &result.message) {
+            (Some(data), _) => data.clone(),
+            (None, Some(message)) => serde_json::json!({ "message": message }),
+            (None, None) => return String::new(),
+        };
+        serde_yaml::to_string(&value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .unwrap_or_else(|e| format!("Error: {}", e))
+    }
+
+    fn name(&self) -> &str {
+        "yaml"
+    }
+}
+
+/// The message as a paragraph and tabular data as a Markdown table; other
+/// data is shown as a JSON code block
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownFormatter;
+
+impl OutputFormatter for MarkdownFormatter {
+    fn format(&self, result: &CommandResult) -> String {
+        let mut parts = Vec::new();
+        parts.extend(result.message.clone());
+        if let Some(data) = &result.data {
+            parts.push(match Table::from_value(data) {
+                Some(table) => markdown_table(&table),
+                None => format!("```json\n{}\n```", pretty_json(data)),
+            });
+        }
+        parts.join("\n\n")
+    }
+
+    fn name(&self) -> &str {
+        "markdown"
+    }
+}
+
+/// Tabular data as CSV with a header row; other data as a single cell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvFormatter;
+
+impl OutputFormatter for CsvFormatter {
+    fn format(&self, result: &CommandResult) -> String {
+        let Some(data) = &result.data else {
+            return result.message.clone().unwrap_or_default();
+        };
+        let table = Table::from_value(data).unwrap_or_else(|| Table {
+            columns: vec!["value".to_string()],
+            rows: vec![vec![cell(data)]],
+        });
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let written = std::iter::once(&table.columns)
+            .chain(&table.rows)
+            .try_for_each(|row| writer.write_record(row));
+        match written.map_err(|e| e.to_string()).and_then(|()| {
+            writer
+                .into_inner()
+                .map_err(|e| e.to_string())
+                .map(|bytes| String::from_utf8_lossy(&bytes).trim_end().to_string())
+        }) {
+            Ok(csv) => csv,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "csv"
+    }
+}
+
+/// Rows of cells under named columns
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Lay out an array of objects (columns in order of first appearance)
+    /// or of plain values, or a single object as a row; `None` for other
+    /// data
+    fn from_value(data: &Value) -> Option<Self> {
+        let items = match data {
+            Value::Array(items) => items.as_slice(),
+            Value::Object(_) => std::slice::from_ref(data),
+            _ => return None,
+        };
+        if items.iter().all(|item| !item.is_object()) {
+            return Some(Self {
+                columns: vec!["value".to_string()],
+                rows: items.iter().map(|item| vec![cell(item)]).collect(),
+            });
+        }
+
+        let mut columns: Vec<String> = Vec::new();
+        for item in items {
+            let object = item.as_object()?;
+            for key in object.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let rows = items
+            .iter()
+            .filter_map(Value::as_object)
+            .map(|object| {
+                columns
+                    .iter()
+                    .map(|column| object.get(column).map(cell).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Some(Self { columns, rows })
+    }
+}
+
+/// A value as table cell text: strings as they are, nothing for null, and
+/// compact JSON otherwise
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn markdown_table(table: &Table) -> String {
+    let row = |cells: &[String]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|c| c.replace('|', "\\|").replace('\n', "<br>"))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let mut lines = vec![
+        row(&table.columns),
+        format!("|{}", " --- |".repeat(table.columns.len())),
+    ];
+    lines.extend(table.rows.iter().map(|cells| row(cells)));
+    lines.join("\n")
+}
+
+fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!("Error: {}", e))
+}
+
+/// Output formatters by name
+#[derive(Clone)]
+pub struct FormatterRegistry {
+    formatters: BTreeMap<String, Arc<dyn OutputFormatter>>,
+}
+
+impl FormatterRegistry {
+    /// A registry with no formats
+    pub fn new() -> Self {
+        Self {
+            formatters: BTreeMap::new(),
+        }
+    }
+
+    /// A registry with the built-in formats
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(Arc::new(TextFormatter))
+            .register(Arc::new(JsonFormatter))
+            .register(Arc::new(YamlFormatter))
+            .register(Arc::new(MarkdownFormatter))
+            .register(Arc::new(CsvFormatter));
+        registry
+    }
+
+    /// Add `formatter`, replacing any with the same name
+    pub fn register(&mut self, formatter: Arc<dyn OutputFormatter>) -> &mut Self {
+        self.formatters
+            .insert(formatter.name().to_string(), formatter);
+        self
+    }
+
+    /// Names of the registered formats, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters.keys().map(String::as_str).collect()
+    }
+
+    /// The formatter for `name`
+    pub fn get(&self, name: &str) -> CliResult<Arc<dyn OutputFormatter>> {
+        self.formatters.get(name).cloned().ok_or_else(|| {
+            CliError::ValidationError(format!(
+                "Unknown output format '{}'; available formats: {}",
+                name,
+                self.names().join(", ")
+            ))
+        })
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn providers() -> CommandResult {
+        CommandResult::success_with_data(json!([
+            {"name": "openai", "model": "gpt-4o", "healthy": true},
+            {"name": "local", "model": "llama|3", "note": "slow,\nbut free"},
+        ]))
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let output = MarkdownFormatter.format(&providers());
+        assert_eq!(
+            output,
+            "| healthy | model | name | note |\n\
+             | --- | --- | --- | --- |\n\
+             | true | gpt-4o | openai |  |\n\
+             |  | llama\\|3 | local | slow,<br>but free |"
+        );
+
+        let mut result = CommandResult::success_with_data(json!(42));
+        result.message = Some("Answer".to_string());
+        assert_eq!(
+            MarkdownFormatter.format(&result),
+            "Answer\n\n```json\n42\n```"
+        );
+    }
+
+    #[test]
+    fn test_csv_table() {
+        let output = CsvFormatter.format(&providers());
+        assert_eq!(
+            output,
+            "healthy,model,name,note\n\
+             true,gpt-4o,openai,\n\
+             ,llama|3,local,\"slow,\nbut free\""
+        );
+
+        let result = CommandResult::success_with_data(json!(["a", "b,c"]));
+        assert_eq!(CsvFormatter.format(&result), "value\na\n\"b,c\"");
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = FormatterRegistry::builtin();
+        assert_eq!(
+            registry.names(),
+            vec!["csv", "json", "markdown", "text", "yaml"]
+        );
+        assert_eq!(registry.get("yaml").unwrap().name(), "yaml");
+
+        let err = registry.get("xml").err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Unknown output format 'xml'; available formats: csv, json, markdown"));
+    }
+
+    #[test]
+    fn test_text_and_json_keep_messages() {
+        let result = CommandResult::success_with_message("Done");
+        assert_eq!(TextFormatter.format(&result), "Done");
+        assert_eq!(
+            JsonFormatter.format(&result),
+            "{\n  \"message\": \"Done\"\n}"
+        );
+        assert_eq!(YamlFormatter.format(&result), "message: Done");
+    }
+}
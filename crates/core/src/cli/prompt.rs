@@ -0,0 +1,253 @@
+//! Interactive prompting with a non-interactive guard
+//!
+//! Every code path that reads from the user goes through a [`Prompter`], which
+//! refuses to block on stdin when it is not a terminal or `--no-input` is set.
+//! This keeps CI and piped invocations from hanging on a prompt nobody can answer.
+
+use super::{CliError, CliResult};
+use parking_lot::Mutex;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Source of interactive user input
+pub struct Prompter {
+    no_input: bool,
+    terminal: bool,
+    /// Draw selection menus on the terminal rather than reading a number
+    menus: bool,
+    input: Mutex<Box<dyn BufRead + Send>>,
+}
+
+impl Prompter {
+    /// Create a prompter reading from stdin
+    pub fn stdin(no_input: bool) -> Self {
+        Self {
+            no_input,
+            terminal: io::stdin().is_terminal(),
+            menus: io::stderr().is_terminal(),
+            input: Mutex::new(Box::new(io::BufReader::new(io::stdin()))),
+        }
+    }
+
+    /// Create a prompter reading from an arbitrary source
+    ///
+    /// `terminal` states whether the source should be treated as a TTY.
+    pub fn from_reader(reader: impl BufRead + Send + 'static, terminal: bool) -> Self {
+        Self {
+            no_input: false,
+            terminal,
+            menus: false,
+            input: Mutex::new(Box::new(reader)),
+        }
+    }
+
+    /// Disable prompting regardless of the input source
+    pub fn with_no_input(mut self, no_input: bool) -> Self {
+        self.no_input = no_input;
+        self
+    }
+
+    /// Whether prompting the user is possible
+    pub fn is_interactive(&self) -> bool {
+        self.terminal && !self.no_input
+    }
+
+    /// Fail with an actionable error if prompting is not possible
+    ///
+    /// `alternative` names the flag(s) that supply the value non-interactively.
+    pub fn ensure_interactive(&self, alternative: &str) -> CliResult<()> {
+        if self.no_input {
+            return Err(CliError::NonInteractive(format!(
+                "--no-input is set; provide {}",
+                alternative
+            )));
+        }
+
+        if !self.terminal {
+            return Err(CliError::NonInteractive(format!(
+                "stdin is not a terminal; provide {}",
+                alternative
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Prompt for a single line of input
+    pub fn read_line(&self, prompt: &str, alternative: &str) -> CliResult<String> {
+        self.ensure_interactive(alternative)?;
+
+        eprint!("{}", prompt);
+        io::stderr()
+            .flush()
+            .map_err(|e| CliError::NonInteractive(e.to_string()))?;
+
+        let mut line = String::new();
+        let read = self
+            .input
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| CliError::NonInteractive(e.to_string()))?;
+
+        if read == 0 {
+            return Err(CliError::NonInteractive(format!(
+                "end of input; provide {}",
+                alternative
+            )));
+        }
+
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Choose one of `items`, returning its index
+    ///
+    /// On a terminal this shows an arrow-key menu starting at `default`;
+    /// otherwise the items are listed with numbers and the answer is read as
+    /// a line, where an empty answer picks `default`.
+    pub fn select(
+        &self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+        alternative: &str,
+    ) -> CliResult<usize> {
+        self.ensure_interactive(alternative)?;
+        if items.is_empty() {
+            return Err(CliError::ValidationError(format!(
+                "nothing to choose for {}",
+                prompt
+            )));
+        }
+        let default = default.min(items.len() - 1);
+
+        if self.menus {
+            return dialoguer::Select::new()
+                .with_prompt(prompt)
+                .items(items)
+                .default(default)
+                .interact_opt()
+                .map_err(|e| CliError::NonInteractive(e.to_string()))?
+                .ok_or_else(|| {
+                    CliError::NonInteractive(format!("cancelled; provide {}", alternative))
+                });
+        }
+
+        for (idx, item) in items.iter().enumerate() {
+            eprintln!("  {}) {}", idx + 1, item);
+        }
+        loop {
+            let answer = self.read_line(&format!("{} [{}]: ", prompt, default + 1), alternative)?;
+            let answer = answer.trim();
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            match answer.parse::<usize>() {
+                Ok(choice) if (1..=items.len()).contains(&choice) => return Ok(choice - 1),
+                _ => eprintln!("Enter a number from 1 to {}", items.len()),
+            }
+        }
+    }
+
+    /// Ask a yes/no question before a destructive action
+    ///
+    /// Returns `true` without prompting when `force` is set. Otherwise reads a
+    /// y/N answer, defaulting to no; fails like [`Prompter::read_line`] when
+    /// prompting is not possible.
+    pub fn confirm(&self, prompt: &str, force: bool) -> CliResult<bool> {
+        if force {
+            return Ok(true);
+        }
+
+        let answer = self.read_line(&format!("{} [y/N] ", prompt), "--force")?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_non_terminal_refuses_prompt() {
+        let prompter = Prompter::from_reader(Cursor::new("secret\n"), false);
+
+        assert!(!prompter.is_interactive());
+        let err = prompter.read_line("Key: ", "--key").unwrap_err();
+        assert!(matches!(err, CliError::NonInteractive(_)));
+        assert!(err.to_string().contains("stdin is not a terminal"));
+        assert!(err.to_string().contains("--key"));
+    }
+
+    #[test]
+    fn test_no_input_refuses_prompt() {
+        let prompter = Prompter::from_reader(Cursor::new("secret\n"), true).with_no_input(true);
+
+        let err = prompter.ensure_interactive("--force").unwrap_err();
+        assert!(err.to_string().contains("--no-input"));
+    }
+
+    #[test]
+    fn test_read_line_from_terminal() {
+        let prompter = Prompter::from_reader(Cursor::new("first\r\nsecond\n"), true);
+
+        assert_eq!(prompter.read_line("> ", "--key").unwrap(), "first");
+        assert_eq!(prompter.read_line("> ", "--key").unwrap(), "second");
+        assert!(prompter.read_line("> ", "--key").is_err());
+    }
+
+    #[test]
+    fn test_confirm_force_skips_prompt() {
+        let prompter = Prompter::from_reader(Cursor::new(""), false).with_no_input(true);
+
+        assert!(prompter.confirm("Delete everything?", true).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_reads_answer() {
+        let prompter = Prompter::from_reader(Cursor::new("Yes\n\nn\n"), true);
+
+        assert!(prompter.confirm("Proceed?", false).unwrap());
+        assert!(!prompter.confirm("Proceed?", false).unwrap());
+        assert!(!prompter.confirm("Proceed?", false).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_without_terminal_suggests_force() {
+        let prompter = Prompter::from_reader(Cursor::new("y\n"), false);
+
+        let err = prompter.confirm("Proceed?", false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+    }
+
+    #[test]
+    fn test_select_reads_number_or_default() {
+        let items = vec!["openai".to_string(), "anthropic".to_string()];
+        let prompter = Prompter::from_reader(Cursor::new("2\n\n7\n1\n"), true);
+
+        assert_eq!(
+            prompter
+                .select("Provider", &items, 0, "--provider")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            prompter
+                .select("Provider", &items, 1, "--provider")
+                .unwrap(),
+            1
+        );
+        // Out-of-range answers are asked again
+        assert_eq!(
+            prompter
+                .select("Provider", &items, 1, "--provider")
+                .unwrap(),
+            0
+        );
+
+        let prompter = Prompter::from_reader(Cursor::new("1\n"), false);
+        let err = prompter
+            .select("Provider", &items, 0, "--provider")
+            .unwrap_err();
+        assert!(err.to_string().contains("--provider"));
+    }
+}
@@ -1,7 +1,8 @@
 //! Command routing system with dynamic dispatch
 
-use super::{CliError, CliResult, CommandContext, Commands};
+use super::{CliError, CliResult, CommandContext};
 use async_trait::async_trait;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info, instrument};
@@ -77,9 +78,33 @@ impl CommandResult {
     }
 }
 
+/// Cut rendered output to at most `max_bytes`, noting how much was left out
+///
+/// The cut falls on a character boundary, so a multi-byte character is
+/// dropped whole rather than split.
+pub fn truncate_output(text: &str, max_bytes: usize) -> Cow<'_, str> {
+    if text.len() <= max_bytes {
+        return Cow::Borrowed(text);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!(
+        "{}\u{2026}[truncated, {} bytes omitted]",
+        &text[..end],
+        text.len() - end
+    ))
+}
+
 /// Command router for dispatching commands to handlers
+///
+/// Handlers are keyed by name, so commands outside the [`super::Commands`]
+/// enum (parsed as [`super::Commands::External`]) route the same way as
+/// built-in ones once a handler with that name is registered.
 pub struct CommandRouter {
     handlers: HashMap<String, Arc<dyn CommandHandler>>,
+    aliases: HashMap<String, String>,
 }
 
 impl CommandRouter {
@@ -87,23 +112,49 @@ impl CommandRouter {
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
+            aliases: HashMap::new(),
         }
     }
 
     /// Register a command handler
     pub fn register<H: CommandHandler + 'static>(&mut self, handler: H) -> &mut Self {
+        self.register_shared(Arc::new(handler))
+    }
+
+    /// Register an already shared handler, e.g. one provided by a plugin
+    pub fn register_shared(&mut self, handler: Arc<dyn CommandHandler>) -> &mut Self {
         let name = handler.name().to_string();
-        self.handlers.insert(name, Arc::new(handler));
+        self.handlers.insert(name, handler);
+        self
+    }
+
+    /// Route `alias` to the handler registered as `target`
+    pub fn register_alias(
+        &mut self,
+        alias: impl Into<String>,
+        target: impl Into<String>,
+    ) -> &mut Self {
+        self.aliases.insert(alias.into(), target.into());
         self
     }
 
+    /// Resolve an alias to its target command name
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
     /// Route and execute command
     #[instrument(skip(self, ctx))]
     pub async fn route(&self, ctx: &CommandContext) -> CliResult<CommandResult> {
-        let command_name = self.get_command_name(&ctx.cli.command);
+        self.route_name(ctx.cli.command_name(), ctx).await
+    }
+
+    /// Execute the handler registered for `name`, resolving aliases first
+    pub async fn route_name(&self, name: &str, ctx: &CommandContext) -> CliResult<CommandResult> {
+        let command_name = self.resolve(name);
         info!("Routing command: {}", command_name);
 
-        let handler = self.handlers.get(&command_name).ok_or_else(|| {
+        let handler = self.handlers.get(command_name).ok_or_else(|| {
             CliError::RoutingError(format!(
                 "No handler registered for command: {}",
                 command_name
@@ -114,22 +165,6 @@ impl CommandRouter {
         handler.execute(ctx).await
     }
 
-    /// Get command name from CLI
-    fn get_command_name(&self, command: &Option<Commands>) -> String {
-        match command {
-            Some(Commands::Chat { .. }) => "chat".to_string(),
-            Some(Commands::Plan { .. }) => "plan".to_string(),
-            Some(Commands::Work { .. }) => "work".to_string(),
-            Some(Commands::Providers { .. }) => "providers".to_string(),
-            Some(Commands::Creds { .. }) => "creds".to_string(),
-            Some(Commands::Memory { .. }) => "memory".to_string(),
-            Some(Commands::Agents { .. }) => "agents".to_string(),
-            Some(Commands::Checkpoint { .. }) => "checkpoint".to_string(),
-            Some(Commands::Config { .. }) => "config".to_string(),
-            None => "default".to_string(),
-        }
-    }
-
     /// List registered handlers
     pub fn list_handlers(&self) -> Vec<&str> {
         self.handlers.keys().map(|s| s.as_str()).collect()
@@ -146,6 +181,7 @@ impl Default for CommandRouter {
 mod tests {
     use super::*;
     use crate::cli::Cli;
+    use clap::Parser;
 
     struct TestHandler {
         name: String,
@@ -189,7 +225,7 @@ mod tests {
             name: "chat".to_string(),
         });
 
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let ctx = CommandContext::new(cli);
 
         let result = router.route(&ctx).await.unwrap();
@@ -200,13 +236,44 @@ mod tests {
     #[tokio::test]
     async fn test_router_missing_handler() {
         let router = CommandRouter::new();
-        let cli = Cli::try_parse_from(&["ai", "chat"]).unwrap();
+        let cli = Cli::try_parse_from(["ai", "chat"]).unwrap();
         let ctx = CommandContext::new(cli);
 
         let result = router.route(&ctx).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_router_alias() {
+        let mut router = CommandRouter::new();
+        router
+            .register(TestHandler {
+                name: "chat".to_string(),
+            })
+            .register_alias("talk", "chat");
+        assert_eq!(router.resolve("talk"), "chat");
+        assert_eq!(router.resolve("chat"), "chat");
+
+        let cli = Cli::try_parse_from(["ai", "talk"]).unwrap();
+        let result = router.route(&CommandContext::new(cli)).await.unwrap();
+        assert_eq!(result.message, Some("Test executed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_router_external_command() {
+        let mut router = CommandRouter::new();
+        router.register_shared(Arc::new(TestHandler {
+            name: "deploy".to_string(),
+        }));
+
+        let cli = Cli::try_parse_from(["ai", "deploy", "--env", "prod"]).unwrap();
+        let ctx = CommandContext::new(cli);
+        assert!(router.route(&ctx).await.unwrap().success);
+
+        let cli = Cli::try_parse_from(["ai", "unknown"]).unwrap();
+        assert!(router.route(&CommandContext::new(cli)).await.is_err());
+    }
+
     #[test]
     fn test_command_result_success() {
         let result = CommandResult::success();
@@ -221,4 +288,23 @@ mod tests {
         assert_eq!(result.exit_code, 1);
         assert_eq!(result.message, Some("Test error".to_string()));
     }
+
+    #[test]
+    fn test_truncate_oversized_result() {
+        let result = CommandResult::success_with_data(serde_json::json!({
+            "notes": "é".repeat(100),
+        }));
+        let rendered = serde_json::to_string_pretty(&result.data).unwrap();
+
+        // 21 bytes ends inside the fourth "é", which is dropped whole
+        let truncated = truncate_output(&rendered, 21);
+        let (kept, marker) = truncated.split_once('\u{2026}').unwrap();
+        assert_eq!(kept, "{\n  \"notes\": \"ééé");
+        assert_eq!(
+            marker,
+            format!("[truncated, {} bytes omitted]", rendered.len() - kept.len())
+        );
+
+        assert_eq!(truncate_output(&rendered, rendered.len()), rendered);
+    }
 }
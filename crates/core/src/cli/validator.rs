@@ -1,12 +1,43 @@
 //! Input validation and sanitization
 
-use super::{CliError, CliResult};
+use super::{CliError, CliResult, MAX_TIMEOUT_SECS};
+use crate::GenerationSettings;
 use regex::Regex;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 /// Input validator for CLI arguments
 pub struct InputValidator;
 
+/// Directory that file edits are confined to
+///
+/// The path is canonical, so symlinks in the root itself are resolved once
+/// and paths checked against it compare like for like.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectRoot {
+    path: PathBuf,
+}
+
+impl ProjectRoot {
+    /// Root at the existing directory `path`
+    pub fn new(path: impl AsRef<Path>) -> CliResult<Self> {
+        let path = path.as_ref();
+        let canonical = path.canonicalize().map_err(|e| {
+            CliError::ValidationError(format!("Project directory {}: {}", path.display(), e))
+        })?;
+        if !canonical.is_dir() {
+            return Err(CliError::ValidationError(format!(
+                "Project directory {} is not a directory",
+                path.display()
+            )));
+        }
+        Ok(Self { path: canonical })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
 impl InputValidator {
     /// Validate file path
     pub fn validate_path(path: &str) -> CliResult<()> {
@@ -32,6 +63,48 @@ impl InputValidator {
         Ok(())
     }
 
+    /// Validate a project-relative path and resolve it inside `root`
+    ///
+    /// The deepest part of the path that exists is canonicalized, so a
+    /// symlink that leads out of the root is rejected just like `..`.
+    /// Returns the absolute path to read or write.
+    pub fn validate_path_within(root: &ProjectRoot, path: &str) -> CliResult<PathBuf> {
+        Self::validate_path(path)?;
+        let relative = Path::new(path);
+        let escapes =
+            || CliError::ValidationError(format!("Path escapes the project directory: {}", path));
+        if path.is_empty() || relative.is_absolute() {
+            return Err(CliError::ValidationError(format!(
+                "Path must be relative to the project: {}",
+                path
+            )));
+        }
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(escapes());
+        }
+
+        let joined = root.path().join(relative);
+        let mut existing = joined.as_path();
+        let mut missing = Vec::new();
+        while std::fs::symlink_metadata(existing).is_err() {
+            let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                return Err(escapes());
+            };
+            missing.push(name);
+            existing = parent;
+        }
+        // A dangling symlink fails to canonicalize; its target is unknown
+        let mut resolved = existing.canonicalize().map_err(|_| escapes())?;
+        resolved.extend(missing.into_iter().rev());
+        if !resolved.starts_with(root.path()) {
+            return Err(escapes());
+        }
+        Ok(resolved)
+    }
+
     /// Validate provider name
     pub fn validate_provider_name(name: &str) -> CliResult<()> {
         // Only allow alphanumeric, hyphen, and underscore
@@ -52,6 +125,19 @@ impl InputValidator {
         Ok(())
     }
 
+    /// Validate a provider endpoint URL
+    pub fn validate_base_url(url: &str) -> CliResult<()> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| CliError::ValidationError(format!("Invalid URL '{}': {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(CliError::ValidationError(format!(
+                "Invalid URL '{}': expected an http(s) address",
+                url
+            )));
+        }
+        Ok(())
+    }
+
     /// Validate API key format
     pub fn validate_api_key(key: &str) -> CliResult<()> {
         if key.is_empty() {
@@ -85,8 +171,7 @@ impl InputValidator {
     /// Sanitize user input (prevent injection attacks)
     pub fn sanitize_input(input: &str) -> String {
         input
-            .replace('\0', "")
-            .replace('\r', "")
+            .replace(['\0', '\r'], "")
             .chars()
             .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
             .collect()
@@ -102,6 +187,48 @@ impl InputValidator {
         Ok(())
     }
 
+    /// Validate sampling temperature (0.0-2.0)
+    pub fn validate_temperature(value: f32) -> CliResult<()> {
+        if !(0.0..=2.0).contains(&value) {
+            return Err(CliError::ValidationError(
+                "Temperature must be between 0.0 and 2.0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate response token limit
+    pub fn validate_max_tokens(value: u32) -> CliResult<()> {
+        if value == 0 {
+            return Err(CliError::ValidationError(
+                "Max tokens must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate generation settings
+    pub fn validate_generation(settings: &GenerationSettings) -> CliResult<()> {
+        if let Some(temperature) = settings.temperature {
+            Self::validate_temperature(temperature)?;
+        }
+        if let Some(max_tokens) = settings.max_tokens {
+            Self::validate_max_tokens(max_tokens)?;
+        }
+        Ok(())
+    }
+
+    /// Validate a time limit in seconds named `name`
+    pub fn validate_timeout(name: &str, secs: u64) -> CliResult<()> {
+        if secs == 0 || secs > MAX_TIMEOUT_SECS {
+            return Err(CliError::ValidationError(format!(
+                "{} must be between 1 and {} seconds",
+                name, MAX_TIMEOUT_SECS
+            )));
+        }
+        Ok(())
+    }
+
     /// Validate limit value
     pub fn validate_limit(value: usize, max: usize) -> CliResult<()> {
         if value == 0 {
@@ -125,6 +252,14 @@ impl InputValidator {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_base_url() {
+        assert!(InputValidator::validate_base_url("https://api.example.com/v1").is_ok());
+        assert!(InputValidator::validate_base_url("http://localhost:11434").is_ok());
+        assert!(InputValidator::validate_base_url("ftp://example.com").is_err());
+        assert!(InputValidator::validate_base_url("not a url").is_err());
+    }
+
     #[test]
     fn test_validate_path_success() {
         assert!(InputValidator::validate_path("/home/user/file.txt").is_ok());
@@ -137,6 +272,48 @@ mod tests {
         assert!(InputValidator::validate_path("/home/../etc/passwd").is_err());
     }
 
+    #[test]
+    fn test_validate_path_within_root() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("project/src")).unwrap();
+        let root = ProjectRoot::new(temp_dir.path().join("project")).unwrap();
+
+        let resolved = InputValidator::validate_path_within(&root, "src/new/main.rs").unwrap();
+        assert_eq!(resolved, root.path().join("src/new/main.rs"));
+        assert!(InputValidator::validate_path_within(&root, "./README.md").is_ok());
+
+        for path in ["../outside.txt", "src/../../outside.txt", "/etc/passwd", ""] {
+            assert!(
+                InputValidator::validate_path_within(&root, path).is_err(),
+                "{} should be rejected",
+                path
+            );
+        }
+        assert!(ProjectRoot::new(temp_dir.path().join("missing")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_path_within_rejects_symlink_escape() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("project/src")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("outside")).unwrap();
+        let link = |target: &str, name: &str| {
+            std::os::unix::fs::symlink(temp_dir.path().join(target), temp_dir.path().join(name))
+                .unwrap()
+        };
+        link("outside", "project/out");
+        link("outside/missing.txt", "project/dangling.txt");
+        link("project/src", "project/code");
+        let root = ProjectRoot::new(temp_dir.path().join("project")).unwrap();
+
+        assert!(InputValidator::validate_path_within(&root, "out/file.txt").is_err());
+        assert!(InputValidator::validate_path_within(&root, "dangling.txt").is_err());
+        // Links that stay inside the project are fine
+        let resolved = InputValidator::validate_path_within(&root, "code/lib.rs").unwrap();
+        assert_eq!(resolved, root.path().join("src/lib.rs"));
+    }
+
     #[test]
     fn test_validate_path_null_byte() {
         assert!(InputValidator::validate_path("/home/user\0/file.txt").is_err());
@@ -227,6 +404,17 @@ mod tests {
         assert!(InputValidator::validate_threshold(1.1).is_err());
     }
 
+    #[test]
+    fn test_validate_generation() {
+        assert!(InputValidator::validate_temperature(0.0).is_ok());
+        assert!(InputValidator::validate_temperature(2.0).is_ok());
+        assert!(InputValidator::validate_temperature(2.1).is_err());
+        assert!(InputValidator::validate_temperature(f32::NAN).is_err());
+        assert!(InputValidator::validate_max_tokens(1).is_ok());
+        assert!(InputValidator::validate_max_tokens(0).is_err());
+        assert!(InputValidator::validate_generation(&GenerationSettings::default()).is_ok());
+    }
+
     #[test]
     fn test_validate_limit_success() {
         assert!(InputValidator::validate_limit(10, 100).is_ok());
@@ -1,12 +1,13 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CoreConfig {
     pub app_name: String,
     pub version: String,
     pub default_mode: String,
-    pub ai_providers: HashMap<String, ProviderConfig>,
+    /// Keyed by provider name; sorted so saved files are stable
+    pub ai_providers: BTreeMap<String, ProviderConfig>,
     pub default_provider: String,
     pub cache_dir: String,
     pub log_level: String,
@@ -25,7 +26,7 @@ impl Default for CoreConfig {
             app_name: "AIrchitect CLI".to_string(),
             version: "1.0.0".to_string(),
             default_mode: "planning".to_string(),
-            ai_providers: HashMap::new(),
+            ai_providers: BTreeMap::new(),
             default_provider: "openai".to_string(),
             cache_dir: ".cache".to_string(),
             log_level: "info".to_string(),
@@ -39,7 +40,7 @@ impl CoreConfig {
     }
 
     pub fn load_from_file(path: &str) -> Result<Self, ai_cli_utils::error::AIError> {
-        let contents = std::fs::read_to_string(path)?;
+        let contents = ai_cli_utils::fs::read_text(path)?;
         let config: CoreConfig = serde_json::from_str(&contents)?;
         Ok(config)
     }
@@ -50,3 +51,57 @@ impl CoreConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_providers(names: &[&str]) -> CoreConfig {
+        let mut config = CoreConfig::new();
+        for name in names {
+            config.ai_providers.insert(
+                name.to_string(),
+                ProviderConfig {
+                    api_key: None,
+                    base_url: format!("https://{}.example.com", name),
+                    default_model: "model".to_string(),
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn test_serialization_is_stable() {
+        let first =
+            serde_json::to_string_pretty(&with_providers(&["openai", "anthropic", "ollama"]))
+                .unwrap();
+        let second =
+            serde_json::to_string_pretty(&with_providers(&["ollama", "openai", "anthropic"]))
+                .unwrap();
+        assert_eq!(first, second);
+        assert!(first.find("anthropic").unwrap() < first.find("ollama").unwrap());
+    }
+
+    #[test]
+    fn test_load_windows_authored_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let json = serde_json::to_string_pretty(&with_providers(&["openai"])).unwrap();
+        let crlf = json.replace('\n', "\r\n");
+
+        for (name, contents) in [
+            ("bom.json", format!("\u{feff}{}", json)),
+            ("crlf.json", crlf.clone()),
+            ("both.json", format!("\u{feff}{}\r\n", crlf)),
+        ] {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            let config = CoreConfig::load_from_file(path.to_str().unwrap()).unwrap();
+            assert_eq!(
+                config.ai_providers["openai"].default_model, "model",
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Loading environment variables from dotenv files
+//!
+//! API keys and other settings often live in a `.env` file next to the
+//! project. Loading one fills in variables the environment does not
+//! already set, so an exported variable always wins over the file.
+
+use std::path::Path;
+
+/// File loaded when `--env-file` is not given, if it exists
+pub const DEFAULT_ENV_FILE: &str = ".env";
+
+/// Load `KEY=value` pairs from `path` into the process environment
+///
+/// Variables that are already set are left alone, and malformed lines are
+/// skipped with a warning. Returns the names of the variables set.
+pub fn load_env_file(path: &Path) -> Result<Vec<String>, dotenvy::Error> {
+    let mut loaded = Vec::new();
+    for (line, item) in dotenvy::from_path_iter(path)?.enumerate() {
+        match item {
+            Ok((key, value)) => {
+                if std::env::var_os(&key).is_none() {
+                    std::env::set_var(&key, value);
+                    loaded.push(key);
+                }
+            }
+            Err(e) => log::warn!("Skipping entry {} of {}: {}", line + 1, path.display(), e),
+        }
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::ProviderConfig;
+    use ai_cli_providers::factory::build_provider;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_env_file_keys_reach_provider_resolution() {
+        let qwen = ProviderConfig {
+            name: "qwen".to_string(),
+            enabled: true,
+            model: "qwen-max".to_string(),
+            base_url: String::new(),
+            api_key: None,
+            deployment: None,
+            api_version: None,
+        };
+        assert!(build_provider(&qwen).is_err());
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(".env");
+        std::env::set_var("DOTENV_TEST_EXPORTED", "from-environment");
+        std::fs::write(
+            &path,
+            "# keys\nQWEN_API_KEY=sk-from-file\nnot a valid line\nDOTENV_TEST_EXPORTED=from-file\nDOTENV_TEST_QUOTED=\"a b\"\n",
+        )
+        .unwrap();
+
+        let loaded = load_env_file(&path).unwrap();
+        assert_eq!(loaded, vec!["QWEN_API_KEY", "DOTENV_TEST_QUOTED"]);
+        assert_eq!(std::env::var("DOTENV_TEST_QUOTED").unwrap(), "a b");
+        assert_eq!(
+            std::env::var("DOTENV_TEST_EXPORTED").unwrap(),
+            "from-environment"
+        );
+        assert_eq!(build_provider(&qwen).unwrap().name(), "qwen");
+
+        assert!(load_env_file(&temp_dir.path().join("missing.env")).is_err());
+    }
+}
@@ -1,7 +1,36 @@
 //! Error types for AIrchitect CLI
 
+use crate::cli::CliError;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
 use thiserror::Error;
 
+/// Process exit codes, so scripts can tell failure categories apart
+///
+/// | Code | Meaning |
+/// |------|---------|
+/// | 0 | Success |
+/// | 1 | General failure |
+/// | 2 | Configuration error |
+/// | 3 | Authentication or credential error |
+/// | 4 | Rate limited by a provider |
+/// | 5 | Timed out |
+/// | 6 | Invalid command or input |
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    pub const GENERAL: i32 = 1;
+    pub const CONFIG: i32 = 2;
+    pub const AUTH: i32 = 3;
+    pub const RATE_LIMIT: i32 = 4;
+    pub const TIMEOUT: i32 = 5;
+    pub const VALIDATION: i32 = 6;
+
+    /// Whether a failure with this code may succeed if the command is retried
+    pub fn is_retryable(code: i32) -> bool {
+        matches!(code, RATE_LIMIT | TIMEOUT)
+    }
+}
+
 /// AIrchitect CLI error types
 #[derive(Error, Debug)]
 pub enum AICliError {
@@ -29,6 +58,14 @@ pub enum AICliError {
     #[error("Checkpoint error: {0}")]
     CheckpointError(String),
 
+    /// Rate limit error
+    #[error("Rate limit exceeded: {0}")]
+    RateLimitError(String),
+
+    /// Timeout error
+    #[error("Timed out: {0}")]
+    TimeoutError(String),
+
     /// IO error
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
@@ -77,8 +114,213 @@ impl AICliError {
         AICliError::CheckpointError(msg.into())
     }
 
+    /// Create a new rate limit error
+    pub fn rate_limit(msg: impl Into<String>) -> Self {
+        AICliError::RateLimitError(msg.into())
+    }
+
+    /// Create a new timeout error
+    pub fn timeout(msg: impl Into<String>) -> Self {
+        AICliError::TimeoutError(msg.into())
+    }
+
     /// Create a new generic error
     pub fn generic(msg: impl Into<String>) -> Self {
         AICliError::GenericError(msg.into())
     }
+
+    /// Process exit code for this error; see [`exit_code`]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AICliError::ConfigError(_) => exit_code::CONFIG,
+            AICliError::CredentialError(_) => exit_code::AUTH,
+            AICliError::RateLimitError(_) => exit_code::RATE_LIMIT,
+            AICliError::TimeoutError(_) => exit_code::TIMEOUT,
+            AICliError::HttpError(e) if e.is_timeout() => exit_code::TIMEOUT,
+            AICliError::HttpError(e) => match e.status().map(|s| s.as_u16()) {
+                Some(401 | 403) => exit_code::AUTH,
+                Some(429) => exit_code::RATE_LIMIT,
+                _ => exit_code::GENERAL,
+            },
+            _ => exit_code::GENERAL,
+        }
+    }
+
+    /// Whether the same command may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AICliError::HttpError(e) if e.is_connect() => true,
+            AICliError::HttpError(e) if e.status().is_some_and(|s| s.is_server_error()) => true,
+            other => exit_code::is_retryable(other.exit_code()),
+        }
+    }
+}
+
+/// Error details written to stderr, as JSON with `--json-errors`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    /// Process exit code; see [`exit_code`]
+    pub code: i32,
+    pub message: String,
+    /// Whether the same command may succeed if retried
+    pub retryable: bool,
+}
+
+impl ErrorReport {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: exit_code::is_retryable(code),
+        }
+    }
+
+    /// Write the report as `{"error": {...}}` when `json` is set, otherwise
+    /// as an `Error: message` line
+    pub fn write_to(&self, out: &mut dyn Write, json: bool) -> io::Result<()> {
+        if json {
+            let body = serde_json::json!({ "error": self });
+            writeln!(out, "{}", body)
+        } else {
+            writeln!(out, "Error: {}", self.message)
+        }
+    }
+}
+
+impl From<&AICliError> for ErrorReport {
+    fn from(err: &AICliError) -> Self {
+        Self {
+            code: err.exit_code(),
+            message: err.to_string(),
+            retryable: err.is_retryable(),
+        }
+    }
+}
+
+impl From<&CliError> for ErrorReport {
+    fn from(err: &CliError) -> Self {
+        Self::new(err.exit_code(), err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(AICliError::config("bad").exit_code(), exit_code::CONFIG);
+        assert_eq!(AICliError::credential("none").exit_code(), exit_code::AUTH);
+        assert_eq!(
+            AICliError::rate_limit("slow down").exit_code(),
+            exit_code::RATE_LIMIT
+        );
+        assert_eq!(AICliError::timeout("30s").exit_code(), exit_code::TIMEOUT);
+        assert_eq!(AICliError::provider("down").exit_code(), exit_code::GENERAL);
+        assert_eq!(AICliError::generic("oops").exit_code(), exit_code::GENERAL);
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let mut codes = vec![
+            exit_code::SUCCESS,
+            exit_code::GENERAL,
+            exit_code::CONFIG,
+            exit_code::AUTH,
+            exit_code::RATE_LIMIT,
+            exit_code::TIMEOUT,
+            exit_code::VALIDATION,
+        ];
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_failing_command_reports_json_error() {
+        use crate::cli::middleware::{Middleware, ValidationMiddleware};
+        use crate::cli::{Cli, CommandContext};
+        use clap::Parser;
+
+        let cli =
+            Cli::try_parse_from(["ai", "--json-errors", "--timeout", "0", "version"]).unwrap();
+        assert!(cli.json_errors());
+        let mut ctx = CommandContext::new(cli);
+        let err = ValidationMiddleware.before(&mut ctx).await.unwrap_err();
+
+        let mut stderr = Vec::new();
+        ErrorReport::from(&err)
+            .write_to(&mut stderr, ctx.cli.json_errors())
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&stderr).unwrap();
+        assert_eq!(json["error"]["code"], exit_code::VALIDATION);
+        assert_eq!(json["error"]["retryable"], false);
+        assert!(json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("--provider-timeout"));
+    }
+
+    #[test]
+    fn test_provider_failures_keep_their_exit_code() {
+        use crate::cli::CliError;
+        use ai_cli_ai_engine::provider::ProviderError;
+
+        let cases = [
+            (
+                ProviderError::AuthError("401".into()),
+                exit_code::AUTH,
+                false,
+            ),
+            (
+                ProviderError::RateLimitError("429".into()),
+                exit_code::RATE_LIMIT,
+                true,
+            ),
+            (
+                ProviderError::TimeoutError("30s".into()),
+                exit_code::TIMEOUT,
+                true,
+            ),
+            (
+                ProviderError::InvalidRequest("bad".into()),
+                exit_code::VALIDATION,
+                false,
+            ),
+            (
+                ProviderError::Unavailable("down".into()),
+                exit_code::GENERAL,
+                false,
+            ),
+        ];
+        for (err, code, retryable) in cases {
+            let report = ErrorReport::from(&CliError::provider_failure("openai", err));
+            assert_eq!((report.code, report.retryable), (code, retryable));
+            assert!(
+                report.message.contains("openai failed"),
+                "{}",
+                report.message
+            );
+        }
+        let err: CliError = ProviderError::AuthError("bad key".into()).into();
+        assert_eq!(
+            err.to_string(),
+            "Authentication failed: Authentication error: bad key"
+        );
+    }
+
+    #[test]
+    fn test_error_report_text_and_retryable() {
+        let report = ErrorReport::from(&AICliError::rate_limit("slow down"));
+        assert!(report.retryable);
+        assert_eq!(report.code, exit_code::RATE_LIMIT);
+
+        let mut out = Vec::new();
+        report.write_to(&mut out, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Error: Rate limit exceeded: slow down\n"
+        );
+        assert!(!ErrorReport::from(&AICliError::config("bad")).retryable);
+    }
 }
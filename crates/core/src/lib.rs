@@ -2,10 +2,19 @@
 //!
 //! This library provides the core functionality for the AIrchitect CLI system.
 
+pub mod build_info;
 pub mod cli;
 pub mod config;
+pub mod dotenv;
 pub mod error;
-
+pub mod logging;
+pub mod metrics;
+pub mod watcher;
+
+use ai_cli_ai_engine::quota::QuotaLimits;
+use ai_cli_ai_engine::retry::RetryPolicy;
+use ai_cli_ai_engine::AIEngineConfig;
+use ai_cli_security::secret::Secret;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +24,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Main AIrchitect CLI application
 pub struct AICli {
     config: AppConfig,
+    quiet: bool,
 }
 
 /// Application configuration
@@ -28,8 +38,43 @@ pub struct AppConfig {
 
     /// Available providers
     pub providers: Vec<ProviderConfig>,
+
+    /// Sampling temperature for providers that do not set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_temperature: Option<f32>,
+
+    /// Response token limit for providers that do not set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_max_tokens: Option<u32>,
+
+    /// Providers to try in turn when a request fails with a transient
+    /// error; just the default provider when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failover_order: Vec<String>,
+
+    /// Processors run over each response before it is shown, in order:
+    /// `strip-thinking`, `extract-code` or `trim`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_processors: Vec<String>,
+
+    /// Connect to the default provider in the background at startup, so
+    /// the first request does not pay for the handshake
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warmup: bool,
+
+    /// Printed results are cut after this many bytes; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<usize>,
+
+    /// Time limit for each provider request in seconds, overridden by
+    /// `--provider-timeout`; [`DEFAULT_REQUEST_TIMEOUT_SECS`] when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
 }
 
+/// Provider request time limit used when neither the config nor a flag sets one
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 300;
+
 /// Provider configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
@@ -40,10 +85,53 @@ pub struct ProviderConfig {
     pub enabled: bool,
 
     /// API key for the provider
-    pub api_key: Option<String>,
+    pub api_key: Option<Secret>,
 
     /// Default model for the provider
     pub default_model: Option<String>,
+
+    /// API endpoint; the provider's standard endpoint when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+
+    /// Sampling temperature, overriding the global default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_temperature: Option<f32>,
+
+    /// Response token limit, overriding the global default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_max_tokens: Option<u32>,
+
+    /// Azure deployment name; the default model when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+
+    /// Azure API version; the adapter's default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+
+    /// Request and token limits per quota period; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<QuotaLimits>,
+}
+
+/// Generation parameters applied to prompt requests
+///
+/// Unset values are left to the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerationSettings {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+impl GenerationSettings {
+    /// Fill the values not set here from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            temperature: self.temperature.or(fallback.temperature),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+        }
+    }
 }
 
 impl Default for AppConfig {
@@ -57,14 +145,178 @@ impl Default for AppConfig {
                     enabled: true,
                     api_key: None,
                     default_model: Some("gpt-4".to_string()),
+                    base_url: None,
+                    default_temperature: None,
+                    default_max_tokens: None,
+                    deployment: None,
+                    api_version: None,
+                    quota: None,
                 },
                 ProviderConfig {
                     name: "anthropic".to_string(),
                     enabled: true,
                     api_key: None,
                     default_model: Some("claude-3-opus".to_string()),
+                    base_url: None,
+                    default_temperature: None,
+                    default_max_tokens: None,
+                    deployment: None,
+                    api_version: None,
+                    quota: None,
                 },
             ],
+            default_temperature: None,
+            default_max_tokens: None,
+            failover_order: Vec::new(),
+            response_processors: Vec::new(),
+            warmup: false,
+            max_output_bytes: None,
+            request_timeout: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load configuration from a JSON file
+    pub fn load_from_file(path: impl AsRef<std::path::Path>) -> AICliResult<Self> {
+        let contents = ai_cli_utils::fs::read_text(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Load configuration from a JSON file, using defaults when it does not exist
+    pub fn load_or_default(path: impl AsRef<std::path::Path>) -> AICliResult<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_from_file(path)
+    }
+
+    /// Load config files in order, each merged over the ones before it with
+    /// [`merge_config`]
+    ///
+    /// Missing files are skipped; when none exists the defaults are used.
+    /// Errors name the file they come from.
+    pub fn load_layered<P: AsRef<std::path::Path>>(paths: &[P]) -> AICliResult<Self> {
+        match Self::load_layered_value(paths)? {
+            Some(value) => serde_json::from_value(value).map_err(|e| {
+                let names: Vec<String> = paths
+                    .iter()
+                    .map(|p| p.as_ref().display().to_string())
+                    .collect();
+                error::AICliError::config(format!("{}: {}", names.join(" + "), e))
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The merged JSON of the config files that exist, before it is read as
+    /// an [`AppConfig`]
+    pub fn load_layered_value<P: AsRef<std::path::Path>>(
+        paths: &[P],
+    ) -> AICliResult<Option<serde_json::Value>> {
+        let mut merged: Option<serde_json::Value> = None;
+        for path in paths.iter().map(AsRef::as_ref) {
+            if !path.exists() {
+                continue;
+            }
+            let layer = ai_cli_utils::fs::read_text(path)
+                .map_err(error::AICliError::from)
+                .and_then(|contents| Ok(serde_json::from_str(&contents)?))
+                .map_err(|e| error::AICliError::config(format!("{}: {}", path.display(), e)))?;
+            match &mut merged {
+                Some(base) => merge_config(base, layer),
+                None => merged = Some(layer),
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Generation defaults for `provider`, its own settings taking precedence
+    pub fn generation_defaults(&self, provider: &str) -> GenerationSettings {
+        let global = GenerationSettings {
+            temperature: self.default_temperature,
+            max_tokens: self.default_max_tokens,
+        };
+        match self.providers.iter().find(|p| p.name == provider) {
+            Some(p) => GenerationSettings {
+                temperature: p.default_temperature,
+                max_tokens: p.default_max_tokens,
+            }
+            .or(global),
+            None => global,
+        }
+    }
+
+    /// Engine settings for this configuration: its providers, the default
+    /// retry policy and the request timeout
+    pub fn engine_config(&self) -> AIEngineConfig {
+        let retry = RetryPolicy::default();
+        AIEngineConfig {
+            default_provider: self.default_provider.clone(),
+            providers: self
+                .providers
+                .iter()
+                .map(|provider| (provider.name.clone(), provider.into()))
+                .collect(),
+            max_retries: u8::try_from(retry.max_retries).unwrap_or(u8::MAX),
+            timeout: self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS),
+            backoff: retry.backoff,
+        }
+    }
+
+    /// Save configuration to a JSON file atomically, creating parent directories
+    pub fn save_to_file(&self, path: impl AsRef<std::path::Path>) -> AICliResult<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        ai_cli_utils::fs::write_atomic(path.as_ref(), contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Merge the JSON form of a config `overlay` into `base`
+///
+/// Objects merge key by key, and lists of named entries such as
+/// `providers` merge entry by entry on `name`, with new names appended.
+/// Any other value in the overlay replaces the one in `base`.
+pub fn merge_config(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    use serde_json::Value;
+
+    let is_named = |items: &[Value]| items.iter().all(|v| v.get("name").is_some());
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay))
+            if !base.is_empty() && is_named(base) && is_named(&overlay) =>
+        {
+            for entry in overlay {
+                match base.iter_mut().find(|b| b.get("name") == entry.get("name")) {
+                    Some(existing) => merge_config(existing, entry),
+                    None => base.push(entry),
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+impl From<&ProviderConfig> for ai_cli_ai_engine::ProviderConfig {
+    fn from(config: &ProviderConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            enabled: config.enabled,
+            model: config.default_model.clone().unwrap_or_default(),
+            base_url: config.base_url.clone().unwrap_or_default(),
+            api_key: config.api_key.clone(),
+            deployment: config.deployment.clone(),
+            api_version: config.api_version.clone(),
         }
     }
 }
@@ -72,7 +324,28 @@ impl Default for AppConfig {
 impl AICli {
     /// Create a new AIrchitect CLI instance
     pub fn new(config: AppConfig) -> Self {
-        AICli { config }
+        AICli {
+            config,
+            quiet: false,
+        }
+    }
+
+    /// Leave out banners and other informational messages
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Message printed once the application finishes, unless quiet
+    pub fn completion_banner(&self) -> Option<&'static str> {
+        (!self.quiet).then_some("AIrchitect CLI completed successfully")
+    }
+
+    /// Print an informational message, unless quiet
+    fn announce(&self, message: &str) {
+        if !self.quiet {
+            println!("{}", message);
+        }
     }
 
     /// Get the application configuration
@@ -94,7 +367,7 @@ impl AICli {
     /// Initialize the application
     async fn initialize(&self) -> Result<()> {
         if self.config.debug {
-            println!("Initializing AIrchitect CLI v{}", VERSION);
+            self.announce(&format!("Initializing AIrchitect CLI v{}", VERSION));
         }
 
         // TODO: Initialize logging
@@ -110,7 +383,7 @@ impl AICli {
         // TODO: Implement main application loop
         // This would handle command parsing, TUI rendering, etc.
 
-        println!("AIrchitect CLI initialized. Use --help for available commands.");
+        self.announce("AIrchitect CLI initialized. Use --help for available commands.");
 
         Ok(())
     }
@@ -169,13 +442,19 @@ mod tests {
         let provider = ProviderConfig {
             name: "test_provider".to_string(),
             enabled: false,
-            api_key: Some("test_key".to_string()),
+            api_key: Some(Secret::from("test_key")),
             default_model: Some("test_model".to_string()),
+            base_url: None,
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
         };
 
         assert_eq!(provider.name, "test_provider");
         assert!(!provider.enabled);
-        assert_eq!(provider.api_key, Some("test_key".to_string()));
+        assert_eq!(provider.api_key, Some(Secret::from("test_key")));
         assert_eq!(provider.default_model, Some("test_model".to_string()));
     }
 
@@ -186,12 +465,87 @@ mod tests {
             enabled: true,
             api_key: None,
             default_model: None,
+            base_url: None,
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
         };
 
         assert!(provider.api_key.is_none());
         assert!(provider.default_model.is_none());
     }
 
+    #[test]
+    fn test_merge_partial_configs() {
+        let mut base = serde_json::json!({
+            "debug": false,
+            "default_provider": "openai",
+            "providers": [
+                {"name": "openai", "enabled": true, "api_key": null, "default_model": "gpt-4"},
+                {"name": "anthropic", "enabled": true, "api_key": null, "default_model": "claude-3-opus"}
+            ],
+            "failover_order": ["openai", "anthropic"]
+        });
+        merge_config(
+            &mut base,
+            serde_json::json!({
+                "debug": true,
+                "providers": [
+                    {"name": "anthropic", "api_key": "sk-personal"},
+                    {"name": "ollama", "enabled": true, "api_key": null, "default_model": "llama3"}
+                ],
+                "failover_order": ["anthropic"]
+            }),
+        );
+
+        let config: AppConfig = serde_json::from_value(base).unwrap();
+        assert!(config.debug);
+        assert_eq!(config.default_provider, "openai");
+        assert_eq!(config.failover_order, vec!["anthropic"]);
+        let names: Vec<&str> = config.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["openai", "anthropic", "ollama"]);
+        assert_eq!(
+            config.providers[1].api_key.as_ref().map(Secret::expose),
+            Some("sk-personal")
+        );
+        assert_eq!(
+            config.providers[1].default_model.as_deref(),
+            Some("claude-3-opus")
+        );
+    }
+
+    #[test]
+    fn test_load_layered_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let team = temp_dir.path().join("team.json");
+        let personal = temp_dir.path().join("personal.json");
+        AppConfig::default().save_to_file(&team).unwrap();
+        std::fs::write(
+            &personal,
+            r#"{"default_provider": "anthropic", "providers": [{"name": "openai", "enabled": false}]}"#,
+        )
+        .unwrap();
+
+        let missing = temp_dir.path().join("missing.json");
+        let config = AppConfig::load_layered(&[&team, &missing, &personal]).unwrap();
+        assert_eq!(config.default_provider, "anthropic");
+        assert!(!config.providers[0].enabled);
+        assert_eq!(config.providers[0].default_model.as_deref(), Some("gpt-4"));
+
+        // Alone, the partial override is not a complete config
+        let err = AppConfig::load_layered(&[&personal]).unwrap_err();
+        assert!(err.to_string().contains("personal.json"));
+        assert_eq!(
+            AppConfig::load_layered(&[&missing])
+                .unwrap()
+                .providers
+                .len(),
+            2
+        );
+    }
+
     // AICli tests
     #[test]
     fn test_ai_cli_new() {
@@ -202,6 +556,15 @@ mod tests {
         assert_eq!(cli.config().default_provider, config.default_provider);
     }
 
+    #[test]
+    fn test_quiet_omits_banner() {
+        let cli = AICli::new(AppConfig::default());
+        assert!(cli.completion_banner().is_some());
+
+        let cli = cli.with_quiet(true);
+        assert_eq!(cli.completion_banner(), None);
+    }
+
     #[test]
     fn test_ai_cli_with_custom_config() {
         let config = AppConfig {
@@ -210,9 +573,22 @@ mod tests {
             providers: vec![ProviderConfig {
                 name: "custom".to_string(),
                 enabled: true,
-                api_key: Some("key123".to_string()),
+                api_key: Some(Secret::from("key123")),
                 default_model: Some("model-v1".to_string()),
+                base_url: None,
+                default_temperature: None,
+                default_max_tokens: None,
+                deployment: None,
+                api_version: None,
+                quota: None,
             }],
+            default_temperature: None,
+            default_max_tokens: None,
+            failover_order: Vec::new(),
+            response_processors: Vec::new(),
+            warmup: false,
+            max_output_bytes: None,
+            request_timeout: None,
         };
 
         let cli = AICli::new(config.clone());
@@ -248,6 +624,13 @@ mod tests {
             debug: true,
             default_provider: "openai".to_string(),
             providers: vec![],
+            default_temperature: None,
+            default_max_tokens: None,
+            failover_order: Vec::new(),
+            response_processors: Vec::new(),
+            warmup: false,
+            max_output_bytes: None,
+            request_timeout: None,
         };
 
         let cli = AICli::new(config);
@@ -263,6 +646,13 @@ mod tests {
             debug: false,
             default_provider: "anthropic".to_string(),
             providers: vec![],
+            default_temperature: None,
+            default_max_tokens: None,
+            failover_order: Vec::new(),
+            response_processors: Vec::new(),
+            warmup: false,
+            max_output_bytes: None,
+            request_timeout: None,
         };
 
         let cli = AICli::new(config);
@@ -277,15 +667,27 @@ mod tests {
         config.providers.push(ProviderConfig {
             name: "custom1".to_string(),
             enabled: true,
-            api_key: Some("key1".to_string()),
+            api_key: Some(Secret::from("key1")),
             default_model: Some("model1".to_string()),
+            base_url: None,
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
         });
 
         config.providers.push(ProviderConfig {
             name: "custom2".to_string(),
             enabled: false,
-            api_key: Some("key2".to_string()),
+            api_key: Some(Secret::from("key2")),
             default_model: Some("model2".to_string()),
+            base_url: None,
+            default_temperature: None,
+            default_max_tokens: None,
+            deployment: None,
+            api_version: None,
+            quota: None,
         });
 
         assert_eq!(config.providers.len(), 4); // 2 default + 2 custom
@@ -310,7 +712,7 @@ mod tests {
         let mut config = AppConfig::default();
 
         if let Some(provider) = config.providers.iter_mut().find(|p| p.name == "openai") {
-            provider.api_key = Some("new_api_key".to_string());
+            provider.api_key = Some(Secret::from("new_api_key"));
             provider.enabled = false;
         }
 
@@ -319,10 +721,26 @@ mod tests {
             .iter()
             .find(|p| p.name == "openai")
             .unwrap();
-        assert_eq!(openai.api_key, Some("new_api_key".to_string()));
+        assert_eq!(openai.api_key, Some(Secret::from("new_api_key")));
         assert!(!openai.enabled);
     }
 
+    #[test]
+    fn test_app_config_file_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("config.json");
+
+        let config = AppConfig {
+            default_provider: "anthropic".to_string(),
+            ..AppConfig::default()
+        };
+        config.save_to_file(&path).unwrap();
+
+        let loaded = AppConfig::load_from_file(&path).unwrap();
+        assert_eq!(loaded.default_provider, "anthropic");
+        assert_eq!(loaded.providers.len(), 2);
+    }
+
     #[test]
     fn test_config_clone() {
         let config1 = AppConfig::default();
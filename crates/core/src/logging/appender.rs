@@ -1,12 +1,11 @@
 //! File appenders for logging
 
-use super::{LogError, LogResult};
+use super::LogResult;
+use parking_lot::Mutex;
 use std::fs::{File, OpenOptions};
-use std::io::{self, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use parking_lot::Mutex;
-use chrono::Utc;
 
 /// File appender for writing logs to a file
 pub struct FileAppender {
@@ -24,10 +23,7 @@ impl FileAppender {
             std::fs::create_dir_all(parent)?;
         }
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)?;
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
 
         Ok(Self {
             file: Arc::new(Mutex::new(file)),
@@ -61,11 +57,7 @@ pub struct RotatingFileAppender {
 
 impl RotatingFileAppender {
     /// Create a new rotating file appender
-    pub fn new(
-        base_path: impl AsRef<Path>,
-        max_size: u64,
-        max_files: usize,
-    ) -> LogResult<Self> {
+    pub fn new(base_path: impl AsRef<Path>, max_size: u64, max_files: usize) -> LogResult<Self> {
         let base_path = base_path.as_ref().to_path_buf();
 
         // Create parent directory if it doesn't exist
@@ -73,7 +65,7 @@ impl RotatingFileAppender {
             std::fs::create_dir_all(parent)?;
         }
 
-        let mut appender = Self {
+        let appender = Self {
             base_path,
             max_size,
             max_files,
@@ -124,17 +116,15 @@ impl RotatingFileAppender {
             *file = None;
         }
 
-        // Rotate existing files
+        // Drop the oldest file, then shift the rest up by one
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
         for i in (1..self.max_files).rev() {
             let from = self.rotated_path(i);
-            let to = self.rotated_path(i + 1);
-
             if from.exists() {
-                if i + 1 <= self.max_files {
-                    std::fs::rename(&from, &to)?;
-                } else {
-                    std::fs::remove_file(&from)?;
-                }
+                std::fs::rename(&from, self.rotated_path(i + 1))?;
             }
         }
 
@@ -149,7 +139,7 @@ impl RotatingFileAppender {
     }
 
     /// Open current log file
-    fn open_current_file(&mut self) -> LogResult<()> {
+    fn open_current_file(&self) -> LogResult<()> {
         let file = OpenOptions::new()
             .create(true)
             .append(true)
@@ -202,7 +192,10 @@ mod tests {
         appender.write(b"Test log entry").unwrap();
 
         let mut content = String::new();
-        File::open(&log_path).unwrap().read_to_string(&mut content).unwrap();
+        File::open(&log_path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
         assert!(content.contains("Test log entry"));
     }
 
@@ -216,7 +209,10 @@ mod tests {
         appender.write(b"Line 2").unwrap();
 
         let mut content = String::new();
-        File::open(&log_path).unwrap().read_to_string(&mut content).unwrap();
+        File::open(&log_path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
         assert!(content.contains("Line 1"));
         assert!(content.contains("Line 2"));
     }
@@ -240,7 +236,10 @@ mod tests {
         appender.write(b"Test log entry").unwrap();
 
         let mut content = String::new();
-        File::open(&log_path).unwrap().read_to_string(&mut content).unwrap();
+        File::open(&log_path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
         assert!(content.contains("Test log entry"));
     }
 
@@ -254,7 +253,9 @@ mod tests {
 
         // Write enough data to trigger rotation
         appender.write(b"Line 1 - This is a long line").unwrap();
-        appender.write(b"Line 2 - This is another long line").unwrap();
+        appender
+            .write(b"Line 2 - This is another long line")
+            .unwrap();
 
         // Check that rotation happened
         let rotated_path = log_path.with_extension(".1");
@@ -266,7 +267,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let log_path = temp_dir.path().join("subdir").join("test.log");
 
-        let appender = FileAppender::new(&log_path).unwrap();
+        FileAppender::new(&log_path).unwrap();
         assert!(log_path.parent().unwrap().exists());
         assert!(log_path.exists());
     }
@@ -2,11 +2,11 @@
 
 use super::{LogError, LogResult};
 use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use parking_lot::Mutex;
-use sha2::{Sha256, Digest};
 
 /// Audit log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,9 +154,7 @@ impl AuditLogger {
             Vec::new()
         };
 
-        let last_hash = entries.last()
-            .map(|e| e.hash.clone())
-            .unwrap_or_default();
+        let last_hash = entries.last().map(|e| e.hash.clone()).unwrap_or_default();
 
         Ok(Self {
             path,
@@ -173,12 +171,14 @@ impl AuditLogger {
 
         // Verify entry
         if !entry.verify() {
-            return Err(LogError::AuditError("Entry verification failed".to_string()));
+            return Err(LogError::AuditError(
+                "Entry verification failed".to_string(),
+            ));
         }
 
         // Append to file
-        let json = serde_json::to_string(&entry)
-            .map_err(|e| LogError::FormatError(e.to_string()))?;
+        let json =
+            serde_json::to_string(&entry).map_err(|e| LogError::FormatError(e.to_string()))?;
 
         use std::io::Write;
         let mut file = std::fs::OpenOptions::new()
@@ -235,7 +235,8 @@ impl AuditLogger {
 
     /// Get entries by event type
     pub fn entries_by_type(&self, event_type: &str) -> Vec<AuditEntry> {
-        self.entries.lock()
+        self.entries
+            .lock()
             .iter()
             .filter(|e| e.event_type == event_type)
             .cloned()
@@ -259,15 +260,13 @@ mod tests {
 
     #[test]
     fn test_audit_entry_with_resource() {
-        let entry = AuditEntry::new("test", "user1", "action1")
-            .with_resource("resource1");
+        let entry = AuditEntry::new("test", "user1", "action1").with_resource("resource1");
         assert_eq!(entry.resource, Some("resource1".to_string()));
     }
 
     #[test]
     fn test_audit_entry_with_metadata() {
-        let entry = AuditEntry::new("test", "user1", "action1")
-            .with_metadata("key1", "value1");
+        let entry = AuditEntry::new("test", "user1", "action1").with_metadata("key1", "value1");
         assert_eq!(entry.metadata.get("key1"), Some(&"value1".to_string()));
     }
 
@@ -314,9 +313,15 @@ mod tests {
 
         let logger = AuditLogger::new(&audit_path).unwrap();
 
-        logger.log(AuditEntry::new("test", "user1", "action1")).unwrap();
-        logger.log(AuditEntry::new("test", "user2", "action2")).unwrap();
-        logger.log(AuditEntry::new("test", "user3", "action3")).unwrap();
+        logger
+            .log(AuditEntry::new("test", "user1", "action1"))
+            .unwrap();
+        logger
+            .log(AuditEntry::new("test", "user2", "action2"))
+            .unwrap();
+        logger
+            .log(AuditEntry::new("test", "user3", "action3"))
+            .unwrap();
 
         assert_eq!(logger.entries().len(), 3);
 
@@ -333,8 +338,12 @@ mod tests {
 
         let logger = AuditLogger::new(&audit_path).unwrap();
 
-        logger.log(AuditEntry::new("test", "user1", "action1")).unwrap();
-        logger.log(AuditEntry::new("test", "user2", "action2")).unwrap();
+        logger
+            .log(AuditEntry::new("test", "user1", "action1"))
+            .unwrap();
+        logger
+            .log(AuditEntry::new("test", "user2", "action2"))
+            .unwrap();
 
         assert!(logger.verify_chain().unwrap());
     }
@@ -346,9 +355,15 @@ mod tests {
 
         let logger = AuditLogger::new(&audit_path).unwrap();
 
-        logger.log(AuditEntry::new("login", "user1", "login")).unwrap();
-        logger.log(AuditEntry::new("logout", "user1", "logout")).unwrap();
-        logger.log(AuditEntry::new("login", "user2", "login")).unwrap();
+        logger
+            .log(AuditEntry::new("login", "user1", "login"))
+            .unwrap();
+        logger
+            .log(AuditEntry::new("logout", "user1", "logout"))
+            .unwrap();
+        logger
+            .log(AuditEntry::new("login", "user2", "login"))
+            .unwrap();
 
         let login_entries = logger.entries_by_type("login");
         assert_eq!(login_entries.len(), 2);
@@ -10,24 +10,27 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use thiserror::Error;
-use tracing::{Level, Subscriber};
+use tracing::level_filters::LevelFilter;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{
-    fmt,
-    layer::SubscriberExt,
-    util::SubscriberInitExt,
-    EnvFilter,
-    Layer,
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry,
 };
 
 pub mod appender;
 pub mod audit;
 pub mod filter;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod redact;
 
 pub use appender::{FileAppender, RotatingFileAppender};
 pub use audit::AuditLogger;
 pub use filter::DynamicFilter;
+pub use redact::RedactingMakeWriter;
 
 /// Logging error types
 #[derive(Error, Debug)]
@@ -47,6 +50,35 @@ pub enum LogError {
 
 pub type LogResult<T> = Result<T, LogError>;
 
+/// Filter of the installed logger, with the config it was built from
+static FILTER: OnceLock<(reload::Handle<EnvFilter, Registry>, Arc<LogConfig>)> = OnceLock::new();
+
+/// Change the level of the logger installed by [`LoggerBuilder::init`]
+///
+/// Module levels from the builder still apply; the new level replaces the
+/// builder's one and any `RUST_LOG` filter.
+pub fn set_level(level: &str) -> LogResult<()> {
+    let (handle, config) = FILTER
+        .get()
+        .ok_or_else(|| LogError::ConfigError("No logger is installed".to_string()))?;
+    let filter = EnvFilter::try_new(level)
+        .map_err(|e| LogError::ConfigError(format!("Invalid log level: {}", e)))?;
+    let filter = filter_for(filter, &config.module_levels)?;
+    handle
+        .reload(filter)
+        .map_err(|e| LogError::ConfigError(e.to_string()))?;
+    // Records from the `log` facade are dropped above its own max level
+    log::set_max_level(match LevelFilter::current() {
+        LevelFilter::OFF => log::LevelFilter::Off,
+        LevelFilter::ERROR => log::LevelFilter::Error,
+        LevelFilter::WARN => log::LevelFilter::Warn,
+        LevelFilter::INFO => log::LevelFilter::Info,
+        LevelFilter::DEBUG => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    });
+    Ok(())
+}
+
 /// Log configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogConfig {
@@ -67,6 +99,10 @@ pub struct LogConfig {
 
     /// Per-module log levels
     pub module_levels: std::collections::HashMap<String, String>,
+
+    /// OTLP collector to export spans to (requires the `otlp` feature)
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
 }
 
 /// Log output format
@@ -103,6 +139,7 @@ impl Default for LogConfig {
             file: None,
             audit: false,
             module_levels: std::collections::HashMap::new(),
+            otlp_endpoint: None,
         }
     }
 }
@@ -152,60 +189,119 @@ impl LoggerBuilder {
 
     /// Add module-specific log level
     pub fn module_level(mut self, module: impl Into<String>, level: impl Into<String>) -> Self {
-        self.config.module_levels.insert(module.into(), level.into());
+        self.config
+            .module_levels
+            .insert(module.into(), level.into());
+        self
+    }
+
+    /// Export spans to an OpenTelemetry collector at `endpoint`
+    #[cfg(feature = "otlp")]
+    pub fn otlp(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.otlp_endpoint = Some(endpoint.into());
         self
     }
 
     /// Build and initialize logger
+    ///
+    /// Console output goes to stderr so it never mixes with command output.
+    /// Records from the `log` facade are forwarded to the same subscriber.
     pub fn init(self) -> LogResult<Logger> {
-        let filter = self.build_filter()?;
-
-        let subscriber = tracing_subscriber::registry().with(filter);
-
-        // Add console layer if enabled
-        let subscriber = if self.config.console {
-            let console_layer = match self.config.format {
-                LogFormat::Json => fmt::layer()
-                    .json()
-                    .with_current_span(true)
-                    .with_span_list(true)
-                    .boxed(),
-                LogFormat::Pretty => fmt::layer()
-                    .pretty()
-                    .with_line_number(true)
-                    .with_thread_ids(true)
-                    .boxed(),
-                LogFormat::Compact => fmt::layer()
-                    .compact()
-                    .boxed(),
-            };
-            subscriber.with(console_layer)
-        } else {
-            subscriber.with(None::<fmt::Layer<_>>)
+        self.init_with_writer(std::io::stderr)
+    }
+
+    /// Build and initialize logger, writing console output to `writer`
+    fn init_with_writer<W>(self, writer: W) -> LogResult<Logger>
+    where
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        let (filter, handle) = reload::Layer::new(self.build_filter()?);
+
+        let console = self.config.console.then(|| self.console_layer(writer));
+
+        let subscriber = tracing_subscriber::registry().with(filter).with(console);
+
+        #[cfg(feature = "otlp")]
+        let (subscriber, tracer_provider) = {
+            use opentelemetry::trace::TracerProvider as _;
+
+            let tracer_provider = self
+                .config
+                .otlp_endpoint
+                .as_deref()
+                .map(otlp::tracer_provider)
+                .transpose()?;
+            let layer = tracer_provider.as_ref().map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer(otlp::SERVICE_NAME))
+            });
+            (subscriber.with(layer), tracer_provider)
         };
+        #[cfg(not(feature = "otlp"))]
+        if self.config.otlp_endpoint.is_some() {
+            return Err(LogError::ConfigError(
+                "OTLP export requires building with the `otlp` feature".to_string(),
+            ));
+        }
 
-        subscriber.init();
+        subscriber
+            .try_init()
+            .map_err(|e| LogError::ConfigError(e.to_string()))?;
 
+        let config = Arc::new(self.config);
+        let _ = FILTER.set((handle, config.clone()));
         Ok(Logger {
-            config: Arc::new(self.config),
+            config,
+            #[cfg(feature = "otlp")]
+            tracer_provider,
         })
     }
 
+    /// Formatting layer for the configured format, redacting secrets
+    /// before anything reaches `writer`
+    fn console_layer<S, W>(&self, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        let writer = RedactingMakeWriter::new(writer);
+        match self.config.format {
+            LogFormat::Json => fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(writer)
+                .boxed(),
+            LogFormat::Pretty => fmt::layer()
+                .pretty()
+                .with_line_number(true)
+                .with_thread_ids(true)
+                .with_writer(writer)
+                .boxed(),
+            LogFormat::Compact => fmt::layer().compact().with_writer(writer).boxed(),
+        }
+    }
+
     fn build_filter(&self) -> LogResult<EnvFilter> {
-        let mut filter = EnvFilter::try_from_default_env()
+        let filter = EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new(&self.config.level));
+        filter_for(filter, &self.config.module_levels)
+    }
+}
 
-        // Add module-specific filters
-        for (module, level) in &self.config.module_levels {
-            let directive = format!("{}={}", module, level);
-            filter = filter.add_directive(
-                directive.parse()
-                    .map_err(|e| LogError::ConfigError(format!("Invalid filter directive: {}", e)))?
-            );
-        }
-
-        Ok(filter)
+/// `filter` with the module-specific levels added
+fn filter_for(
+    mut filter: EnvFilter,
+    module_levels: &std::collections::HashMap<String, String>,
+) -> LogResult<EnvFilter> {
+    for (module, level) in module_levels {
+        let directive = format!("{}={}", module, level);
+        filter = filter.add_directive(
+            directive
+                .parse()
+                .map_err(|e| LogError::ConfigError(format!("Invalid filter directive: {}", e)))?,
+        );
     }
+    Ok(filter)
 }
 
 impl Default for LoggerBuilder {
@@ -217,6 +313,8 @@ impl Default for LoggerBuilder {
 /// Logger instance
 pub struct Logger {
     config: Arc<LogConfig>,
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
 }
 
 impl Logger {
@@ -231,6 +329,16 @@ impl Logger {
     }
 }
 
+#[cfg(feature = "otlp")]
+impl Drop for Logger {
+    fn drop(&mut self) {
+        // Flush batched spans; export failures must not abort shutdown
+        if let Some(provider) = self.tracer_provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
 impl Default for Logger {
     fn default() -> Self {
         Self::new().expect("Failed to initialize default logger")
@@ -299,7 +407,10 @@ mod tests {
             .module_level("ai_cli_engine", "debug");
 
         assert_eq!(builder.config.module_levels.len(), 2);
-        assert_eq!(builder.config.module_levels.get("ai_cli_core"), Some(&"trace".to_string()));
+        assert_eq!(
+            builder.config.module_levels.get("ai_cli_core"),
+            Some(&"trace".to_string())
+        );
     }
 
     #[test]
@@ -311,15 +422,13 @@ mod tests {
 
     #[test]
     fn test_log_event_with_target() {
-        let event = LogEvent::new("info", "Test")
-            .with_target("test_module");
+        let event = LogEvent::new("info", "Test").with_target("test_module");
         assert_eq!(event.target, "test_module");
     }
 
     #[test]
     fn test_log_event_with_fields() {
-        let event = LogEvent::new("info", "Test")
-            .with_field("key", serde_json::json!("value"));
+        let event = LogEvent::new("info", "Test").with_field("key", serde_json::json!("value"));
 
         assert_eq!(event.fields.len(), 1);
         assert_eq!(event.fields.get("key"), Some(&serde_json::json!("value")));
@@ -345,6 +454,78 @@ mod tests {
         assert!(json.contains("app.log"));
     }
 
+    // Installs the global subscriber, which the OTLP test below also needs
+    #[cfg(not(feature = "otlp"))]
+    #[test]
+    fn test_init_redacts_tracing_and_log_records() {
+        let buffer = redact::BufferWriter::new();
+        let _logger = LoggerBuilder::new()
+            .level("info")
+            .format(LogFormat::Compact)
+            .init_with_writer(buffer.clone())
+            .unwrap();
+
+        tracing::warn!("retrying with api_key=sk-tracing123");
+        log::warn!("retrying with token=sk-log456");
+        log::debug!("filtered out");
+
+        let output = buffer.contents();
+        assert!(output.contains("retrying with api_key="), "{}", output);
+        assert!(output.contains("retrying with token="), "{}", output);
+        assert!(!output.contains("sk-tracing123"), "{}", output);
+        assert!(!output.contains("sk-log456"), "{}", output);
+        assert!(!output.contains("filtered out"), "{}", output);
+
+        set_level("debug").unwrap();
+        tracing::debug!("tracing after reload");
+        log::debug!("log after reload");
+        set_level("warn").unwrap();
+        tracing::info!("quiet again");
+        let output = buffer.contents();
+        assert!(output.contains("tracing after reload"), "{}", output);
+        assert!(output.contains("log after reload"), "{}", output);
+        assert!(!output.contains("quiet again"), "{}", output);
+        assert!(set_level("ai_cli=loud").is_err());
+
+        assert!(LoggerBuilder::new().init().is_err());
+    }
+
+    #[cfg(feature = "otlp")]
+    #[test]
+    fn test_otlp_layer_with_unreachable_endpoint() {
+        let logger = LoggerBuilder::new()
+            .console(false)
+            .otlp("http://127.0.0.1:9")
+            .init()
+            .unwrap();
+        assert!(logger.tracer_provider.is_some());
+
+        tracing::info_span!("otlp_test").in_scope(|| tracing::info!("exported"));
+        drop(logger);
+    }
+
+    #[test]
+    fn test_console_output_is_redacted() {
+        for format in [LogFormat::Json, LogFormat::Pretty, LogFormat::Compact] {
+            let buffer = redact::BufferWriter::new();
+            let layer = LoggerBuilder::new()
+                .format(format)
+                .console_layer(buffer.clone());
+            let subscriber = tracing_subscriber::registry().with(layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!(api_key = "sk-test1234567890", "calling provider");
+                tracing::warn!("header was Bearer abc.def.ghi");
+            });
+
+            let output = buffer.contents();
+            assert!(output.contains("calling provider"), "{}", output);
+            assert!(output.contains(redact::REDACTED), "{}", output);
+            assert!(!output.contains("sk-test1234567890"), "{}", output);
+            assert!(!output.contains("abc.def.ghi"), "{}", output);
+        }
+    }
+
     #[test]
     fn test_log_config_serialization() {
         let config = LogConfig::default();
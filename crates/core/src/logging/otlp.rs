@@ -0,0 +1,41 @@
+//! OpenTelemetry span export over OTLP/HTTP
+
+use super::{LogError, LogResult};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+
+/// Service name reported in the `service.name` resource attribute
+pub const SERVICE_NAME: &str = "ai-cli";
+
+/// Build a tracer provider exporting batched spans to a collector
+///
+/// `endpoint` is the collector base URL, e.g. `http://localhost:4318`; the
+/// standard `/v1/traces` path is appended unless already present. The
+/// collector is not contacted until spans are exported, so an unreachable
+/// endpoint only surfaces as failed exports.
+pub fn tracer_provider(endpoint: &str) -> LogResult<SdkTracerProvider> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let url = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(url)
+        .build()
+        .map_err(|e| LogError::ConfigError(format!("Invalid OTLP exporter: {}", e)))?;
+
+    let resource = Resource::builder()
+        .with_service_name(SERVICE_NAME)
+        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+        .build();
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
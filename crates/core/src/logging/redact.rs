@@ -0,0 +1,171 @@
+//! Secret redaction for log output
+//!
+//! Formatted log lines pass through [`RedactingMakeWriter`] before reaching
+//! their destination, so secrets are scrubbed no matter which field or
+//! `Debug` impl they leaked through.
+
+use regex::Regex;
+use std::io::{self, Write};
+use std::sync::{Arc, LazyLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Replacement text for redacted secrets
+pub const REDACTED: &str = "***REDACTED***";
+
+/// Patterns whose whole match is a secret
+static SECRET_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // OpenAI/Anthropic style API keys
+        r"\bsk-[A-Za-z0-9_-]+",
+        // Google API keys
+        r"\bAIza[A-Za-z0-9_-]{20,}",
+        // Authorization header values
+        r"Bearer\s+\S+",
+    ]
+    .iter()
+    .map(|p| Regex::new(p).expect("valid redaction pattern"))
+    .collect()
+});
+
+/// `key=value` / `"key": "value"` pairs whose key names a credential
+static CREDENTIAL_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b((?:api[_-]?key|token|secret|password|authorization|credential)"?\s*[:=]\s*"?)[^\s",}]+"#,
+    )
+    .expect("valid redaction pattern")
+});
+
+/// Replace every secret in `text` with [`REDACTED`]
+pub fn redact(text: &str) -> String {
+    let mut output = text.to_string();
+    for pattern in SECRET_PATTERNS.iter() {
+        output = pattern.replace_all(&output, REDACTED).into_owned();
+    }
+    CREDENTIAL_FIELD
+        .replace_all(&output, format!("${{1}}{}", REDACTED))
+        .into_owned()
+}
+
+/// [`MakeWriter`] wrapper that redacts everything written through it
+#[derive(Debug, Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/// Writer that redacts each buffer before passing it on
+///
+/// The fmt layer writes each formatted event in a single call, so a secret
+/// is never split across writes.
+pub struct RedactingWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// In-memory [`MakeWriter`], useful for capturing log output
+#[derive(Debug, Clone, Default)]
+pub struct BufferWriter {
+    buffer: Arc<parking_lot::Mutex<Vec<u8>>>,
+}
+
+impl BufferWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far, lossily decoded as UTF-8
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock()).into_owned()
+    }
+}
+
+impl Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for BufferWriter {
+    type Writer = BufferWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_patterns() {
+        assert_eq!(
+            redact("using key sk-abc123XYZ for openai"),
+            format!("using key {} for openai", REDACTED)
+        );
+        assert_eq!(
+            redact("Authorization: Bearer eyJhbGciOi"),
+            format!("Authorization: {}", REDACTED)
+        );
+        assert_eq!(
+            redact(r#"ProviderConfig { api_key: "plainsecret", model: "gpt-4" }"#),
+            format!(
+                r#"ProviderConfig {{ api_key: "{}", model: "gpt-4" }}"#,
+                REDACTED
+            )
+        );
+        assert_eq!(
+            redact("password=hunter2 ok"),
+            format!("password={} ok", REDACTED)
+        );
+        assert_eq!(redact("max_token=100"), "max_token=100");
+        assert_eq!(redact("task-runner"), "task-runner");
+        assert_eq!(redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn test_redacting_writer() {
+        let buffer = BufferWriter::new();
+        let make_writer = RedactingMakeWriter::new(buffer.clone());
+
+        make_writer
+            .make_writer()
+            .write_all(b"token=abc.def sk-live123")
+            .unwrap();
+        assert_eq!(
+            buffer.contents(),
+            format!("token={} {}", REDACTED, REDACTED)
+        );
+    }
+}
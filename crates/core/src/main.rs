@@ -3,46 +3,202 @@
 //! This is the main entry point for the Rust-based core components
 //! of the AIrchitect CLI system.
 
-use ai_cli_core::{cli::Cli, AICli, AppConfig};
-use clap::Parser;
+use ai_cli_core::cli::middleware::{
+    LoggingMiddleware, MetricsMiddleware, ReadOnlyMiddleware, TimeoutMiddleware,
+    ValidationMiddleware,
+};
+use ai_cli_core::cli::output::{FormatterRegistry, OutputFormatter};
+use ai_cli_core::cli::router::{truncate_output, CommandResult};
+use ai_cli_core::cli::{handlers, Cli, CliResult, CommandContext, Commands, MiddlewareChain};
+use ai_cli_core::dotenv::{self, DEFAULT_ENV_FILE};
+use ai_cli_core::error::{exit_code, AICliError, ErrorReport};
+use ai_cli_core::logging::{LogFormat, Logger, LoggerBuilder};
+use ai_cli_core::metrics::{self, Metrics};
+use ai_cli_core::{AICli, AppConfig};
+use std::path::Path;
 use std::process;
+use std::sync::{Arc, Mutex};
+
+/// Logger for the whole process, dropped by [`exit`] so spans are exported
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
 
 /// Main entry point for the AIrchitect CLI
 #[tokio::main]
 async fn main() {
     // Parse command line arguments
-    let cli = Cli::parse();
+    let mut cli = Cli::parse_args();
+
+    // Load the dotenv file before anything reads the environment, then
+    // parse again so flags backed by variables see its values
+    if load_env_file(&cli) {
+        cli = Cli::parse_args();
+    }
+
+    // Set up logging based on verbose level, with `RUST_LOG` and
+    // `OTEL_EXPORTER_OTLP_ENDPOINT` from the dotenv file in effect
+    setup_logging(&cli);
+    if cli.version {
+        cli.command = Some(Commands::Version);
+    }
 
-    // Set up logging based on verbose level
-    setup_logging(cli.verbose);
+    // Dispatch subcommands through the router
+    let json_errors = cli.json_errors();
+    if cli.command.is_some() {
+        let max_output_bytes = max_output_bytes(&cli);
+        let formatter = match FormatterRegistry::builtin().get(cli.format.name()) {
+            Ok(formatter) => formatter,
+            Err(e) => report_error(&ErrorReport::from(&e), json_errors),
+        };
+        match dispatch(cli).await {
+            Ok(result) => {
+                print_result(&result, formatter.as_ref(), json_errors, max_output_bytes);
+                exit(result.exit_code);
+            }
+            Err(e) => report_error(&ErrorReport::from(&e), json_errors),
+        }
+    }
 
     // Create default configuration
     let config = AppConfig::default();
 
     // Create the AIrchitect CLI application
-    let app = AICli::new(config);
+    let app = AICli::new(config).with_quiet(cli.quiet);
 
     // Run the application
     match app.run().await {
         Ok(()) => {
-            println!("AIrchitect CLI completed successfully");
-            process::exit(0);
+            if let Some(banner) = app.completion_banner() {
+                println!("{}", banner);
+            }
+            exit(exit_code::SUCCESS);
         }
         Err(e) => {
-            eprintln!("Error: {}", e);
-            process::exit(1);
+            let report = e.downcast_ref::<AICliError>().map_or_else(
+                || ErrorReport::new(exit_code::GENERAL, e.to_string()),
+                ErrorReport::from,
+            );
+            report_error(&report, json_errors);
+        }
+    }
+}
+
+/// Run a parsed command through the middleware chain and router
+async fn dispatch(cli: Cli) -> CliResult<CommandResult> {
+    let metrics = Arc::new(Metrics::new());
+    let router = handlers::default_router(&cli, metrics.clone())?;
+    if let Some(port) = cli.metrics_port {
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(metrics, addr).await {
+                log::warn!("Cannot serve metrics on {}: {}", addr, e);
+            }
+        });
+    }
+    let chain = MiddlewareChain::new()
+        .add(ValidationMiddleware)
+        .add(ReadOnlyMiddleware)
+        .add(TimeoutMiddleware)
+        .add(MetricsMiddleware::with_registry(metrics))
+        .add(LoggingMiddleware);
+
+    let mut ctx = CommandContext::new(cli);
+    chain.execute(&mut ctx, &router).await
+}
+
+/// Load `--env-file`, or `./.env` when it exists, returning whether any
+/// variable was set
+///
+/// A missing or unreadable `--env-file` is an error; a broken `./.env` is
+/// only a warning, printed directly since logging is not set up yet.
+fn load_env_file(cli: &Cli) -> bool {
+    let path = Path::new(cli.env_file.as_deref().unwrap_or(DEFAULT_ENV_FILE));
+    if cli.env_file.is_none() && !path.exists() {
+        return false;
+    }
+    match dotenv::load_env_file(path) {
+        Ok(loaded) => !loaded.is_empty(),
+        Err(e) if cli.env_file.is_some() => report_error(
+            &ErrorReport::new(exit_code::CONFIG, format!("{}: {}", path.display(), e)),
+            cli.json_errors(),
+        ),
+        Err(e) => {
+            eprintln!("Warning: ignoring {}: {}", path.display(), e);
+            false
         }
     }
 }
 
-/// Set up logging based on verbose level
-fn setup_logging(verbose_level: u8) {
-    match verbose_level {
-        0 => std::env::set_var("RUST_LOG", "info"),
-        1 => std::env::set_var("RUST_LOG", "debug"),
-        _ => std::env::set_var("RUST_LOG", "trace"),
+/// Write an error to stderr and exit with its code
+fn report_error(report: &ErrorReport, json: bool) -> ! {
+    let _ = report.write_to(&mut std::io::stderr(), json);
+    exit(report.code);
+}
+
+/// Output limit from `--max-output-bytes`, or else the configuration
+fn max_output_bytes(cli: &Cli) -> Option<usize> {
+    cli.max_output_bytes.or_else(|| {
+        AppConfig::load_layered(&handlers::config_layers(cli))
+            .ok()
+            .and_then(|config| config.max_output_bytes)
+    })
+}
+
+/// Print a command result to stdout with `formatter` (or its message to
+/// stderr for failures), cutting what goes to stdout after `max_bytes`
+fn print_result(
+    result: &CommandResult,
+    formatter: &dyn OutputFormatter,
+    json_errors: bool,
+    max_bytes: Option<usize>,
+) {
+    let mut shown = result.clone();
+    if !result.success {
+        if let Some(message) = shown.message.take() {
+            if json_errors {
+                let report = ErrorReport::new(result.exit_code, message.as_str());
+                let _ = report.write_to(&mut std::io::stderr(), true);
+            } else {
+                eprintln!("{}", message);
+            }
+        }
     }
 
-    // Initialize the logger
-    env_logger::init();
+    let output = formatter.format(&shown);
+    if output.is_empty() {
+        return;
+    }
+    match max_bytes {
+        Some(max) => println!("{}", truncate_output(&output, max)),
+        None => println!("{}", output),
+    }
+}
+
+/// Set up logging based on verbose level; quiet wins over verbose
+///
+/// `RUST_LOG`, when set, overrides the level. The logger is kept in
+/// [`LOGGER`] until [`exit`] flushes it.
+fn setup_logging(cli: &Cli) {
+    let builder = LoggerBuilder::new()
+        .level(cli.log_level().to_string().to_lowercase())
+        .format(LogFormat::Compact);
+    #[cfg(feature = "otlp")]
+    let builder = match &cli.otlp_endpoint {
+        Some(endpoint) => builder.otlp(endpoint),
+        None => builder,
+    };
+    #[cfg(not(feature = "otlp"))]
+    if cli.otlp_endpoint.is_some() {
+        eprintln!("Warning: ignoring --otlp-endpoint; this build has no OTLP support");
+    }
+    match builder.init() {
+        Ok(logger) => *LOGGER.lock().unwrap_or_else(|e| e.into_inner()) = Some(logger),
+        Err(e) => eprintln!("Warning: logging is disabled: {}", e),
+    }
+}
+
+/// Flush the logger's pending spans, then exit with `code`
+fn exit(code: i32) -> ! {
+    drop(LOGGER.lock().unwrap_or_else(|e| e.into_inner()).take());
+    process::exit(code);
 }
@@ -0,0 +1,299 @@
+//! In-process metrics registry with Prometheus text exposition
+//!
+//! Counters and histograms are keyed by a single label value (command name,
+//! provider name or exit code), which is all the CLI needs and keeps the
+//! rendering trivial.
+
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Histogram bucket upper bounds, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket; the last slot is `+Inf`
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        let slot = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[slot] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    commands: BTreeMap<String, u64>,
+    command_latency: BTreeMap<String, Histogram>,
+    provider_requests: BTreeMap<String, u64>,
+    provider_latency: BTreeMap<String, Histogram>,
+    errors: BTreeMap<String, u64>,
+}
+
+/// Registry of CLI metrics
+#[derive(Debug, Default)]
+pub struct Metrics {
+    state: Mutex<MetricsState>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a command invocation
+    pub fn record_command(&self, command: &str) {
+        *self
+            .state
+            .lock()
+            .commands
+            .entry(command.to_string())
+            .or_default() += 1;
+    }
+
+    /// Record how long a command took
+    pub fn observe_command_latency(&self, command: &str, elapsed: Duration) {
+        self.state
+            .lock()
+            .command_latency
+            .entry(command.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Count a provider request and record its latency
+    pub fn record_provider_request(&self, provider: &str, elapsed: Duration) {
+        let mut state = self.state.lock();
+        *state
+            .provider_requests
+            .entry(provider.to_string())
+            .or_default() += 1;
+        state
+            .provider_latency
+            .entry(provider.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Count a failed command by exit code
+    pub fn record_error(&self, code: i32) {
+        *self
+            .state
+            .lock()
+            .errors
+            .entry(code.to_string())
+            .or_default() += 1;
+    }
+
+    /// Total commands recorded across all names
+    pub fn command_total(&self) -> u64 {
+        self.state.lock().commands.values().sum()
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let state = self.state.lock();
+        let mut out = String::new();
+
+        render_counter(
+            &mut out,
+            "ai_cli_commands_total",
+            "Commands executed",
+            "command",
+            &state.commands,
+        );
+        render_histogram(
+            &mut out,
+            "ai_cli_command_duration_seconds",
+            "Command execution time",
+            "command",
+            &state.command_latency,
+        );
+        render_counter(
+            &mut out,
+            "ai_cli_provider_requests_total",
+            "Requests sent to AI providers",
+            "provider",
+            &state.provider_requests,
+        );
+        render_histogram(
+            &mut out,
+            "ai_cli_provider_request_duration_seconds",
+            "AI provider request latency",
+            "provider",
+            &state.provider_latency,
+        );
+        render_counter(
+            &mut out,
+            "ai_cli_errors_total",
+            "Failed commands by exit code",
+            "code",
+            &state.errors,
+        );
+
+        out
+    }
+}
+
+fn render_counter(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &BTreeMap<String, u64>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (value, count) in values {
+        let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, escape(value), count);
+    }
+}
+
+fn render_histogram(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &BTreeMap<String, Histogram>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for (value, histogram) in values {
+        let value = escape(value);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}",
+                name, label, value, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}",
+            name, label, value, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{{{}=\"{}\"}} {}",
+            name, label, value, histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{{}=\"{}\"}} {}",
+            name, label, value, histogram.count
+        );
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve `/metrics` on `addr` until the task is dropped
+///
+/// Every request gets the current rendering regardless of path or method,
+/// which is all a Prometheus scraper needs.
+pub async fn serve(metrics: Arc<Metrics>, addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            // Drain the request head; its contents don't matter
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+
+            let body = metrics.render_prometheus();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_contains_metric_names() {
+        let metrics = Metrics::new();
+        metrics.record_command("chat");
+        metrics.record_command("chat");
+        metrics.observe_command_latency("chat", Duration::from_millis(30));
+        metrics.record_provider_request("openai", Duration::from_millis(700));
+        metrics.record_error(2);
+
+        let output = metrics.render_prometheus();
+        assert!(output.contains("# TYPE ai_cli_commands_total counter"));
+        assert!(output.contains("ai_cli_commands_total{command=\"chat\"} 2"));
+        assert!(output.contains("# TYPE ai_cli_command_duration_seconds histogram"));
+        assert!(output
+            .contains("ai_cli_command_duration_seconds_bucket{command=\"chat\",le=\"0.025\"} 0"));
+        assert!(output
+            .contains("ai_cli_command_duration_seconds_bucket{command=\"chat\",le=\"0.05\"} 1"));
+        assert!(output.contains("ai_cli_command_duration_seconds_count{command=\"chat\"} 1"));
+        assert!(output.contains("ai_cli_provider_requests_total{provider=\"openai\"} 1"));
+        assert!(output.contains(
+            "ai_cli_provider_request_duration_seconds_bucket{provider=\"openai\",le=\"+Inf\"} 1"
+        ));
+        assert!(output.contains("ai_cli_errors_total{code=\"2\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_responds_with_metrics() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.record_command("config");
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let server = tokio::spawn(serve(metrics, addr));
+
+        let mut response = String::new();
+        for _ in 0..50 {
+            if let Ok(mut stream) = tokio::net::TcpStream::connect(addr).await {
+                stream
+                    .write_all(b"GET /metrics HTTP/1.1\r\n\r\n")
+                    .await
+                    .unwrap();
+                stream.read_to_string(&mut response).await.unwrap();
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("ai_cli_commands_total{command=\"config\"} 1"));
+    }
+}
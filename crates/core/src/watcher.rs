@@ -0,0 +1,312 @@
+//! Hot reload of the configuration file
+//!
+//! [`ConfigWatcher`] watches the config file and, once writes settle, reloads
+//! it and applies the settings that are safe to change in a running session:
+//! `debug`, `default_provider` and each provider's `default_model`. Other
+//! changes (adding or removing providers, toggling them, swapping API keys)
+//! are logged once and ignored until the next restart.
+//!
+//! Only `chat` watches the config: it follows `debug` with the log level and,
+//! unless flags pinned them, `default_provider` and `default_model` with the
+//! session's provider and model. Other commands and the TUI read the config
+//! once at start.
+
+use crate::error::AICliError;
+use crate::{AICliResult, AppConfig};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Quiet period after the last file event before reloading
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(250);
+
+type ReloadCallback = Box<dyn Fn(&AppConfig) + Send + Sync>;
+
+/// Watches a config file and keeps an in-memory [`AppConfig`] up to date
+pub struct ConfigWatcher {
+    current: Arc<RwLock<AppConfig>>,
+    callbacks: Arc<RwLock<Vec<ReloadCallback>>>,
+    // Dropping the watcher closes the event channel, which ends the reload thread
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// Start watching `path`, beginning from `initial`
+    pub fn new(path: impl Into<PathBuf>, initial: AppConfig) -> AICliResult<Self> {
+        Self::with_debounce(path, initial, DEFAULT_DEBOUNCE)
+    }
+
+    /// Start watching with a custom debounce interval
+    pub fn with_debounce(
+        path: impl Into<PathBuf>,
+        initial: AppConfig,
+        debounce: Duration,
+    ) -> AICliResult<Self> {
+        let path = path.into();
+        let current = Arc::new(RwLock::new(initial));
+        let callbacks: Arc<RwLock<Vec<ReloadCallback>>> = Arc::new(RwLock::new(Vec::new()));
+
+        let (tx, rx) = mpsc::channel();
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    let touches_file = event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                    if touches_file && !event.kind.is_access() {
+                        let _ = tx.send(());
+                    }
+                }
+            })
+            .map_err(|e| AICliError::ConfigError(format!("Cannot watch config: {}", e)))?;
+
+        // Watch the directory: editors often replace the file rather than write it
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                AICliError::ConfigError(format!("Cannot watch {}: {}", dir.display(), e))
+            })?;
+
+        let thread_current = current.clone();
+        let thread_callbacks = callbacks.clone();
+        // `initial` may carry overrides from other layers; only edits made
+        // after this point count as changes
+        let mut last_loaded =
+            AppConfig::load_from_file(&path).unwrap_or_else(|_| current.read().clone());
+        std::thread::spawn(move || {
+            while rx.recv().is_ok() {
+                // Debounce: wait until events stop arriving
+                loop {
+                    match rx.recv_timeout(debounce) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                if let Some(loaded) =
+                    reload(&path, &last_loaded, &thread_current, &thread_callbacks)
+                {
+                    last_loaded = loaded;
+                }
+            }
+        });
+
+        Ok(Self {
+            current,
+            callbacks,
+            _watcher: watcher,
+        })
+    }
+
+    /// Register a callback run with the effective config after each reload
+    pub fn on_reload(&self, callback: impl Fn(&AppConfig) + Send + Sync + 'static) {
+        self.callbacks.write().push(Box::new(callback));
+    }
+
+    /// Get a snapshot of the current configuration
+    pub fn current(&self) -> AppConfig {
+        self.current.read().clone()
+    }
+}
+
+/// Reload `path`, returning the loaded file unless it is invalid
+fn reload(
+    path: &Path,
+    last_loaded: &AppConfig,
+    current: &RwLock<AppConfig>,
+    callbacks: &RwLock<Vec<ReloadCallback>>,
+) -> Option<AppConfig> {
+    let loaded = match AppConfig::load_from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid config {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let (effective, rejected) = apply_file_changes(&current.read(), last_loaded, &loaded);
+    for change in &rejected {
+        tracing::warn!("Ignoring change to {}; restart to apply it", change);
+    }
+    *current.write() = effective.clone();
+    tracing::info!("Reloaded configuration from {}", path.display());
+
+    for callback in callbacks.read().iter() {
+        callback(&effective);
+    }
+    Some(loaded)
+}
+
+/// Merge `loaded` into `current` like [`apply_safe_changes`], reporting only
+/// the rejected changes made since `last_loaded`, the file as it was last
+/// loaded
+///
+/// A rejected change stays in the file, so without this it would be
+/// reported again on every later reload.
+pub fn apply_file_changes(
+    current: &AppConfig,
+    last_loaded: &AppConfig,
+    loaded: &AppConfig,
+) -> (AppConfig, Vec<String>) {
+    let (effective, rejected) = apply_safe_changes(current, loaded);
+    let (_, known) = apply_safe_changes(current, last_loaded);
+    let (_, edited) = apply_safe_changes(last_loaded, loaded);
+    let rejected = rejected
+        .into_iter()
+        .filter(|change| !known.contains(change) || edited.contains(change))
+        .collect();
+    (effective, rejected)
+}
+
+/// Merge the safe-to-change settings of `loaded` into `current`
+///
+/// Returns the merged config and the keys of changes that were rejected.
+pub fn apply_safe_changes(current: &AppConfig, loaded: &AppConfig) -> (AppConfig, Vec<String>) {
+    let mut effective = current.clone();
+    let mut rejected = Vec::new();
+
+    effective.debug = loaded.debug;
+
+    for provider in &mut effective.providers {
+        match loaded.providers.iter().find(|p| p.name == provider.name) {
+            Some(new) => {
+                provider.default_model = new.default_model.clone();
+                if new.enabled != provider.enabled {
+                    rejected.push(format!("providers[{}].enabled", provider.name));
+                }
+                if new.api_key != provider.api_key {
+                    rejected.push(format!("providers[{}].api_key", provider.name));
+                }
+            }
+            None => rejected.push(format!("providers[{}] (removed)", provider.name)),
+        }
+    }
+    for new in &loaded.providers {
+        if !current.providers.iter().any(|p| p.name == new.name) {
+            rejected.push(format!("providers[{}] (added)", new.name));
+        }
+    }
+
+    if loaded.default_provider != current.default_provider {
+        if effective
+            .providers
+            .iter()
+            .any(|p| p.name == loaded.default_provider)
+        {
+            effective.default_provider = loaded.default_provider.clone();
+        } else {
+            rejected.push(format!(
+                "default_provider ('{}' is not configured)",
+                loaded.default_provider
+            ));
+        }
+    }
+
+    (effective, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_security::secret::Secret;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_safe_changes() {
+        let current = AppConfig::default();
+        let mut loaded = AppConfig {
+            debug: true,
+            default_provider: "anthropic".to_string(),
+            ..AppConfig::default()
+        };
+        loaded.providers[0].default_model = Some("gpt-4o".to_string());
+        loaded.providers[0].api_key = Some(Secret::from("sk-new"));
+        loaded.providers.remove(1);
+
+        let (effective, rejected) = apply_safe_changes(&current, &loaded);
+
+        assert!(effective.debug);
+        assert_eq!(effective.default_provider, "anthropic");
+        assert_eq!(
+            effective.providers[0].default_model.as_deref(),
+            Some("gpt-4o")
+        );
+        assert_eq!(effective.providers[0].api_key, None);
+        assert_eq!(effective.providers.len(), 2);
+        assert_eq!(
+            rejected,
+            vec![
+                "providers[openai].api_key".to_string(),
+                "providers[anthropic] (removed)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejected_changes_are_reported_once() {
+        let current = AppConfig::default();
+        let mut first = AppConfig::default();
+        first.providers[0].api_key = Some(Secret::from("sk-new"));
+
+        let (effective, rejected) = apply_file_changes(&current, &current, &first);
+        assert_eq!(rejected, vec!["providers[openai].api_key".to_string()]);
+
+        // A later edit leaves the rejected key in place
+        let mut second = first.clone();
+        second.debug = true;
+        let (effective, rejected) = apply_file_changes(&effective, &first, &second);
+        assert!(rejected.is_empty());
+        assert!(effective.debug);
+        assert_eq!(effective.providers[0].api_key, None);
+
+        // Changing it again is a new change
+        let mut third = second.clone();
+        third.providers[0].api_key = Some(Secret::from("sk-newer"));
+        let (_, rejected) = apply_file_changes(&effective, &second, &third);
+        assert_eq!(rejected, vec!["providers[openai].api_key".to_string()]);
+    }
+
+    #[test]
+    fn test_reload_on_file_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        AppConfig::default().save_to_file(&path).unwrap();
+
+        let watcher =
+            ConfigWatcher::with_debounce(&path, AppConfig::default(), Duration::from_millis(50))
+                .unwrap();
+        let reloads = Arc::new(AtomicUsize::new(0));
+        let counter = reloads.clone();
+        watcher.on_reload(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let updated = AppConfig {
+            debug: true,
+            default_provider: "anthropic".to_string(),
+            ..AppConfig::default()
+        };
+        updated.save_to_file(&path).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !watcher.current().debug && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let current = watcher.current();
+        assert!(current.debug);
+        assert_eq!(current.default_provider, "anthropic");
+        assert!(reloads.load(Ordering::SeqCst) >= 1);
+    }
+}
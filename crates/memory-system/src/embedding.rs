@@ -0,0 +1,135 @@
+//! Text embedding providers for populating and querying vector stores
+
+use crate::vector_store::{Vector, VectorResult, VectorStoreError};
+use ai_cli_ai_engine::provider::AIProvider;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Turns text into embedding vectors
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed each text, returning one vector per input in the same order
+    async fn embed(&self, texts: &[String]) -> VectorResult<Vec<Vector>>;
+
+    /// Length of the vectors produced
+    fn dimension(&self) -> usize;
+}
+
+/// Deterministic embedder that hashes words into a fixed number of buckets
+///
+/// Needs no network and always gives the same vector for the same text,
+/// which makes it useful for tests and offline use. Texts sharing words end
+/// up close together; there is no notion of meaning beyond that.
+pub struct HashEmbedder {
+    dimension: usize,
+}
+
+impl HashEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+        }
+    }
+
+    /// Embed a single text
+    pub fn embed_text(&self, text: &str) -> Vector {
+        let mut vector = vec![0.0; self.dimension];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            let hash = fnv1a(&word.to_lowercase());
+            let bucket = (hash % self.dimension as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[bucket] += sign;
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbedder {
+    async fn embed(&self, texts: &[String]) -> VectorResult<Vec<Vector>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Embeddings from an AI provider's embedding endpoint
+pub struct ProviderEmbedder {
+    provider: Arc<dyn AIProvider>,
+    model: String,
+    dimension: usize,
+}
+
+impl ProviderEmbedder {
+    /// `dimension` must match what `model` returns
+    pub fn new(provider: Arc<dyn AIProvider>, model: impl Into<String>, dimension: usize) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            dimension,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ProviderEmbedder {
+    async fn embed(&self, texts: &[String]) -> VectorResult<Vec<Vector>> {
+        self.provider
+            .embed(texts.to_vec(), &self.model)
+            .await
+            .map_err(|e| VectorStoreError::EmbeddingError(e.to_string()))
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::InMemoryVectorStore;
+
+    #[test]
+    fn test_hash_embedder_is_deterministic_and_normalized() {
+        let embedder = HashEmbedder::new(32);
+        let a = embedder.embed_text("Rust memory safety");
+        assert_eq!(a, embedder.embed_text("rust MEMORY safety"));
+        assert_eq!(a.len(), 32);
+        let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert!(embedder.embed_text("").iter().all(|x| *x == 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_hash_embedder_ranks_shared_words_higher() {
+        let embedder = HashEmbedder::new(64);
+        let texts = vec![
+            "borrow checker rules".to_string(),
+            "garden tomatoes".to_string(),
+        ];
+        let vectors = embedder.embed(&texts).await.unwrap();
+        let query = embedder.embed_text("the borrow checker");
+
+        let close = InMemoryVectorStore::cosine_similarity(&query, &vectors[0]);
+        let far = InMemoryVectorStore::cosine_similarity(&query, &vectors[1]);
+        assert!(close > far);
+    }
+}
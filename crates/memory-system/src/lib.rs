@@ -1,10 +1,20 @@
 //! Project memory with LlamaIndex integration for AIrchitect CLI
 
 pub mod context;
+pub mod embedding;
+pub mod rerank;
 pub mod storage;
+pub mod vector_store;
 
+use embedding::EmbeddingProvider;
+use rerank::Reranker;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vector_store::{
+    SearchQuery, SearchResult, VectorDocument, VectorResult, VectorStore, VectorStoreError,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryConfig {
@@ -14,17 +24,112 @@ pub struct MemoryConfig {
     pub vector_store: String,
 }
 
+impl MemoryConfig {
+    /// `max_size` in bytes, e.g. `100MB`; `None` if it cannot be parsed
+    ///
+    /// Units are `B`, `KB`, `MB` and `GB`, in powers of 1024.
+    pub fn max_size_bytes(&self) -> Option<usize> {
+        let size = self.max_size.trim().to_ascii_uppercase();
+        let digits = size
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(size.len());
+        let (number, unit) = size.split_at(digits);
+        let multiplier: usize = match unit.trim() {
+            "" | "B" => 1,
+            "KB" => 1 << 10,
+            "MB" => 1 << 20,
+            "GB" => 1 << 30,
+            _ => return None,
+        };
+        number.parse::<usize>().ok()?.checked_mul(multiplier)
+    }
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        MemoryConfig {
+            enabled: true,
+            max_size: "100MB".to_string(),
+            ttl: 86400,
+            vector_store: "local".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
     pub key: String,
     pub value: String,
     pub timestamp: u64,
     pub tags: Vec<String>,
+    /// Embedding of `value`; `None` until one has been computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<vector_store::Vector>,
 }
 
+impl MemoryEntry {
+    /// Approximate bytes held by the entry's key, value and tags
+    pub fn size_bytes(&self) -> usize {
+        self.key.len() + self.value.len() + self.tags.iter().map(String::len).sum::<usize>()
+    }
+}
+
+/// Summary of what is stored in memory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    pub total_entries: usize,
+    /// Number of entries carrying each tag
+    pub tags: BTreeMap<String, usize>,
+    pub oldest_timestamp: Option<u64>,
+    pub newest_timestamp: Option<u64>,
+    /// Approximate size in bytes, see [`MemoryEntry::size_bytes`]
+    pub total_bytes: usize,
+}
+
+/// Outcome of [`MemorySystem::index_documents`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexReport {
+    /// Number of documents embedded and inserted
+    pub indexed: usize,
+    /// Documents that could not be embedded, with the reason
+    pub failures: Vec<IndexFailure>,
+}
+
+/// A document skipped during indexing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexFailure {
+    pub id: String,
+    pub error: String,
+}
+
+/// Outcome of [`MemorySystem::gc`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// Entries removed for outliving the TTL
+    pub expired: usize,
+    /// Entries removed to get under the size budget
+    pub evicted: usize,
+    /// Approximate bytes freed, see [`MemoryEntry::size_bytes`]
+    pub bytes_reclaimed: usize,
+}
+
+impl GcReport {
+    /// Total number of entries removed
+    pub fn removed(&self) -> usize {
+        self.expired + self.evicted
+    }
+}
+
+/// How many vector search candidates are fetched per requested result when reranking
+pub const RERANK_CANDIDATE_FACTOR: usize = 4;
+
 pub struct MemorySystem {
     pub config: MemoryConfig,
     entries: HashMap<String, MemoryEntry>,
+    vector_store: Option<Arc<dyn VectorStore>>,
+    reranker: Option<Arc<dyn Reranker>>,
+    /// File the entries are kept in, for a file-backed memory
+    path: Option<PathBuf>,
 }
 
 impl MemorySystem {
@@ -32,7 +137,235 @@ impl MemorySystem {
         MemorySystem {
             config,
             entries: HashMap::new(),
+            vector_store: None,
+            reranker: None,
+            path: None,
+        }
+    }
+
+    /// Memory backed by the JSON file at `path`, loading its entries if it exists
+    ///
+    /// Changes are only written back by [`MemorySystem::persist`].
+    pub fn open(
+        config: MemoryConfig,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self, ai_cli_utils::error::AIError> {
+        let path = path.into();
+        let mut memory = Self::new(config);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let entries: Vec<MemoryEntry> = serde_json::from_str(&contents)?;
+                for entry in entries {
+                    memory.insert(entry);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        memory.path = Some(path);
+        Ok(memory)
+    }
+
+    /// File the memory is kept in, if it is file-backed
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write the entries to the backing file; a no-op for memory that is not file-backed
+    pub fn persist(&self) -> Result<(), ai_cli_utils::error::AIError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut entries: Vec<&MemoryEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        let contents = serde_json::to_string_pretty(&entries)?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        ai_cli_utils::fs::write_atomic(path, contents.as_bytes())?;
+        Ok(())
+    }
+
+    /// Use `store` for embeddings and semantic search
+    pub fn with_vector_store(mut self, store: Arc<dyn VectorStore>) -> Self {
+        self.vector_store = Some(store);
+        self
+    }
+
+    /// Reorder semantic search results with `reranker`
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    pub fn vector_store(&self) -> Option<&Arc<dyn VectorStore>> {
+        self.vector_store.as_ref()
+    }
+
+    fn require_vector_store(&self) -> VectorResult<&Arc<dyn VectorStore>> {
+        self.vector_store
+            .as_ref()
+            .ok_or_else(|| VectorStoreError::StorageError("no vector store configured".to_string()))
+    }
+
+    /// Embed `(id, content)` pairs in batches and insert them into the vector store
+    ///
+    /// When a batch fails to embed, its documents are retried one at a time
+    /// so a single bad document only skips itself; those are listed in the
+    /// report. An embedder whose vectors don't match the store's dimension
+    /// aborts the whole run.
+    pub async fn index_documents(
+        &self,
+        docs: Vec<(String, String)>,
+        embedder: &dyn EmbeddingProvider,
+        batch_size: usize,
+    ) -> VectorResult<IndexReport> {
+        let store = self.require_vector_store()?;
+        let check_dimension = |actual: usize| {
+            if actual == store.dimension() {
+                Ok(())
+            } else {
+                Err(VectorStoreError::InvalidDimension {
+                    expected: store.dimension(),
+                    actual,
+                })
+            }
+        };
+        check_dimension(embedder.dimension())?;
+
+        let total = docs.len();
+        let mut report = IndexReport::default();
+        for batch in docs.chunks(batch_size.max(1)) {
+            let texts: Vec<String> = batch.iter().map(|(_, content)| content.clone()).collect();
+            let vectors = match embedder.embed(&texts).await {
+                Ok(vectors) if vectors.len() == batch.len() => {
+                    vectors.into_iter().map(Ok).collect()
+                }
+                Ok(vectors) => {
+                    log::warn!(
+                        "Embedder returned {} vectors for {} documents; retrying individually",
+                        vectors.len(),
+                        batch.len()
+                    );
+                    Self::embed_each(embedder, &texts).await
+                }
+                Err(e) => {
+                    log::warn!("Batch embedding failed ({}); retrying individually", e);
+                    Self::embed_each(embedder, &texts).await
+                }
+            };
+
+            let mut documents = Vec::with_capacity(batch.len());
+            for ((id, content), vector) in batch.iter().zip(vectors) {
+                match vector {
+                    Ok(vector) => {
+                        check_dimension(vector.len())?;
+                        documents.push(VectorDocument::new(id.clone(), content.clone(), vector));
+                    }
+                    Err(e) => report.failures.push(IndexFailure {
+                        id: id.clone(),
+                        error: e.to_string(),
+                    }),
+                }
+            }
+
+            report.indexed += documents.len();
+            store.insert_batch(documents).await?;
+            log::info!(
+                "Indexed {}/{} documents ({} failed)",
+                report.indexed,
+                total,
+                report.failures.len()
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Find the `top_k` stored documents closest to `query`
+    ///
+    /// With a reranker configured, a larger candidate set
+    /// ([`RERANK_CANDIDATE_FACTOR`] times `top_k`) is fetched first and the
+    /// reranker's order decides the final results. Entries without an
+    /// embedding that mention the query fill any remaining slots with a
+    /// score of 0.0; if the query itself can't be embedded, those keyword
+    /// matches are all that is returned.
+    pub async fn semantic_search(
+        &self,
+        query: &str,
+        embedder: &dyn EmbeddingProvider,
+        top_k: usize,
+    ) -> VectorResult<Vec<SearchResult>> {
+        let store = self.require_vector_store()?;
+        let embedding = match embedder.embed(&[query.to_string()]).await {
+            Ok(mut vectors) if vectors.len() == 1 => vectors.pop(),
+            Ok(_) => {
+                log::warn!("No embedding for query; falling back to keyword search");
+                None
+            }
+            Err(e) => {
+                log::warn!("Embedding failed ({}); falling back to keyword search", e);
+                None
+            }
+        };
+
+        let mut results = match (embedding, &self.reranker) {
+            (Some(embedding), Some(reranker)) => {
+                let candidates = store
+                    .search(SearchQuery::new(
+                        embedding,
+                        top_k.saturating_mul(RERANK_CANDIDATE_FACTOR),
+                    ))
+                    .await?;
+                let mut results = reranker.rerank(query, candidates).await;
+                results.truncate(top_k);
+                results
+            }
+            (Some(embedding), None) => store.search(SearchQuery::new(embedding, top_k)).await?,
+            (None, _) => Vec::new(),
+        };
+
+        // Entries stored while embeddings were unavailable can only match by keyword
+        let remaining = top_k.saturating_sub(results.len());
+        results.extend(
+            self.keyword_matches(query)
+                .into_iter()
+                .take(remaining)
+                .map(|entry| SearchResult {
+                    document: VectorDocument::new(
+                        entry.key.clone(),
+                        entry.value.clone(),
+                        Vec::new(),
+                    ),
+                    score: 0.0,
+                    rank: 0,
+                }),
+        );
+        for (idx, result) in results.iter_mut().enumerate() {
+            result.rank = idx + 1;
+        }
+        Ok(results)
+    }
+
+    /// Embed texts one by one, keeping each result separate
+    async fn embed_each(
+        embedder: &dyn EmbeddingProvider,
+        texts: &[String],
+    ) -> Vec<VectorResult<vector_store::Vector>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            let result =
+                embedder
+                    .embed(std::slice::from_ref(text))
+                    .await
+                    .and_then(|mut vectors| {
+                        vectors.pop().filter(|_| vectors.is_empty()).ok_or_else(|| {
+                            VectorStoreError::EmbeddingError("expected one embedding".to_string())
+                        })
+                    });
+            results.push(result);
         }
+        results
     }
 
     pub fn store(
@@ -49,16 +382,143 @@ impl MemorySystem {
                 .unwrap()
                 .as_secs(),
             tags,
+            embedding: None,
         };
 
         self.entries.insert(key, entry);
         Ok(())
     }
 
+    /// Store an entry and index its embedding for semantic search
+    ///
+    /// If embedding or indexing fails, the entry is still stored without an
+    /// embedding and a warning is logged; [`MemorySystem::reindex_missing`]
+    /// fills it in later.
+    pub async fn store_embedded(
+        &mut self,
+        key: String,
+        value: String,
+        tags: Vec<String>,
+        embedder: &dyn EmbeddingProvider,
+    ) -> Result<(), ai_cli_utils::error::AIError> {
+        self.store(key.clone(), value, tags)?;
+        if let Err(e) = self
+            .embed_entries(std::slice::from_ref(&key), embedder)
+            .await
+        {
+            log::warn!("Stored '{}' without an embedding: {}", key, e);
+        }
+        Ok(())
+    }
+
+    /// Compute embeddings for entries stored without one, returning how many were filled
+    pub async fn reindex_missing(
+        &mut self,
+        embedder: &dyn EmbeddingProvider,
+    ) -> VectorResult<usize> {
+        let mut keys: Vec<String> = self
+            .entries
+            .values()
+            .filter(|entry| entry.embedding.is_none())
+            .map(|entry| entry.key.clone())
+            .collect();
+        keys.sort();
+        self.embed_entries(&keys, embedder).await?;
+        Ok(keys.len())
+    }
+
+    /// Embed the given entries, add them to the vector store and record the embeddings
+    async fn embed_entries(
+        &mut self,
+        keys: &[String],
+        embedder: &dyn EmbeddingProvider,
+    ) -> VectorResult<()> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let store = self.require_vector_store()?.clone();
+
+        let texts: Vec<String> = keys
+            .iter()
+            .filter_map(|key| self.entries.get(key))
+            .map(|entry| entry.value.clone())
+            .collect();
+        let vectors = embedder.embed(&texts).await?;
+        if vectors.len() != keys.len() {
+            return Err(VectorStoreError::EmbeddingError(format!(
+                "expected {} embeddings, got {}",
+                keys.len(),
+                vectors.len()
+            )));
+        }
+
+        let documents = keys
+            .iter()
+            .zip(texts)
+            .zip(&vectors)
+            .map(|((key, text), vector)| VectorDocument::new(key.clone(), text, vector.clone()))
+            .collect();
+        store.insert_batch(documents).await?;
+
+        for (key, vector) in keys.iter().zip(vectors) {
+            if let Some(entry) = self.entries.get_mut(key) {
+                entry.embedding = Some(vector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Entries without an embedding whose value or tags mention `query`, newest first
+    fn keyword_matches(&self, query: &str) -> Vec<&MemoryEntry> {
+        let needle = query.to_lowercase();
+        let mut matches: Vec<&MemoryEntry> = self
+            .entries
+            .values()
+            .filter(|entry| entry.embedding.is_none())
+            .filter(|entry| {
+                entry.value.to_lowercase().contains(&needle)
+                    || entry.tags.iter().any(|tag| tag.to_lowercase() == needle)
+            })
+            .collect();
+        matches.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.key.cmp(&b.key)));
+        matches
+    }
+
+    /// Store `entry` as is, keeping its timestamp
+    pub fn insert(&mut self, entry: MemoryEntry) {
+        self.entries.insert(entry.key.clone(), entry);
+    }
+
     pub fn retrieve(&self, key: &str) -> Option<&MemoryEntry> {
         self.entries.get(key)
     }
 
+    pub fn remove(&mut self, key: &str) -> Option<MemoryEntry> {
+        self.entries.remove(key)
+    }
+
+    /// Entries whose key starts with `prefix`, sorted by key
+    pub fn retrieve_prefix(&self, prefix: &str) -> Vec<&MemoryEntry> {
+        let mut entries: Vec<&MemoryEntry> = self
+            .entries
+            .values()
+            .filter(|entry| entry.key.starts_with(prefix))
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+
+    /// Remove every entry whose key starts with `prefix`, returning how many were removed
+    pub fn delete_prefix(&mut self, prefix: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|key, _| !key.starts_with(prefix));
+        before - self.entries.len()
+    }
+
+    pub fn entries(&self) -> Vec<&MemoryEntry> {
+        self.entries.values().collect()
+    }
+
     pub fn search_by_tags(&self, tags: &[String]) -> Vec<&MemoryEntry> {
         self.entries
             .values()
@@ -66,20 +526,104 @@ impl MemorySystem {
             .collect()
     }
 
-    pub fn cleanup_expired(&mut self) {
+    /// Remove entries older than the TTL, returning the bytes they held
+    pub fn cleanup_expired(&mut self) -> (usize, usize) {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        self.entries
-            .retain(|_, entry| now - entry.timestamp < self.config.ttl);
+        let (mut removed, mut bytes) = (0, 0);
+        self.entries.retain(|_, entry| {
+            let keep = now.saturating_sub(entry.timestamp) < self.config.ttl;
+            if !keep {
+                removed += 1;
+                bytes += entry.size_bytes();
+            }
+            keep
+        });
+        (removed, bytes)
+    }
+
+    /// Remove the least recently stored entries until the rest fit in
+    /// `max_bytes`, returning how many were removed and the bytes they held
+    pub fn evict_to(&mut self, max_bytes: usize) -> (usize, usize) {
+        let mut total: usize = self.entries.values().map(MemoryEntry::size_bytes).sum();
+        if total <= max_bytes {
+            return (0, 0);
+        }
+        let mut by_age: Vec<(u64, String)> = self
+            .entries
+            .values()
+            .map(|entry| (entry.timestamp, entry.key.clone()))
+            .collect();
+        by_age.sort();
+
+        let (mut removed, mut bytes) = (0, 0);
+        for (_, key) in by_age {
+            if total <= max_bytes {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                let size = entry.size_bytes();
+                total -= size;
+                bytes += size;
+                removed += 1;
+            }
+        }
+        (removed, bytes)
+    }
+
+    /// Remove expired entries, then evict down to `max_size`
+    ///
+    /// A `max_size` that cannot be parsed skips eviction with a warning.
+    pub fn gc(&mut self) -> GcReport {
+        let (expired, expired_bytes) = self.cleanup_expired();
+        let (evicted, evicted_bytes) = match self.config.max_size_bytes() {
+            Some(max_bytes) => self.evict_to(max_bytes),
+            None => {
+                log::warn!(
+                    "Cannot parse memory max_size '{}'; skipping eviction",
+                    self.config.max_size
+                );
+                (0, 0)
+            }
+        };
+        GcReport {
+            expired,
+            evicted,
+            bytes_reclaimed: expired_bytes + evicted_bytes,
+        }
     }
 
     pub fn count(&self) -> usize {
         self.entries.len()
     }
 
+    pub fn stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats {
+            total_entries: self.entries.len(),
+            ..Default::default()
+        };
+        for entry in self.entries.values() {
+            for tag in &entry.tags {
+                *stats.tags.entry(tag.clone()).or_default() += 1;
+            }
+            stats.oldest_timestamp = Some(
+                stats
+                    .oldest_timestamp
+                    .map_or(entry.timestamp, |t| t.min(entry.timestamp)),
+            );
+            stats.newest_timestamp = Some(
+                stats
+                    .newest_timestamp
+                    .map_or(entry.timestamp, |t| t.max(entry.timestamp)),
+            );
+            stats.total_bytes += entry.size_bytes();
+        }
+        stats
+    }
+
     pub fn clear(&mut self) {
         self.entries.clear();
     }
@@ -144,6 +688,7 @@ mod tests {
             value: "test_value".to_string(),
             timestamp: 1234567890,
             tags: vec!["tag1".to_string(), "tag2".to_string()],
+            embedding: None,
         };
 
         assert_eq!(entry.key, "test_key");
@@ -159,6 +704,7 @@ mod tests {
             value: "value".to_string(),
             timestamp: 1000,
             tags: vec!["test".to_string()],
+            embedding: None,
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -355,6 +901,62 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_prefix_lookup_and_delete() {
+        let mut system = MemorySystem::new(create_test_config());
+        for key in ["project/foo/a", "project/foo/b", "project/bar/a", "other"] {
+            system
+                .store(key.to_string(), "value".to_string(), vec![])
+                .unwrap();
+        }
+
+        let keys: Vec<&str> = system
+            .retrieve_prefix("project/foo/")
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["project/foo/a", "project/foo/b"]);
+        assert_eq!(system.retrieve_prefix("project/").len(), 3);
+        assert!(system.retrieve_prefix("missing/").is_empty());
+
+        assert_eq!(system.delete_prefix("project/foo/"), 2);
+        assert_eq!(system.delete_prefix("project/foo/"), 0);
+        assert!(system.retrieve("project/bar/a").is_some());
+        assert_eq!(system.count(), 2);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut system = MemorySystem::new(create_test_config());
+        assert_eq!(system.stats(), MemoryStats::default());
+
+        system
+            .store(
+                "key1".to_string(),
+                "value1".to_string(),
+                vec!["web".to_string(), "api".to_string()],
+            )
+            .unwrap();
+        system
+            .store(
+                "key2".to_string(),
+                "v2".to_string(),
+                vec!["web".to_string()],
+            )
+            .unwrap();
+        system
+            .store("key3".to_string(), "v3".to_string(), vec![])
+            .unwrap();
+
+        let stats = system.stats();
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.tags.get("web"), Some(&2));
+        assert_eq!(stats.tags.get("api"), Some(&1));
+        assert_eq!(stats.tags.len(), 2);
+        assert_eq!(stats.total_bytes, (4 + 6 + 3 + 3) + (4 + 2 + 3) + (4 + 2));
+        assert!(stats.oldest_timestamp.unwrap() <= stats.newest_timestamp.unwrap());
+    }
+
     #[test]
     fn test_cleanup_expired_entries() {
         let config = MemoryConfig {
@@ -385,6 +987,74 @@ mod tests {
         assert!(system.retrieve("key1").is_none());
     }
 
+    #[test]
+    fn test_gc_expires_then_evicts_oldest() {
+        let mut system = MemorySystem::new(MemoryConfig {
+            max_size: "30B".to_string(),
+            ..create_test_config()
+        });
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for (key, age) in [("stale", 7200), ("old", 60), ("mid", 30), ("new", 0)] {
+            system.insert(MemoryEntry {
+                key: key.to_string(),
+                value: "0123456789".to_string(),
+                timestamp: now - age,
+                tags: vec![],
+                embedding: None,
+            });
+        }
+
+        let report = system.gc();
+        assert_eq!(
+            report,
+            GcReport {
+                expired: 1,
+                evicted: 1,
+                bytes_reclaimed: 15 + 13,
+            }
+        );
+        assert_eq!(report.removed(), 2);
+        assert!(system.retrieve("old").is_none());
+        assert!(system.retrieve("mid").is_some() && system.retrieve("new").is_some());
+
+        assert_eq!(system.gc(), GcReport::default());
+        assert_eq!(create_test_config().max_size_bytes(), Some(100 << 20));
+        let unparsed = MemoryConfig {
+            max_size: "lots".to_string(),
+            ..create_test_config()
+        };
+        assert_eq!(unparsed.max_size_bytes(), None);
+    }
+
+    #[test]
+    fn test_open_and_persist_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("memory/entries.json");
+
+        let mut memory = MemorySystem::open(create_test_config(), &path).unwrap();
+        assert_eq!(memory.count(), 0);
+        memory
+            .store(
+                "plan".to_string(),
+                "step 1".to_string(),
+                vec!["api".to_string()],
+            )
+            .unwrap();
+        memory.persist().unwrap();
+
+        let reopened = MemorySystem::open(create_test_config(), &path).unwrap();
+        assert_eq!(reopened.path(), Some(path.as_path()));
+        let entry = reopened.retrieve("plan").unwrap();
+        assert_eq!(entry.value, "step 1");
+        assert_eq!(entry.tags, vec!["api".to_string()]);
+
+        // Memory that is not file-backed has nothing to write
+        assert!(MemorySystem::new(create_test_config()).persist().is_ok());
+    }
+
     #[test]
     fn test_cleanup_no_expired() {
         let config = create_test_config();
@@ -425,6 +1095,20 @@ mod tests {
         assert!(system.retrieve("key2").is_none());
     }
 
+    #[test]
+    fn test_remove_entry() {
+        let config = create_test_config();
+        let mut system = MemorySystem::new(config);
+
+        system
+            .store("key1".to_string(), "value1".to_string(), vec![])
+            .unwrap();
+
+        assert_eq!(system.remove("key1").unwrap().value, "value1");
+        assert!(system.remove("key1").is_none());
+        assert!(system.entries().is_empty());
+    }
+
     #[test]
     fn test_store_with_empty_value() {
         let config = create_test_config();
@@ -481,4 +1165,217 @@ mod tests {
         assert!(entry.timestamp >= before);
         assert!(entry.timestamp <= after);
     }
+
+    /// Fails on any text containing "bad"
+    struct PickyEmbedder(embedding::HashEmbedder);
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for PickyEmbedder {
+        async fn embed(&self, texts: &[String]) -> VectorResult<Vec<vector_store::Vector>> {
+            if texts.iter().any(|t| t.contains("bad")) {
+                return Err(VectorStoreError::EmbeddingError("rejected".to_string()));
+            }
+            self.0.embed(texts).await
+        }
+
+        fn dimension(&self) -> usize {
+            self.0.dimension()
+        }
+    }
+
+    fn corpus() -> Vec<(String, String)> {
+        [
+            ("rust", "Rust ownership and the borrow checker"),
+            ("python", "Python list comprehensions"),
+            ("garden", "Growing tomatoes in the garden"),
+            ("bad", "a bad document"),
+            ("cooking", "Slow cooking beans"),
+        ]
+        .iter()
+        .map(|(id, content)| (id.to_string(), content.to_string()))
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_index_documents_in_batches() {
+        let store = Arc::new(vector_store::InMemoryVectorStore::new(64));
+        let system = MemorySystem::new(create_test_config()).with_vector_store(store.clone());
+        let embedder = PickyEmbedder(embedding::HashEmbedder::new(64));
+
+        let report = system
+            .index_documents(corpus(), &embedder, 2)
+            .await
+            .unwrap();
+        assert_eq!(report.indexed, 4);
+        assert_eq!(
+            report.failures,
+            vec![IndexFailure {
+                id: "bad".to_string(),
+                error: "Embedding error: rejected".to_string(),
+            }]
+        );
+        assert_eq!(store.count().await.unwrap(), 4);
+
+        let query = embedding::HashEmbedder::new(64).embed_text("borrow checker");
+        let results = store
+            .search(vector_store::SearchQuery::new(query, 1))
+            .await
+            .unwrap();
+        assert_eq!(results[0].document.id, "rust");
+    }
+
+    #[tokio::test]
+    async fn test_index_documents_dimension_mismatch() {
+        let store = Arc::new(vector_store::InMemoryVectorStore::new(16));
+        let system = MemorySystem::new(create_test_config()).with_vector_store(store.clone());
+
+        let err = system
+            .index_documents(corpus(), &embedding::HashEmbedder::new(8), 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            VectorStoreError::InvalidDimension {
+                expected: 16,
+                actual: 8
+            }
+        ));
+        assert_eq!(store.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_index_documents_requires_vector_store() {
+        let system = MemorySystem::new(create_test_config());
+        let result = system
+            .index_documents(corpus(), &embedding::HashEmbedder::new(8), 2)
+            .await;
+        assert!(matches!(result, Err(VectorStoreError::StorageError(_))));
+    }
+
+    /// Reverses whatever order it is given
+    struct ReversingReranker;
+
+    #[async_trait::async_trait]
+    impl Reranker for ReversingReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            mut candidates: Vec<SearchResult>,
+        ) -> Vec<SearchResult> {
+            candidates.reverse();
+            candidates
+        }
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_with_reranker() {
+        let store = Arc::new(vector_store::InMemoryVectorStore::new(64));
+        let embedder = embedding::HashEmbedder::new(64);
+        MemorySystem::new(create_test_config())
+            .with_vector_store(store.clone())
+            .index_documents(corpus(), &embedder, 10)
+            .await
+            .unwrap();
+
+        let plain = MemorySystem::new(create_test_config()).with_vector_store(store.clone());
+        let results = plain
+            .semantic_search("borrow checker", &embedder, 2)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document.id, "rust");
+
+        // The reranker sees all 5 candidates, so reversing puts the worst match first
+        let reranked = MemorySystem::new(create_test_config())
+            .with_vector_store(store)
+            .with_reranker(Arc::new(ReversingReranker));
+        let results = reranked
+            .semantic_search("borrow checker", &embedder, 2)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_ne!(results[0].document.id, "rust");
+        assert!(results.iter().all(|r| r.document.id != "rust"));
+        assert_eq!((results[0].rank, results[1].rank), (1, 2));
+    }
+
+    /// Fails until switched on, then embeds like [`embedding::HashEmbedder`]
+    struct OutageEmbedder {
+        up: std::sync::atomic::AtomicBool,
+        inner: embedding::HashEmbedder,
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for OutageEmbedder {
+        async fn embed(&self, texts: &[String]) -> VectorResult<Vec<vector_store::Vector>> {
+            if !self.up.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(VectorStoreError::EmbeddingError(
+                    "service unavailable".to_string(),
+                ));
+            }
+            self.inner.embed(texts).await
+        }
+
+        fn dimension(&self) -> usize {
+            self.inner.dimension()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_survives_embedding_outage() {
+        let store = Arc::new(vector_store::InMemoryVectorStore::new(32));
+        let mut system = MemorySystem::new(create_test_config()).with_vector_store(store.clone());
+        let embedder = OutageEmbedder {
+            up: std::sync::atomic::AtomicBool::new(false),
+            inner: embedding::HashEmbedder::new(32),
+        };
+
+        system
+            .store_embedded(
+                "notes".to_string(),
+                "The borrow checker rejects aliasing".to_string(),
+                vec!["rust".to_string()],
+                &embedder,
+            )
+            .await
+            .unwrap();
+        assert!(system.retrieve("notes").unwrap().embedding.is_none());
+        assert_eq!(store.count().await.unwrap(), 0);
+
+        // Search falls back to keyword and tag matching
+        let results = system
+            .semantic_search("borrow", &embedder, 5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.id, "notes");
+        assert_eq!(results[0].score, 0.0);
+        assert_eq!(
+            system
+                .semantic_search("RUST", &embedder, 5)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(system
+            .semantic_search("tomatoes", &embedder, 5)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(system.reindex_missing(&embedder).await.is_err());
+
+        embedder.up.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(system.reindex_missing(&embedder).await.unwrap(), 1);
+        assert_eq!(system.reindex_missing(&embedder).await.unwrap(), 0);
+        assert!(system.retrieve("notes").unwrap().embedding.is_some());
+        assert_eq!(store.count().await.unwrap(), 1);
+
+        let results = system
+            .semantic_search("borrow checker", &embedder, 5)
+            .await
+            .unwrap();
+        assert_eq!(results[0].document.id, "notes");
+        assert!(results[0].score > 0.0);
+    }
 }
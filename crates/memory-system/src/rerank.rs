@@ -0,0 +1,189 @@
+//! Second-pass reordering of vector search results
+
+use crate::vector_store::SearchResult;
+use ai_cli_ai_engine::provider::{
+    AIProvider, Message, MessageRole, PromptRequest, RequestMetadata,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Reorders search candidates by relevance to the query
+///
+/// Implementations may replace scores; ranks are reassigned by the caller.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    async fn rerank(&self, query: &str, candidates: Vec<SearchResult>) -> Vec<SearchResult>;
+}
+
+/// Keeps the vector search order
+pub struct IdentityReranker;
+
+#[async_trait]
+impl Reranker for IdentityReranker {
+    async fn rerank(&self, _query: &str, candidates: Vec<SearchResult>) -> Vec<SearchResult> {
+        candidates
+    }
+}
+
+/// Asks a model to score each candidate against the query
+///
+/// Scores are the model's 0-10 rating scaled to 0.0-1.0. A candidate the
+/// model fails to rate scores 0.0, and ties keep the original order.
+pub struct CrossEncoderReranker {
+    provider: Arc<dyn AIProvider>,
+    model: String,
+}
+
+impl CrossEncoderReranker {
+    pub fn new(provider: Arc<dyn AIProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+        }
+    }
+
+    async fn score(&self, query: &str, passage: &str) -> Option<f32> {
+        let request = PromptRequest {
+            model: self.model.clone(),
+            system_prompt: Some(
+                "Rate how relevant the passage is to the query on a scale from 0 to 10. \
+                 Reply with the number only."
+                    .to_string(),
+            ),
+            messages: vec![Message {
+                role: MessageRole::User,
+                content: format!("Query: {}\n\nPassage: {}", query, passage),
+                name: None,
+            }],
+            temperature: Some(0.0),
+            max_tokens: Some(8),
+            stop_sequences: None,
+            parameters: HashMap::new(),
+            metadata: RequestMetadata::default(),
+        };
+
+        match self.provider.send_prompt(request).await {
+            Ok(response) => parse_score(&response.content),
+            Err(e) => {
+                log::warn!("Reranking with {} failed: {}", self.provider.name(), e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for CrossEncoderReranker {
+    async fn rerank(&self, query: &str, candidates: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut scored = Vec::with_capacity(candidates.len());
+        for mut candidate in candidates {
+            candidate.score = self
+                .score(query, &candidate.document.content)
+                .await
+                .unwrap_or(0.0);
+            scored.push(candidate);
+        }
+        // Stable sort, so equal scores keep the vector search order
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored
+    }
+}
+
+/// Read the first number in a reply as a 0-10 rating
+fn parse_score(reply: &str) -> Option<f32> {
+    reply
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|token| token.parse::<f32>().ok())
+        .map(|score| score.clamp(0.0, 10.0) / 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::VectorDocument;
+    use ai_cli_ai_engine::provider::{
+        FinishReason, HealthStatus, ModelInfo, PromptResponse, ProviderError, ProviderResult,
+        ResponseMetadata, ResponseStream, TokenUsage,
+    };
+
+    /// Rates passages by how many times they mention "rust"
+    struct CountingProvider;
+
+    #[async_trait]
+    impl AIProvider for CountingProvider {
+        async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+            let passage = request.messages[0]
+                .content
+                .split("Passage: ")
+                .nth(1)
+                .unwrap();
+            if passage.contains("error") {
+                return Err(ProviderError::ModelError("boom".to_string()));
+            }
+            Ok(PromptResponse {
+                content: format!("{}", passage.matches("rust").count()),
+                model: request.model,
+                usage: TokenUsage::empty(),
+                finish_reason: FinishReason::Stop,
+                metadata: ResponseMetadata {
+                    request_id: request.metadata.request_id,
+                    timestamp: chrono::Utc::now(),
+                    latency_ms: 0,
+                    cost: None,
+                },
+            })
+        }
+
+        async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+            Err(ProviderError::InvalidRequest("no streaming".to_string()))
+        }
+
+        async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+
+        async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+            Ok(HealthStatus::healthy(0))
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    fn result(id: &str, content: &str, score: f32) -> SearchResult {
+        SearchResult {
+            document: VectorDocument::new(id, content, vec![0.0]),
+            score,
+            rank: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_score() {
+        assert_eq!(parse_score("7"), Some(0.7));
+        assert_eq!(parse_score("Score: 10/10"), Some(1.0));
+        assert_eq!(parse_score("42"), Some(1.0));
+        assert_eq!(parse_score("none"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cross_encoder_reranker() {
+        let reranker = CrossEncoderReranker::new(Arc::new(CountingProvider), "judge");
+        let candidates = vec![
+            result("one", "rust", 0.9),
+            result("broken", "error rust rust rust", 0.8),
+            result("three", "rust rust rust", 0.7),
+            result("zero", "python", 0.6),
+        ];
+
+        let ids: Vec<String> = reranker
+            .rerank("rust", candidates)
+            .await
+            .into_iter()
+            .map(|r| r.document.id)
+            .collect();
+        assert_eq!(ids, vec!["three", "one", "broken", "zero"]);
+    }
+}
@@ -5,10 +5,14 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 /// Vector store error types
 #[derive(Error, Debug)]
@@ -27,6 +31,9 @@ pub enum VectorStoreError {
 
     #[error("Query error: {0}")]
     QueryError(String),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(String),
 }
 
 pub type VectorResult<T> = Result<T, VectorStoreError>;
@@ -69,11 +76,56 @@ pub struct SearchResult {
     pub rank: usize,
 }
 
+/// How search scores a document against the query
+///
+/// Higher scores always mean closer matches, and [`SearchQuery::threshold`]
+/// is a minimum score on the metric's own scale.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMetric {
+    /// Cosine of the angle between the vectors, in -1.0..=1.0
+    #[default]
+    Cosine,
+    /// Raw dot product; unbounded, so thresholds depend on vector magnitudes
+    DotProduct,
+    /// `1 / (1 + distance)` over Euclidean distance, in 0.0..=1.0 where 1.0 is identical
+    Euclidean,
+}
+
+impl SimilarityMetric {
+    /// Score `b` against `a`; mismatched lengths and non-finite results score 0.0
+    pub fn score(&self, a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+
+        let score = match self {
+            SimilarityMetric::Cosine => return InMemoryVectorStore::cosine_similarity(a, b),
+            SimilarityMetric::DotProduct => a.iter().zip(b).map(|(x, y)| x * y).sum(),
+            SimilarityMetric::Euclidean => {
+                let distance = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f32>()
+                    .sqrt();
+                1.0 / (1.0 + distance)
+            }
+        };
+        if score.is_finite() {
+            score
+        } else {
+            0.0
+        }
+    }
+}
+
 /// Search query parameters
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
     pub embedding: Vector,
     pub top_k: usize,
+    /// Minimum score to include, on the store's [`SimilarityMetric`] scale
     pub threshold: f32,
     pub filters: HashMap<String, String>,
 }
@@ -114,7 +166,9 @@ pub trait VectorStore: Send + Sync {
     /// Delete a document
     async fn delete(&self, id: &str) -> VectorResult<bool>;
 
-    /// Search for similar documents
+    /// Search for similar documents, best match first
+    ///
+    /// Equal scores are ordered by document ID so results are reproducible.
     async fn search(&self, query: SearchQuery) -> VectorResult<Vec<SearchResult>>;
 
     /// Get total document count
@@ -131,18 +185,29 @@ pub trait VectorStore: Send + Sync {
 pub struct InMemoryVectorStore {
     documents: Arc<RwLock<HashMap<String, VectorDocument>>>,
     dimension: usize,
+    metric: SimilarityMetric,
 }
 
 impl InMemoryVectorStore {
     pub fn new(dimension: usize) -> Self {
+        Self::with_metric(dimension, SimilarityMetric::default())
+    }
+
+    /// Create a store that scores with `metric`
+    pub fn with_metric(dimension: usize, metric: SimilarityMetric) -> Self {
         Self {
             documents: Arc::new(RwLock::new(HashMap::new())),
             dimension,
+            metric,
         }
     }
 
+    pub fn metric(&self) -> SimilarityMetric {
+        self.metric
+    }
+
     /// Compute cosine similarity between two vectors
-    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         if a.len() != b.len() {
             return 0.0;
         }
@@ -155,14 +220,30 @@ impl InMemoryVectorStore {
             return 0.0;
         }
 
-        dot_product / (norm_a * norm_b)
+        let similarity = dot_product / (norm_a * norm_b);
+        if similarity.is_finite() {
+            similarity
+        } else {
+            0.0
+        }
+    }
+
+    /// Result order: score descending, then document ID ascending on ties
+    fn compare_results(a: &SearchResult, b: &SearchResult) -> Ordering {
+        Self::compare_scores(a.score, b.score).then_with(|| a.document.id.cmp(&b.document.id))
+    }
+
+    /// Order scores descending, with `NaN` after every number
+    fn compare_scores(a: f32, b: f32) -> Ordering {
+        b.partial_cmp(&a)
+            .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
     }
 
     /// Check if document matches filters
     fn matches_filters(doc: &VectorDocument, filters: &HashMap<String, String>) -> bool {
-        filters.iter().all(|(k, v)| {
-            doc.metadata.get(k).map(|val| val == v).unwrap_or(false)
-        })
+        filters
+            .iter()
+            .all(|(k, v)| doc.metadata.get(k).map(|val| val == v).unwrap_or(false))
     }
 }
 
@@ -176,7 +257,10 @@ impl VectorStore for InMemoryVectorStore {
             });
         }
 
-        self.documents.write().await.insert(document.id.clone(), document);
+        self.documents
+            .write()
+            .await
+            .insert(document.id.clone(), document);
         Ok(())
     }
 
@@ -217,7 +301,7 @@ impl VectorStore for InMemoryVectorStore {
             .values()
             .filter(|doc| Self::matches_filters(doc, &query.filters))
             .map(|doc| {
-                let score = Self::cosine_similarity(&query.embedding, &doc.embedding);
+                let score = self.metric.score(&query.embedding, &doc.embedding);
                 SearchResult {
                     document: doc.clone(),
                     score,
@@ -227,8 +311,8 @@ impl VectorStore for InMemoryVectorStore {
             .filter(|result| result.score >= query.threshold)
             .collect();
 
-        // Sort by score descending
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        // Sort by score descending, breaking ties by ID so results are reproducible
+        results.sort_by(Self::compare_results);
 
         // Assign ranks and limit results
         results.truncate(query.top_k);
@@ -253,6 +337,234 @@ impl VectorStore for InMemoryVectorStore {
     }
 }
 
+/// One line of a [`FileVectorStore`] log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogRecord {
+    Put { document: VectorDocument },
+    Delete { id: String },
+    Clear,
+}
+
+/// Vector store persisted as an append-only JSON Lines log
+///
+/// Every change is appended to the file, so deletes and overwrites leave
+/// stale records behind until [`FileVectorStore::compact`] rewrites it.
+/// Reads are served from memory.
+pub struct FileVectorStore {
+    path: PathBuf,
+    memory: InMemoryVectorStore,
+    log: Mutex<File>,
+}
+
+impl FileVectorStore {
+    /// Open the store at `path`, replaying any existing log
+    pub fn open(path: impl Into<PathBuf>, dimension: usize) -> VectorResult<Self> {
+        Self::open_with_metric(path, dimension, SimilarityMetric::default())
+    }
+
+    /// Open the store at `path`, scoring searches with `metric`
+    pub fn open_with_metric(
+        path: impl Into<PathBuf>,
+        dimension: usize,
+        metric: SimilarityMetric,
+    ) -> VectorResult<Self> {
+        let path = path.into();
+        let mut documents = HashMap::new();
+        if path.exists() {
+            Self::replay(&path, &mut documents)?;
+        }
+
+        let memory = InMemoryVectorStore {
+            documents: Arc::new(RwLock::new(documents)),
+            dimension,
+            metric,
+        };
+        let log = Self::open_log(&path)?;
+        Ok(Self {
+            path,
+            memory,
+            log: Mutex::new(log),
+        })
+    }
+
+    /// Get the path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the log with only the live documents, returning bytes reclaimed
+    ///
+    /// The new file replaces the old one atomically. Writers wait for the
+    /// swap; reads are served from memory and are not blocked.
+    pub async fn compact(&self) -> VectorResult<u64> {
+        let mut log = self.log.lock().await;
+        let before = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+
+        let mut contents = Vec::new();
+        {
+            let documents = self.memory.documents.read().await;
+            let mut live: Vec<&VectorDocument> = documents.values().collect();
+            live.sort_by(|a, b| a.id.cmp(&b.id));
+            for document in live {
+                let record = serde_json::to_string(&LogRecord::Put {
+                    document: document.clone(),
+                })
+                .map_err(|e| VectorStoreError::SerializationError(e.to_string()))?;
+                contents.extend_from_slice(record.as_bytes());
+                contents.push(b'\n');
+            }
+        }
+
+        ai_cli_utils::fs::write_atomic(&self.path, &contents).map_err(storage_error)?;
+        *log = Self::open_log(&self.path)?;
+        Ok(before.saturating_sub(contents.len() as u64))
+    }
+
+    /// Apply the log at `path` to `documents`
+    ///
+    /// Every append ends its records with a newline, so a final line
+    /// without one is what a crash mid-append leaves behind: it is cut from
+    /// the file with a warning, so the next append starts on a fresh line.
+    /// Unparsable complete records are errors.
+    fn replay(path: &Path, documents: &mut HashMap<String, VectorDocument>) -> VectorResult<()> {
+        let mut reader = BufReader::new(File::open(path).map_err(storage_error)?);
+        let mut line = Vec::new();
+        let mut valid_len = 0u64;
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).map_err(storage_error)?;
+            if read == 0 {
+                return Ok(());
+            }
+            if !line.ends_with(b"\n") {
+                log::warn!(
+                    "Dropping torn final record of {} at byte {}",
+                    path.display(),
+                    valid_len
+                );
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .and_then(|file| file.set_len(valid_len))
+                    .map_err(storage_error)?;
+                return Ok(());
+            }
+            valid_len += read as u64;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_slice(&line)
+                .map_err(|e| VectorStoreError::SerializationError(e.to_string()))?;
+            match record {
+                LogRecord::Put { document } => {
+                    documents.insert(document.id.clone(), document);
+                }
+                LogRecord::Delete { id } => {
+                    documents.remove(&id);
+                }
+                LogRecord::Clear => documents.clear(),
+            }
+        }
+    }
+
+    fn open_log(path: &Path) -> VectorResult<File> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(storage_error)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(storage_error)
+    }
+
+    fn append(log: &mut File, records: &[LogRecord]) -> VectorResult<()> {
+        let mut buf = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut buf, record)
+                .map_err(|e| VectorStoreError::SerializationError(e.to_string()))?;
+            buf.push(b'\n');
+        }
+        log.write_all(&buf).map_err(storage_error)?;
+        log.flush().map_err(storage_error)
+    }
+
+    fn check_dimension(&self, document: &VectorDocument) -> VectorResult<()> {
+        if document.embedding.len() != self.memory.dimension {
+            return Err(VectorStoreError::InvalidDimension {
+                expected: self.memory.dimension,
+                actual: document.embedding.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn storage_error(e: std::io::Error) -> VectorStoreError {
+    VectorStoreError::StorageError(e.to_string())
+}
+
+#[async_trait]
+impl VectorStore for FileVectorStore {
+    async fn insert(&self, document: VectorDocument) -> VectorResult<()> {
+        self.check_dimension(&document)?;
+        let mut log = self.log.lock().await;
+        Self::append(
+            &mut log,
+            &[LogRecord::Put {
+                document: document.clone(),
+            }],
+        )?;
+        self.memory.insert(document).await
+    }
+
+    async fn insert_batch(&self, documents: Vec<VectorDocument>) -> VectorResult<()> {
+        for document in &documents {
+            self.check_dimension(document)?;
+        }
+        let mut log = self.log.lock().await;
+        let records: Vec<LogRecord> = documents
+            .iter()
+            .cloned()
+            .map(|document| LogRecord::Put { document })
+            .collect();
+        Self::append(&mut log, &records)?;
+        self.memory.insert_batch(documents).await
+    }
+
+    async fn get(&self, id: &str) -> VectorResult<Option<VectorDocument>> {
+        self.memory.get(id).await
+    }
+
+    async fn delete(&self, id: &str) -> VectorResult<bool> {
+        let mut log = self.log.lock().await;
+        if self.memory.get(id).await?.is_none() {
+            return Ok(false);
+        }
+        Self::append(&mut log, &[LogRecord::Delete { id: id.to_string() }])?;
+        self.memory.delete(id).await
+    }
+
+    async fn search(&self, query: SearchQuery) -> VectorResult<Vec<SearchResult>> {
+        self.memory.search(query).await
+    }
+
+    async fn count(&self) -> VectorResult<usize> {
+        self.memory.count().await
+    }
+
+    async fn clear(&self) -> VectorResult<()> {
+        let mut log = self.log.lock().await;
+        Self::append(&mut log, &[LogRecord::Clear])?;
+        self.memory.clear().await
+    }
+
+    fn dimension(&self) -> usize {
+        self.memory.dimension
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,8 +583,7 @@ mod tests {
 
     #[test]
     fn test_vector_document_with_metadata() {
-        let doc = VectorDocument::new("doc1", "Test", vec![1.0])
-            .with_metadata("key", "value");
+        let doc = VectorDocument::new("doc1", "Test", vec![1.0]).with_metadata("key", "value");
         assert_eq!(doc.metadata.get("key"), Some(&"value".to_string()));
     }
 
@@ -305,6 +616,133 @@ mod tests {
         assert!(similarity.abs() < 0.001);
     }
 
+    #[test]
+    fn test_cosine_similarity_non_finite() {
+        let huge = vec![f32::MAX, f32::MAX];
+        assert_eq!(InMemoryVectorStore::cosine_similarity(&huge, &huge), 0.0);
+
+        let nan = vec![f32::NAN, 1.0];
+        assert_eq!(
+            InMemoryVectorStore::cosine_similarity(&nan, &[1.0, 1.0]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_compare_scores_puts_nan_last() {
+        let mut scores = [0.2, f32::NAN, 0.9, 0.5];
+        scores.sort_by(|a, b| InMemoryVectorStore::compare_scores(*a, *b));
+        assert_eq!(&scores[..3], &[0.9, 0.5, 0.2]);
+        assert!(scores[3].is_nan());
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_degenerate_vectors_last() {
+        let store = InMemoryVectorStore::new(2);
+
+        store
+            .insert(VectorDocument::new(
+                "overflow",
+                "Huge",
+                create_test_embedding(2, f32::MAX),
+            ))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("nan", "Broken", vec![f32::NAN, 0.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("close", "Close", vec![1.0, 0.1]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("far", "Far", vec![0.1, 1.0]))
+            .await
+            .unwrap();
+
+        let query = SearchQuery::new(vec![1.0, 0.0], 10);
+        let results = store.search(query).await.unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].document.id, "close");
+        assert_eq!(results[1].document.id, "far");
+        assert!(results[2..].iter().all(|r| r.score == 0.0));
+        assert!(results.iter().all(|r| r.score.is_finite()));
+    }
+
+    #[tokio::test]
+    async fn test_search_breaks_ties_by_id() {
+        let store = InMemoryVectorStore::new(2);
+        for id in ["doc-c", "doc-a", "doc-b"] {
+            store
+                .insert(VectorDocument::new(id, "Same", vec![1.0, 1.0]))
+                .await
+                .unwrap();
+        }
+
+        for _ in 0..5 {
+            let results = store
+                .search(SearchQuery::new(vec![1.0, 1.0], 10))
+                .await
+                .unwrap();
+            let ids: Vec<_> = results.iter().map(|r| r.document.id.as_str()).collect();
+            assert_eq!(ids, vec!["doc-a", "doc-b", "doc-c"]);
+        }
+    }
+
+    /// IDs in rank order for a fixed fixture under `metric`
+    async fn ranking(metric: SimilarityMetric, query: Vector) -> Vec<String> {
+        let store = InMemoryVectorStore::with_metric(2, metric);
+        store
+            .insert(VectorDocument::new("short", "Short", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("long", "Long", vec![3.0, 0.5]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("across", "Across", vec![0.0, 1.0]))
+            .await
+            .unwrap();
+
+        store
+            .search(SearchQuery::new(query, 10))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.document.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_similarity_metrics_rank_fixture() {
+        // Cosine ignores magnitude: "short" points exactly along the query
+        assert_eq!(
+            ranking(SimilarityMetric::Cosine, vec![1.0, 0.0]).await,
+            vec!["short", "long", "across"]
+        );
+        // Dot product rewards the longer vector in the same direction
+        assert_eq!(
+            ranking(SimilarityMetric::DotProduct, vec![1.0, 0.0]).await,
+            vec!["long", "short", "across"]
+        );
+        // Euclidean prefers whatever is nearest in space
+        assert_eq!(
+            ranking(SimilarityMetric::Euclidean, vec![0.2, 0.9]).await,
+            vec!["across", "short", "long"]
+        );
+    }
+
+    #[test]
+    fn test_euclidean_score_range() {
+        let metric = SimilarityMetric::Euclidean;
+        assert_eq!(metric.score(&[1.0, 2.0], &[1.0, 2.0]), 1.0);
+        assert!((metric.score(&[0.0, 0.0], &[3.0, 4.0]) - 1.0 / 6.0).abs() < 1e-6);
+        assert_eq!(metric.score(&[f32::MAX, 0.0], &[-f32::MAX, 0.0]), 0.0);
+    }
+
     #[tokio::test]
     async fn test_in_memory_store_insert() {
         let store = InMemoryVectorStore::new(3);
@@ -349,9 +787,18 @@ mod tests {
     async fn test_in_memory_store_search() {
         let store = InMemoryVectorStore::new(2);
 
-        store.insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0])).await.unwrap();
-        store.insert(VectorDocument::new("doc2", "Second", vec![0.0, 1.0])).await.unwrap();
-        store.insert(VectorDocument::new("doc3", "Third", vec![1.0, 1.0])).await.unwrap();
+        store
+            .insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("doc2", "Second", vec![0.0, 1.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("doc3", "Third", vec![1.0, 1.0]))
+            .await
+            .unwrap();
 
         let query = SearchQuery::new(vec![1.0, 0.0], 2);
         let results = store.search(query).await.unwrap();
@@ -365,8 +812,14 @@ mod tests {
     async fn test_in_memory_store_search_with_threshold() {
         let store = InMemoryVectorStore::new(2);
 
-        store.insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0])).await.unwrap();
-        store.insert(VectorDocument::new("doc2", "Second", vec![0.0, 1.0])).await.unwrap();
+        store
+            .insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("doc2", "Second", vec![0.0, 1.0]))
+            .await
+            .unwrap();
 
         let query = SearchQuery::new(vec![1.0, 0.0], 10).with_threshold(0.9);
         let results = store.search(query).await.unwrap();
@@ -379,7 +832,10 @@ mod tests {
     async fn test_in_memory_store_clear() {
         let store = InMemoryVectorStore::new(2);
 
-        store.insert(VectorDocument::new("doc1", "Test", vec![1.0, 2.0])).await.unwrap();
+        store
+            .insert(VectorDocument::new("doc1", "Test", vec![1.0, 2.0]))
+            .await
+            .unwrap();
         assert_eq!(store.count().await.unwrap(), 1);
 
         store.clear().await.unwrap();
@@ -403,19 +859,131 @@ mod tests {
     async fn test_search_with_filters() {
         let store = InMemoryVectorStore::new(2);
 
-        let doc1 = VectorDocument::new("doc1", "First", vec![1.0, 0.0])
-            .with_metadata("category", "A");
-        let doc2 = VectorDocument::new("doc2", "Second", vec![1.0, 0.0])
-            .with_metadata("category", "B");
+        let doc1 =
+            VectorDocument::new("doc1", "First", vec![1.0, 0.0]).with_metadata("category", "A");
+        let doc2 =
+            VectorDocument::new("doc2", "Second", vec![1.0, 0.0]).with_metadata("category", "B");
 
         store.insert(doc1).await.unwrap();
         store.insert(doc2).await.unwrap();
 
-        let query = SearchQuery::new(vec![1.0, 0.0], 10)
-            .with_filter("category", "A");
+        let query = SearchQuery::new(vec![1.0, 0.0], 10).with_filter("category", "A");
         let results = store.search(query).await.unwrap();
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].document.id, "doc1");
     }
+
+    #[tokio::test]
+    async fn test_file_store_reopens_from_log() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("vectors.jsonl");
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        store
+            .insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        store
+            .insert(VectorDocument::new("doc2", "Second", vec![0.0, 1.0]))
+            .await
+            .unwrap();
+        assert!(store.delete("doc2").await.unwrap());
+        assert!(!store.delete("doc2").await.unwrap());
+        drop(store);
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert_eq!(store.get("doc1").await.unwrap().unwrap().content, "First");
+    }
+
+    #[tokio::test]
+    async fn test_file_store_drops_torn_final_record() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("vectors.jsonl");
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        store
+            .insert(VectorDocument::new("doc1", "First", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        drop(store);
+        let intact = std::fs::read_to_string(&path).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"op":"put","document":{"id":"doc2","con"#)
+            .unwrap();
+        drop(file);
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), intact);
+        store
+            .insert(VectorDocument::new("doc3", "Third", vec![0.0, 1.0]))
+            .await
+            .unwrap();
+        drop(store);
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        assert_eq!(store.count().await.unwrap(), 2);
+        drop(store);
+
+        // A tear can split a character, or fall just before the newline
+        let intact = std::fs::read(&path).unwrap();
+        let document = VectorDocument::new("doc4", "Fourth \u{e9}t\u{e9}", vec![1.0, 1.0]);
+        let record = serde_json::to_vec(&LogRecord::Put { document }).unwrap();
+        let split_char = record.iter().position(|b| *b == 0xc3).unwrap() + 1;
+        for torn in [&record[..split_char], &record[..]] {
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(torn).unwrap();
+            drop(file);
+
+            let store = FileVectorStore::open(&path, 2).unwrap();
+            assert_eq!(store.count().await.unwrap(), 2);
+            assert_eq!(std::fs::read(&path).unwrap(), intact);
+        }
+
+        // Damage before the last record is not a torn write
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("not json\n{}", contents)).unwrap();
+        assert!(matches!(
+            FileVectorStore::open(&path, 2),
+            Err(VectorStoreError::SerializationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_file_store_compact() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("vectors.jsonl");
+
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        for i in 0..50 {
+            store
+                .insert(VectorDocument::new(
+                    "doc",
+                    format!("v{}", i),
+                    vec![1.0, 0.0],
+                ))
+                .await
+                .unwrap();
+        }
+        store
+            .insert(VectorDocument::new("other", "Other", vec![0.0, 1.0]))
+            .await
+            .unwrap();
+        let before = std::fs::metadata(&path).unwrap().len();
+
+        let reclaimed = store.compact().await.unwrap();
+        let after = std::fs::metadata(&path).unwrap().len();
+        assert!(after < before);
+        assert_eq!(reclaimed, before - after);
+        assert_eq!(store.get("doc").await.unwrap().unwrap().content, "v49");
+
+        // Writes after compaction go to the new file
+        store.delete("other").await.unwrap();
+        drop(store);
+        let store = FileVectorStore::open(&path, 2).unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert_eq!(store.get("doc").await.unwrap().unwrap().content, "v49");
+    }
 }
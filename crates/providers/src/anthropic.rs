@@ -0,0 +1,253 @@
+//! Anthropic implementation of the async [`AIProvider`] trait
+
+use crate::{http, AIProviderAdapter, AnthropicAdapter};
+use ai_cli_ai_engine::provider::{
+    AIProvider, FinishReason, HealthStatus, MessageRole, ModelInfo, PromptRequest, PromptResponse,
+    ProviderError, ProviderResult, ResponseMetadata, ResponseStream, TokenUsage,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Instant;
+
+/// API version sent in the `anthropic-version` header
+const API_VERSION: &str = "2023-06-01";
+
+/// The Messages API requires `max_tokens`; used when the request leaves it unset
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    model: String,
+    content: Vec<ContentBlock>,
+    stop_reason: Option<String>,
+    usage: Usage,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+#[async_trait]
+impl AIProvider for AnthropicAdapter {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let started = Instant::now();
+
+        // System messages go in the top-level `system` field, not the message list
+        let mut system: Vec<&str> = request.system_prompt.iter().map(String::as_str).collect();
+        let mut messages = Vec::new();
+        for message in &request.messages {
+            let role = match message.role {
+                MessageRole::System => {
+                    system.push(&message.content);
+                    continue;
+                }
+                MessageRole::Assistant => "assistant",
+                // Anthropic only has user and assistant turns; tool results are user turns
+                MessageRole::User | MessageRole::Tool | MessageRole::Function => "user",
+            };
+            messages.push(json!({ "role": role, "content": message.content }));
+        }
+
+        let mut body = json!({
+            "model": request.model,
+            "messages": messages,
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(stop) = &request.stop_sequences {
+            body["stop_sequences"] = json!(stop);
+        }
+
+        let response: MessagesResponse = self
+            .http
+            .send_json(
+                self.http
+                    .post(http::endpoint(&self.base_url, "/v1/messages"))
+                    .header("x-api-key", &self.api_key)
+                    .header("anthropic-version", API_VERSION)
+                    .json(&body),
+            )
+            .await?;
+
+        Ok(PromptResponse {
+            content: response
+                .content
+                .into_iter()
+                .map(|block| block.text)
+                .collect(),
+            model: response.model,
+            usage: TokenUsage::new(response.usage.input_tokens, response.usage.output_tokens),
+            finish_reason: match response.stop_reason.as_deref() {
+                Some("max_tokens") => FinishReason::Length,
+                Some("tool_use") => FinishReason::ToolCalls,
+                _ => FinishReason::Stop,
+            },
+            metadata: ResponseMetadata {
+                request_id: request.metadata.request_id,
+                timestamp: Utc::now(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                cost: None,
+            },
+        })
+    }
+
+    async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+        Err(ProviderError::Unavailable(
+            "streaming not supported yet".to_string(),
+        ))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        Ok(AIProviderAdapter::get_models(self)
+            .into_iter()
+            .map(|id| ModelInfo {
+                name: id.clone(),
+                id,
+                description: None,
+                context_window: 200_000,
+                max_output_tokens: Some(4096),
+                pricing: None,
+                capabilities: vec!["chat".to_string()],
+            })
+            .collect())
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        if self.is_available() {
+            Ok(HealthStatus::healthy(0))
+        } else {
+            Ok(HealthStatus::unhealthy("missing API key"))
+        }
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.http.warmup(&self.base_url).await
+    }
+
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::provider::{Message, RequestMetadata};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_send_prompt_uses_messages_api() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_header("x-api-key", "sk-ant")
+            .match_header("anthropic-version", API_VERSION)
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "system": "be brief",
+                "max_tokens": DEFAULT_MAX_TOKENS,
+                "messages": [{ "role": "user", "content": "hi" }]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "model": "claude-3-opus",
+                    "content": [{ "type": "text", "text": "hello" }],
+                    "stop_reason": "max_tokens",
+                    "usage": { "input_tokens": 3, "output_tokens": 1 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter = AnthropicAdapter::new("sk-ant".to_string(), server.url());
+        let response = adapter
+            .send_prompt(PromptRequest {
+                model: "claude-3-opus".to_string(),
+                system_prompt: Some("be brief".to_string()),
+                messages: vec![Message {
+                    role: MessageRole::User,
+                    content: "hi".to_string(),
+                    name: None,
+                }],
+                temperature: None,
+                max_tokens: None,
+                stop_sequences: None,
+                parameters: HashMap::new(),
+                metadata: RequestMetadata::default(),
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.content, "hello");
+        assert_eq!(response.usage.total_tokens, 4);
+        assert!(matches!(response.finish_reason, FinishReason::Length));
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_sent_as_user_turns() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [
+                    { "role": "user", "content": "add 40 and 2" },
+                    { "role": "assistant", "content": "calling calc" },
+                    { "role": "user", "content": "42" }
+                ]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "model": "claude-3-opus",
+                    "content": [{ "type": "text", "text": "42" }],
+                    "stop_reason": "end_turn",
+                    "usage": { "input_tokens": 9, "output_tokens": 1 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            name: None,
+        };
+        let adapter = AnthropicAdapter::new("sk-ant".to_string(), server.url());
+        adapter
+            .send_prompt(PromptRequest {
+                model: "claude-3-opus".to_string(),
+                system_prompt: None,
+                messages: vec![
+                    message(MessageRole::User, "add 40 and 2"),
+                    message(MessageRole::Assistant, "calling calc"),
+                    message(MessageRole::Tool, "42"),
+                ],
+                temperature: None,
+                max_tokens: None,
+                stop_sequences: None,
+                parameters: HashMap::new(),
+                metadata: RequestMetadata::default(),
+            })
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,168 @@
+//! Azure OpenAI implementation of the async [`AIProvider`] trait
+//!
+//! Azure serves the OpenAI chat completions API per deployment, so request
+//! bodies and responses are shared with the OpenAI adapter.
+
+use crate::openai::{chat_body, chat_response, ChatCompletion};
+use crate::{http, AIProviderAdapter, AzureOpenAIAdapter};
+use ai_cli_ai_engine::provider::{
+    AIProvider, HealthStatus, ModelInfo, PromptRequest, PromptResponse, ProviderCapabilities,
+    ProviderError, ProviderResult, ResponseStream,
+};
+use async_trait::async_trait;
+use std::time::Instant;
+
+/// API version used when the config does not name one
+pub const DEFAULT_API_VERSION: &str = "2024-02-01";
+
+impl AzureOpenAIAdapter {
+    /// URL of the deployment's chat completions endpoint
+    pub fn chat_url(&self) -> String {
+        format!(
+            "{}?api-version={}",
+            http::endpoint(
+                &self.endpoint,
+                &format!("/openai/deployments/{}/chat/completions", self.deployment)
+            ),
+            self.api_version
+        )
+    }
+}
+
+#[async_trait]
+impl AIProvider for AzureOpenAIAdapter {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let started = Instant::now();
+        let completion: ChatCompletion = self
+            .http
+            .send_json(
+                self.http
+                    .post(self.chat_url())
+                    .header("api-key", &self.api_key)
+                    .json(&chat_body(&request)),
+            )
+            .await?;
+        chat_response(completion, request, started)
+    }
+
+    async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+        Err(ProviderError::Unavailable(
+            "streaming not supported yet".to_string(),
+        ))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        Ok(vec![ModelInfo {
+            id: self.deployment.clone(),
+            name: self.deployment.clone(),
+            description: Some("Azure OpenAI deployment".to_string()),
+            context_window: 8192,
+            max_output_tokens: None,
+            pricing: None,
+            capabilities: vec!["chat".to_string()],
+        }])
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        if self.is_available() {
+            Ok(HealthStatus::healthy(0))
+        } else {
+            Ok(HealthStatus::unhealthy("missing API key"))
+        }
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.http.warmup(&self.endpoint).await
+    }
+
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: false,
+            function_calling: false,
+            vision: false,
+            embeddings: false,
+            fine_tuning: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::provider::{Message, MessageRole, RequestMetadata};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_chat_url() {
+        let adapter = AzureOpenAIAdapter::new(
+            "az-key".to_string(),
+            "https://contoso.openai.azure.com/".to_string(),
+            "gpt4o-prod".to_string(),
+        );
+        assert_eq!(
+            adapter.chat_url(),
+            "https://contoso.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-02-01"
+        );
+        assert!(adapter
+            .with_api_version("2024-06-01")
+            .chat_url()
+            .ends_with("?api-version=2024-06-01"));
+    }
+
+    #[tokio::test]
+    async fn test_send_prompt_uses_deployment_and_api_key_header() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/openai/deployments/gpt4o-prod/chat/completions")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "api-version".to_string(),
+                "2024-06-01".to_string(),
+            ))
+            .match_header("api-key", "az-key")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "messages": [{ "role": "user", "content": "hi" }]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "model": "gpt-4o",
+                    "choices": [{ "message": { "content": "hello" }, "finish_reason": "stop" }],
+                    "usage": { "prompt_tokens": 2, "completion_tokens": 1 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter =
+            AzureOpenAIAdapter::new("az-key".to_string(), server.url(), "gpt4o-prod".to_string())
+                .with_api_version("2024-06-01");
+        let response = adapter
+            .send_prompt(PromptRequest {
+                model: "gpt-4o".to_string(),
+                system_prompt: None,
+                messages: vec![Message {
+                    role: MessageRole::User,
+                    content: "hi".to_string(),
+                    name: None,
+                }],
+                temperature: None,
+                max_tokens: None,
+                stop_sequences: None,
+                parameters: HashMap::new(),
+                metadata: RequestMetadata::default(),
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.content, "hello");
+        assert_eq!(response.usage.total_tokens, 3);
+    }
+}
@@ -0,0 +1,288 @@
+//! Record provider HTTP traffic and replay it offline
+//!
+//! A [`RecordingTransport`] sits between the adapters and the network. When
+//! recording, every request/response pair is appended to a cassette file;
+//! when replaying, responses are served from the cassette without touching
+//! the network. Requests are matched on method, URL and a hash of the body.
+//! Headers, and with them API keys, are never written to the cassette.
+//!
+//! Setting `PROVIDER_CASSETTE=<path>` routes every adapter through one shared
+//! transport: it replays when the file exists and records otherwise.
+
+use ai_cli_ai_engine::provider::{ProviderError, ProviderResult};
+use ai_cli_utils::fs::write_atomic;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Environment variable naming the cassette file
+pub const CASSETTE_ENV: &str = "PROVIDER_CASSETTE";
+
+/// Whether a transport talks to the network or serves recorded responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// One recorded request and the response it received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    /// SHA-256 of the request body, hex encoded
+    pub body_hash: String,
+    pub status: u16,
+    pub response: String,
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, url: &str, body_hash: &str) -> bool {
+        self.method == method && self.url == url && self.body_hash == body_hash
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Recorded interactions already served, so repeated requests replay in order
+    replayed: Vec<bool>,
+}
+
+/// HTTP transport that records to, or replays from, a cassette file
+#[derive(Debug)]
+pub struct RecordingTransport {
+    mode: CassetteMode,
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+impl RecordingTransport {
+    /// Record into `path`, replacing any existing cassette once the first
+    /// interaction is saved
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: CassetteMode::Record,
+            path: path.into(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Serve responses recorded in `path`
+    pub fn replay(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let cassette: Cassette = serde_json::from_slice(&std::fs::read(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let replayed = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            mode: CassetteMode::Replay,
+            path,
+            state: Mutex::new(State {
+                interactions: cassette.interactions,
+                replayed,
+            }),
+        })
+    }
+
+    /// Replay `path` if it exists, otherwise record into it
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(path))
+        }
+    }
+
+    /// The transport selected by `PROVIDER_CASSETTE`, shared by every adapter
+    ///
+    /// A cassette that cannot be read is logged and ignored.
+    pub fn from_env() -> Option<Arc<Self>> {
+        static SHARED: OnceLock<Option<Arc<RecordingTransport>>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                let path = std::env::var_os(CASSETTE_ENV).filter(|p| !p.is_empty())?;
+                match Self::open(&path) {
+                    Ok(transport) => Some(Arc::new(transport)),
+                    Err(e) => {
+                        log::warn!("Ignoring cassette {:?}: {}", path, e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Interactions recorded so far, or loaded for replay
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock().interactions.clone()
+    }
+
+    /// Send `request`, returning the response status and body
+    pub(crate) async fn send(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> ProviderResult<(u16, String)> {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        let body_hash = body_hash(request.body().and_then(reqwest::Body::as_bytes));
+
+        if self.mode == CassetteMode::Replay {
+            return self.replayed(&method, &url, &body_hash);
+        }
+
+        let (status, response) = crate::http::fetch(client, request).await?;
+        let mut state = self.lock();
+        state.interactions.push(Interaction {
+            method,
+            url,
+            body_hash,
+            status,
+            response: response.clone(),
+        });
+        if let Err(e) = self.save(&state.interactions) {
+            log::warn!("Cannot write cassette {}: {}", self.path.display(), e);
+        }
+        Ok((status, response))
+    }
+
+    /// The first unserved recording matching the request, else the last match
+    fn replayed(&self, method: &str, url: &str, body_hash: &str) -> ProviderResult<(u16, String)> {
+        let mut state = self.lock();
+        let matching: Vec<usize> = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.matches(method, url, body_hash))
+            .map(|(idx, _)| idx)
+            .collect();
+        let idx = matching
+            .iter()
+            .copied()
+            .find(|&idx| !state.replayed[idx])
+            .or_else(|| matching.last().copied())
+            .ok_or_else(|| {
+                ProviderError::NetworkError(format!(
+                    "no recorded response for {} {} in {}",
+                    method,
+                    url,
+                    self.path.display()
+                ))
+            })?;
+        state.replayed[idx] = true;
+        let interaction = &state.interactions[idx];
+        Ok((interaction.status, interaction.response.clone()))
+    }
+
+    fn save(&self, interactions: &[Interaction]) -> io::Result<()> {
+        let cassette = serde_json::json!({ "interactions": interactions });
+        let json = serde_json::to_vec_pretty(&cassette)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        write_atomic(&self.path, &json)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn body_hash(body: Option<&[u8]>) -> String {
+    format!("{:x}", Sha256::digest(body.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OpenAIAdapter;
+    use ai_cli_ai_engine::provider::AIProvider;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_record_then_replay_offline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("embeddings.json");
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/embeddings")
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "data": [{ "index": 0, "embedding": [0.5, 0.5] }] }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let recorder = Arc::new(RecordingTransport::record(&path));
+        let adapter =
+            OpenAIAdapter::new("sk-secret".to_string(), url.clone()).with_transport(recorder);
+        let recorded = adapter
+            .embed(vec!["a".to_string()], "text-embedding-3-small")
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        let cassette = std::fs::read_to_string(&path).unwrap();
+        assert!(!cassette.contains("sk-secret"));
+
+        // Replay never reaches the server, which would fail the `expect(1)`
+        let player = Arc::new(RecordingTransport::open(&path).unwrap());
+        assert_eq!(player.mode(), CassetteMode::Replay);
+        let adapter = OpenAIAdapter::new("sk-other".to_string(), url).with_transport(player);
+        let replayed = adapter
+            .embed(vec!["a".to_string()], "text-embedding-3-small")
+            .await
+            .unwrap();
+        assert_eq!(replayed, recorded);
+        mock.assert_async().await;
+
+        // A different body has no recording
+        let err = adapter
+            .embed(vec!["b".to_string()], "text-embedding-3-small")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ProviderError::NetworkError(ref msg) if msg.contains("no recorded response"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_serves_repeated_requests_in_order() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cassette.json");
+        let interaction = |response: &str| Interaction {
+            method: "GET".to_string(),
+            url: "http://localhost/v1/models".to_string(),
+            body_hash: body_hash(None),
+            status: 200,
+            response: response.to_string(),
+        };
+        let transport = RecordingTransport::record(&path);
+        transport
+            .save(&[interaction("first"), interaction("second")])
+            .unwrap();
+
+        let transport = RecordingTransport::replay(&path).unwrap();
+        let hash = body_hash(None);
+        let url = "http://localhost/v1/models";
+        assert_eq!(transport.replayed("GET", url, &hash).unwrap().1, "first");
+        assert_eq!(transport.replayed("GET", url, &hash).unwrap().1, "second");
+        assert_eq!(transport.replayed("GET", url, &hash).unwrap().1, "second");
+        assert!(transport.replayed("POST", url, &hash).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Construct providers from configuration
+//!
+//! [`build_provider`] is the single place that maps a provider name to its
+//! adapter, so callers never match on provider names themselves.
+
+use crate::{
+    AIProviderAdapter, AnthropicAdapter, AzureOpenAIAdapter, GoogleAdapter, OllamaAdapter,
+    OpenAIAdapter,
+};
+use ai_cli_ai_engine::provider::{AIProvider, ProviderRegistry};
+use ai_cli_ai_engine::ProviderConfig;
+use ai_cli_security::credentials::CredentialManager;
+use ai_cli_utils::error::AIError;
+use std::sync::Arc;
+
+/// Build a provider from its configuration
+///
+/// The API key comes from the config, then the `<NAME>_API_KEY` environment
+/// variable. Ollama runs locally and needs no key.
+pub fn build_provider(config: &ProviderConfig) -> Result<Arc<dyn AIProvider>, AIError> {
+    build_provider_with_credentials(config, None)
+}
+
+/// Build a provider, falling back to the credential store for the API key
+///
+/// A default model the adapter does not list is logged as a warning.
+pub fn build_provider_with_credentials(
+    config: &ProviderConfig,
+    credentials: Option<&CredentialManager>,
+) -> Result<Arc<dyn AIProvider>, AIError> {
+    let name = config.name.to_lowercase();
+    let base_url = base_url(config)?;
+    if let Some(warning) = check_default_model(config) {
+        log::warn!("{}", warning);
+    }
+    if name == "ollama" {
+        return Ok(Arc::new(OllamaAdapter::new(base_url)));
+    }
+    let api_key = resolve_api_key(config, credentials).ok_or_else(|| {
+        AIError::ConfigError(format!(
+            "No API key for {}; set {} or store a credential",
+            config.name,
+            env_var_name(&name)
+        ))
+    })?;
+
+    match name.as_str() {
+        "openai" => Ok(Arc::new(OpenAIAdapter::new(api_key, base_url))),
+        "anthropic" => Ok(Arc::new(AnthropicAdapter::new(api_key, base_url))),
+        "google" => Ok(Arc::new(GoogleAdapter::new(api_key, base_url))),
+        "qwen" => Ok(Arc::new(
+            OpenAIAdapter::new(api_key, base_url).with_name("qwen"),
+        )),
+        "azure" => {
+            let deployment = config
+                .deployment
+                .clone()
+                .filter(|d| !d.is_empty())
+                .unwrap_or_else(|| config.model.clone());
+            if deployment.is_empty() {
+                return Err(AIError::ConfigError(
+                    "No deployment for azure; set deployment or a default model".to_string(),
+                ));
+            }
+            let mut adapter = AzureOpenAIAdapter::new(api_key, base_url, deployment);
+            if let Some(api_version) = config.api_version.clone().filter(|v| !v.is_empty()) {
+                adapter = adapter.with_api_version(api_version);
+            }
+            Ok(Arc::new(adapter))
+        }
+        _ => Err(AIError::ConfigError(format!(
+            "Unknown provider: {}",
+            config.name
+        ))),
+    }
+}
+
+/// Registry of every enabled provider in `configs`, resolving API keys as
+/// [`build_provider_with_credentials`] does
+///
+/// Providers that cannot be built, e.g. for a missing key or an unknown
+/// name, are returned with their error rather than stopping the others.
+pub fn registry_from_config(
+    configs: &[ProviderConfig],
+    credentials: Option<&CredentialManager>,
+) -> (ProviderRegistry, Vec<(String, AIError)>) {
+    ProviderRegistry::from_config(configs, |config| {
+        build_provider_with_credentials(config, credentials)
+    })
+}
+
+/// Build every enabled provider and register it
+///
+/// Disabled providers are skipped. Returns the number of providers registered.
+pub async fn register_providers(
+    registry: &ProviderRegistry,
+    configs: &[ProviderConfig],
+    credentials: Option<&CredentialManager>,
+) -> Result<usize, AIError> {
+    let mut registered = 0;
+    for config in configs.iter().filter(|c| c.enabled) {
+        registry
+            .register(build_provider_with_credentials(config, credentials)?)
+            .await;
+        registered += 1;
+    }
+    Ok(registered)
+}
+
+/// Models the adapter for `config` lists in its metadata
+///
+/// `None` when the adapter cannot say: unknown providers, Azure (whose
+/// deployments are named by the user), and adapters with an empty list.
+pub fn supported_models(config: &ProviderConfig) -> Option<Vec<String>> {
+    let base_url = base_url(config).ok()?;
+    let metadata = match config.name.to_lowercase().as_str() {
+        "openai" => OpenAIAdapter::new(String::new(), base_url).get_metadata(),
+        "anthropic" => AnthropicAdapter::new(String::new(), base_url).get_metadata(),
+        "google" => GoogleAdapter::new(String::new(), base_url).get_metadata(),
+        "qwen" => OpenAIAdapter::new(String::new(), base_url)
+            .with_name("qwen")
+            .get_metadata(),
+        "ollama" => OllamaAdapter::new(base_url).get_metadata(),
+        _ => return None,
+    };
+    Some(metadata.supported_models).filter(|models| !models.is_empty())
+}
+
+/// Warning for a default model the provider does not list
+///
+/// Only a warning: the lists are fixed in the adapters and fall behind the
+/// models providers actually serve.
+pub fn check_default_model(config: &ProviderConfig) -> Option<String> {
+    let models = supported_models(config)?;
+    if config.model.is_empty() || models.contains(&config.model) {
+        return None;
+    }
+    Some(format!(
+        "Default model '{}' is not one of the models {} lists ({})",
+        config.model,
+        config.name,
+        models.join(", ")
+    ))
+}
+
+/// Standard endpoint of a built-in provider
+///
+/// Used when a provider's config leaves `base_url` empty; set it to go
+/// through a proxy or a self-hosted deployment instead. Qwen is reached
+/// through DashScope's OpenAI-compatible API.
+pub fn default_base_url(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("https://api.openai.com"),
+        "anthropic" => Some("https://api.anthropic.com"),
+        "google" => Some("https://generativelanguage.googleapis.com"),
+        "qwen" => Some("https://dashscope-intl.aliyuncs.com/compatible-mode"),
+        "ollama" => Some("http://localhost:11434"),
+        _ => None,
+    }
+}
+
+/// The configured endpoint, or the provider's standard one when unset
+///
+/// Azure has no standard endpoint; each resource has its own.
+fn base_url(config: &ProviderConfig) -> Result<String, AIError> {
+    match config.base_url.trim() {
+        "" if config.name.eq_ignore_ascii_case("azure") => Err(AIError::ConfigError(
+            "No base_url for azure; set it to the resource endpoint".to_string(),
+        )),
+        "" => default_base_url(&config.name.to_lowercase())
+            .map(str::to_string)
+            .ok_or_else(|| AIError::ConfigError(format!("Unknown provider: {}", config.name))),
+        url => Ok(url.to_string()),
+    }
+}
+
+/// Environment variable an API key for `provider` is read from when the
+/// config has none, e.g. `OPENAI_API_KEY`
+pub fn env_var_name(provider: &str) -> String {
+    format!("{}_API_KEY", provider.to_uppercase().replace('-', "_"))
+}
+
+fn resolve_api_key(
+    config: &ProviderConfig,
+    credentials: Option<&CredentialManager>,
+) -> Option<String> {
+    let name = config.name.to_lowercase();
+    config
+        .api_key
+        .as_ref()
+        .map(|key| key.expose().to_string())
+        .filter(|key| !key.is_empty())
+        .or_else(|| std::env::var(env_var_name(&name)).ok())
+        .filter(|key| !key.is_empty())
+        .or_else(|| {
+            credentials
+                .and_then(|c| c.get_credential(&name))
+                .map(|key| key.expose().to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_security::secret::Secret;
+
+    fn config(name: &str, api_key: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            name: name.to_string(),
+            enabled: true,
+            model: "default".to_string(),
+            base_url: String::new(),
+            api_key: api_key.map(Secret::from),
+            deployment: None,
+            api_version: None,
+        }
+    }
+
+    #[test]
+    fn test_build_known_providers() {
+        for name in ["openai", "anthropic", "google", "qwen"] {
+            let provider = build_provider(&config(name, Some("key"))).unwrap();
+            assert_eq!(provider.name(), name);
+        }
+    }
+
+    #[test]
+    fn test_default_base_urls() {
+        for (name, url) in [
+            ("openai", "https://api.openai.com"),
+            ("anthropic", "https://api.anthropic.com"),
+            ("google", "https://generativelanguage.googleapis.com"),
+            (
+                "qwen",
+                "https://dashscope-intl.aliyuncs.com/compatible-mode",
+            ),
+            ("ollama", "http://localhost:11434"),
+        ] {
+            assert_eq!(default_base_url(name), Some(url));
+            assert_eq!(base_url(&config(name, None)).unwrap(), url);
+        }
+        assert_eq!(default_base_url("nope"), None);
+
+        let mut proxied = config("openai", None);
+        proxied.base_url = " https://proxy.example.com ".to_string();
+        assert_eq!(base_url(&proxied).unwrap(), "https://proxy.example.com");
+    }
+
+    #[test]
+    fn test_build_ollama_without_key() {
+        let provider = build_provider(&config("ollama", None)).unwrap();
+        assert_eq!(provider.name(), "ollama");
+    }
+
+    #[test]
+    fn test_build_azure() {
+        let err = build_provider(&config("azure", Some("key"))).err().unwrap();
+        assert!(err.to_string().contains("No base_url for azure"));
+
+        let mut azure = config("azure", Some("key"));
+        azure.base_url = "https://contoso.openai.azure.com".to_string();
+        azure.deployment = Some("gpt4o-prod".to_string());
+        let provider = build_provider(&azure).unwrap();
+        assert_eq!(provider.name(), "azure");
+    }
+
+    #[test]
+    fn test_check_default_model() {
+        let mut openai = config("openai", None);
+        openai.model = "gpt-4".to_string();
+        assert_eq!(check_default_model(&openai), None);
+
+        openai.model = "gpt-4-trubo".to_string();
+        let warning = check_default_model(&openai).unwrap();
+        assert!(warning.contains("'gpt-4-trubo'"));
+        assert!(warning.contains("gpt-4, gpt-3.5-turbo"));
+
+        // Unlisted models are not reported for adapters that list none
+        for name in ["qwen", "ollama", "nope"] {
+            assert_eq!(check_default_model(&config(name, None)), None);
+        }
+    }
+
+    #[test]
+    fn test_build_unknown_provider() {
+        let err = build_provider(&config("nope", Some("key"))).err().unwrap();
+        assert!(err.to_string().contains("Unknown provider: nope"));
+    }
+
+    #[test]
+    fn test_key_from_credential_store() {
+        let mut credentials = CredentialManager::new();
+        credentials
+            .store_credential("anthropic".to_string(), "sk-stored".to_string())
+            .unwrap();
+
+        let provider =
+            build_provider_with_credentials(&config("anthropic", None), Some(&credentials));
+        assert!(provider.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_registry_from_config() {
+        let mut disabled = config("google", None);
+        disabled.enabled = false;
+        let configs = [
+            config("openai", Some("key")),
+            config("nope", Some("key")),
+            disabled,
+        ];
+
+        let (registry, failed) = registry_from_config(&configs, None);
+        assert_eq!(registry.list().await, vec!["openai".to_string()]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "nope");
+        assert!(failed[0].1.to_string().contains("Unknown provider: nope"));
+    }
+
+    #[tokio::test]
+    async fn test_register_skips_disabled() {
+        let registry = ProviderRegistry::new();
+        let mut disabled = config("google", None);
+        disabled.enabled = false;
+
+        let count = register_providers(&registry, &[config("openai", Some("key")), disabled], None)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(registry.list().await, vec!["openai".to_string()]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Google Gemini implementation of the async [`AIProvider`] trait
+
+use crate::{http, AIProviderAdapter, GoogleAdapter};
+use ai_cli_ai_engine::provider::{
+    AIProvider, FinishReason, HealthStatus, MessageRole, ModelInfo, PromptRequest, PromptResponse,
+    ProviderError, ProviderResult, ResponseMetadata, ResponseStream, TokenUsage,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Instant;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<Content>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Deserialize)]
+struct Part {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+}
+
+#[async_trait]
+impl AIProvider for GoogleAdapter {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let started = Instant::now();
+
+        let mut system: Vec<&str> = request.system_prompt.iter().map(String::as_str).collect();
+        let mut contents = Vec::new();
+        for message in &request.messages {
+            let role = match message.role {
+                MessageRole::System => {
+                    system.push(&message.content);
+                    continue;
+                }
+                MessageRole::Assistant => "model",
+                MessageRole::User | MessageRole::Tool | MessageRole::Function => "user",
+            };
+            contents.push(json!({ "role": role, "parts": [{ "text": message.content }] }));
+        }
+
+        let mut body = json!({ "contents": contents });
+        if !system.is_empty() {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system.join("\n\n") }] });
+        }
+        let mut generation_config = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if let Some(stop) = &request.stop_sequences {
+            generation_config.insert("stopSequences".to_string(), json!(stop));
+        }
+        if !generation_config.is_empty() {
+            body["generationConfig"] = serde_json::Value::Object(generation_config);
+        }
+
+        let path = format!("/v1beta/models/{}:generateContent", request.model);
+        let response: GenerateContentResponse = self
+            .http
+            .send_json(
+                self.http
+                    .post(http::endpoint(&self.base_url, &path))
+                    .header("x-goog-api-key", &self.api_key)
+                    .json(&body),
+            )
+            .await?;
+
+        let candidate = response.candidates.into_iter().next().ok_or_else(|| {
+            ProviderError::ModelError("response contained no candidates".to_string())
+        })?;
+        let usage = response
+            .usage_metadata
+            .map(|u| TokenUsage::new(u.prompt_token_count, u.candidates_token_count))
+            .unwrap_or_else(TokenUsage::empty);
+
+        Ok(PromptResponse {
+            content: candidate
+                .content
+                .map(|c| c.parts.into_iter().map(|p| p.text).collect())
+                .unwrap_or_default(),
+            model: request.model,
+            usage,
+            finish_reason: match candidate.finish_reason.as_deref() {
+                Some("MAX_TOKENS") => FinishReason::Length,
+                Some("SAFETY") | Some("RECITATION") => FinishReason::ContentFilter,
+                _ => FinishReason::Stop,
+            },
+            metadata: ResponseMetadata {
+                request_id: request.metadata.request_id,
+                timestamp: Utc::now(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                cost: None,
+            },
+        })
+    }
+
+    async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+        Err(ProviderError::Unavailable(
+            "streaming not supported yet".to_string(),
+        ))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        Ok(AIProviderAdapter::get_models(self)
+            .into_iter()
+            .map(|id| ModelInfo {
+                name: id.clone(),
+                id,
+                description: None,
+                context_window: 32_768,
+                max_output_tokens: Some(8192),
+                pricing: None,
+                capabilities: vec!["chat".to_string()],
+            })
+            .collect())
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        if self.is_available() {
+            Ok(HealthStatus::healthy(0))
+        } else {
+            Ok(HealthStatus::unhealthy("missing API key"))
+        }
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.http.warmup(&self.base_url).await
+    }
+
+    fn name(&self) -> &str {
+        "google"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::provider::{Message, RequestMetadata};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_send_prompt_uses_generate_content() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1beta/models/gemini-pro:generateContent")
+            .match_header("x-goog-api-key", "g-key")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "contents": [
+                    { "role": "user", "parts": [{ "text": "hi" }] },
+                    { "role": "model", "parts": [{ "text": "hey" }] }
+                ]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "candidates": [{
+                        "content": { "parts": [{ "text": "hello" }] },
+                        "finishReason": "STOP"
+                    }],
+                    "usageMetadata": { "promptTokenCount": 2, "candidatesTokenCount": 1 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter = GoogleAdapter::new("g-key".to_string(), server.url());
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            name: None,
+        };
+        let response = adapter
+            .send_prompt(PromptRequest {
+                model: "gemini-pro".to_string(),
+                system_prompt: None,
+                messages: vec![
+                    message(MessageRole::User, "hi"),
+                    message(MessageRole::Assistant, "hey"),
+                ],
+                temperature: None,
+                max_tokens: None,
+                stop_sequences: None,
+                parameters: HashMap::new(),
+                metadata: RequestMetadata::default(),
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.content, "hello");
+        assert_eq!(response.usage.total_tokens, 3);
+    }
+}
@@ -0,0 +1,164 @@
+//! Shared HTTP plumbing for the async provider implementations
+
+use crate::cassette::RecordingTransport;
+use ai_cli_ai_engine::provider::{classify_status, ProviderError, ProviderResult};
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Lines of a newline-delimited response body, as they arrive
+pub(crate) type LineStream = Pin<Box<dyn Stream<Item = ProviderResult<String>> + Send>>;
+
+/// HTTP client used by the adapters, optionally routed through a cassette
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    client: reqwest::Client,
+    transport: Option<Arc<RecordingTransport>>,
+}
+
+impl HttpClient {
+    /// A client using the transport selected by `PROVIDER_CASSETTE`, if any
+    pub(crate) fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            transport: RecordingTransport::from_env(),
+        }
+    }
+
+    pub(crate) fn set_transport(&mut self, transport: Arc<RecordingTransport>) {
+        self.transport = Some(transport);
+    }
+
+    pub(crate) fn post(&self, url: String) -> reqwest::RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub(crate) fn get(&self, url: String) -> reqwest::RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Open a connection to `base_url` for later requests to reuse
+    ///
+    /// Any HTTP response will do, whatever its status; only failing to
+    /// connect is an error. Replayed cassettes need no connection.
+    pub(crate) async fn warmup(&self, base_url: &str) -> ProviderResult<()> {
+        if self.transport.is_some() {
+            return Ok(());
+        }
+        self.client
+            .head(base_url)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(network_error)
+    }
+
+    /// Send a request and decode its JSON response, mapping HTTP failures to
+    /// provider errors
+    pub(crate) async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> ProviderResult<T> {
+        let request = request
+            .build()
+            .map_err(|e| ProviderError::InvalidRequest(e.to_string()))?;
+        let (status, text) = match &self.transport {
+            Some(transport) => transport.send(&self.client, request).await?,
+            None => fetch(&self.client, request).await?,
+        };
+
+        if !(200..300).contains(&status) {
+            return Err(classify_status(status, &text));
+        }
+
+        serde_json::from_str(&text).map_err(|e| ProviderError::SerializationError(e.to_string()))
+    }
+
+    /// Send a request whose response is newline-delimited, yielding each
+    /// non-empty line as it arrives
+    pub(crate) async fn send_lines(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> ProviderResult<LineStream> {
+        let request = request
+            .build()
+            .map_err(|e| ProviderError::InvalidRequest(e.to_string()))?;
+
+        // Cassettes hold whole bodies, so replayed lines arrive at once
+        if let Some(transport) = &self.transport {
+            let (status, text) = transport.send(&self.client, request).await?;
+            if !(200..300).contains(&status) {
+                return Err(classify_status(status, &text));
+            }
+            let lines: Vec<_> = text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| Ok(line.to_string()))
+                .collect();
+            return Ok(Box::pin(futures::stream::iter(lines)));
+        }
+
+        let response = self.client.execute(request).await.map_err(network_error)?;
+        let status = response.status().as_u16();
+        if !(200..300).contains(&status) {
+            let text = response.text().await.map_err(network_error)?;
+            return Err(classify_status(status, &text));
+        }
+
+        let state = (response.bytes_stream(), Vec::new(), false);
+        Ok(Box::pin(futures::stream::unfold(
+            state,
+            |(mut bytes, mut buffer, mut finished)| async move {
+                loop {
+                    let line = match buffer.iter().position(|&b| b == b'\n') {
+                        Some(end) => Some(buffer.drain(..=end).collect::<Vec<u8>>()),
+                        None if finished && !buffer.is_empty() => Some(std::mem::take(&mut buffer)),
+                        None if finished => return None,
+                        None => None,
+                    };
+                    if let Some(line) = line {
+                        let line = String::from_utf8_lossy(&line).trim().to_string();
+                        if !line.is_empty() {
+                            return Some((Ok(line), (bytes, buffer, finished)));
+                        }
+                        continue;
+                    }
+                    match bytes.next().await {
+                        Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                        Some(Err(e)) => {
+                            buffer.clear();
+                            return Some((Err(network_error(e)), (bytes, buffer, true)));
+                        }
+                        None => finished = true,
+                    }
+                }
+            },
+        )))
+    }
+}
+
+fn network_error(e: reqwest::Error) -> ProviderError {
+    if e.is_timeout() {
+        ProviderError::TimeoutError(e.to_string())
+    } else {
+        ProviderError::NetworkError(e.to_string())
+    }
+}
+
+/// Execute a request over the network, returning the status and body
+pub(crate) async fn fetch(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+) -> ProviderResult<(u16, String)> {
+    let response = client.execute(request).await.map_err(network_error)?;
+    let status = response.status().as_u16();
+    let text = response.text().await.map_err(network_error)?;
+    Ok((status, text))
+}
+
+/// Join a base URL and an endpoint path
+pub(crate) fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
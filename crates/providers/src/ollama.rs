@@ -0,0 +1,374 @@
+//! Ollama implementation of the async [`AIProvider`] trait
+//!
+//! Talks to a local Ollama server's `/api/chat` and `/api/tags` endpoints.
+//! Streamed responses are newline-delimited JSON, one object per chunk.
+
+use crate::{http, OllamaAdapter};
+use ai_cli_ai_engine::provider::{
+    AIProvider, FinishReason, HealthStatus, ModelInfo, PromptRequest, PromptResponse,
+    ProviderCapabilities, ProviderError, ProviderResult, ResponseMetadata, ResponseStream,
+    StreamChunk, TokenUsage,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// How long [`crate::AIProviderAdapter::is_available`] waits for the server
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// One response object, or one line of a streamed response
+#[derive(Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    model: String,
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct TagList {
+    models: Vec<Tag>,
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    name: String,
+}
+
+impl ChatResponse {
+    fn content(&self) -> String {
+        self.message
+            .as_ref()
+            .map(|m| m.content.clone())
+            .unwrap_or_default()
+    }
+
+    fn finish_reason(&self) -> Option<FinishReason> {
+        self.done.then_some(match self.done_reason.as_deref() {
+            Some("length") => FinishReason::Length,
+            _ => FinishReason::Stop,
+        })
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        self.done.then(|| {
+            TokenUsage::new(
+                self.prompt_eval_count.unwrap_or(0),
+                self.eval_count.unwrap_or(0),
+            )
+        })
+    }
+}
+
+/// Whether something accepts connections at `base_url`'s host and port
+pub(crate) fn server_reachable(base_url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(base_url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    (host, port)
+        .to_socket_addrs()
+        .map(|mut addrs| addrs.any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok()))
+        .unwrap_or(false)
+}
+
+impl OllamaAdapter {
+    fn chat_body(request: &PromptRequest, stream: bool) -> serde_json::Value {
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(json!({ "role": "system", "content": system_prompt }));
+        }
+        for message in &request.messages {
+            messages.push(json!({ "role": message.role.as_str(), "content": message.content }));
+        }
+
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+        if let Some(stop) = &request.stop_sequences {
+            options.insert("stop".to_string(), json!(stop));
+        }
+
+        let mut body = json!({ "model": request.model, "messages": messages, "stream": stream });
+        if !options.is_empty() {
+            body["options"] = serde_json::Value::Object(options);
+        }
+        body
+    }
+}
+
+#[async_trait]
+impl AIProvider for OllamaAdapter {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let started = Instant::now();
+        let response: ChatResponse = self
+            .http
+            .send_json(
+                self.http
+                    .post(http::endpoint(&self.base_url, "/api/chat"))
+                    .json(&Self::chat_body(&request, false)),
+            )
+            .await?;
+        if let Some(error) = response.error {
+            return Err(ProviderError::ModelError(error));
+        }
+
+        Ok(PromptResponse {
+            content: response.content(),
+            usage: response.usage().unwrap_or_else(TokenUsage::empty),
+            finish_reason: response.finish_reason().unwrap_or(FinishReason::Stop),
+            model: response.model,
+            metadata: ResponseMetadata {
+                request_id: request.metadata.request_id,
+                timestamp: Utc::now(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                cost: None,
+            },
+        })
+    }
+
+    async fn stream_prompt(&self, request: PromptRequest) -> ProviderResult<ResponseStream> {
+        let lines = self
+            .http
+            .send_lines(
+                self.http
+                    .post(http::endpoint(&self.base_url, "/api/chat"))
+                    .json(&Self::chat_body(&request, true)),
+            )
+            .await?;
+
+        Ok(Box::pin(lines.map(|line| {
+            let response: ChatResponse = serde_json::from_str(&line?)
+                .map_err(|e| ProviderError::SerializationError(e.to_string()))?;
+            if let Some(error) = response.error {
+                return Err(ProviderError::ModelError(error));
+            }
+            Ok(StreamChunk {
+                content: response.content(),
+                finish_reason: response.finish_reason(),
+                usage: response.usage(),
+            })
+        })))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        let tags: TagList = self
+            .http
+            .send_json(self.http.get(http::endpoint(&self.base_url, "/api/tags")))
+            .await?;
+        Ok(tags
+            .models
+            .into_iter()
+            .map(|tag| ModelInfo {
+                id: tag.name.clone(),
+                name: tag.name,
+                description: None,
+                context_window: 8192,
+                max_output_tokens: None,
+                pricing: None,
+                capabilities: vec!["chat".to_string()],
+            })
+            .collect())
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        let started = Instant::now();
+        match AIProvider::get_models(self).await {
+            Ok(_) => Ok(HealthStatus::healthy(started.elapsed().as_millis() as u64)),
+            Err(e) => Ok(HealthStatus::unhealthy(format!(
+                "Ollama server not reachable at {}: {}",
+                self.base_url, e
+            ))),
+        }
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.http.warmup(&self.base_url).await
+    }
+
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: false,
+            vision: false,
+            embeddings: false,
+            fine_tuning: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AIProviderAdapter;
+    use ai_cli_ai_engine::provider::{Message, MessageRole, RequestMetadata};
+    use std::collections::HashMap;
+
+    fn request(prompt: &str) -> PromptRequest {
+        PromptRequest {
+            model: "llama3".to_string(),
+            system_prompt: Some("be brief".to_string()),
+            messages: vec![Message {
+                role: MessageRole::User,
+                content: prompt.to_string(),
+                name: None,
+            }],
+            temperature: Some(0.5),
+            max_tokens: None,
+            stop_sequences: None,
+            parameters: HashMap::new(),
+            metadata: RequestMetadata::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_prompt_uses_chat_endpoint() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "model": "llama3",
+                "stream": false,
+                "options": { "temperature": 0.5 },
+                "messages": [
+                    { "role": "system", "content": "be brief" },
+                    { "role": "user", "content": "hi" }
+                ]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "model": "llama3",
+                    "message": { "role": "assistant", "content": "hello" },
+                    "done": true,
+                    "done_reason": "stop",
+                    "prompt_eval_count": 7,
+                    "eval_count": 2
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter = OllamaAdapter::new(server.url());
+        let response = adapter.send_prompt(request("hi")).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.content, "hello");
+        assert_eq!(response.model, "llama3");
+        assert_eq!(response.usage, TokenUsage::new(7, 2));
+    }
+
+    #[tokio::test]
+    async fn test_warmup_connects_whatever_the_status() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("HEAD", "/")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        OllamaAdapter::new(server.url()).warmup().await.unwrap();
+        mock.assert_async().await;
+
+        let err = OllamaAdapter::new("http://127.0.0.1:1".to_string())
+            .warmup()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::NetworkError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_stream_prompt_reads_ndjson() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(json!({ "stream": true })))
+            .with_chunked_body(|w| {
+                // A line split across writes still arrives as one chunk
+                w.write_all(b"{\"message\":{\"content\":\"Hel\"},\"done\":false}\n{\"message\":")?;
+                w.write_all(b"{\"content\":\"lo\"},\"done\":false}\n")?;
+                w.write_all(
+                    b"{\"message\":{\"content\":\"\"},\"done\":true,\"done_reason\":\"length\",\"prompt_eval_count\":4,\"eval_count\":2}",
+                )
+            })
+            .create_async()
+            .await;
+
+        let adapter = OllamaAdapter::new(server.url());
+        let chunks: Vec<StreamChunk> = adapter
+            .stream_prompt(request("hi"))
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(content, "Hello");
+        assert_eq!(chunks.len(), 3);
+        let last = chunks.last().unwrap();
+        assert!(matches!(last.finish_reason, Some(FinishReason::Length)));
+        assert_eq!(last.usage, Some(TokenUsage::new(4, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_get_models_lists_tags() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/tags")
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({ "models": [{ "name": "llama3:latest" }, { "name": "mistral:7b" }] })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter = OllamaAdapter::new(server.url());
+        let models = AIProvider::get_models(&adapter).await.unwrap();
+        let ids: Vec<_> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["llama3:latest", "mistral:7b"]);
+        assert!(adapter.is_available());
+        assert!(adapter.get_health_status().await.unwrap().healthy);
+    }
+
+    #[test]
+    fn test_unreachable_server_is_unavailable() {
+        // Bind then drop a listener so the port is very likely closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let adapter = OllamaAdapter::new(format!("http://127.0.0.1:{}", port));
+        assert!(!adapter.is_available());
+        assert!(!OllamaAdapter::new("not a url".to_string()).is_available());
+    }
+}
@@ -0,0 +1,263 @@
+//! OpenAI implementation of the async [`AIProvider`] trait
+
+use crate::{http, AIProviderAdapter, OpenAIAdapter};
+use ai_cli_ai_engine::provider::{
+    AIProvider, FinishReason, HealthStatus, ModelInfo, PromptRequest, PromptResponse,
+    ProviderCapabilities, ProviderError, ProviderResult, ResponseMetadata, ResponseStream,
+    TokenUsage, Vector,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Instant;
+
+#[derive(Deserialize)]
+pub(crate) struct ChatCompletion {
+    model: String,
+    choices: Vec<ChatChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingList {
+    data: Vec<Embedding>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    index: usize,
+    embedding: Vector,
+}
+
+impl OpenAIAdapter {
+    async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> ProviderResult<T> {
+        self.http
+            .send_json(
+                self.http
+                    .post(http::endpoint(&self.base_url, path))
+                    .bearer_auth(&self.api_key)
+                    .json(&body),
+            )
+            .await
+    }
+}
+
+/// Chat completions request body for `request`
+pub(crate) fn chat_body(request: &PromptRequest) -> serde_json::Value {
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &request.system_prompt {
+        messages.push(json!({ "role": "system", "content": system_prompt }));
+    }
+    for message in &request.messages {
+        messages.push(json!({ "role": message.role.as_str(), "content": message.content }));
+    }
+
+    let mut body = json!({ "model": request.model, "messages": messages });
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+    if let Some(stop) = &request.stop_sequences {
+        body["stop"] = json!(stop);
+    }
+    body
+}
+
+/// Convert a chat completion into the response to `request`
+pub(crate) fn chat_response(
+    completion: ChatCompletion,
+    request: PromptRequest,
+    started: Instant,
+) -> ProviderResult<PromptResponse> {
+    let choice =
+        completion.choices.into_iter().next().ok_or_else(|| {
+            ProviderError::ModelError("response contained no choices".to_string())
+        })?;
+
+    Ok(PromptResponse {
+        content: choice.message.content.unwrap_or_default(),
+        model: completion.model,
+        usage: completion
+            .usage
+            .map(|u| TokenUsage::new(u.prompt_tokens, u.completion_tokens))
+            .unwrap_or_else(TokenUsage::empty),
+        finish_reason: match choice.finish_reason.as_deref() {
+            Some("length") => FinishReason::Length,
+            Some("content_filter") => FinishReason::ContentFilter,
+            _ => FinishReason::Stop,
+        },
+        metadata: ResponseMetadata {
+            request_id: request.metadata.request_id,
+            timestamp: Utc::now(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            cost: None,
+        },
+    })
+}
+
+#[async_trait]
+impl AIProvider for OpenAIAdapter {
+    async fn send_prompt(&self, request: PromptRequest) -> ProviderResult<PromptResponse> {
+        let started = Instant::now();
+        let completion: ChatCompletion = self
+            .post("/v1/chat/completions", chat_body(&request))
+            .await?;
+        chat_response(completion, request, started)
+    }
+
+    async fn stream_prompt(&self, _request: PromptRequest) -> ProviderResult<ResponseStream> {
+        Err(ProviderError::Unavailable(
+            "streaming not supported yet".to_string(),
+        ))
+    }
+
+    async fn get_models(&self) -> ProviderResult<Vec<ModelInfo>> {
+        Ok(AIProviderAdapter::get_models(self)
+            .into_iter()
+            .map(|id| ModelInfo {
+                name: id.clone(),
+                id,
+                description: None,
+                context_window: 8192,
+                max_output_tokens: None,
+                pricing: None,
+                capabilities: vec!["chat".to_string()],
+            })
+            .collect())
+    }
+
+    async fn get_health_status(&self) -> ProviderResult<HealthStatus> {
+        if self.is_available() {
+            Ok(HealthStatus::healthy(0))
+        } else {
+            Ok(HealthStatus::unhealthy("missing API key"))
+        }
+    }
+
+    async fn embed(&self, texts: Vec<String>, model: &str) -> ProviderResult<Vec<Vector>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let expected = texts.len();
+        let list: EmbeddingList = self
+            .post("/v1/embeddings", json!({ "model": model, "input": texts }))
+            .await?;
+
+        if list.data.len() != expected {
+            return Err(ProviderError::ModelError(format!(
+                "expected {} embeddings, got {}",
+                expected,
+                list.data.len()
+            )));
+        }
+
+        // The API documents `index` as the position in the input; don't rely on order
+        let mut data = list.data;
+        data.sort_by_key(|e| e.index);
+        Ok(data.into_iter().map(|e| e.embedding).collect())
+    }
+
+    async fn warmup(&self) -> ProviderResult<()> {
+        self.http.warmup(&self.base_url).await
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: false,
+            function_calling: false,
+            vision: false,
+            embeddings: true,
+            fine_tuning: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_embed_posts_to_embeddings_endpoint() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/embeddings")
+            .match_header("authorization", "Bearer sk-test")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "model": "text-embedding-3-small",
+                "input": ["a", "b"]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "data": [
+                        { "index": 1, "embedding": [0.0, 1.0] },
+                        { "index": 0, "embedding": [1.0, 0.0] }
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string(), server.url());
+        let vectors = adapter
+            .embed(
+                vec!["a".to_string(), "b".to_string()],
+                "text-embedding-3-small",
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_maps_auth_failure() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/v1/embeddings")
+            .with_status(401)
+            .with_body("bad key")
+            .create_async()
+            .await;
+
+        let adapter = OpenAIAdapter::new("sk-bad".to_string(), server.url());
+        let err = adapter
+            .embed(vec!["a".to_string()], "text-embedding-3-small")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::AuthError(_)));
+    }
+}
@@ -1,8 +1,18 @@
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::secret::Secret;
+use ai_cli_utils::fs::write_atomic_with_mode;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+/// Credentials by name; values are held as [`Secret`]s so they never show
+/// up in debug output
+///
+/// A manager from [`CredentialManager::open`] writes every change back to
+/// its file; one from [`CredentialManager::new`] lives in memory only.
+#[derive(Debug)]
 pub struct CredentialManager {
-    credentials: HashMap<String, String>,
+    credentials: HashMap<String, Secret>,
+    path: Option<PathBuf>,
 }
 
 impl Default for CredentialManager {
@@ -15,21 +25,66 @@ impl CredentialManager {
     pub fn new() -> Self {
         CredentialManager {
             credentials: HashMap::new(),
+            path: None,
         }
     }
 
-    pub fn store_credential(&mut self, key: String, value: String) -> Result<()> {
-        self.credentials.insert(key, value);
-        Ok(())
+    /// Credentials stored as JSON at `path`, which need not exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let credentials = if path.exists() {
+            let json = std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Cannot parse {}", path.display()))?
+        } else {
+            HashMap::new()
+        };
+        Ok(CredentialManager {
+            credentials,
+            path: Some(path),
+        })
     }
 
-    pub fn get_credential(&self, key: &str) -> Option<&String> {
+    /// File the credentials are kept in, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write the credentials to the file, readable by the owner only
+    fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let sorted: BTreeMap<&String, &Secret> = self.credentials.iter().collect();
+        let json = serde_json::to_string_pretty(&sorted)?;
+        write_atomic_with_mode(path, json.as_bytes(), 0o600)
+            .with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    pub fn store_credential(&mut self, key: String, value: impl Into<Secret>) -> Result<()> {
+        let previous = self.credentials.insert(key.clone(), value.into());
+        self.persist().inspect_err(|_| match previous {
+            Some(previous) => {
+                self.credentials.insert(key, previous);
+            }
+            None => {
+                self.credentials.remove(&key);
+            }
+        })
+    }
+
+    pub fn get_credential(&self, key: &str) -> Option<&Secret> {
         self.credentials.get(key)
     }
 
     pub fn remove_credential(&mut self, key: &str) -> Result<()> {
-        self.credentials.remove(key);
-        Ok(())
+        let Some(previous) = self.credentials.remove(key) else {
+            return Ok(());
+        };
+        self.persist().inspect_err(|_| {
+            self.credentials.insert(key.to_string(), previous);
+        })
     }
 
     pub fn list_credentials(&self) -> Vec<String> {
@@ -49,11 +104,43 @@ mod tests {
             .store_credential("api_key".to_string(), "secret123".to_string())
             .unwrap();
         assert_eq!(
-            manager.get_credential("api_key"),
-            Some(&"secret123".to_string())
+            manager.get_credential("api_key").map(Secret::expose),
+            Some("secret123")
         );
+        assert!(!format!("{:?}", manager).contains("secret123"));
 
         manager.remove_credential("api_key").unwrap();
         assert_eq!(manager.get_credential("api_key"), None);
     }
+
+    #[test]
+    fn test_opened_credentials_are_persisted() {
+        let dir = std::env::temp_dir().join(format!("ai-creds-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("credentials.json");
+
+        let mut manager = CredentialManager::open(&path).unwrap();
+        assert!(manager.list_credentials().is_empty());
+        manager
+            .store_credential("openai".to_string(), "sk-one")
+            .unwrap();
+        manager
+            .store_credential("anthropic".to_string(), "sk-two")
+            .unwrap();
+        manager.remove_credential("anthropic").unwrap();
+
+        let reopened = CredentialManager::open(&path).unwrap();
+        assert_eq!(reopened.list_credentials(), vec!["openai".to_string()]);
+        assert_eq!(
+            reopened.get_credential("openai").map(Secret::expose),
+            Some("sk-one")
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
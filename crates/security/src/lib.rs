@@ -2,6 +2,7 @@
 
 pub mod credentials;
 pub mod encryption;
+pub mod secret;
 
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +28,7 @@ mod tests {
     use super::*;
     use crate::credentials::CredentialManager;
     use crate::encryption::Aes256GcmEncryption;
+    use crate::secret::Secret;
 
     // SecurityConfig tests
     #[test]
@@ -184,7 +186,7 @@ mod tests {
             .unwrap();
 
         let retrieved = manager.get_credential("api_key");
-        assert_eq!(retrieved, Some(&"secret123".to_string()));
+        assert_eq!(retrieved.map(Secret::expose), Some("secret123"));
     }
 
     #[test]
@@ -246,16 +248,16 @@ mod tests {
             .store_credential("key".to_string(), "old_value".to_string())
             .unwrap();
         assert_eq!(
-            manager.get_credential("key"),
-            Some(&"old_value".to_string())
+            manager.get_credential("key").map(Secret::expose),
+            Some("old_value")
         );
 
         manager
             .store_credential("key".to_string(), "new_value".to_string())
             .unwrap();
         assert_eq!(
-            manager.get_credential("key"),
-            Some(&"new_value".to_string())
+            manager.get_credential("key").map(Secret::expose),
+            Some("new_value")
         );
     }
 
@@ -269,7 +271,7 @@ mod tests {
         manager
             .store_credential(key.to_string(), value.to_string())
             .unwrap();
-        assert_eq!(manager.get_credential(key), Some(&value.to_string()));
+        assert_eq!(manager.get_credential(key).map(Secret::expose), Some(value));
     }
 
     #[test]
@@ -280,8 +282,8 @@ mod tests {
             .store_credential("日本語キー".to_string(), "🔑🔒".to_string())
             .unwrap();
         assert_eq!(
-            manager.get_credential("日本語キー"),
-            Some(&"🔑🔒".to_string())
+            manager.get_credential("日本語キー").map(Secret::expose),
+            Some("🔑🔒")
         );
     }
 
@@ -299,7 +301,8 @@ mod tests {
 
         // Retrieve and encrypt
         let credential = manager.get_credential("openai").unwrap();
-        let encrypted = Aes256GcmEncryption::encrypt(credential.as_bytes(), password).unwrap();
+        let encrypted =
+            Aes256GcmEncryption::encrypt(credential.expose().as_bytes(), password).unwrap();
 
         // Decrypt and verify
         let decrypted = Aes256GcmEncryption::decrypt(&encrypted, password).unwrap();
@@ -325,7 +328,8 @@ mod tests {
         let mut encrypted_creds = std::collections::HashMap::new();
         for key in manager.list_credentials() {
             let value = manager.get_credential(&key).unwrap();
-            let encrypted = Aes256GcmEncryption::encrypt(value.as_bytes(), password).unwrap();
+            let encrypted =
+                Aes256GcmEncryption::encrypt(value.expose().as_bytes(), password).unwrap();
             encrypted_creds.insert(key, encrypted);
         }
 
@@ -335,7 +339,7 @@ mod tests {
             let decrypted_str = String::from_utf8(decrypted).unwrap();
 
             let original = manager.get_credential(&key).unwrap();
-            assert_eq!(&decrypted_str, original);
+            assert_eq!(decrypted_str, original.expose());
         }
     }
 }
@@ -0,0 +1,75 @@
+//! A string that keeps its value out of logs and error messages
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Shown in place of a secret's value
+const REDACTED: &str = "***";
+
+/// A credential such as an API key
+///
+/// `Debug` and `Display` print `***`, so a secret inside a config or an
+/// error message is never written out by accident. The value is only
+/// available through [`Secret::expose`]. Serialization writes the value
+/// itself, so configs and credential stores still round-trip.
+#[derive(Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret value, to be handed to whatever needs it
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_redacted_when_formatted() {
+        let secret = Secret::from("sk-live-123");
+        assert_eq!(format!("{:?}", secret), "***");
+        assert_eq!(format!("{}", secret), "***");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(***)");
+        assert_eq!(secret.expose(), "sk-live-123");
+
+        // Persisted as the plain value
+        let json = serde_json::to_string(&secret).unwrap();
+        assert_eq!(json, "\"sk-live-123\"");
+        assert_eq!(serde_json::from_str::<Secret>(&json).unwrap(), secret);
+    }
+}
@@ -0,0 +1,125 @@
+//! Copying responses to the system clipboard
+
+use crate::components::{OutputPane, StatusBar};
+use thiserror::Error;
+
+/// Clipboard error types
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardError {
+    #[error("Clipboard unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Nothing to copy")]
+    Empty,
+}
+
+/// Somewhere text can be copied to
+pub trait Clipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+/// The system clipboard, opened on each copy so a missing display only
+/// matters when the user actually copies
+#[derive(Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| ClipboardError::Unavailable(e.to_string()))
+    }
+}
+
+/// Copy the last response in `pane`, reporting the outcome in `status`
+pub fn copy_last_response(
+    clipboard: &mut dyn Clipboard,
+    pane: &OutputPane,
+    status: &mut StatusBar,
+) -> Result<(), ClipboardError> {
+    let result = pane
+        .last_response()
+        .ok_or(ClipboardError::Empty)
+        .and_then(|text| {
+            clipboard.set_text(text)?;
+            Ok(text.chars().count())
+        });
+    match &result {
+        Ok(chars) => status.set_message(format!("Copied {} characters", chars)),
+        Err(e) => status.set_message(e.to_string()),
+    }
+    result.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Rect, UIComponent};
+    use crate::Theme;
+
+    /// Records copied text, or fails like a headless session
+    struct FakeClipboard {
+        copied: Vec<String>,
+        available: bool,
+    }
+
+    impl Clipboard for FakeClipboard {
+        fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+            if !self.available {
+                return Err(ClipboardError::Unavailable("no display".to_string()));
+            }
+            self.copied.push(text.to_string());
+            Ok(())
+        }
+    }
+
+    fn setup() -> (OutputPane, StatusBar) {
+        let mut pane = OutputPane::new(false, false);
+        pane.update("first answer");
+        pane.update("**second** answer");
+        let mut status = StatusBar::new(Theme::Default);
+        status.resize(Rect::new(0, 0, 80, 1));
+        (pane, status)
+    }
+
+    #[test]
+    fn test_copies_last_response() {
+        use crate::keybindings::{KeyBindings, TuiAction};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let ctrl_y = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(
+            KeyBindings::default().resolve(&ctrl_y),
+            Some(TuiAction::CopyLast)
+        );
+
+        let (pane, mut status) = setup();
+        let mut clipboard = FakeClipboard {
+            copied: Vec::new(),
+            available: true,
+        };
+
+        copy_last_response(&mut clipboard, &pane, &mut status).unwrap();
+        assert_eq!(clipboard.copied, vec!["**second** answer"]);
+        assert!(status.line().contains("Copied 17 characters"));
+    }
+
+    #[test]
+    fn test_headless_copy_reports_error() {
+        let (pane, mut status) = setup();
+        let mut clipboard = FakeClipboard {
+            copied: Vec::new(),
+            available: false,
+        };
+
+        let err = copy_last_response(&mut clipboard, &pane, &mut status).unwrap_err();
+        assert!(matches!(err, ClipboardError::Unavailable(_)));
+        assert!(status.line().contains("Clipboard unavailable: no display"));
+
+        let empty = OutputPane::new(false, false);
+        assert_eq!(
+            copy_last_response(&mut clipboard, &empty, &mut status),
+            Err(ClipboardError::Empty)
+        );
+    }
+}
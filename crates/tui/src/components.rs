@@ -1,3 +1,6 @@
+use crate::Theme;
+use ai_cli_ai_engine::cost::CostTracker;
+use crossterm::style::{ResetColor, SetBackgroundColor, SetForegroundColor};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,8 +11,280 @@ pub struct ComponentConfig {
     pub y: u16,
 }
 
+/// Screen area assigned to a component, in terminal cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
 pub trait UIComponent {
     fn render(&self) -> String;
     fn update(&mut self, data: &str);
     fn handle_input(&mut self, input: &str) -> bool;
+
+    /// Lay the component out in `area`, called on startup and whenever the
+    /// terminal is resized
+    fn resize(&mut self, area: Rect) {
+        let _ = area;
+    }
+}
+
+/// One-line summary of the session: provider, model, tokens and cost
+///
+/// A notice, if set, comes first. Segments that don't fit are dropped from
+/// the right, cost first.
+pub struct StatusBar {
+    theme: Theme,
+    width: u16,
+    provider: String,
+    model: String,
+    tokens: u32,
+    cost: Option<(f64, String)>,
+    message: Option<String>,
+}
+
+impl StatusBar {
+    pub fn new(theme: Theme) -> Self {
+        StatusBar {
+            theme,
+            width: 0,
+            provider: String::new(),
+            model: String::new(),
+            tokens: 0,
+            cost: None,
+            message: None,
+        }
+    }
+
+    /// Show a short notice ahead of the session details
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
+    /// Show the provider and model in use
+    pub fn set_session(&mut self, provider: impl Into<String>, model: impl Into<String>) {
+        self.provider = provider.into();
+        self.model = model.into();
+    }
+
+    /// Show the session totals, called after each response
+    pub fn set_usage(&mut self, tracker: &CostTracker) {
+        self.tokens = tracker.usage().total_tokens;
+        self.cost = tracker
+            .estimated_cost()
+            .map(|(cost, currency)| (cost, currency.to_string()));
+    }
+
+    /// The status text, fitted to exactly the bar's width
+    pub fn line(&self) -> String {
+        let width = usize::from(self.width);
+        let mut segments: Vec<String> = self.message.iter().cloned().collect();
+        segments.extend([
+            self.provider.clone(),
+            self.model.clone(),
+            format!("{} tokens", group_thousands(self.tokens)),
+        ]);
+        if let Some((cost, currency)) = &self.cost {
+            segments.push(format!("{:.4} {}", cost, currency));
+        }
+
+        let mut line = format!(" {} ", segments.join(" | "));
+        while line.chars().count() > width && segments.len() > 1 {
+            segments.pop();
+            line = format!(" {} ", segments.join(" | "));
+        }
+        if line.chars().count() > width {
+            line = match width {
+                0 => String::new(),
+                _ => line.chars().take(width - 1).chain(['…']).collect(),
+            };
+        }
+        let padding = width - line.chars().count();
+        line.extend(std::iter::repeat_n(' ', padding));
+        line
+    }
+}
+
+impl UIComponent for StatusBar {
+    fn render(&self) -> String {
+        let (foreground, background) = self.theme.status_colors();
+        format!(
+            "{}{}{}{}",
+            SetForegroundColor(foreground),
+            SetBackgroundColor(background),
+            self.line(),
+            ResetColor
+        )
+    }
+
+    // Status comes from `set_session` and `set_usage`, not free text
+    fn update(&mut self, _data: &str) {}
+
+    fn handle_input(&mut self, _input: &str) -> bool {
+        false
+    }
+
+    fn resize(&mut self, area: Rect) {
+        self.width = area.width;
+    }
+}
+
+/// Scrollback of assistant responses, rendered as markdown
+///
+/// Without colors, responses are shown exactly as received.
+pub struct OutputPane {
+    colors: bool,
+    highlight: bool,
+    area: Rect,
+    responses: Vec<String>,
+}
+
+impl OutputPane {
+    pub fn new(colors: bool, highlight: bool) -> Self {
+        OutputPane {
+            colors,
+            highlight,
+            area: Rect::default(),
+            responses: Vec::new(),
+        }
+    }
+
+    /// The full text of the latest response
+    pub fn last_response(&self) -> Option<&str> {
+        self.responses.last().map(String::as_str)
+    }
+
+    /// The most recent lines that fit the pane, oldest first
+    pub fn visible_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for response in &self.responses {
+            if self.colors {
+                lines.extend(
+                    crate::markdown::render(response, self.highlight)
+                        .iter()
+                        .map(|line| line.to_ansi()),
+                );
+            } else {
+                lines.extend(response.lines().map(str::to_string));
+            }
+        }
+        let keep = usize::from(self.area.height).min(lines.len());
+        lines.split_off(lines.len() - keep)
+    }
+}
+
+impl UIComponent for OutputPane {
+    fn render(&self) -> String {
+        self.visible_lines().join("\r\n")
+    }
+
+    /// Append a response
+    fn update(&mut self, data: &str) {
+        self.responses.push(data.to_string());
+    }
+
+    fn handle_input(&mut self, _input: &str) -> bool {
+        false
+    }
+
+    fn resize(&mut self, area: Rect) {
+        self.area = area;
+    }
+}
+
+/// Format a count with `,` between groups of three digits
+fn group_thousands(n: u32) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_cli_ai_engine::provider::{ModelPricing, TokenUsage};
+
+    fn status_bar(width: u16) -> StatusBar {
+        let mut tracker = CostTracker::with_pricing(ModelPricing {
+            prompt_price_per_1k: 0.01,
+            completion_price_per_1k: 0.03,
+            currency: "USD".to_string(),
+        });
+        tracker.record(&TokenUsage::new(1000, 234));
+
+        let mut bar = StatusBar::new(Theme::Dark);
+        bar.resize(Rect::new(0, 0, width, 1));
+        bar.set_session("anthropic", "claude-3-opus");
+        bar.set_usage(&tracker);
+        bar
+    }
+
+    #[test]
+    fn test_status_bar_shows_session() {
+        let bar = status_bar(60);
+        let line = bar.line();
+        assert_eq!(
+            line.trim_end(),
+            " anthropic | claude-3-opus | 1,234 tokens | 0.0170 USD"
+        );
+        assert_eq!(line.chars().count(), 60);
+        assert!(bar.render().contains(&line));
+    }
+
+    #[test]
+    fn test_status_bar_truncates_on_narrow_terminals() {
+        assert_eq!(
+            status_bar(44).line().trim_end(),
+            " anthropic | claude-3-opus | 1,234 tokens"
+        );
+        assert_eq!(status_bar(12).line(), " anthropic  ");
+        assert_eq!(status_bar(6).line(), " anth…");
+        assert_eq!(status_bar(0).line(), "");
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_output_pane_plain_without_colors() {
+        let mut pane = OutputPane::new(false, false);
+        pane.resize(Rect::new(0, 0, 80, 2));
+        pane.update("# Title\nSome **bold** text\n- item");
+        assert_eq!(pane.visible_lines(), vec!["Some **bold** text", "- item"]);
+
+        let mut pane = OutputPane::new(true, false);
+        pane.resize(Rect::new(0, 0, 80, 10));
+        pane.update("Some **bold** text");
+        let rendered = pane.render();
+        assert!(!rendered.contains("**"));
+        assert!(rendered.contains("bold"));
+    }
 }
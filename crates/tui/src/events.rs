@@ -8,6 +8,18 @@ pub enum Event {
     Custom(String),
 }
 
+impl Event {
+    /// Convert a terminal event, dropping kinds the UI does not handle
+    pub fn from_terminal(event: crossterm::event::Event) -> Option<Self> {
+        match event {
+            crossterm::event::Event::Key(key) => Some(Event::Key(key)),
+            crossterm::event::Event::Mouse(mouse) => Some(Event::Mouse(mouse)),
+            crossterm::event::Event::Resize(width, height) => Some(Event::Resize(width, height)),
+            _ => None,
+        }
+    }
+}
+
 pub struct EventHandler {
     sender: mpsc::Sender<Event>,
     receiver: mpsc::Receiver<Event>,
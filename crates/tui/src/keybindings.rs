@@ -0,0 +1,283 @@
+//! Remappable key bindings
+//!
+//! Keys are written as `+`-separated modifiers and a key name, e.g.
+//! `ctrl+c`, `shift+tab`, `pageup` or `q`. A config maps each action to its
+//! keys; actions it lists replace their defaults, the rest keep them.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use thiserror::Error;
+
+/// Something the user can ask the UI to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuiAction {
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    Submit,
+    Cancel,
+    Clear,
+    /// Copy the last response to the clipboard
+    CopyLast,
+    Quit,
+}
+
+/// Key binding config: the keys bound to each action
+pub type KeyBindingsConfig = BTreeMap<TuiAction, Vec<String>>;
+
+/// Key binding error types
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyBindingError {
+    #[error("Invalid key '{0}'")]
+    InvalidKey(String),
+
+    #[error("Key '{key}' is bound to both {first:?} and {second:?}")]
+    Conflict {
+        key: String,
+        first: TuiAction,
+        second: TuiAction,
+    },
+}
+
+/// A key with its modifiers, ignoring press/release state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Parse a key such as `ctrl+c` or `pagedown`, case-insensitively
+    pub fn parse(spec: &str) -> Result<Self, KeyBindingError> {
+        let invalid = || KeyBindingError::InvalidKey(spec.to_string());
+        let lower = spec.trim().to_lowercase();
+        let mut parts: Vec<&str> = lower.split('+').collect();
+        let name = parts
+            .pop()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(invalid)?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let code = match name {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(invalid()),
+                    },
+                }
+            }
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl From<&KeyEvent> for Key {
+    fn from(event: &KeyEvent) -> Self {
+        // Terminals report shifted letters as uppercase, sometimes with SHIFT
+        let code = match event.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        let modifiers = match event.code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => event.modifiers | KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Key { code, modifiers }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            other => f.write_str(&format!("{:?}", other).to_lowercase()),
+        }
+    }
+}
+
+/// Default keys for each action
+const DEFAULTS: &[(TuiAction, &[&str])] = &[
+    (TuiAction::ScrollUp, &["up", "ctrl+p"]),
+    (TuiAction::ScrollDown, &["down", "ctrl+n"]),
+    (TuiAction::PageUp, &["pageup"]),
+    (TuiAction::PageDown, &["pagedown"]),
+    (TuiAction::Submit, &["enter"]),
+    (TuiAction::Cancel, &["esc"]),
+    (TuiAction::Clear, &["ctrl+l"]),
+    (TuiAction::CopyLast, &["ctrl+y"]),
+    (TuiAction::Quit, &["ctrl+c", "ctrl+d"]),
+];
+
+/// Map from keys to the actions they trigger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    bindings: HashMap<Key, TuiAction>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::from_config(&KeyBindingsConfig::new()).expect("default key bindings are valid")
+    }
+}
+
+impl KeyBindings {
+    /// Defaults overridden by `config`, failing on unknown or conflicting keys
+    pub fn from_config(config: &KeyBindingsConfig) -> Result<Self, KeyBindingError> {
+        let mut merged: KeyBindingsConfig = DEFAULTS
+            .iter()
+            .map(|(action, keys)| (*action, keys.iter().map(|k| k.to_string()).collect()))
+            .collect();
+        for (action, keys) in config {
+            merged.insert(*action, keys.clone());
+        }
+
+        let mut bindings = HashMap::new();
+        for (action, keys) in &merged {
+            for spec in keys {
+                let key = Key::parse(spec)?;
+                if let Some(first) = bindings.insert(key, *action) {
+                    if first != *action {
+                        return Err(KeyBindingError::Conflict {
+                            key: key.to_string(),
+                            first,
+                            second: *action,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(KeyBindings { bindings })
+    }
+
+    /// Parse a JSON config such as `{"quit": ["q", "ctrl+c"]}`
+    pub fn from_json(json: &str) -> Result<Self, ai_cli_utils::error::AIError> {
+        let config: KeyBindingsConfig = serde_json::from_str(json)
+            .map_err(|e| ai_cli_utils::error::AIError::ConfigError(e.to_string()))?;
+        Self::from_config(&config)
+            .map_err(|e| ai_cli_utils::error::AIError::ConfigError(e.to_string()))
+    }
+
+    /// The action bound to `event`, if any
+    pub fn resolve(&self, event: &KeyEvent) -> Option<TuiAction> {
+        self.bindings.get(&Key::from(event)).copied()
+    }
+
+    /// Keys bound to `action`, sorted by their display form
+    pub fn keys_for(&self, action: TuiAction) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(TuiAction::Quit)
+        );
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(TuiAction::Submit)
+        );
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Char('q'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_remapped_binding_replaces_default() {
+        let bindings = KeyBindings::from_json(r#"{"quit": ["q", "Ctrl+Q"]}"#).unwrap();
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Some(TuiAction::Quit)
+        );
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+            Some(TuiAction::Quit)
+        );
+        assert_eq!(
+            bindings.resolve(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(bindings.keys_for(TuiAction::Quit), vec!["ctrl+q", "q"]);
+    }
+
+    #[test]
+    fn test_conflicting_bindings_rejected() {
+        let mut config = KeyBindingsConfig::new();
+        config.insert(TuiAction::Clear, vec!["ctrl+c".to_string()]);
+        let err = KeyBindings::from_config(&config).unwrap_err();
+        assert_eq!(
+            err,
+            KeyBindingError::Conflict {
+                key: "ctrl+c".to_string(),
+                first: TuiAction::Clear,
+                second: TuiAction::Quit,
+            }
+        );
+
+        let mut config = KeyBindingsConfig::new();
+        config.insert(TuiAction::Submit, vec!["hyper+x".to_string()]);
+        assert!(matches!(
+            KeyBindings::from_config(&config),
+            Err(KeyBindingError::InvalidKey(_))
+        ));
+    }
+}
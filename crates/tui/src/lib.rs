@@ -1,9 +1,16 @@
 //! Terminal UI rendering engine for AIrchitect CLI
 
+pub mod clipboard;
 pub mod components;
 pub mod events;
+pub mod keybindings;
+pub mod markdown;
 pub mod renderer;
 
+use components::{Rect, UIComponent};
+use crossterm::style::Color;
+use events::Event;
+use keybindings::{KeyBindings, TuiAction};
 use std::io;
 
 #[derive(Debug, Clone)]
@@ -11,6 +18,9 @@ pub struct UIConfig {
     pub theme: Theme,
     pub animations: bool,
     pub syntax_highlighting: bool,
+    /// Style output; off for `--no-color`
+    pub colors: bool,
+    pub key_bindings: KeyBindings,
 }
 
 #[derive(Debug, Clone)]
@@ -21,10 +31,24 @@ pub enum Theme {
     HighContrast,
 }
 
+impl Theme {
+    /// Foreground and background colors of the status bar
+    pub fn status_colors(&self) -> (Color, Color) {
+        match self {
+            Theme::Default => (Color::White, Color::DarkBlue),
+            Theme::Dark => (Color::Grey, Color::DarkGrey),
+            Theme::Light => (Color::Black, Color::Grey),
+            Theme::HighContrast => (Color::Black, Color::Yellow),
+        }
+    }
+}
+
 pub struct TerminalUI {
     pub config: UIConfig,
     pub width: u16,
     pub height: u16,
+    components: Vec<Box<dyn UIComponent>>,
+    needs_redraw: bool,
 }
 
 impl TerminalUI {
@@ -32,11 +56,73 @@ impl TerminalUI {
         let (width, height) = crossterm::terminal::size()
             .map_err(|e| ai_cli_utils::error::AIError::GenericError(e.to_string()))?;
 
-        Ok(TerminalUI {
+        Ok(Self::with_size(config, width, height))
+    }
+
+    /// Create a UI for a terminal of the given size without querying it
+    pub fn with_size(config: UIConfig, width: u16, height: u16) -> Self {
+        TerminalUI {
             config,
             width,
             height,
-        })
+            components: Vec::new(),
+            needs_redraw: true,
+        }
+    }
+
+    /// The full terminal area
+    pub fn area(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Add a component, laying it out in the current area
+    pub fn add_component(&mut self, mut component: Box<dyn UIComponent>) {
+        component.resize(self.area());
+        self.components.push(component);
+        self.needs_redraw = true;
+    }
+
+    /// Apply an event to the UI state, returning whether a redraw is needed
+    ///
+    /// A resize updates the stored dimensions and lays every component out
+    /// in the new area.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if let Event::Resize(width, height) = *event {
+            if (width, height) != (self.width, self.height) {
+                self.width = width;
+                self.height = height;
+                let area = self.area();
+                for component in &mut self.components {
+                    component.resize(area);
+                }
+                self.needs_redraw = true;
+            }
+        }
+        self.needs_redraw
+    }
+
+    /// The action a key event is bound to, for the event loop to dispatch
+    pub fn action_for(&self, event: &Event) -> Option<TuiAction> {
+        match event {
+            Event::Key(key) => self.config.key_bindings.resolve(key),
+            _ => None,
+        }
+    }
+
+    /// Whether the screen is out of date
+    pub fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    /// Redraw every component if anything changed since the last draw
+    pub fn draw(&mut self) -> Result<(), ai_cli_utils::error::AIError> {
+        if !self.needs_redraw {
+            return Ok(());
+        }
+        let content: Vec<String> = self.components.iter().map(|c| c.render()).collect();
+        self.render(&content.join("\n"))?;
+        self.needs_redraw = false;
+        Ok(())
     }
 
     pub fn init(&mut self) -> Result<(), ai_cli_utils::error::AIError> {
@@ -84,3 +170,73 @@ impl TerminalUI {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records every area it is laid out in
+    struct Pane {
+        areas: Arc<Mutex<Vec<Rect>>>,
+    }
+
+    impl UIComponent for Pane {
+        fn render(&self) -> String {
+            String::new()
+        }
+
+        fn update(&mut self, _data: &str) {}
+
+        fn handle_input(&mut self, _input: &str) -> bool {
+            false
+        }
+
+        fn resize(&mut self, area: Rect) {
+            self.areas.lock().unwrap().push(area);
+        }
+    }
+
+    fn config() -> UIConfig {
+        UIConfig {
+            theme: Theme::Default,
+            animations: false,
+            syntax_highlighting: false,
+            colors: true,
+            key_bindings: KeyBindings::default(),
+        }
+    }
+
+    #[test]
+    fn test_resize_updates_dimensions_and_relayouts() {
+        let areas = Arc::new(Mutex::new(Vec::new()));
+        let mut ui = TerminalUI::with_size(config(), 80, 24);
+        ui.add_component(Box::new(Pane {
+            areas: areas.clone(),
+        }));
+        ui.needs_redraw = false;
+
+        assert!(ui.handle_event(&Event::Resize(120, 40)));
+        assert_eq!((ui.width, ui.height), (120, 40));
+        assert!(ui.needs_redraw());
+        assert_eq!(
+            *areas.lock().unwrap(),
+            vec![Rect::new(0, 0, 80, 24), Rect::new(0, 0, 120, 40)]
+        );
+
+        // The same size again is not a layout change
+        ui.needs_redraw = false;
+        assert!(!ui.handle_event(&Event::Resize(120, 40)));
+        assert_eq!(areas.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_key_events_resolve_to_actions() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let ui = TerminalUI::with_size(config(), 80, 24);
+        let quit = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert_eq!(ui.action_for(&quit), Some(TuiAction::Quit));
+        assert_eq!(ui.action_for(&Event::Resize(1, 1)), None);
+    }
+}
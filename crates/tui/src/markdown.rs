@@ -0,0 +1,339 @@
+//! Markdown to styled terminal lines
+//!
+//! Covers what model responses actually use: ATX headers, bullet and
+//! numbered lists, fenced code blocks and inline bold, italic and code.
+//! Anything else passes through as plain text.
+
+use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor};
+use std::fmt::Write;
+
+/// How a span of text is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    /// Language keyword inside a code block
+    pub keyword: bool,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style {
+        bold: false,
+        italic: false,
+        code: false,
+        keyword: false,
+    };
+
+    fn bold() -> Self {
+        Style {
+            bold: true,
+            ..Style::PLAIN
+        }
+    }
+
+    fn code() -> Self {
+        Style {
+            code: true,
+            ..Style::PLAIN
+        }
+    }
+}
+
+/// Text drawn in one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, style: Style) -> Self {
+        Span {
+            text: text.into(),
+            style,
+        }
+    }
+
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self::new(text, Style::PLAIN)
+    }
+}
+
+/// One output line: an indent followed by styled spans
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    pub indent: usize,
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    pub fn new(indent: usize, spans: Vec<Span>) -> Self {
+        Line { indent, spans }
+    }
+
+    /// The line's text without styling
+    pub fn to_plain(&self) -> String {
+        let mut text = " ".repeat(self.indent);
+        for span in &self.spans {
+            text.push_str(&span.text);
+        }
+        text
+    }
+
+    /// The line with ANSI styling
+    pub fn to_ansi(&self) -> String {
+        let mut out = " ".repeat(self.indent);
+        for span in &self.spans {
+            let style = span.style;
+            if style == Style::PLAIN {
+                out.push_str(&span.text);
+                continue;
+            }
+            if style.bold {
+                let _ = write!(out, "{}", SetAttribute(Attribute::Bold));
+            }
+            if style.italic {
+                let _ = write!(out, "{}", SetAttribute(Attribute::Italic));
+            }
+            if style.keyword {
+                let _ = write!(out, "{}", SetForegroundColor(Color::Magenta));
+            } else if style.code {
+                let _ = write!(out, "{}", SetForegroundColor(Color::Cyan));
+            }
+            let _ = write!(out, "{}{}", span.text, SetAttribute(Attribute::Reset));
+        }
+        out
+    }
+}
+
+/// Render markdown into styled lines
+///
+/// With `highlight` set, keywords in fenced code blocks are styled too.
+pub fn render(markdown: &str, highlight: bool) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut fence: Option<String> = None;
+
+    for raw in markdown.lines() {
+        let trimmed = raw.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            fence = match fence {
+                Some(_) => None,
+                None => Some(info.trim().to_lowercase()),
+            };
+            continue;
+        }
+        if let Some(language) = &fence {
+            let spans = if highlight {
+                highlight_code(language, raw)
+            } else {
+                vec![Span::new(raw, Style::code())]
+            };
+            lines.push(Line::new(2, spans));
+            continue;
+        }
+
+        let indent = raw.len() - trimmed.len();
+        if let Some(text) = heading(trimmed) {
+            lines.push(Line::new(0, vec![Span::new(text, Style::bold())]));
+        } else if let Some(text) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .or_else(|| trimmed.strip_prefix("+ "))
+        {
+            let mut spans = vec![Span::plain("• ")];
+            spans.extend(inline(text));
+            lines.push(Line::new(indent + 2, spans));
+        } else if let Some((number, text)) = numbered(trimmed) {
+            let mut spans = vec![Span::plain(format!("{}. ", number))];
+            spans.extend(inline(text));
+            lines.push(Line::new(indent + 2, spans));
+        } else {
+            lines.push(Line::new(indent, inline(trimmed)));
+        }
+    }
+    lines
+}
+
+/// Text of an ATX header (`# Title`)
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    ((1..=6).contains(&level) && (text.is_empty() || text.starts_with(' ')))
+        .then(|| text.trim().trim_end_matches('#').trim_end())
+}
+
+/// Number and text of a numbered list item (`1. item`)
+fn numbered(line: &str) -> Option<(&str, &str)> {
+    let (number, text) = line.split_once(". ")?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then_some((number, text))
+}
+
+/// Split a line into spans for `**bold**`, `*italic*`/`_italic_` and `` `code` ``
+fn inline(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let marker = if rest.starts_with("**") {
+            Some(("**", Style::bold()))
+        } else if c == '`' {
+            Some(("`", Style::code()))
+        } else if (c == '*' || c == '_')
+            && !plain.ends_with(|p: char| p.is_alphanumeric())
+            && !rest[1..].starts_with(char::is_whitespace)
+        {
+            Some((
+                &rest[..1],
+                Style {
+                    italic: true,
+                    ..Style::PLAIN
+                },
+            ))
+        } else {
+            None
+        };
+
+        if let Some((marker, style)) = marker {
+            let body = &rest[marker.len()..];
+            if let Some(end) = body.find(marker).filter(|&end| end > 0) {
+                if !plain.is_empty() {
+                    spans.push(Span::plain(std::mem::take(&mut plain)));
+                }
+                spans.push(Span::new(&body[..end], style));
+                rest = &body[end + marker.len()..];
+                continue;
+            }
+        }
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !plain.is_empty() {
+        spans.push(Span::plain(plain));
+    }
+    spans
+}
+
+/// Keywords styled in code blocks, by language
+fn keywords(language: &str) -> &'static [&'static str] {
+    match language {
+        "rust" | "rs" => &[
+            "as", "async", "await", "const", "else", "enum", "fn", "for", "if", "impl", "let",
+            "loop", "match", "mod", "mut", "pub", "return", "self", "Self", "struct", "trait",
+            "use", "where", "while",
+        ],
+        "python" | "py" => &[
+            "and", "as", "async", "await", "class", "def", "elif", "else", "for", "from", "if",
+            "import", "in", "is", "lambda", "not", "or", "return", "while", "with", "yield",
+        ],
+        "javascript" | "js" | "typescript" | "ts" => &[
+            "async", "await", "class", "const", "else", "export", "for", "function", "if",
+            "import", "let", "new", "return", "var", "while",
+        ],
+        "sh" | "bash" | "shell" => &[
+            "case", "do", "done", "elif", "else", "esac", "fi", "for", "if", "then", "while",
+        ],
+        _ => &[],
+    }
+}
+
+/// Split a code line into code spans, marking the language's keywords
+fn highlight_code(language: &str, line: &str) -> Vec<Span> {
+    let keywords = keywords(language);
+    let mut spans: Vec<Span> = Vec::new();
+    let mut push = |text: &str, style: Style| match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(Span::new(text, style)),
+    };
+
+    let mut start = 0;
+    for (idx, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        let is_word = c.is_alphanumeric() || c == '_';
+        if is_word && idx < line.len() {
+            continue;
+        }
+        let word = &line[start..idx];
+        if !word.is_empty() {
+            let style = Style {
+                keyword: keywords.contains(&word),
+                ..Style::code()
+            };
+            push(word, style);
+        }
+        if idx < line.len() {
+            push(&line[idx..idx + c.len_utf8()], Style::code());
+        }
+        start = idx + c.len_utf8();
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn italic() -> Style {
+        Style {
+            italic: true,
+            ..Style::PLAIN
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_structure() {
+        let markdown = "# Plan\nUse **care** and `cargo test`.\n- first *step*\n  2. nested\n```rust\nlet x = 1;\n```";
+        let lines = render(markdown, true);
+
+        assert_eq!(
+            lines,
+            vec![
+                Line::new(0, vec![Span::new("Plan", Style::bold())]),
+                Line::new(
+                    0,
+                    vec![
+                        Span::plain("Use "),
+                        Span::new("care", Style::bold()),
+                        Span::plain(" and "),
+                        Span::new("cargo test", Style::code()),
+                        Span::plain("."),
+                    ]
+                ),
+                Line::new(
+                    2,
+                    vec![
+                        Span::plain("• "),
+                        Span::plain("first "),
+                        Span::new("step", italic()),
+                    ]
+                ),
+                Line::new(4, vec![Span::plain("2. "), Span::plain("nested")]),
+                Line::new(
+                    2,
+                    vec![
+                        Span::new(
+                            "let",
+                            Style {
+                                keyword: true,
+                                ..Style::code()
+                            }
+                        ),
+                        Span::new(" x = 1;", Style::code()),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(lines[1].to_plain(), "Use care and cargo test.");
+    }
+
+    #[test]
+    fn test_unmatched_markers_stay_literal() {
+        assert_eq!(
+            render("2 * 3 = 6 and snake_case_name", false),
+            vec![Line::new(
+                0,
+                vec![Span::plain("2 * 3 = 6 and snake_case_name")]
+            )]
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub ai_providers: HashMap<String, ProviderConfig>,
+    /// Keyed by provider name; sorted so saved files are stable
+    pub ai_providers: BTreeMap<String, ProviderConfig>,
     pub default_provider: String,
     pub cache_dir: String,
     pub log_level: String,
@@ -19,7 +20,7 @@ pub struct ProviderConfig {
 impl Default for Config {
     fn default() -> Self {
         Config {
-            ai_providers: HashMap::new(),
+            ai_providers: BTreeMap::new(),
             default_provider: "openai".to_string(),
             cache_dir: ".cache".to_string(),
             log_level: "info".to_string(),
@@ -33,7 +34,7 @@ impl Config {
     }
 
     pub fn load_from_file(path: &str) -> Result<Self, crate::error::AIError> {
-        let contents = std::fs::read_to_string(path)?;
+        let contents = crate::fs::read_text(path)?;
         let config: Config = serde_json::from_str(&contents)?;
         Ok(config)
     }
@@ -44,3 +45,35 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_providers(names: &[&str]) -> Config {
+        let mut config = Config::new();
+        for name in names {
+            config.ai_providers.insert(
+                name.to_string(),
+                ProviderConfig {
+                    api_key: None,
+                    base_url: format!("https://{}.example.com", name),
+                    default_model: "model".to_string(),
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn test_serialization_is_stable() {
+        let first =
+            serde_json::to_string_pretty(&with_providers(&["openai", "anthropic", "ollama"]))
+                .unwrap();
+        let second =
+            serde_json::to_string_pretty(&with_providers(&["ollama", "openai", "anthropic"]))
+                .unwrap();
+        assert_eq!(first, second);
+        assert!(first.find("anthropic").unwrap() < first.find("ollama").unwrap());
+    }
+}
@@ -14,6 +14,9 @@ pub enum AIError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("No providers configured; add one to the providers section of your config")]
+    NoProvidersConfigured,
+
     #[error("Generic error: {0}")]
     GenericError(String),
 }
@@ -0,0 +1,157 @@
+//! Filesystem helpers
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temporary files written at the same time by one process
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+/// Write `contents` to `path` so readers see either the old or the new file
+///
+/// The data goes to a temporary file in the same directory, which is then
+/// renamed over the target. Parent directories are created as needed. An
+/// existing file keeps its permissions, and the directory is synced so the
+/// rename survives a crash.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic_inner(path, contents, None)
+}
+
+/// Write `contents` to `path` like [`write_atomic`], with the Unix
+/// permission bits `mode`
+///
+/// The temporary file is created with `mode`, so the contents are never
+/// readable more widely, even briefly. Other platforms ignore `mode`.
+pub fn write_atomic_with_mode(path: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
+    write_atomic_inner(path, contents, Some(mode))
+}
+
+fn write_atomic_inner(path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let tmp_path = parent.join(format!(
+        ".{}.tmp-{}-{}",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    // A given mode replaces the existing file's permissions
+    let permissions = match fs::metadata(path) {
+        _ if mode.is_some() => None,
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(mode);
+        }
+        let mut file = options.open(&tmp_path)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return result;
+    }
+    sync_dir(parent)
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+// Directories cannot be opened as files here; the rename is as durable as it gets
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Read a text file such as a config file, dropping a leading UTF-8 byte
+/// order mark and turning CRLF line endings into LF
+///
+/// Editors on Windows commonly save files this way, and a BOM is not valid
+/// JSON.
+pub fn read_text(path: impl AsRef<Path>) -> io::Result<String> {
+    let contents = fs::read_to_string(path)?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(&contents);
+    Ok(contents.replace("\r\n", "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_concurrent_writes_to_one_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("shared.txt");
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(path, format!("writer {}", i).as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert!(fs::read_to_string(&path).unwrap().starts_with("writer "));
+        let leftovers = fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_keeps_permissions_of_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.sh");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&path, b"#!/bin/sh\necho hi\n").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "#!/bin/sh\necho hi\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("secrets.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_atomic_with_mode(&path, b"{\"key\": 1}", 0o600).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
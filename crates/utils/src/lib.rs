@@ -2,6 +2,7 @@
 
 pub mod config;
 pub mod error;
+pub mod fs;
 pub mod logging;
 
 /// A simple utility function
@@ -17,4 +18,35 @@ mod tests {
     fn test_get_version() {
         assert!(!get_version().is_empty());
     }
+
+    #[test]
+    fn test_read_text_drops_bom_and_crlf() {
+        let dir = std::env::temp_dir().join(format!("ai-cli-utils-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.json");
+        fs::write_atomic(
+            &path,
+            "\u{feff}{\r\n  \"a\": \"x\r\ny\"\r\n}\r\n".as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_text(&path).unwrap(), "{\n  \"a\": \"x\ny\"\n}\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("ai-cli-utils-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("file.txt");
+
+        fs::write_atomic(&path, b"first").unwrap();
+        fs::write_atomic(&path, b"second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+
+        let leftovers: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers.len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }